@@ -0,0 +1,166 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfxkey::{KeyPair, Secret};
+use network::{
+    dns_discovery::{DnsTree, TreeUrl},
+    node_table::{NodeContact, NodeTable},
+};
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const TRUSTED_NODES_FILE: &str = "trusted_nodes.json";
+
+struct Config {
+    net_config_dir: String,
+    secret: Secret,
+    domain: String,
+    seq: u64,
+    links: Vec<TreeUrl>,
+    limit: usize,
+    output: Option<String>,
+}
+
+fn parse_config() -> Config {
+    let matches = clap::Command::new("cfx-dns-tree")
+        .version("0.1")
+        .about(
+"Build and sign a node list to publish in DNS TXT records from the trusted
+nodes of a node, which have been contacted successfully. The records are
+written as a JSON map from the record names to their TXT values, and the URL
+of the tree to configure in `dns_discovery_urls` is printed.
+Example usage:
+    cfx-dns-tree
+        --net-config-dir ./run/blockchain_data/net_config
+        --key <secret key>
+        --domain nodes.example.org
+        --output records.json")
+        .arg(
+            clap::Arg::new("net-config-dir")
+                .long("net-config-dir")
+                .value_name("PATH")
+                .help("Specifies the net config directory of the node")
+                .required(true),
+        )
+        .arg(
+            clap::Arg::new("key")
+                .long("key")
+                .value_name("HEX")
+                .help("Sets the secret key to sign the tree with")
+                .required(true),
+        )
+        .arg(
+            clap::Arg::new("domain")
+                .long("domain")
+                .value_name("DOMAIN")
+                .help("Sets the domain to publish the tree at")
+                .required(true),
+        )
+        .arg(
+            clap::Arg::new("seq")
+                .long("seq")
+                .value_name("NUM")
+                .value_parser(clap::value_parser!(u64))
+                .help(
+                    "Sets the sequence number of the tree, which must be \
+                     increased on every update (default current unix time)",
+                ),
+        )
+        .arg(
+            clap::Arg::new("link")
+                .long("link")
+                .value_name("URL")
+                .action(clap::ArgAction::Append)
+                .help("Adds a link to another tree, i.e. cfxtree://<key>@<domain>"),
+        )
+        .arg(
+            clap::Arg::new("limit")
+                .long("limit")
+                .value_name("NUM")
+                .value_parser(clap::value_parser!(usize))
+                .help("Sets the maximum number of nodes in the tree (default 200)"),
+        )
+        .arg(
+            clap::Arg::new("output")
+                .long("output")
+                .value_name("FILE")
+                .help("Specifies the output file (default stdout)"),
+        )
+        .get_matches();
+
+    Config {
+        net_config_dir: matches
+            .get_one::<String>("net-config-dir")
+            .unwrap()
+            .clone(),
+        secret: matches
+            .get_one::<String>("key")
+            .unwrap()
+            .trim_start_matches("0x")
+            .parse()
+            .expect("invalid secret key"),
+        domain: matches
+            .get_one::<String>("domain")
+            .unwrap()
+            .trim_end_matches('.')
+            .to_lowercase(),
+        seq: matches.get_one::<u64>("seq").cloned().unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        }),
+        links: matches
+            .get_many::<String>("link")
+            .unwrap_or_default()
+            .map(|link| link.parse().expect("invalid link"))
+            .collect(),
+        limit: matches.get_one::<usize>("limit").cloned().unwrap_or(200),
+        output: matches.get_one::<String>("output").cloned(),
+    }
+}
+
+fn main() {
+    let config = parse_config();
+    let key_pair = KeyPair::from_secret(config.secret.clone())
+        .expect("invalid secret key");
+
+    let node_table =
+        NodeTable::new(Some(config.net_config_dir), TRUSTED_NODES_FILE);
+    // The entries are ordered by failure percentage, so the most reliable
+    // nodes are published.
+    let nodes: Vec<_> = node_table
+        .entries()
+        .into_iter()
+        .filter(|entry| {
+            matches!(
+                node_table.get(&entry.id).and_then(|n| n.last_contact),
+                Some(NodeContact::Success(_))
+            )
+        })
+        .take(config.limit)
+        .collect();
+    let node_count = nodes.len();
+
+    let tree = DnsTree::build(nodes, config.links, config.seq, &config.secret)
+        .expect("failed to sign the tree");
+    let records =
+        serde_json::to_string_pretty(&tree.to_txt_records(&config.domain))
+            .unwrap();
+    match &config.output {
+        Some(path) => fs::write(path, records).expect("failed to write output"),
+        None => println!("{}", records),
+    }
+
+    let url = TreeUrl {
+        public: *key_pair.public(),
+        domain: config.domain,
+    };
+    eprintln!(
+        "built tree with {} nodes and seq {}: {}",
+        node_count, config.seq, url
+    );
+}
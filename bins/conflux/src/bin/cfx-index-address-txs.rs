@@ -0,0 +1,104 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfxcore::{
+    block_data_manager::{db_manager::DBManager, BackfillResult},
+    pow::PowComputer,
+};
+use std::sync::Arc;
+
+fn open_db(db_path: &str) -> std::io::Result<Arc<db::SystemDB>> {
+    let db_config = db::db_config(
+        std::path::Path::new(db_path),
+        None,
+        db::DatabaseCompactionProfile::default(),
+        cfxcore::db::NUM_COLUMNS,
+        false,
+    );
+
+    db::open_database(db_path, &db_config)
+}
+
+struct Config {
+    db_path: String,
+    from_epoch: u64,
+    to_epoch: u64,
+}
+
+fn parse_config() -> Config {
+    let matches = clap::Command::new("cfx-index-address-txs")
+        .version("0.1")
+        .about(
+"Backfill the address transaction index of a stopped node from the executed
+epochs in its local blockchain db. Epochs which are not executed locally, e.g.
+epochs before the checkpoint, are skipped. Epochs below the highest indexed
+epoch cannot be backfilled, so run it before enabling the index on the node.
+Example usage:
+    cfx-index-address-txs
+        --db-path ./run/blockchain_data/blockchain_db
+        --from-epoch 1
+        --to-epoch 100000")
+        .arg(
+            clap::Arg::new("db-path")
+                .long("db-path")
+                .value_name("PATH")
+                .help("Specifies local blockchain db directory")
+                .required(true),
+        )
+        .arg(
+            clap::Arg::new("from-epoch")
+                .long("from-epoch")
+                .value_name("NUM")
+                .value_parser(clap::value_parser!(u64))
+                .help("Sets the first epoch to index (default 1)"),
+        )
+        .arg(
+            clap::Arg::new("to-epoch")
+                .long("to-epoch")
+                .value_name("NUM")
+                .value_parser(clap::value_parser!(u64))
+                .help("Sets the last epoch to index")
+                .required(true),
+        )
+        .get_matches();
+
+    Config {
+        db_path: matches.get_one::<String>("db-path").unwrap().clone(),
+        from_epoch: matches.get_one::<u64>("from-epoch").cloned().unwrap_or(1),
+        to_epoch: *matches.get_one::<u64>("to-epoch").unwrap(),
+    }
+}
+
+fn main() {
+    let config = parse_config();
+    let db = open_db(&config.db_path).unwrap();
+    // The PoW quality of the loaded block headers is not used here.
+    let db_manager =
+        DBManager::new_from_rocksdb(db, Arc::new(PowComputer::new(false)));
+
+    let mut indexed = 0u64;
+    let mut skipped = 0u64;
+    for epoch in config.from_epoch..=config.to_epoch {
+        match db_manager.index_epoch_address_txs_from_db(epoch) {
+            BackfillResult::Indexed => indexed += 1,
+            BackfillResult::NotExecuted => skipped += 1,
+            BackfillResult::BelowIndexedTip(tip) => {
+                eprintln!(
+                    "cannot backfill epoch {}: epoch {} is already indexed, \
+                     and backfilling would break the order of the index",
+                    epoch, tip
+                );
+                std::process::exit(1);
+            }
+        }
+        if (epoch - config.from_epoch + 1) % 10_000 == 0 {
+            println!("processed up to epoch {}", epoch);
+        }
+    }
+
+    println!(
+        "indexed {} epochs, skipped {} epochs that are not executed",
+        indexed, skipped
+    );
+}
@@ -271,6 +271,12 @@ pub enum Commands {
     /// transactions
     #[command(subcommand_required = true, arg_required_else_help = true)]
     Rpc(RpcCommand),
+    /// Manage chain specifications
+    #[command(subcommand_required = true, arg_required_else_help = true)]
+    ChainSpec(ChainSpecSubcommands),
+    /// Verify execution witnesses without a node
+    #[command(subcommand_required = true, arg_required_else_help = true)]
+    Witness(WitnessSubcommands),
 }
 
 /**
@@ -318,6 +324,65 @@ pub struct AccountImportArgs {
     pub import_path: Vec<String>,
 }
 
+/**
+ * --------------- Chain Spec Subcommands ---------------
+ */
+
+/// Chain Spec Subcommands
+#[derive(Args, Debug)]
+pub struct ChainSpecSubcommands {
+    #[command(subcommand)]
+    pub command: ChainSpecCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ChainSpecCommand {
+    /// Print the chain specification of a built-in chain, or of the chain
+    /// configured by --config if --chain is not given.
+    Export(ChainSpecExportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ChainSpecExportArgs {
+    /// The built-in chain to export, e.g. mainnet.
+    #[arg(long, value_name = "CHAIN")]
+    pub chain: Option<String>,
+}
+
+/**
+ * --------------- Witness Subcommands ---------------
+ */
+
+/// Witness Subcommands
+#[derive(Args, Debug)]
+pub struct WitnessSubcommands {
+    #[command(subcommand)]
+    pub command: WitnessCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WitnessCommand {
+    /// Re-execute an epoch from a witness with the chain configured by
+    /// --config, and check it against the trusted state roots before and
+    /// after the epoch. No node database is needed.
+    Verify(WitnessVerifyArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct WitnessVerifyArgs {
+    /// JSON file of the witness returned by `execution-witness`
+    #[arg(long, required = true, value_name = "FILE")]
+    pub file: String,
+    /// The trusted state root hash of the parent epoch, i.e. the deferred
+    /// state root of the pivot block 5 epochs after it
+    #[arg(long, required = true, value_name = "HASH")]
+    pub parent_state_root: String,
+    /// The trusted state root hash of the epoch, i.e. the deferred state
+    /// root of the pivot block 5 epochs after it
+    #[arg(long, required = true, value_name = "HASH")]
+    pub state_root: String,
+}
+
 /**
  * --------------- RPC Subcommands ---------------
  */
@@ -867,6 +932,12 @@ pub enum RpcLocalCommand {
     /// Get the consensus graph state
     #[command(name = "consensus-graph-state")]
     ConsensusGraphState(RpcLocalConsensusGraphStateArgs),
+    /// Get the witness to re-execute an epoch without the state database
+    #[command(name = "execution-witness")]
+    ExecutionWitness(RpcLocalExecutionWitnessArgs),
+    /// Re-execute an epoch from a witness and return the state root
+    #[command(name = "verify-witness")]
+    VerifyWitness(RpcLocalVerifyWitnessArgs),
     /// Test subcommands (used for test purpose only)
     #[command(subcommand_required = true, arg_required_else_help = true)]
     Test(RpcLocalTestSubcommands),
@@ -1278,6 +1349,50 @@ pub struct RpcLocalConsensusGraphStateArgs {
     pub rpc_method: String,
 }
 
+#[derive(Args, Debug)]
+pub struct RpcLocalExecutionWitnessArgs {
+    #[arg(
+        id = "rpc-method",
+        long = "rpc-method",
+        default_value = "debug_executionWitness",
+        hide = true
+    )]
+    pub rpc_method: String,
+    #[arg(
+        id = "rpc-args",
+        long = "rpc-args",
+        hide = true,
+        default_value = "epoch:u64",
+        value_delimiter = ','
+    )]
+    pub rpc_args: Vec<String>,
+    /// Epoch number
+    #[arg(long, required = true, value_name = "NUMBER")]
+    pub epoch: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct RpcLocalVerifyWitnessArgs {
+    #[arg(
+        id = "rpc-method",
+        long = "rpc-method",
+        default_value = "debug_verifyExecutionWitness",
+        hide = true
+    )]
+    pub rpc_method: String,
+    #[arg(
+        id = "rpc-args",
+        long = "rpc-args",
+        hide = true,
+        default_value = "file:json-file",
+        value_delimiter = ','
+    )]
+    pub rpc_args: Vec<String>,
+    /// JSON file of the witness returned by `execution-witness`
+    #[arg(long, required = true, value_name = "FILE")]
+    pub file: String,
+}
+
 #[derive(Args, Debug)]
 pub struct RpcLocalTestSubcommands {
     #[command(subcommand)]
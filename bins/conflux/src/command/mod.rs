@@ -5,3 +5,4 @@
 pub mod account;
 pub mod helpers;
 pub mod rpc;
+pub mod witness;
@@ -81,6 +81,7 @@ impl<'a> ArgSchema<'a> {
             },
             "bool" => Ok(Some(Value::Bool(matches.get_flag(self.arg_name)))),
             "u64" => self.u64(matches),
+            "json-file" => self.json_file(matches),
             "password" => Ok(Some(self.password()?)),
             "password2" => Ok(Some(self.password2()?)),
             _ => {
@@ -104,6 +105,19 @@ impl<'a> ArgSchema<'a> {
         Ok(Some(Value::String(format!("{:#x}", val))))
     }
 
+    fn json_file(&self, matches: &ArgMatches) -> Result<Option<Value>, String> {
+        let path = match matches.get_one::<String>(self.arg_name) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path, e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("invalid JSON in {}: {}", path, e))
+    }
+
     fn object(&self, matches: &ArgMatches) -> Result<Value, String> {
         let fields: Vec<&str> = self
             .arg_type
@@ -308,6 +322,20 @@ mod tests {
                 expected_url: "http://localhost:12539",
                 expected_params: vec![json!("0x654321fedcba"), json!(false)],
             },
+            TestCase {
+                name: "local execution-witness",
+                args: vec![
+                    "conflux",
+                    "rpc",
+                    "local",
+                    "execution-witness",
+                    "--epoch",
+                    "100",
+                ],
+                expected_method: "debug_executionWitness",
+                expected_url: "http://localhost:12539",
+                expected_params: vec![json!("0x64")],
+            },
         ];
 
         for test_case in test_cases {
@@ -0,0 +1,56 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_executor::machine::{Machine, VmFactory};
+use cfx_types::H256;
+use cfxcore::{pow::PowComputer, EpochExecutor, ExecutionWitness};
+use client::configuration::Configuration;
+use std::{fs, str::FromStr, sync::Arc};
+
+/// Re-executes the epoch in the witness file with the chain parameters in
+/// `conf`, after checking the state roots in the witness against the trusted
+/// ones. Returns the hash of the verified state root.
+pub fn verify(
+    conf: &Configuration, file: &str, parent_state_root: &str, state_root: &str,
+) -> Result<String, String> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| format!("failed to read {}: {}", file, e))?;
+    let witness: ExecutionWitness = serde_json::from_str(&content)
+        .map_err(|e| format!("failed to parse witness: {}", e))?;
+
+    for (name, expected, root) in [
+        (
+            "parent state root",
+            parent_state_root,
+            &witness.parent_state_root,
+        ),
+        ("state root", state_root, &witness.state_root),
+    ] {
+        let expected = H256::from_str(expected.trim_start_matches("0x"))
+            .map_err(|e| format!("invalid {} {}: {:?}", name, expected, e))?;
+        if root.aux_info.state_root_hash != expected {
+            return Err(format!(
+                "{} {:?} in the witness is not the trusted one {:?}",
+                name, root.aux_info.state_root_hash, expected
+            ));
+        }
+    }
+
+    let machine = Arc::new(Machine::new_with_builtin(
+        conf.common_params(),
+        VmFactory::new(1024 * 32),
+    ));
+    let pow = Arc::new(PowComputer::new(conf.pow_config().use_octopus()));
+    let executor = EpochExecutor::new(
+        conf.execution_config(),
+        machine,
+        conf.raw_conf.transaction_epoch_bound,
+        pow,
+    );
+    executor
+        .check_execution_witness(&witness)
+        .map_err(|e| format!("failed to verify witness: {}", e))?;
+    serde_json::to_string_pretty(&witness.state_root.aux_info.state_root_hash)
+        .map_err(|e| format!("failed to serialize state root: {}", e))
+}
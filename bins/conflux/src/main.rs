@@ -14,18 +14,16 @@ use clap::{crate_version, ArgMatches, CommandFactory};
 use cli::Cli;
 use client::{
     archive::ArchiveClient,
-    common::{shutdown_handler, ClientTrait},
-    configuration::Configuration,
+    common::{
+        logger::{setup_logger, setup_tracing, shutdown_tracing},
+        shutdown_handler, ClientTrait,
+    },
+    configuration::{ChainSpec, Configuration},
     full::FullClient,
     light::LightClient,
 };
 use command::account::{AccountCmd, ImportAccounts, ListAccounts, NewAccount};
-use log::{info, LevelFilter};
-use log4rs::{
-    append::{console::ConsoleAppender, file::FileAppender},
-    config::{Appender, Config as LogConfig, Logger, Root},
-    encode::pattern::PatternEncoder,
-};
+use log::info;
 use network::throttling::THROTTLING_SERVICE;
 use parking_lot::{Condvar, Mutex};
 use std::sync::Arc;
@@ -67,6 +65,7 @@ fn main() -> Result<(), String> {
     let conf = Configuration::parse(&matches)?;
 
     setup_logger(&conf)?;
+    setup_tracing(&conf)?;
 
     THROTTLING_SERVICE.write().initialize(
         conf.raw_conf.egress_queue_capacity,
@@ -112,6 +111,7 @@ Current Version: {}
     };
     info!("Conflux client started");
     shutdown_handler::run(client_handle, exit);
+    shutdown_tracing();
 
     Ok(())
 }
@@ -139,6 +139,45 @@ fn handle_sub_command(matches: &ArgMatches) -> Result<Option<String>, String> {
         return Ok(Some(execute_output));
     }
 
+    // chain spec sub-commands
+    if let Some(("chain-spec", chain_spec_matches)) = matches.subcommand() {
+        let spec = match chain_spec_matches.subcommand() {
+            Some(("export", export_matches)) => {
+                match export_matches.get_one::<String>("chain") {
+                    Some(chain) => ChainSpec::builtin(chain)?,
+                    None => ChainSpec::from_configuration(
+                        &Configuration::parse(matches)?,
+                    )?,
+                }
+            }
+            _ => unreachable!(),
+        };
+        let output = serde_json::to_string_pretty(&spec)
+            .map_err(|e| format!("failed to serialize chain spec: {}", e))?;
+        return Ok(Some(output));
+    }
+
+    // execution witness sub-commands
+    if let Some(("witness", witness_matches)) = matches.subcommand() {
+        let output = match witness_matches.subcommand() {
+            Some(("verify", verify_matches)) => {
+                let arg = |name: &str| {
+                    verify_matches
+                        .get_one::<String>(name)
+                        .expect("required argument")
+                };
+                command::witness::verify(
+                    &Configuration::parse(matches)?,
+                    arg("file"),
+                    arg("parent_state_root"),
+                    arg("state_root"),
+                )?
+            }
+            _ => unreachable!(),
+        };
+        return Ok(Some(output));
+    }
+
     // general RPC commands
     let mut subcmd_matches = matches;
     while let Some(m) = subcmd_matches.subcommand() {
@@ -153,73 +192,3 @@ fn handle_sub_command(matches: &ArgMatches) -> Result<Option<String>, String> {
 
     Ok(None)
 }
-
-// If log_conf is provided, use it for log configuration and ignore
-// log_file and log_level. Otherwise, set stdout to INFO and set
-// all our crate log to log_level.
-fn setup_logger(conf: &Configuration) -> Result<(), String> {
-    match conf.raw_conf.log_conf {
-        Some(ref log_conf) => {
-            log4rs::init_file(log_conf, Default::default()).map_err(|e| {
-                format!(
-                    "failed to initialize log with log config file: {:?}",
-                    e
-                )
-            })?;
-        }
-        None => {
-            let mut conf_builder =
-                LogConfig::builder().appender(Appender::builder().build(
-                    "stdout",
-                    Box::new(ConsoleAppender::builder().build()),
-                ));
-            let mut root_builder = Root::builder().appender("stdout");
-            if let Some(ref log_file) = conf.raw_conf.log_file {
-                conf_builder =
-                    conf_builder.appender(Appender::builder().build(
-                        "logfile",
-                        Box::new(
-                            FileAppender::builder().encoder(
-                                Box::new(
-                                    PatternEncoder::new(
-                                        "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}")))
-                                .build(log_file)
-                                .map_err(
-                                    |e| format!("failed to build log pattern: {:?}", e))?,
-                        ),
-                    ));
-                root_builder = root_builder.appender("logfile");
-            };
-            // Should add new crate names here
-            for crate_name in [
-                "blockgen",
-                "cfxcore",
-                "cfx_statedb",
-                "cfx_storage",
-                "conflux",
-                "db",
-                "keymgr",
-                "network",
-                "txgen",
-                "client",
-                "primitives",
-                "io",
-            ]
-            .iter()
-            {
-                conf_builder = conf_builder.logger(
-                    Logger::builder()
-                        .build(*crate_name, conf.raw_conf.log_level),
-                );
-            }
-            let log_config = conf_builder
-                .build(root_builder.build(LevelFilter::Info))
-                .map_err(|e| format!("failed to build log config: {:?}", e))?;
-            log4rs::init_config(log_config).map_err(|e| {
-                format!("failed to initialize log with config: {:?}", e)
-            })?;
-        }
-    };
-
-    Ok(())
-}
@@ -3,7 +3,9 @@
 // See http://www.gnu.org/licenses/
 mod miner;
 
+pub use crate::miner::stratum::WorkerStats as StratumWorkerStats;
 use crate::miner::{
+    get_work::GetWork,
     stratum::{Options as StratumOption, Stratum},
     work_notify::NotifyWork,
 };
@@ -64,6 +66,7 @@ pub struct BlockGenerator {
     state: RwLock<MiningState>,
     workers: Mutex<Vec<(Worker, mpsc::Sender<ProofOfWorkProblem>)>>,
     pub stratum: RwLock<Option<Stratum>>,
+    get_work: GetWork,
     pos_verifier: Arc<PosVerifier>,
 }
 
@@ -150,6 +153,8 @@ impl BlockGenerator {
         pow_config: ProofOfWorkConfig, pow: Arc<PowComputer>,
         mining_author: Address, pos_verifier: Arc<PosVerifier>,
     ) -> Self {
+        let get_work =
+            GetWork::new(pow.clone(), pow_config.pow_problem_window_size);
         BlockGenerator {
             pow_config,
             pow,
@@ -161,6 +166,7 @@ impl BlockGenerator {
             state: RwLock::new(MiningState::Start),
             workers: Mutex::new(Vec::new()),
             stratum: RwLock::new(None),
+            get_work,
             pos_verifier,
         }
     }
@@ -183,6 +189,8 @@ impl BlockGenerator {
         if bg.pow_config.use_stratum() {
             let stratum = bg.stratum.read();
             stratum.as_ref().unwrap().notify(problem);
+        } else if bg.pow_config.use_get_work() {
+            bg.get_work.notify(problem);
         } else {
             for item in bg.workers.lock().iter() {
                 item.1
@@ -747,6 +755,15 @@ impl BlockGenerator {
 
     pub fn pow_config(&self) -> ProofOfWorkConfig { self.pow_config.clone() }
 
+    /// Share statistics of the workers connected to the Stratum server.
+    /// Return an empty list if Stratum mining is not enabled.
+    pub fn stratum_worker_stats(&self) -> Vec<StratumWorkerStats> {
+        self.stratum
+            .read()
+            .as_ref()
+            .map_or_else(Vec::new, |stratum| stratum.worker_stats())
+    }
+
     /// Start num_worker new workers
     pub fn start_new_worker(
         num_worker: u32, bg: Arc<BlockGenerator>,
@@ -775,6 +792,7 @@ impl BlockGenerator {
             listen_addr: bg.pow_config.stratum_listen_addr.clone(),
             port: bg.pow_config.stratum_port,
             secret: bg.pow_config.stratum_secret,
+            vardiff: bg.pow_config.stratum_vardiff.clone(),
         };
         let stratum = Stratum::start(
             &cfg,
@@ -788,6 +806,14 @@ impl BlockGenerator {
         solution_receiver
     }
 
+    pub fn start_new_get_work_worker(
+        bg: Arc<BlockGenerator>,
+    ) -> mpsc::Receiver<ProofOfWorkSolution> {
+        let (solution_sender, solution_receiver) = mpsc::channel();
+        bg.get_work.start(solution_sender);
+        solution_receiver
+    }
+
     pub fn start_mining(bg: Arc<BlockGenerator>, _payload_len: u32) {
         let mut current_mining_block = None;
         let mut recent_mining_blocks = vec![];
@@ -799,6 +825,8 @@ impl BlockGenerator {
         let receiver: mpsc::Receiver<ProofOfWorkSolution> =
             if bg.pow_config.use_stratum() {
                 BlockGenerator::start_new_stratum_worker(bg.clone())
+            } else if bg.pow_config.use_get_work() {
+                BlockGenerator::start_new_get_work_worker(bg.clone())
             } else {
                 BlockGenerator::start_new_worker(1, bg.clone())
             };
@@ -963,6 +991,35 @@ impl BlockGenerator {
     }
 }
 
+impl PowWorkProvider for BlockGenerator {
+    fn work(&self) -> Option<(ProofOfWorkProblem, H256)> {
+        if !self.pow_config.use_get_work() {
+            return None;
+        }
+        self.get_work.work()
+    }
+
+    fn submit_work(&self, pow_hash: H256, nonce: U256) -> bool {
+        self.pow_config.use_get_work()
+            && self.get_work.submit_work(pow_hash, nonce)
+    }
+
+    fn submit_hashrate(&self, hashrate: U256, id: H256) -> bool {
+        if !self.pow_config.use_get_work() {
+            return false;
+        }
+        self.get_work.submit_hashrate(hashrate, id);
+        true
+    }
+
+    fn hashrate(&self) -> U256 { self.get_work.hashrate() }
+
+    fn is_mining(&self) -> bool {
+        self.pow_config.use_get_work()
+            && matches!(*self.state.read(), MiningState::Start)
+    }
+}
+
 impl Stopable for BlockGenerator {
     fn stop(&self) { Self::stop(self) }
 }
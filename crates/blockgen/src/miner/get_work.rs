@@ -0,0 +1,127 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Work provider for remote miners polling through `eth_getWork`
+
+use crate::miner::work_notify::NotifyWork;
+use cfx_types::{H256, U256};
+use cfxcore::pow::{
+    validate, PowComputer, ProofOfWorkProblem, ProofOfWorkSolution,
+};
+use log::{debug, info, trace, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+/// A hashrate reported through `eth_submitHashrate` is ignored after this
+/// period.
+const HASHRATE_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub struct GetWork {
+    pow: Arc<PowComputer>,
+    window_size: usize,
+    recent_problems: Mutex<VecDeque<(ProofOfWorkProblem, HashSet<U256>)>>,
+    solution_sender: Mutex<Option<mpsc::Sender<ProofOfWorkSolution>>>,
+    hashrates: Mutex<HashMap<H256, (U256, Instant)>>,
+}
+
+impl GetWork {
+    pub fn new(pow: Arc<PowComputer>, pow_window_size: usize) -> Self {
+        GetWork {
+            pow,
+            window_size: pow_window_size,
+            recent_problems: Mutex::new(VecDeque::new()),
+            solution_sender: Mutex::new(None),
+            hashrates: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start accepting solutions, which are forwarded to `solution_sender`.
+    pub fn start(&self, solution_sender: mpsc::Sender<ProofOfWorkSolution>) {
+        *self.solution_sender.lock() = Some(solution_sender);
+    }
+
+    /// The latest problem and the seed hash of its PoW cache
+    pub fn work(&self) -> Option<(ProofOfWorkProblem, H256)> {
+        let problem = self.recent_problems.lock().back()?.0;
+        Some((problem, self.pow.seed_hash(problem.block_height)))
+    }
+
+    pub fn submit_work(&self, pow_hash: H256, nonce: U256) -> bool {
+        trace!("submit_work: pow_hash={:?}, nonce={}", pow_hash, nonce);
+        let sol = ProofOfWorkSolution { nonce };
+        {
+            let mut probs = self.recent_problems.lock();
+            let (problem, solved_nonces) = match probs
+                .iter_mut()
+                .find(|(problem, _)| problem.block_hash == pow_hash)
+            {
+                Some(entry) => entry,
+                None => {
+                    debug!("Solution for a stale job! pow_hash={:?}", pow_hash);
+                    return false;
+                }
+            };
+            if solved_nonces.contains(&nonce) {
+                debug!("Problem already solved with nonce = {}", nonce);
+                return false;
+            }
+            if !validate(self.pow.clone(), problem, &sol) {
+                debug!("Incorrect nonce {} for pow_hash={:?}", nonce, pow_hash);
+                return false;
+            }
+            solved_nonces.insert(nonce);
+        }
+
+        info!("Remote miner mined a block through getWork!");
+        match self.solution_sender.lock().as_ref() {
+            Some(sender) => {
+                if let Err(e) = sender.send(sol) {
+                    warn!("{}", e);
+                    return false;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn submit_hashrate(&self, hashrate: U256, id: H256) {
+        self.hashrates.lock().insert(id, (hashrate, Instant::now()));
+    }
+
+    pub fn hashrate(&self) -> U256 {
+        let mut hashrates = self.hashrates.lock();
+        hashrates.retain(|_, (_, reported_at)| {
+            reported_at.elapsed() < HASHRATE_TIMEOUT
+        });
+        hashrates
+            .values()
+            .fold(U256::zero(), |total, (hashrate, _)| {
+                total.saturating_add(*hashrate)
+            })
+    }
+}
+
+impl NotifyWork for GetWork {
+    fn notify(&self, prob: ProofOfWorkProblem) {
+        trace!("Notify getWork problem");
+
+        let mut probs = self.recent_problems.lock();
+        // The same problem may be notified again as heartbeat.
+        if probs
+            .back()
+            .map_or(false, |(last, _)| last.block_hash == prob.block_hash)
+        {
+            return;
+        }
+        if probs.len() == self.window_size {
+            probs.pop_front();
+        }
+        probs.push_back((prob, HashSet::new()));
+    }
+}
@@ -29,19 +29,70 @@ use cfx_stratum::{
     Error as StratumServiceError, JobDispatcher, PushWorkHandler,
     Stratum as StratumService,
 };
-use cfx_types::{H256, U256};
+use cfx_types::{H256, U256, U512};
 use cfxcore::pow::{
-    validate, PowComputer, ProofOfWorkProblem, ProofOfWorkSolution,
+    difficulty_to_boundary, PowComputer, ProofOfWorkProblem,
+    ProofOfWorkSolution, StratumVardiffConfig,
+};
+use lazy_static::lazy_static;
+use log::{debug, info, trace, warn};
+use metrics::{
+    register_meter_with_group, Counter, CounterUsize, CounterVec, Meter,
 };
-use log::{info, trace, warn};
 use parking_lot::Mutex;
 use std::{
-    collections::HashSet,
+    cmp::{max, min},
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryFrom,
     fmt,
     net::{AddrParseError, SocketAddr},
     sync::{mpsc, Arc},
+    time::Instant,
 };
 
+lazy_static! {
+    static ref ACCEPTED_SHARES_METER: Arc<dyn Meter> =
+        register_meter_with_group("stratum", "accepted_shares");
+    static ref REJECTED_SHARES_METER: Arc<dyn Meter> =
+        register_meter_with_group("stratum", "rejected_shares");
+    static ref STALE_SHARES_METER: Arc<dyn Meter> =
+        register_meter_with_group("stratum", "stale_shares");
+    static ref MINED_BLOCKS_METER: Arc<dyn Meter> =
+        register_meter_with_group("stratum", "mined_blocks");
+    static ref WORKER_ACCEPTED_SHARES: Arc<CounterVec> =
+        CounterVec::register_with_group(
+            "stratum",
+            "worker_accepted_shares",
+            &["worker"]
+        );
+    static ref WORKER_REJECTED_SHARES: Arc<CounterVec> =
+        CounterVec::register_with_group(
+            "stratum",
+            "worker_rejected_shares",
+            &["worker"]
+        );
+    static ref WORKER_STALE_SHARES: Arc<CounterVec> =
+        CounterVec::register_with_group(
+            "stratum",
+            "worker_stale_shares",
+            &["worker"]
+        );
+    static ref WORKER_MINED_BLOCKS: Arc<CounterVec> =
+        CounterVec::register_with_group(
+            "stratum",
+            "worker_mined_blocks",
+            &["worker"]
+        );
+}
+
+/// The share difficulty of a worker changes at most by this factor in a
+/// single retarget.
+const VARDIFF_MAX_ADJUSTMENT_FACTOR: u64 = 4;
+
+/// Worker ids are chosen by miners, so per-worker metrics are only kept for
+/// this many distinct connected worker ids.
+const MAX_TRACKED_WORKERS: usize = 1024;
+
 /// Configures stratum server options.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Options {
@@ -51,6 +102,8 @@ pub struct Options {
     pub port: u16,
     /// Secret for peers
     pub secret: Option<H256>,
+    /// Share difficulty adjustment, disabled if `None`
+    pub vardiff: Option<StratumVardiffConfig>,
 }
 
 fn clean_0x(s: &str) -> &str {
@@ -113,16 +166,288 @@ impl fmt::Display for PayloadError {
     }
 }
 
+/// Share statistics of a Stratum worker connection.
+#[derive(Debug, Clone)]
+pub struct WorkerStats {
+    pub worker_id: String,
+    pub addr: SocketAddr,
+    /// The share difficulty of the latest job pushed to the worker
+    pub share_difficulty: U256,
+    pub accepted_shares: u64,
+    pub rejected_shares: u64,
+    pub stale_shares: u64,
+    pub mined_blocks: u64,
+}
+
+/// Per worker id metrics, shared by the connections of the same worker id.
+/// They are removed when the last connection of the worker id is closed.
+struct WorkerMetrics {
+    accepted_shares: Arc<CounterUsize>,
+    rejected_shares: Arc<CounterUsize>,
+    stale_shares: Arc<CounterUsize>,
+    mined_blocks: Arc<CounterUsize>,
+}
+
+impl WorkerMetrics {
+    fn register(worker_id: &str) -> Self {
+        WorkerMetrics {
+            accepted_shares: WORKER_ACCEPTED_SHARES
+                .with_label_values(&[worker_id]),
+            rejected_shares: WORKER_REJECTED_SHARES
+                .with_label_values(&[worker_id]),
+            stale_shares: WORKER_STALE_SHARES.with_label_values(&[worker_id]),
+            mined_blocks: WORKER_MINED_BLOCKS.with_label_values(&[worker_id]),
+        }
+    }
+
+    fn unregister(worker_id: &str) {
+        for metrics in [
+            &*WORKER_ACCEPTED_SHARES,
+            &*WORKER_REJECTED_SHARES,
+            &*WORKER_STALE_SHARES,
+            &*WORKER_MINED_BLOCKS,
+        ] {
+            metrics.remove_label_values(&[worker_id]);
+        }
+    }
+}
+
+struct WorkerState {
+    stats: WorkerStats,
+    /// `None` if the number of tracked worker ids has reached
+    /// `MAX_TRACKED_WORKERS`.
+    metrics: Option<Arc<WorkerMetrics>>,
+    /// Share boundaries of the jobs recently pushed to the worker
+    job_boundaries: VecDeque<(H256, U256)>,
+    shares_since_retarget: u64,
+    last_retarget: Instant,
+}
+
+impl WorkerState {
+    fn share_boundary(&self, problem: &ProofOfWorkProblem) -> U256 {
+        self.job_boundaries
+            .iter()
+            .find(|(pow_hash, _)| *pow_hash == problem.block_hash)
+            .map_or(problem.boundary, |(_, boundary)| *boundary)
+    }
+
+    fn on_accepted(&mut self, mined_block: bool) {
+        self.stats.accepted_shares += 1;
+        self.shares_since_retarget += 1;
+        if mined_block {
+            self.stats.mined_blocks += 1;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.accepted_shares.inc(1);
+            if mined_block {
+                metrics.mined_blocks.inc(1);
+            }
+        }
+    }
+
+    fn on_rejected(&mut self) {
+        self.stats.rejected_shares += 1;
+        if let Some(metrics) = &self.metrics {
+            metrics.rejected_shares.inc(1);
+        }
+    }
+
+    fn on_stale(&mut self) {
+        self.stats.stale_shares += 1;
+        if let Some(metrics) = &self.metrics {
+            metrics.stale_shares.inc(1);
+        }
+    }
+
+    fn maybe_retarget(&mut self, config: &StratumVardiffConfig) {
+        let elapsed_ms = self.last_retarget.elapsed().as_millis() as u64;
+        if elapsed_ms < config.retarget_interval_ms {
+            return;
+        }
+        let new_difficulty = retarget_share_difficulty(
+            &self.stats.share_difficulty,
+            self.shares_since_retarget,
+            elapsed_ms,
+            config,
+        );
+        if new_difficulty != self.stats.share_difficulty {
+            debug!(
+                target: "stratum",
+                "Retarget worker {} at {}: {} shares in {} ms, share difficulty {} -> {}",
+                self.stats.worker_id,
+                self.stats.addr,
+                self.shares_since_retarget,
+                elapsed_ms,
+                self.stats.share_difficulty,
+                new_difficulty,
+            );
+        }
+        self.stats.share_difficulty = new_difficulty;
+        self.shares_since_retarget = 0;
+        self.last_retarget = Instant::now();
+    }
+}
+
+/// Scale `current` so that a worker which found `shares` shares in
+/// `elapsed_ms` would find one share every `target_share_interval_ms`.
+fn retarget_share_difficulty(
+    current: &U256, shares: u64, elapsed_ms: u64, config: &StratumVardiffConfig,
+) -> U256 {
+    let actual_interval_ms = max(elapsed_ms / max(shares, 1), 1);
+    let target_interval_ms = config.target_share_interval_ms;
+    let (numerator, denominator) = if actual_interval_ms
+        .saturating_mul(VARDIFF_MAX_ADJUSTMENT_FACTOR)
+        <= target_interval_ms
+    {
+        (VARDIFF_MAX_ADJUSTMENT_FACTOR, 1)
+    } else if target_interval_ms.saturating_mul(VARDIFF_MAX_ADJUSTMENT_FACTOR)
+        <= actual_interval_ms
+    {
+        (1, VARDIFF_MAX_ADJUSTMENT_FACTOR)
+    } else {
+        (target_interval_ms, actual_interval_ms)
+    };
+    let scaled =
+        U512::from(*current) * U512::from(numerator) / U512::from(denominator);
+    max(
+        U256::try_from(scaled).unwrap_or(U256::MAX),
+        config.min_share_difficulty.into(),
+    )
+}
+
 /// Job dispatcher for stratum service
 pub struct StratumJobDispatcher {
     recent_problems: Mutex<Vec<(ProofOfWorkProblem, HashSet<U256>)>>,
+    workers: Mutex<HashMap<SocketAddr, WorkerState>>,
+    /// The metrics of connected worker ids with their connection counts
+    worker_metrics: Mutex<HashMap<String, (Arc<WorkerMetrics>, usize)>>,
     solution_sender: Mutex<mpsc::Sender<ProofOfWorkSolution>>,
     pow: Arc<PowComputer>,
     window_size: usize,
+    vardiff: Option<StratumVardiffConfig>,
 }
 
 impl JobDispatcher for StratumJobDispatcher {
     fn submit(&self, payload: Vec<String>) -> Result<(), StratumServiceError> {
+        self.submit_share(None, payload)
+    }
+
+    fn submit_from(
+        &self, addr: &SocketAddr, payload: Vec<String>,
+    ) -> Result<(), StratumServiceError> {
+        self.submit_share(Some(addr), payload)
+    }
+
+    fn worker_subscribed(&self, addr: &SocketAddr, worker_id: &str) {
+        let metrics = self.acquire_worker_metrics(worker_id);
+        let share_difficulty = self.vardiff.as_ref().map_or_else(
+            || {
+                self.recent_problems
+                    .lock()
+                    .last()
+                    .map_or(U256::one(), |(problem, _)| problem.difficulty)
+            },
+            |vardiff| vardiff.min_share_difficulty.into(),
+        );
+        let previous = self.workers.lock().insert(
+            *addr,
+            WorkerState {
+                stats: WorkerStats {
+                    worker_id: worker_id.to_owned(),
+                    addr: *addr,
+                    share_difficulty,
+                    accepted_shares: 0,
+                    rejected_shares: 0,
+                    stale_shares: 0,
+                    mined_blocks: 0,
+                },
+                metrics,
+                job_boundaries: VecDeque::new(),
+                shares_since_retarget: 0,
+                last_retarget: Instant::now(),
+            },
+        );
+        // The connection subscribed again, possibly with another worker id.
+        if let Some(previous) = previous {
+            self.release_worker_metrics(&previous);
+        }
+    }
+
+    fn worker_disconnected(&self, addr: &SocketAddr) {
+        let worker = self.workers.lock().remove(addr);
+        if let Some(worker) = worker {
+            debug!(
+                target: "stratum",
+                "Stratum worker {} at {} disconnected: {:?}",
+                worker.stats.worker_id, addr, worker.stats
+            );
+            self.release_worker_metrics(&worker);
+        }
+    }
+}
+
+impl StratumJobDispatcher {
+    /// New stratum job dispatcher given the miner and client
+    fn new(
+        solution_sender: mpsc::Sender<ProofOfWorkSolution>,
+        pow: Arc<PowComputer>, pow_window_size: usize,
+        vardiff: Option<StratumVardiffConfig>,
+    ) -> StratumJobDispatcher {
+        StratumJobDispatcher {
+            recent_problems: Mutex::new(vec![]),
+            workers: Mutex::new(HashMap::new()),
+            worker_metrics: Mutex::new(HashMap::new()),
+            solution_sender: Mutex::new(solution_sender),
+            pow,
+            window_size: pow_window_size,
+            vardiff,
+        }
+    }
+
+    fn acquire_worker_metrics(
+        &self, worker_id: &str,
+    ) -> Option<Arc<WorkerMetrics>> {
+        let mut worker_metrics = self.worker_metrics.lock();
+        if let Some((metrics, connections)) = worker_metrics.get_mut(worker_id)
+        {
+            *connections += 1;
+            return Some(metrics.clone());
+        }
+        if worker_metrics.len() >= MAX_TRACKED_WORKERS {
+            debug!(
+                target: "stratum",
+                "Too many stratum worker ids, not tracking metrics of {}",
+                worker_id
+            );
+            return None;
+        }
+        let metrics = Arc::new(WorkerMetrics::register(worker_id));
+        worker_metrics.insert(worker_id.to_owned(), (metrics.clone(), 1));
+        Some(metrics)
+    }
+
+    fn release_worker_metrics(&self, worker: &WorkerState) {
+        if worker.metrics.is_none() {
+            return;
+        }
+        let worker_id = &worker.stats.worker_id;
+        let mut worker_metrics = self.worker_metrics.lock();
+        if let Some((_, connections)) = worker_metrics.get_mut(worker_id) {
+            *connections -= 1;
+            if *connections == 0 {
+                worker_metrics.remove(worker_id);
+                WorkerMetrics::unregister(worker_id);
+            }
+        }
+    }
+
+    /// Validate a share submitted from `addr` against the share boundary of
+    /// the worker, and forward it to the miner if it also solves the block.
+    /// Shares from connections without a subscribed worker are validated
+    /// against the block boundary.
+    fn submit_share(
+        &self, addr: Option<&SocketAddr>, payload: Vec<String>,
+    ) -> Result<(), StratumServiceError> {
         let payload = SubmitPayload::from_args(payload)
             .map_err(|e| StratumServiceError::Dispatch(e.to_string()))?;
 
@@ -137,46 +462,79 @@ impl JobDispatcher for StratumJobDispatcher {
         let sol = ProofOfWorkSolution {
             nonce: payload.nonce,
         };
+        let mut probs = self.recent_problems.lock();
+        let mut workers = self.workers.lock();
+        let mut worker = addr.and_then(|addr| workers.get_mut(addr));
+
+        let (pow_prob, submitted_nonces) = match probs
+            .iter_mut()
+            .find(|(pow_prob, _)| pow_prob.block_hash == payload.pow_hash)
         {
-            let mut probs = self.recent_problems.lock();
-            let mut found = false;
-            for (pow_prob, solved_nonce) in probs.iter_mut() {
-                if pow_prob.block_hash == payload.pow_hash {
-                    if solved_nonce.contains(&sol.nonce) {
-                        return Err(StratumServiceError::InvalidSolution(
-                            format!(
-                                "Problem already solved with nonce = {}! worker_id = {}",
-                                sol.nonce, payload.worker_id
-                            ).into(),
-                        ));
-                    } else if validate(self.pow.clone(), pow_prob, &sol) {
-                        solved_nonce.insert(sol.nonce);
-                        info!(
-                            "Stratum worker {} mined a block!",
-                            payload.worker_id
-                        );
-                        found = true;
-                    } else {
-                        return Err(StratumServiceError::InvalidSolution(
-                            format!(
-                                "Incorrect Nonce! worker_id = {}!",
-                                payload.worker_id
-                            )
-                            .into(),
-                        ));
-                    }
+            Some(entry) => entry,
+            None => {
+                STALE_SHARES_METER.mark(1);
+                if let Some(worker) = worker.as_mut() {
+                    worker.on_stale();
                 }
+                return Err(StratumServiceError::InvalidSolution(format!(
+                    "Solution for a stale job! worker_id = {}",
+                    payload.worker_id
+                )));
             }
-            if !found {
-                return Err(StratumServiceError::InvalidSolution(
-                    format!(
-                        "Solution for a stale job! worker_id = {}",
-                        payload.worker_id
-                    )
-                    .into(),
-                ));
+        };
+
+        if submitted_nonces.contains(&sol.nonce) {
+            REJECTED_SHARES_METER.mark(1);
+            if let Some(worker) = worker.as_mut() {
+                worker.on_rejected();
+            }
+            return Err(StratumServiceError::InvalidSolution(format!(
+                "Problem already solved with nonce = {}! worker_id = {}",
+                sol.nonce, payload.worker_id
+            )));
+        }
+
+        let share_boundary =
+            worker.as_ref().map_or(pow_prob.boundary, |worker| {
+                worker.share_boundary(pow_prob)
+            });
+        let hash = self.pow.compute(
+            &sol.nonce,
+            &pow_prob.block_hash,
+            pow_prob.block_height,
+        );
+        if !ProofOfWorkProblem::validate_hash_against_boundary(
+            &hash,
+            &sol.nonce,
+            &share_boundary,
+        ) {
+            REJECTED_SHARES_METER.mark(1);
+            if let Some(worker) = worker.as_mut() {
+                worker.on_rejected();
+            }
+            return Err(StratumServiceError::InvalidSolution(format!(
+                "Incorrect Nonce! worker_id = {}!",
+                payload.worker_id
+            )));
+        }
+        submitted_nonces.insert(sol.nonce);
+
+        let mined_block = ProofOfWorkProblem::validate_hash_against_boundary(
+            &hash,
+            &sol.nonce,
+            &pow_prob.boundary,
+        );
+        ACCEPTED_SHARES_METER.mark(1);
+        if let Some(worker) = worker.as_mut() {
+            worker.on_accepted(mined_block);
+            if let Some(vardiff) = &self.vardiff {
+                worker.maybe_retarget(vardiff);
             }
+        }
 
+        if mined_block {
+            MINED_BLOCKS_METER.mark(1);
+            info!("Stratum worker {} mined a block!", payload.worker_id);
             match self.solution_sender.lock().send(sol) {
                 Ok(_) => {}
                 Err(e) => {
@@ -187,21 +545,6 @@ impl JobDispatcher for StratumJobDispatcher {
 
         Ok(())
     }
-}
-
-impl StratumJobDispatcher {
-    /// New stratum job dispatcher given the miner and client
-    fn new(
-        solution_sender: mpsc::Sender<ProofOfWorkSolution>,
-        pow: Arc<PowComputer>, pow_window_size: usize,
-    ) -> StratumJobDispatcher {
-        StratumJobDispatcher {
-            recent_problems: Mutex::new(vec![]),
-            solution_sender: Mutex::new(solution_sender),
-            pow,
-            window_size: pow_window_size,
-        }
-    }
 
     fn notify_new_problem(&self, current_problem: &ProofOfWorkProblem) {
         let mut probs = self.recent_problems.lock();
@@ -211,6 +554,66 @@ impl StratumJobDispatcher {
         probs.push((current_problem.clone(), HashSet::new()));
     }
 
+    /// Assign the share boundary of `problem` for the worker at `addr`. The
+    /// share difficulty never exceeds the block difficulty, so every block
+    /// solution is also a valid share.
+    fn assign_share_boundary(
+        &self, addr: &SocketAddr, problem: &ProofOfWorkProblem,
+    ) -> U256 {
+        let vardiff = match &self.vardiff {
+            Some(vardiff) => vardiff,
+            None => return problem.boundary,
+        };
+        let mut workers = self.workers.lock();
+        let worker = match workers.get_mut(addr) {
+            Some(worker) => worker,
+            None => return problem.boundary,
+        };
+        worker.maybe_retarget(vardiff);
+        worker.stats.share_difficulty =
+            min(worker.stats.share_difficulty, problem.difficulty);
+        let boundary = difficulty_to_boundary(&worker.stats.share_difficulty);
+
+        // A job may be pushed again as heartbeat, keep the easiest boundary
+        // so that shares in flight stay valid.
+        match worker
+            .job_boundaries
+            .iter_mut()
+            .find(|(pow_hash, _)| *pow_hash == problem.block_hash)
+        {
+            Some((_, job_boundary)) => {
+                *job_boundary = max(*job_boundary, boundary);
+                *job_boundary
+            }
+            None => {
+                if worker.job_boundaries.len() >= self.window_size {
+                    worker.job_boundaries.pop_front();
+                }
+                worker
+                    .job_boundaries
+                    .push_back((problem.block_hash, boundary));
+                boundary
+            }
+        }
+    }
+
+    fn on_new_job(&self, problem: &ProofOfWorkProblem) {
+        if self.vardiff.is_some() {
+            return;
+        }
+        for worker in self.workers.lock().values_mut() {
+            worker.stats.share_difficulty = problem.difficulty;
+        }
+    }
+
+    fn worker_stats(&self) -> Vec<WorkerStats> {
+        self.workers
+            .lock()
+            .values()
+            .map(|worker| worker.stats.clone())
+            .collect()
+    }
+
     /// Serializes payload for stratum service
     fn payload(
         &self, block_height: u64, pow_hash: H256, boundary: U256,
@@ -254,10 +657,26 @@ impl NotifyWork for Stratum {
         trace!(target: "stratum", "Notify work");
 
         self.dispatcher.notify_new_problem(&prob);
-        self.service.push_work_all(
-            self.dispatcher.payload(prob.block_height, prob.block_hash, prob.boundary)
-        ).unwrap_or_else(
-            |e| warn!(target: "stratum", "Error while pushing work: {:?}", e)
+        self.dispatcher.on_new_job(&prob);
+        let result = if self.dispatcher.vardiff.is_some() {
+            self.service.push_work_each(&|addr, _| {
+                let boundary =
+                    self.dispatcher.assign_share_boundary(addr, &prob);
+                self.dispatcher.payload(
+                    prob.block_height,
+                    prob.block_hash,
+                    boundary,
+                )
+            })
+        } else {
+            self.service.push_work_all(self.dispatcher.payload(
+                prob.block_height,
+                prob.block_hash,
+                prob.boundary,
+            ))
+        };
+        result.unwrap_or_else(
+            |e| warn!(target: "stratum", "Error while pushing work: {:?}", e),
         );
     }
 }
@@ -275,6 +694,7 @@ impl Stratum {
             solution_sender,
             pow,
             pow_window_size,
+            options.vardiff.clone(),
         ));
 
         let stratum_svc = StratumService::start(
@@ -291,4 +711,90 @@ impl Stratum {
             service: stratum_svc,
         })
     }
+
+    /// Share statistics of the subscribed workers
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        self.dispatcher.worker_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vardiff() -> StratumVardiffConfig {
+        StratumVardiffConfig {
+            min_share_difficulty: 100,
+            target_share_interval_ms: 10_000,
+            retarget_interval_ms: 60_000,
+        }
+    }
+
+    #[test]
+    fn test_worker_metrics_are_bounded() {
+        let (sender, _receiver) = mpsc::channel();
+        let dispatcher = StratumJobDispatcher::new(
+            sender,
+            Arc::new(PowComputer::new(false)),
+            2,
+            Some(vardiff()),
+        );
+        let addr = |port: u16| SocketAddr::from(([127, 0, 0, 1], port));
+
+        // Two connections of the same worker id share one entry.
+        dispatcher.worker_subscribed(&addr(1), "miner");
+        dispatcher.worker_subscribed(&addr(2), "miner");
+        assert_eq!(dispatcher.worker_metrics.lock()["miner"].1, 2);
+        dispatcher.worker_disconnected(&addr(1));
+        assert_eq!(dispatcher.worker_metrics.lock()["miner"].1, 1);
+        // Subscribing again with another worker id releases the old one.
+        dispatcher.worker_subscribed(&addr(2), "other");
+        assert!(!dispatcher.worker_metrics.lock().contains_key("miner"));
+        dispatcher.worker_disconnected(&addr(2));
+        assert!(dispatcher.worker_metrics.lock().is_empty());
+
+        for port in 0..MAX_TRACKED_WORKERS as u16 + 10 {
+            dispatcher.worker_subscribed(&addr(port), &port.to_string());
+        }
+        assert_eq!(dispatcher.worker_metrics.lock().len(), MAX_TRACKED_WORKERS);
+        assert_eq!(dispatcher.worker_stats().len(), MAX_TRACKED_WORKERS + 10);
+        for port in 0..MAX_TRACKED_WORKERS as u16 + 10 {
+            dispatcher.worker_disconnected(&addr(port));
+        }
+        assert!(dispatcher.worker_metrics.lock().is_empty());
+        assert!(dispatcher.workers.lock().is_empty());
+    }
+
+    #[test]
+    fn test_retarget_share_difficulty() {
+        let config = vardiff();
+        // One share every 5 seconds: double the difficulty.
+        assert_eq!(
+            retarget_share_difficulty(&1000.into(), 12, 60_000, &config),
+            2000.into()
+        );
+        // On target: unchanged.
+        assert_eq!(
+            retarget_share_difficulty(&1000.into(), 6, 60_000, &config),
+            1000.into()
+        );
+        // Far too fast or too slow: bounded by the adjustment factor.
+        assert_eq!(
+            retarget_share_difficulty(&1000.into(), 600, 60_000, &config),
+            4000.into()
+        );
+        assert_eq!(
+            retarget_share_difficulty(&1000.into(), 0, 60_000, &config),
+            250.into()
+        );
+        // Never below the minimum share difficulty.
+        assert_eq!(
+            retarget_share_difficulty(&200.into(), 0, 60_000, &config),
+            100.into()
+        );
+        assert_eq!(
+            retarget_share_difficulty(&U256::MAX, 600, 60_000, &config),
+            U256::MAX
+        );
+    }
 }
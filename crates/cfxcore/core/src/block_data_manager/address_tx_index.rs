@@ -0,0 +1,407 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Index of executed transactions by sender and recipient address.
+//!
+//! For every address (with space), the index keeps a counter and a list of
+//! entries numbered from zero in execution order, so that the transactions
+//! of an address can be paginated from the newest to the oldest without
+//! iterating the database.
+//!
+//! Every indexed epoch also has a record of the entries written for it. If
+//! the epoch is executed again after a pivot chain reorg, the entries of the
+//! previous execution are removed before the new ones are appended. Entries
+//! of epochs that are no longer on the pivot chain and are not executed
+//! again are filtered out by the readers, which check the epoch hash.
+//!
+//! Since the sequence numbers follow the insertion order, epochs must be
+//! indexed in ascending order. A reorg re-executes all the following epochs
+//! in order, but an offline backfill is refused for epochs below the highest
+//! indexed epoch.
+
+use super::db_manager::{DBManager, DBWriteBatch};
+use cfx_execute_helper::phantom_tx::recover_phantom;
+use cfx_types::{Address, AddressSpaceUtil, AddressWithSpace, Space, H256};
+use primitives::{Action, Block, BlockReceipts, TransactionStatus};
+use rlp::Rlp;
+use rlp_derive::{RlpDecodable, RlpEncodable};
+use std::{collections::HashMap, sync::Arc};
+
+const ADDRESS_TX_COUNT_PREFIX: u8 = 0;
+const ADDRESS_TX_ENTRY_PREFIX: u8 = 1;
+const EPOCH_RECORD_PREFIX: u8 = 2;
+const INDEXED_TIP_KEY: [u8; 1] = [3];
+
+/// A transaction sent from or to an address.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct AddressTransaction {
+    /// The epoch in which the transaction was executed.
+    pub epoch: u64,
+    /// The pivot block hash of the epoch at indexing time.
+    pub epoch_hash: H256,
+    /// The block which packed the transaction.
+    pub block_hash: H256,
+    /// The RPC index of the transaction, i.e. the index in the block for
+    /// Conflux space transactions and the index in the epoch for Ethereum
+    /// space transactions.
+    pub index: u64,
+    pub hash: H256,
+}
+
+#[derive(RlpEncodable, RlpDecodable)]
+struct IndexedEntry {
+    space: Space,
+    address: Address,
+    seq: u64,
+}
+
+#[derive(RlpEncodable, RlpDecodable)]
+struct EpochRecord {
+    epoch_hash: H256,
+    entries: Vec<IndexedEntry>,
+}
+
+fn space_byte(space: Space) -> u8 {
+    match space {
+        Space::Native => 0,
+        Space::Ethereum => 1,
+    }
+}
+
+fn count_key(address: &AddressWithSpace) -> Vec<u8> {
+    let mut key = vec![ADDRESS_TX_COUNT_PREFIX, space_byte(address.space)];
+    key.extend_from_slice(address.address.as_bytes());
+    key
+}
+
+fn entry_key(address: &AddressWithSpace, seq: u64) -> Vec<u8> {
+    let mut key = vec![ADDRESS_TX_ENTRY_PREFIX, space_byte(address.space)];
+    key.extend_from_slice(address.address.as_bytes());
+    key.extend_from_slice(&seq.to_be_bytes());
+    key
+}
+
+fn epoch_record_key(epoch: u64) -> Vec<u8> {
+    let mut key = vec![EPOCH_RECORD_PREFIX];
+    key.extend_from_slice(&epoch.to_be_bytes());
+    key
+}
+
+fn decode_u64(value: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(value.try_into().ok()?))
+}
+
+/// The result of backfilling an epoch from the database.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BackfillResult {
+    Indexed,
+    /// The epoch is not executed locally or some of its data is missing.
+    NotExecuted,
+    /// A later epoch has been indexed, and indexing this epoch would break
+    /// the order of the entries.
+    BelowIndexedTip(u64),
+}
+
+/// Collect the senders and recipients of the transactions executed in an
+/// epoch. `blocks` are the blocks of the epoch in execution order with their
+/// receipts. Skipped transactions are not indexed. Phantom transactions of
+/// cross-space calls are not indexed either, but they are counted in the RPC
+/// index of the following Ethereum space transactions.
+pub fn collect_epoch_address_txs(
+    epoch: u64, epoch_hash: &H256, blocks: &[(Arc<Block>, Arc<BlockReceipts>)],
+) -> Vec<(AddressWithSpace, AddressTransaction)> {
+    let mut entries = Vec::new();
+    let mut evm_tx_index = 0;
+
+    for (block, block_receipts) in blocks {
+        let mut cfx_tx_index = 0;
+
+        for (tx, receipt) in block
+            .transactions
+            .iter()
+            .zip(block_receipts.receipts.iter())
+        {
+            let index = match tx.space() {
+                Space::Native => {
+                    cfx_tx_index += 1;
+                    cfx_tx_index - 1
+                }
+                Space::Ethereum
+                    if receipt.outcome_status != TransactionStatus::Skipped =>
+                {
+                    evm_tx_index += 1;
+                    evm_tx_index - 1
+                }
+                _ => continue,
+            };
+
+            if !matches!(
+                receipt.outcome_status,
+                TransactionStatus::Success | TransactionStatus::Failure
+            ) {
+                continue;
+            }
+
+            evm_tx_index += recover_phantom(&receipt.logs, tx.hash()).len();
+
+            let entry = AddressTransaction {
+                epoch,
+                epoch_hash: *epoch_hash,
+                block_hash: block.hash(),
+                index: index as u64,
+                hash: tx.hash(),
+            };
+            let sender = tx.sender();
+            if let Action::Call(to) = tx.action() {
+                let recipient = to.with_space(tx.space());
+                if recipient != sender {
+                    entries.push((recipient, entry.clone()));
+                }
+            }
+            entries.push((sender, entry));
+        }
+    }
+
+    entries
+}
+
+impl DBManager {
+    /// Record the address transactions of an executed epoch in one batch.
+    /// This should be called from the execution thread only. It does nothing
+    /// if the epoch has already been indexed with the same pivot block.
+    pub fn insert_epoch_address_txs_to_index(
+        &self, epoch: u64, epoch_hash: &H256,
+        entries: Vec<(AddressWithSpace, AddressTransaction)>,
+    ) {
+        let mut batch = DBWriteBatch::default();
+        let record_key = epoch_record_key(epoch);
+        if let Some(record) = self.load_epoch_record(&record_key) {
+            if record.epoch_hash == *epoch_hash {
+                return;
+            }
+            for entry in record.entries {
+                let address = entry.address.with_space(entry.space);
+                batch.delete(entry_key(&address, entry.seq));
+            }
+        }
+
+        let mut counts = HashMap::new();
+        let mut indexed = Vec::with_capacity(entries.len());
+        for (address, entry) in entries {
+            let count = counts
+                .entry(address)
+                .or_insert_with(|| self.address_tx_count(&address));
+            let seq = *count;
+            *count += 1;
+            batch.put(entry_key(&address, seq), rlp::encode(&entry));
+            indexed.push(IndexedEntry {
+                space: address.space,
+                address: address.address,
+                seq,
+            });
+        }
+        for (address, count) in counts {
+            batch.put(count_key(&address), count.to_be_bytes().to_vec());
+        }
+
+        batch.put(
+            record_key,
+            rlp::encode(&EpochRecord {
+                epoch_hash: *epoch_hash,
+                entries: indexed,
+            }),
+        );
+        if self
+            .address_tx_indexed_tip()
+            .map_or(true, |tip| tip < epoch)
+        {
+            batch.put(INDEXED_TIP_KEY.to_vec(), epoch.to_be_bytes().to_vec());
+        }
+        self.write_address_tx_index_batch_to_db(batch);
+    }
+
+    /// Index the address transactions of an executed epoch from the blocks
+    /// and execution results in the database, e.g. to backfill the index
+    /// offline. Epochs must be backfilled in ascending order, and before any
+    /// later epoch is indexed.
+    pub fn index_epoch_address_txs_from_db(
+        &self, epoch: u64,
+    ) -> BackfillResult {
+        let block_hashes = match self.executed_epoch_set_hashes_from_db(epoch) {
+            Some(hashes) if !hashes.is_empty() => hashes,
+            _ => return BackfillResult::NotExecuted,
+        };
+        let epoch_hash = *block_hashes.last().expect("not empty");
+        if self.epoch_address_txs_indexed(epoch, &epoch_hash) {
+            return BackfillResult::Indexed;
+        }
+        if let Some(tip) = self.address_tx_indexed_tip() {
+            if tip >= epoch {
+                return BackfillResult::BelowIndexedTip(tip);
+            }
+        }
+
+        let mut blocks = Vec::with_capacity(block_hashes.len());
+        for hash in &block_hashes {
+            let block = match self.block_from_db(hash) {
+                Some(block) => block,
+                None => return BackfillResult::NotExecuted,
+            };
+            let receipts = match self.block_execution_result_from_db(hash) {
+                Some(result) if result.0 == epoch_hash => {
+                    result.1.block_receipts
+                }
+                _ => return BackfillResult::NotExecuted,
+            };
+            blocks.push((Arc::new(block), receipts));
+        }
+
+        let entries = collect_epoch_address_txs(epoch, &epoch_hash, &blocks);
+        self.insert_epoch_address_txs_to_index(epoch, &epoch_hash, entries);
+        BackfillResult::Indexed
+    }
+
+    /// The highest epoch that has been indexed.
+    pub fn address_tx_indexed_tip(&self) -> Option<u64> {
+        self.address_tx_index_from_db(&INDEXED_TIP_KEY)
+            .and_then(|v| decode_u64(&v))
+    }
+
+    /// Whether the epoch has been indexed with the given pivot block.
+    pub fn epoch_address_txs_indexed(
+        &self, epoch: u64, epoch_hash: &H256,
+    ) -> bool {
+        self.load_epoch_record(&epoch_record_key(epoch))
+            .map_or(false, |record| record.epoch_hash == *epoch_hash)
+    }
+
+    /// The number of entries ever appended for an address. Removed entries
+    /// are included.
+    pub fn address_tx_count(&self, address: &AddressWithSpace) -> u64 {
+        self.address_tx_index_from_db(&count_key(address))
+            .and_then(|v| decode_u64(&v))
+            .unwrap_or(0)
+    }
+
+    /// Get the entry of an address with the given sequence number, if it has
+    /// not been removed.
+    pub fn address_tx_from_index(
+        &self, address: &AddressWithSpace, seq: u64,
+    ) -> Option<AddressTransaction> {
+        let value = self.address_tx_index_from_db(&entry_key(address, seq))?;
+        Rlp::new(&value).as_val().ok()
+    }
+
+    fn load_epoch_record(&self, key: &[u8]) -> Option<EpochRecord> {
+        let value = self.address_tx_index_from_db(key)?;
+        Rlp::new(&value).as_val().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pow::PowComputer;
+    use tempfile::tempdir;
+
+    fn entry(epoch: u64, epoch_hash: H256, index: u64) -> AddressTransaction {
+        AddressTransaction {
+            epoch,
+            epoch_hash,
+            block_hash: epoch_hash,
+            index,
+            hash: H256::from_low_u64_be(epoch * 1000 + index),
+        }
+    }
+
+    #[test]
+    fn test_reexecuted_epoch_replaces_entries() {
+        let dir = tempdir().unwrap();
+        let db_manager = DBManager::new_from_sqlite(
+            dir.path(),
+            Arc::new(PowComputer::new(false)),
+        );
+        let alice = Address::from_low_u64_be(1).with_native_space();
+        let bob = Address::from_low_u64_be(2).with_evm_space();
+
+        let (h1, h2, h2_fork) = (
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(2),
+            H256::from_low_u64_be(3),
+        );
+        db_manager.insert_epoch_address_txs_to_index(
+            1,
+            &h1,
+            vec![(alice, entry(1, h1, 0))],
+        );
+        db_manager.insert_epoch_address_txs_to_index(
+            2,
+            &h2,
+            vec![(alice, entry(2, h2, 0)), (bob, entry(2, h2, 0))],
+        );
+        // Executing the same epoch again does not duplicate entries.
+        db_manager.insert_epoch_address_txs_to_index(
+            2,
+            &h2,
+            vec![(alice, entry(2, h2, 0)), (bob, entry(2, h2, 0))],
+        );
+        assert_eq!(db_manager.address_tx_count(&alice), 2);
+        assert_eq!(db_manager.address_tx_count(&bob), 1);
+        assert_eq!(db_manager.address_tx_indexed_tip(), Some(2));
+
+        // After a reorg, the entries of the old pivot block are removed.
+        db_manager.insert_epoch_address_txs_to_index(
+            2,
+            &h2_fork,
+            vec![(alice, entry(2, h2_fork, 1))],
+        );
+        assert!(db_manager.epoch_address_txs_indexed(2, &h2_fork));
+        assert_eq!(db_manager.address_tx_count(&alice), 3);
+        assert_eq!(
+            db_manager.address_tx_from_index(&alice, 0),
+            Some(entry(1, h1, 0))
+        );
+        assert_eq!(db_manager.address_tx_from_index(&alice, 1), None);
+        assert_eq!(
+            db_manager.address_tx_from_index(&alice, 2),
+            Some(entry(2, h2_fork, 1))
+        );
+        assert_eq!(db_manager.address_tx_from_index(&bob, 0), None);
+        assert_eq!(db_manager.address_tx_indexed_tip(), Some(2));
+    }
+
+    #[test]
+    fn test_entries_of_same_address_in_epoch() {
+        let dir = tempdir().unwrap();
+        let db_manager = DBManager::new_from_sqlite(
+            dir.path(),
+            Arc::new(PowComputer::new(false)),
+        );
+        let alice = Address::from_low_u64_be(1).with_native_space();
+        let h1 = H256::from_low_u64_be(1);
+
+        assert_eq!(db_manager.address_tx_indexed_tip(), None);
+        db_manager.insert_epoch_address_txs_to_index(
+            1,
+            &h1,
+            vec![(alice, entry(1, h1, 0)), (alice, entry(1, h1, 1))],
+        );
+        assert_eq!(db_manager.address_tx_count(&alice), 2);
+        assert_eq!(
+            db_manager.address_tx_from_index(&alice, 0),
+            Some(entry(1, h1, 0))
+        );
+        assert_eq!(
+            db_manager.address_tx_from_index(&alice, 1),
+            Some(entry(1, h1, 1))
+        );
+        assert_eq!(db_manager.address_tx_indexed_tip(), Some(1));
+
+        // Epochs without executed blocks are not backfilled.
+        assert_eq!(
+            db_manager.index_epoch_address_txs_from_db(2),
+            BackfillResult::NotExecuted
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Transposed bloom-bits index of the epoch log blooms.
+//!
+//! Epochs are grouped into sections of `BLOOM_BITS_SECTION_SIZE` epochs. For
+//! every section and each bit of the log bloom, the index stores a bit vector
+//! whose i-th bit is set if the bloom of the i-th epoch in the section has
+//! this bit set. To filter logs, we only load the bit vectors of the bits set
+//! in the filter blooms, and skip the epochs that cannot contain any matching
+//! log.
+//!
+//! The blooms of the epochs in an incomplete section are stored one by one,
+//! and are transposed once the last epoch of the section is executed. If an
+//! epoch of an indexed section is executed again after a pivot chain reorg,
+//! the changed bit vectors of the section are rewritten in one batch.
+
+use super::db_manager::{DBManager, DBWriteBatch};
+use cfx_types::Bloom;
+use log::debug;
+use std::collections::{BTreeMap, HashMap};
+
+/// Number of epochs in a section of the bloom-bits index.
+pub const BLOOM_BITS_SECTION_SIZE: u64 = 4096;
+
+const SECTION_BYTES: usize = BLOOM_BITS_SECTION_SIZE as usize / 8;
+const BLOOM_BITS: usize = 2048;
+
+const EPOCH_BLOOM_PREFIX: u8 = 0;
+const SECTION_BITS_PREFIX: u8 = 1;
+const SECTION_INDEXED_PREFIX: u8 = 2;
+
+const ENCODING_RAW: u8 = 0;
+const ENCODING_SPARSE: u8 = 1;
+
+fn epoch_bloom_key(epoch: u64) -> Vec<u8> {
+    let mut key = vec![EPOCH_BLOOM_PREFIX];
+    key.extend_from_slice(&epoch.to_be_bytes());
+    key
+}
+
+fn section_bits_key(section: u64, bit: usize) -> Vec<u8> {
+    let mut key = vec![SECTION_BITS_PREFIX];
+    key.extend_from_slice(&section.to_be_bytes());
+    key.extend_from_slice(&(bit as u16).to_be_bytes());
+    key
+}
+
+fn section_indexed_key(section: u64) -> Vec<u8> {
+    let mut key = vec![SECTION_INDEXED_PREFIX];
+    key.extend_from_slice(&section.to_be_bytes());
+    key
+}
+
+fn get_bit(bits: &[u8], i: usize) -> bool { bits[i / 8] & (1 << (i % 8)) != 0 }
+
+fn set_bit(bits: &mut [u8], i: usize, value: bool) {
+    if value {
+        bits[i / 8] |= 1 << (i % 8);
+    } else {
+        bits[i / 8] &= !(1 << (i % 8));
+    }
+}
+
+/// Encode a bit vector of a section. Most bit vectors are sparse, so they are
+/// stored as the list of the set offsets if it is shorter than the raw bits.
+fn encode_section_bits(bits: &[u8]) -> Vec<u8> {
+    let offsets: Vec<usize> = (0..BLOOM_BITS_SECTION_SIZE as usize)
+        .filter(|i| get_bit(bits, *i))
+        .collect();
+
+    if offsets.len() * 2 < bits.len() {
+        let mut encoded = Vec::with_capacity(1 + offsets.len() * 2);
+        encoded.push(ENCODING_SPARSE);
+        for offset in offsets {
+            encoded.extend_from_slice(&(offset as u16).to_be_bytes());
+        }
+        encoded
+    } else {
+        let mut encoded = Vec::with_capacity(1 + bits.len());
+        encoded.push(ENCODING_RAW);
+        encoded.extend_from_slice(bits);
+        encoded
+    }
+}
+
+fn decode_section_bits(encoded: &[u8]) -> Option<Vec<u8>> {
+    match encoded.split_first()? {
+        (&ENCODING_RAW, bits) if bits.len() == SECTION_BYTES => {
+            Some(bits.to_vec())
+        }
+        (&ENCODING_SPARSE, offsets) if offsets.len() % 2 == 0 => {
+            let mut bits = vec![0u8; SECTION_BYTES];
+            for offset in offsets.chunks(2) {
+                let offset = u16::from_be_bytes([offset[0], offset[1]]);
+                if offset as u64 >= BLOOM_BITS_SECTION_SIZE {
+                    return None;
+                }
+                set_bit(&mut bits, offset as usize, true);
+            }
+            Some(bits)
+        }
+        _ => None,
+    }
+}
+
+/// Transpose the epoch blooms of a section into one bit vector per bloom
+/// bit. Epochs without a bloom, e.g. epochs before the checkpoint which are
+/// not executed locally, are treated as if all bits are set.
+fn transpose(blooms: &[Option<Bloom>]) -> Vec<Vec<u8>> {
+    let mut section_bits = vec![vec![0u8; SECTION_BYTES]; BLOOM_BITS];
+    for (offset, bloom) in blooms.iter().enumerate() {
+        for (bit, bits) in section_bits.iter_mut().enumerate() {
+            let set = match bloom {
+                Some(bloom) => get_bit(bloom.as_bytes(), bit),
+                None => true,
+            };
+            if set {
+                set_bit(bits, offset, true);
+            }
+        }
+    }
+    section_bits
+}
+
+/// Compute the epochs of a section that may contain logs matching any of the
+/// given blooms. `load_bits` loads the bit vector of a bloom bit.
+fn match_section<F>(
+    bloom_possibilities: &[Bloom], mut load_bits: F,
+) -> Vec<u8>
+where F: FnMut(usize) -> Vec<u8> {
+    let mut loaded: HashMap<usize, Vec<u8>> = HashMap::new();
+    let mut matched = vec![0u8; SECTION_BYTES];
+
+    for bloom in bloom_possibilities {
+        let mut candidates = vec![0xffu8; SECTION_BYTES];
+        for bit in (0..BLOOM_BITS).filter(|b| get_bit(bloom.as_bytes(), *b)) {
+            let bits = loaded.entry(bit).or_insert_with(|| load_bits(bit));
+            for (c, b) in candidates.iter_mut().zip(bits.iter()) {
+                *c &= *b;
+            }
+        }
+        for (m, c) in matched.iter_mut().zip(candidates.iter()) {
+            *m |= *c;
+        }
+    }
+
+    matched
+}
+
+impl DBManager {
+    /// Record the log bloom of an executed epoch in the bloom-bits index.
+    /// This should be called from the execution thread only.
+    pub fn insert_epoch_bloom_to_index(&self, epoch: u64, bloom: &Bloom) {
+        let section = epoch / BLOOM_BITS_SECTION_SIZE;
+        let offset = (epoch % BLOOM_BITS_SECTION_SIZE) as usize;
+
+        if self.is_bloom_bits_section_indexed(section) {
+            debug!(
+                "update indexed bloom bits section {} for epoch {}",
+                section, epoch
+            );
+            let mut batch = DBWriteBatch::default();
+            for bit in 0..BLOOM_BITS {
+                let mut bits = self.load_section_bits(section, bit);
+                let set = get_bit(bloom.as_bytes(), bit);
+                if get_bit(&bits, offset) != set {
+                    set_bit(&mut bits, offset, set);
+                    batch.put(
+                        section_bits_key(section, bit),
+                        encode_section_bits(&bits),
+                    );
+                }
+            }
+            self.write_bloom_bits_batch_to_db(batch);
+            return;
+        }
+
+        self.insert_bloom_bits_to_db(
+            &epoch_bloom_key(epoch),
+            bloom.as_bytes().to_vec(),
+        );
+
+        if offset as u64 == BLOOM_BITS_SECTION_SIZE - 1 {
+            self.index_bloom_bits_section(section);
+        }
+    }
+
+    /// Transpose the epoch blooms of a complete section, and remove them.
+    fn index_bloom_bits_section(&self, section: u64) {
+        let start = section * BLOOM_BITS_SECTION_SIZE;
+        let blooms: Vec<Option<Bloom>> = (start
+            ..start + BLOOM_BITS_SECTION_SIZE)
+            .map(|epoch| {
+                self.bloom_bits_from_db(&epoch_bloom_key(epoch))
+                    .filter(|b| b.len() == Bloom::len_bytes())
+                    .map(|b| Bloom::from_slice(&b))
+            })
+            .collect();
+
+        let mut batch = DBWriteBatch::default();
+        for (bit, bits) in transpose(&blooms).iter().enumerate() {
+            batch
+                .put(section_bits_key(section, bit), encode_section_bits(bits));
+        }
+        batch.put(section_indexed_key(section), vec![]);
+        for epoch in start..start + BLOOM_BITS_SECTION_SIZE {
+            batch.delete(epoch_bloom_key(epoch));
+        }
+        self.write_bloom_bits_batch_to_db(batch);
+        debug!("bloom bits section {} indexed", section);
+    }
+
+    fn is_bloom_bits_section_indexed(&self, section: u64) -> bool {
+        self.bloom_bits_from_db(&section_indexed_key(section))
+            .is_some()
+    }
+
+    fn load_section_bits(&self, section: u64, bit: usize) -> Vec<u8> {
+        self.bloom_bits_from_db(&section_bits_key(section, bit))
+            .and_then(|encoded| decode_section_bits(&encoded))
+            // Missing or corrupted bits should not hide any epoch.
+            .unwrap_or_else(|| vec![0xffu8; SECTION_BYTES])
+    }
+
+    /// Compute the epochs in `[from_epoch, to_epoch]` that may contain logs
+    /// matching any of the given blooms. Only the sections overlapping the
+    /// range are loaded, so the range should be checked by the caller.
+    pub fn bloom_bits_candidates(
+        &self, from_epoch: u64, to_epoch: u64, bloom_possibilities: &[Bloom],
+    ) -> EpochCandidates {
+        let mut sections = BTreeMap::new();
+        for section in from_epoch / BLOOM_BITS_SECTION_SIZE
+            ..=to_epoch / BLOOM_BITS_SECTION_SIZE
+        {
+            let matched = if self.is_bloom_bits_section_indexed(section) {
+                Some(match_section(bloom_possibilities, |bit| {
+                    self.load_section_bits(section, bit)
+                }))
+            } else {
+                None
+            };
+            sections.insert(section, matched);
+        }
+
+        EpochCandidates {
+            from_epoch,
+            to_epoch,
+            sections,
+        }
+    }
+}
+
+/// Epochs of a range that may contain matching logs according to the
+/// bloom-bits index. All epochs of the sections that are not indexed yet are
+/// candidates.
+pub struct EpochCandidates {
+    from_epoch: u64,
+    to_epoch: u64,
+    sections: BTreeMap<u64, Option<Vec<u8>>>,
+}
+
+impl EpochCandidates {
+    /// All epochs in the range are candidates.
+    pub fn all(from_epoch: u64, to_epoch: u64) -> Self {
+        EpochCandidates {
+            from_epoch,
+            to_epoch,
+            sections: BTreeMap::new(),
+        }
+    }
+
+    pub fn contains(&self, epoch: u64) -> bool {
+        if epoch < self.from_epoch || epoch > self.to_epoch {
+            return false;
+        }
+        match self.sections.get(&(epoch / BLOOM_BITS_SECTION_SIZE)) {
+            Some(Some(bits)) => {
+                get_bit(bits, (epoch % BLOOM_BITS_SECTION_SIZE) as usize)
+            }
+            _ => true,
+        }
+    }
+
+    /// Iterate over the candidate epochs in reverse order.
+    pub fn into_iter_rev(self) -> impl Iterator<Item = u64> {
+        (self.from_epoch..=self.to_epoch)
+            .rev()
+            .filter(move |e| self.contains(*e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfx_types::{Address, H256};
+    use primitives::log_entry::LogEntry;
+
+    fn log_bloom(address: u64, topic: u64) -> Bloom {
+        LogEntry {
+            address: Address::from_low_u64_be(address),
+            topics: vec![H256::from_low_u64_be(topic)],
+            data: vec![],
+            space: Default::default(),
+        }
+        .bloom()
+    }
+
+    #[test]
+    fn test_encode_decode_section_bits() {
+        let mut sparse = vec![0u8; SECTION_BYTES];
+        set_bit(&mut sparse, 0, true);
+        set_bit(&mut sparse, 4095, true);
+        let encoded = encode_section_bits(&sparse);
+        assert_eq!(encoded, vec![ENCODING_SPARSE, 0, 0, 0x0f, 0xff]);
+        assert_eq!(decode_section_bits(&encoded).unwrap(), sparse);
+
+        let dense = vec![0x5au8; SECTION_BYTES];
+        let encoded = encode_section_bits(&dense);
+        assert_eq!(encoded[0], ENCODING_RAW);
+        assert_eq!(decode_section_bits(&encoded).unwrap(), dense);
+
+        assert!(decode_section_bits(&[]).is_none());
+        assert!(decode_section_bits(&[ENCODING_RAW, 0]).is_none());
+        assert!(decode_section_bits(&[ENCODING_SPARSE, 0xff, 0xff]).is_none());
+    }
+
+    #[test]
+    fn test_match_section() {
+        let mut blooms = vec![Some(Bloom::zero()); 8];
+        blooms[2] = Some(log_bloom(1, 10));
+        blooms[5] = Some(log_bloom(2, 20));
+        blooms[6] = None;
+        let section_bits = transpose(&blooms);
+        let load_bits = |bit: usize| section_bits[bit].clone();
+
+        let matched = match_section(&[log_bloom(1, 10)], load_bits);
+        let epochs: Vec<usize> =
+            (0..8).filter(|i| get_bit(&matched, *i)).collect();
+        assert_eq!(epochs, vec![2, 6]);
+
+        let matched =
+            match_section(&[log_bloom(1, 10), log_bloom(2, 20)], load_bits);
+        let epochs: Vec<usize> =
+            (0..8).filter(|i| get_bit(&matched, *i)).collect();
+        assert_eq!(epochs, vec![2, 5, 6]);
+
+        // an empty filter matches all epochs
+        let matched = match_section(&[Bloom::zero()], load_bits);
+        assert!((0..8).all(|i| get_bit(&matched, i)));
+    }
+
+    #[test]
+    fn test_epoch_candidates() {
+        let mut bits = vec![0u8; SECTION_BYTES];
+        set_bit(&mut bits, 3, true);
+        let mut sections = BTreeMap::new();
+        sections.insert(1, Some(bits));
+
+        let start = BLOOM_BITS_SECTION_SIZE;
+        let candidates = EpochCandidates {
+            from_epoch: start - 2,
+            to_epoch: start + 10,
+            sections,
+        };
+        assert_eq!(
+            candidates.into_iter_rev().collect::<Vec<_>>(),
+            vec![start + 3, start - 1, start - 2]
+        );
+    }
+}
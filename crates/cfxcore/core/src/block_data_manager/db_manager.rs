@@ -1,14 +1,16 @@
 use crate::{
     block_data_manager::{
-        db_decode_list, db_encode_list, BlamedHeaderVerifiedRoots,
-        BlockExecutionResultWithEpoch, BlockRewardResult, BlockTracesWithEpoch,
-        CheckpointHashes, DataVersionTuple, EpochExecutionContext,
-        LocalBlockInfo, PosRewardInfo,
+        db_decode_list, db_encode_list,
+        freezer::{Freezer, FrozenLocation},
+        BlamedHeaderVerifiedRoots, BlockExecutionResultWithEpoch,
+        BlockRewardResult, BlockTracesWithEpoch, CheckpointHashes,
+        DataVersionTuple, EpochExecutionContext, LocalBlockInfo, PosRewardInfo,
     },
     db::{
-        COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS, COL_BLOCK_TRACES,
-        COL_EPOCH_NUMBER, COL_HASH_BY_BLOCK_NUMBER, COL_MISC,
-        COL_REWARD_BY_POS_EPOCH, COL_TX_INDEX,
+        COL_ADDRESS_TX_INDEX, COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS,
+        COL_BLOCK_TRACES, COL_BLOOM_BITS, COL_EPOCH_NUMBER, COL_FREEZER_INDEX,
+        COL_HASH_BY_BLOCK_NUMBER, COL_MISC, COL_REWARD_BY_POS_EPOCH,
+        COL_TX_INDEX,
     },
     pow::PowComputer,
     verification::VerificationConfig,
@@ -18,7 +20,11 @@ use cfx_internal_common::{
     DatabaseDecodable, DatabaseEncodable, EpochExecutionCommitment,
 };
 use cfx_storage::{
-    storage_db::KeyValueDbTrait, KvdbRocksdb, KvdbSqlite, KvdbSqliteStatements,
+    storage_db::{
+        KeyValueDbAsAnyTrait, KeyValueDbTrait, KeyValueDbTraitSingleWriter,
+        KeyValueDbTraitTransactionalDyn, KeyValueDbTransactionTrait,
+    },
+    KvdbRocksdb, KvdbSqlite, KvdbSqliteStatements,
 };
 use cfx_types::H256;
 use db::SystemDB;
@@ -39,6 +45,7 @@ const EPOCH_SKIPPED_BLOCK_SET_SUFFIX_BYTE: u8 = 7;
 const BLOCK_REWARD_RESULT_SUFFIX_BYTE: u8 = 8;
 const BLOCK_TERMINAL_KEY: &[u8] = b"block_terminals";
 const GC_PROGRESS_KEY: &[u8] = b"gc_progress";
+const FREEZER_IN_USE_KEY: &[u8] = b"freezer_in_use";
 
 #[derive(Clone, Copy, Hash, Ord, PartialOrd, Eq, PartialEq, EnumIter)]
 enum DBTable {
@@ -50,6 +57,9 @@ enum DBTable {
     BlockTraces,
     HashByBlockNumber,
     RewardByPosEpoch,
+    BloomBits,
+    AddressTxIndex,
+    FreezerIndex,
 }
 
 fn rocks_db_col(table: DBTable) -> u32 {
@@ -62,6 +72,9 @@ fn rocks_db_col(table: DBTable) -> u32 {
         DBTable::BlockTraces => COL_BLOCK_TRACES,
         DBTable::HashByBlockNumber => COL_HASH_BY_BLOCK_NUMBER,
         DBTable::RewardByPosEpoch => COL_REWARD_BY_POS_EPOCH,
+        DBTable::BloomBits => COL_BLOOM_BITS,
+        DBTable::AddressTxIndex => COL_ADDRESS_TX_INDEX,
+        DBTable::FreezerIndex => COL_FREEZER_INDEX,
     }
 }
 
@@ -75,13 +88,48 @@ fn sqlite_db_table(table: DBTable) -> String {
         DBTable::BlockTraces => "block_traces",
         DBTable::HashByBlockNumber => "hash_by_block_number",
         DBTable::RewardByPosEpoch => "reward_by_pos_epoch",
+        DBTable::BloomBits => "bloom_bits",
+        DBTable::AddressTxIndex => "address_tx_index",
+        DBTable::FreezerIndex => "freezer_index",
     }
     .into()
 }
 
+/// A table of the DBManager, which supports both single writes and atomic
+/// batches of writes.
+trait TableDb:
+    KeyValueDbTrait<ValueType = Box<[u8]>>
+    + KeyValueDbTraitTransactionalDyn<ValueType = Box<[u8]>>
+{
+}
+
+impl<T> TableDb for T where T: KeyValueDbTrait<ValueType = Box<[u8]>>
+        + KeyValueDbTraitTransactionalDyn<ValueType = Box<[u8]>>
+{
+}
+
+/// Puts and deletes which are written to a table atomically.
+#[derive(Default)]
+pub struct DBWriteBatch {
+    ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl DBWriteBatch {
+    pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.ops.push((key, Some(value)));
+    }
+
+    pub fn delete(&mut self, key: Vec<u8>) { self.ops.push((key, None)); }
+
+    pub fn is_empty(&self) -> bool { self.ops.is_empty() }
+}
+
 pub struct DBManager {
-    table_db: HashMap<DBTable, Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>>,
+    table_db: HashMap<DBTable, Box<dyn TableDb>>,
     pow: Arc<PowComputer>,
+    /// Storage of the block bodies, execution results and traces of ancient
+    /// epochs, whose locations are indexed in `DBTable::FreezerIndex`.
+    freezer: Option<Freezer>,
 }
 
 impl DBManager {
@@ -94,11 +142,14 @@ impl DBManager {
                 Box::new(KvdbRocksdb {
                     kvdb: db.key_value().clone(),
                     col: rocks_db_col(table),
-                })
-                    as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
+                }) as Box<dyn TableDb>,
             );
         }
-        Self { table_db, pow }
+        Self {
+            table_db,
+            pow,
+            freezer: None,
+        }
     }
 }
 
@@ -125,17 +176,86 @@ impl DBManager {
                 false, /* unsafe_mode */
             )
             .expect("Open sqlite failure");
-            table_db.insert(
-                table,
-                Box::new(sqlite_db)
-                    as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
-            );
+            table_db.insert(table, Box::new(sqlite_db) as Box<dyn TableDb>);
+        }
+        Self {
+            table_db,
+            pow,
+            freezer: None,
         }
-        Self { table_db, pow }
     }
 }
 
 impl DBManager {
+    /// Open the freezer in `dir`. Afterwards, block bodies, execution results
+    /// and traces missing in the database are looked up in the freezer.
+    pub fn open_freezer(&mut self, dir: &Path) -> std::io::Result<()> {
+        self.freezer = Some(Freezer::open(dir)?);
+        Ok(())
+    }
+
+    /// Move the bodies, execution results and traces of the blocks from the
+    /// database into the freezer. The data is synced to the freezer files
+    /// once and indexed before it is removed from the database, so it is
+    /// always readable from one of them. If the freezer fails, the data is
+    /// left in the database.
+    pub fn freeze_block_data(
+        &self, block_hashes: &[H256],
+    ) -> std::io::Result<()> {
+        let freezer = match &self.freezer {
+            Some(freezer) => freezer,
+            None => return Ok(()),
+        };
+        let mut frozen = Vec::with_capacity(3 * block_hashes.len());
+        for block_hash in block_hashes {
+            let keys = [
+                (DBTable::Blocks, block_body_key(block_hash)),
+                (DBTable::Blocks, block_execution_result_key(block_hash)),
+                (DBTable::BlockTraces, block_hash.as_bytes().to_vec()),
+            ];
+            for (table, key) in keys {
+                if let Some(value) = self
+                    .table_db
+                    .get(&table)
+                    .unwrap()
+                    .get(&key)
+                    .expect("db read failure")
+                {
+                    frozen.push((table, key, value));
+                }
+            }
+        }
+        if frozen.is_empty() {
+            return Ok(());
+        }
+
+        let values: Vec<&[u8]> =
+            frozen.iter().map(|(_, _, value)| value.as_ref()).collect();
+        let locations = freezer.append(&values)?;
+        freezer.sync()?;
+        self.insert_to_db(DBTable::Misc, FREEZER_IN_USE_KEY, vec![1]);
+        for ((table, key, _), location) in frozen.iter().zip(locations) {
+            self.insert_to_db(
+                DBTable::FreezerIndex,
+                &freezer_index_key(*table, key),
+                location.encode(),
+            );
+            self.table_db
+                .get(table)
+                .unwrap()
+                .delete(key)
+                .expect("db removal failure");
+        }
+        Ok(())
+    }
+
+    /// Whether some block data has been moved into the freezer, which is
+    /// unreachable without opening the freezer.
+    pub fn has_frozen_data(&self) -> bool {
+        self.load_from_db(DBTable::Misc, FREEZER_IN_USE_KEY)
+            .is_some()
+    }
+
     pub fn insert_block_traces_to_db(
         &self, block_hash: &H256, block_traces: &BlockTracesWithEpoch,
     ) {
@@ -206,6 +326,26 @@ impl DBManager {
         )
     }
 
+    pub fn insert_bloom_bits_to_db(&self, key: &[u8], value: Vec<u8>) {
+        self.insert_to_db(DBTable::BloomBits, key, value)
+    }
+
+    pub fn bloom_bits_from_db(&self, key: &[u8]) -> Option<Box<[u8]>> {
+        self.load_from_db(DBTable::BloomBits, key)
+    }
+
+    pub fn write_bloom_bits_batch_to_db(&self, batch: DBWriteBatch) {
+        self.write_batch_to_db(DBTable::BloomBits, batch)
+    }
+
+    pub fn address_tx_index_from_db(&self, key: &[u8]) -> Option<Box<[u8]>> {
+        self.load_from_db(DBTable::AddressTxIndex, key)
+    }
+
+    pub fn write_address_tx_index_batch_to_db(&self, batch: DBWriteBatch) {
+        self.write_batch_to_db(DBTable::AddressTxIndex, batch)
+    }
+
     pub fn hash_by_block_number_from_db(
         &self, block_number: &u64,
     ) -> Option<H256> {
@@ -515,20 +655,66 @@ impl DBManager {
             .expect("db insertion failure");
     }
 
+    /// Write a batch to a table in one transaction. The table must not be
+    /// freezable, because the freezer index is not updated in the batch.
+    fn write_batch_to_db(&self, table: DBTable, batch: DBWriteBatch) {
+        debug_assert!(!is_freezable(table));
+        if batch.is_empty() {
+            return;
+        }
+        let db = self.table_db.get(&table).unwrap();
+        let mut transaction = db
+            .start_transaction_dyn(true /* immediate_write */)
+            .expect("db transaction failure");
+        for (key, value) in batch.ops {
+            match value {
+                Some(value) => transaction.put(&key, &value),
+                None => transaction.delete(&key),
+            }
+            .expect("db batch write failure");
+        }
+        transaction.commit(db.as_any()).expect("db commit failure");
+    }
+
     fn remove_from_db(&self, table: DBTable, db_key: &[u8]) {
         self.table_db
             .get(&table)
             .unwrap()
             .delete(db_key)
             .expect("db removal failure");
+        if self.freezer.is_some() && is_freezable(table) {
+            // The frozen data stays in the freezer files, but it is no longer
+            // reachable.
+            self.remove_from_db(
+                DBTable::FreezerIndex,
+                &freezer_index_key(table, db_key),
+            );
+        }
     }
 
     fn load_from_db(&self, table: DBTable, db_key: &[u8]) -> Option<Box<[u8]>> {
-        self.table_db
+        let value = self
+            .table_db
             .get(&table)
             .unwrap()
             .get(db_key)
-            .expect("db read failure")
+            .expect("db read failure");
+        match &self.freezer {
+            Some(freezer) if value.is_none() && is_freezable(table) => {
+                let location = FrozenLocation::decode(&self.load_from_db(
+                    DBTable::FreezerIndex,
+                    &freezer_index_key(table, db_key),
+                )?)
+                .expect("freezer index decode succeeds");
+                Some(
+                    freezer
+                        .read(&location)
+                        .expect("freezer read failure")
+                        .into_boxed_slice(),
+                )
+            }
+            _ => value,
+        }
     }
 
     fn insert_encodable_val<V>(
@@ -575,6 +761,17 @@ fn append_suffix(h: &H256, suffix: u8) -> Vec<u8> {
     key
 }
 
+fn is_freezable(table: DBTable) -> bool {
+    matches!(table, DBTable::Blocks | DBTable::BlockTraces)
+}
+
+fn freezer_index_key(table: DBTable, db_key: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(db_key.len() + 1);
+    key.push(rocks_db_col(table) as u8);
+    key.extend_from_slice(db_key);
+    key
+}
+
 fn local_block_info_key(block_hash: &H256) -> Vec<u8> {
     append_suffix(block_hash, LOCAL_BLOCK_INFO_SUFFIX_BYTE)
 }
@@ -0,0 +1,275 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Append-only flat file storage of the block data of ancient epochs.
+//!
+//! Frozen values are appended to a series of data files named by their
+//! sequence number. Once the head file reaches the size limit, a new one is
+//! started. The location of each value is kept in an offset index by the
+//! caller, so the files themselves are never rewritten. A crash in the middle
+//! of an append only leaves some unreferenced bytes at the end of the head
+//! file.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The size limit of a freezer data file, after which a new file is started.
+const FREEZER_FILE_SIZE_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
+
+const FREEZER_FILE_PREFIX: &str = "blocks.";
+
+/// Configuration of the ancient block data freezer.
+#[derive(Clone, Debug)]
+pub struct FreezerConfig {
+    /// The directory of the freezer data files.
+    pub dir: PathBuf,
+    /// The number of epochs before the checkpoint whose block bodies,
+    /// execution results and traces are kept in the database. Older ones are
+    /// moved into the freezer.
+    pub defer_epoch_count: usize,
+}
+
+/// The location of a frozen value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrozenLocation {
+    pub file: u32,
+    pub offset: u64,
+    pub len: u32,
+}
+
+impl FrozenLocation {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(16);
+        encoded.extend_from_slice(&self.file.to_be_bytes());
+        encoded.extend_from_slice(&self.offset.to_be_bytes());
+        encoded.extend_from_slice(&self.len.to_be_bytes());
+        encoded
+    }
+
+    pub fn decode(encoded: &[u8]) -> Option<Self> {
+        if encoded.len() != 16 {
+            return None;
+        }
+        Some(FrozenLocation {
+            file: u32::from_be_bytes(encoded[0..4].try_into().ok()?),
+            offset: u64::from_be_bytes(encoded[4..12].try_into().ok()?),
+            len: u32::from_be_bytes(encoded[12..16].try_into().ok()?),
+        })
+    }
+}
+
+struct HeadFile {
+    id: u32,
+    file: File,
+    size: u64,
+}
+
+pub struct Freezer {
+    dir: PathBuf,
+    file_size_limit: u64,
+    head: Mutex<HeadFile>,
+    readers: Mutex<HashMap<u32, File>>,
+}
+
+impl Freezer {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        Self::open_with_file_size_limit(dir, FREEZER_FILE_SIZE_LIMIT)
+    }
+
+    fn open_with_file_size_limit(
+        dir: &Path, file_size_limit: u64,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut head_id = 0;
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            if let Some(id) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(FREEZER_FILE_PREFIX))
+                .and_then(|id| id.parse::<u32>().ok())
+            {
+                head_id = head_id.max(id);
+            }
+        }
+        let file = Self::open_for_append(dir, head_id)?;
+        let size = file.metadata()?.len();
+        info!(
+            "Freezer opened at {:?}, head file {} with {} bytes",
+            dir, head_id, size
+        );
+
+        Ok(Freezer {
+            dir: dir.to_path_buf(),
+            file_size_limit,
+            head: Mutex::new(HeadFile {
+                id: head_id,
+                file,
+                size,
+            }),
+            readers: Default::default(),
+        })
+    }
+
+    fn file_path(dir: &Path, id: u32) -> PathBuf {
+        dir.join(format!("{}{:06}", FREEZER_FILE_PREFIX, id))
+    }
+
+    fn open_for_append(dir: &Path, id: u32) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::file_path(dir, id))
+    }
+
+    /// Append the values to the head file. The values are always stored in a
+    /// single file, and are durable only after `sync`.
+    pub fn append(&self, values: &[&[u8]]) -> io::Result<Vec<FrozenLocation>> {
+        let total_len: u64 = values.iter().map(|v| v.len() as u64).sum();
+        let mut head = self.head.lock();
+        if head.size > 0 && head.size + total_len > self.file_size_limit {
+            // The previous head file is not synced by `sync` any more.
+            head.file.sync_data()?;
+            let id = head.id + 1;
+            *head = HeadFile {
+                id,
+                file: Self::open_for_append(&self.dir, id)?,
+                size: 0,
+            };
+        }
+
+        let mut locations = Vec::with_capacity(values.len());
+        let mut buffer = Vec::with_capacity(total_len as usize);
+        for value in values {
+            locations.push(FrozenLocation {
+                file: head.id,
+                offset: head.size + buffer.len() as u64,
+                len: value.len() as u32,
+            });
+            buffer.extend_from_slice(value);
+        }
+        if let Err(e) = head.file.write_all(&buffer) {
+            // Some bytes might have been written, so the real file size is
+            // unknown.
+            head.size = head.file.metadata()?.len();
+            return Err(e);
+        }
+        head.size += total_len;
+        Ok(locations)
+    }
+
+    /// Sync the values appended so far to disk.
+    pub fn sync(&self) -> io::Result<()> { self.head.lock().file.sync_data() }
+
+    pub fn read(&self, location: &FrozenLocation) -> io::Result<Vec<u8>> {
+        let mut readers = self.readers.lock();
+        let file = match readers.get_mut(&location.file) {
+            Some(file) => file,
+            None => {
+                let file =
+                    File::open(Self::file_path(&self.dir, location.file))?;
+                readers.entry(location.file).or_insert(file)
+            }
+        };
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut value = vec![0u8; location.len as usize];
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block_data_manager::db_manager::DBManager, pow::PowComputer};
+    use primitives::{Block, BlockHeaderBuilder};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempdir().unwrap();
+        let freezer =
+            Freezer::open_with_file_size_limit(dir.path(), 8).unwrap();
+
+        let first = freezer.append(&[b"abc", b"de"]).unwrap();
+        assert_eq!(
+            first,
+            vec![
+                FrozenLocation {
+                    file: 0,
+                    offset: 0,
+                    len: 3
+                },
+                FrozenLocation {
+                    file: 0,
+                    offset: 3,
+                    len: 2
+                },
+            ]
+        );
+        // The values do not fit into the head file, so a new one is started.
+        let second = freezer.append(&[b"fghij"]).unwrap();
+        assert_eq!(second[0].file, 1);
+        assert_eq!(second[0].offset, 0);
+
+        assert_eq!(freezer.read(&first[0]).unwrap(), b"abc");
+        assert_eq!(freezer.read(&first[1]).unwrap(), b"de");
+        assert_eq!(freezer.read(&second[0]).unwrap(), b"fghij");
+        assert_eq!(
+            FrozenLocation::decode(&second[0].encode()),
+            Some(second[0])
+        );
+        drop(freezer);
+
+        // Reopening continues to append to the last file.
+        let freezer =
+            Freezer::open_with_file_size_limit(dir.path(), 8).unwrap();
+        let third = freezer.append(&[b"k"]).unwrap();
+        assert_eq!(
+            third[0],
+            FrozenLocation {
+                file: 1,
+                offset: 5,
+                len: 1
+            }
+        );
+        assert_eq!(freezer.read(&first[1]).unwrap(), b"de");
+        assert_eq!(freezer.read(&third[0]).unwrap(), b"k");
+    }
+
+    #[test]
+    fn test_frozen_block_body_falls_through() {
+        let dir = tempdir().unwrap();
+        let mut db_manager = DBManager::new_from_sqlite(
+            &dir.path().join("db"),
+            Arc::new(PowComputer::new(false)),
+        );
+        db_manager
+            .open_freezer(&dir.path().join("freezer"))
+            .unwrap();
+
+        let block = Block::new(BlockHeaderBuilder::new().build(), vec![]);
+        let hash = block.hash();
+        db_manager.insert_block_header_to_db(&block.block_header);
+        db_manager.insert_block_body_to_db(&block);
+        assert!(!db_manager.has_frozen_data());
+        db_manager.freeze_block_data(&[hash]).unwrap();
+        assert!(db_manager.has_frozen_data());
+
+        assert_eq!(
+            db_manager.block_body_from_db(&hash).map(|b| b.len()),
+            Some(0)
+        );
+        // The header is not frozen.
+        assert!(db_manager.block_header_from_db(&hash).is_some());
+
+        db_manager.remove_block_body_from_db(&hash);
+        assert!(db_manager.block_body_from_db(&hash).is_none());
+    }
+}
@@ -13,7 +13,7 @@ use cfx_storage::{
     state_manager::StateIndex, utils::guarded_value::*, StorageManager,
     StorageManagerTrait,
 };
-use cfx_types::{Bloom, Space, H256};
+use cfx_types::{AddressWithSpace, Bloom, Space, H256};
 use db::SystemDB;
 use malloc_size_of::{new_malloc_size_ops, MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
@@ -26,13 +26,17 @@ use primitives::{
 };
 use rlp::DecoderError;
 use std::{
+    cmp::min,
     collections::{HashMap, HashSet},
     sync::Arc,
 };
 use threadpool::ThreadPool;
+mod address_tx_index;
 pub mod block_data_types;
+mod bloom_bits;
 pub mod db_gc_manager;
 pub mod db_manager;
+mod freezer;
 pub mod tx_data_manager;
 use crate::{
     block_data_manager::{
@@ -40,7 +44,9 @@ use crate::{
     },
     consensus::pos_handler::PosVerifier,
 };
+pub use address_tx_index::{AddressTransaction, BackfillResult};
 pub use block_data_types::*;
+pub use bloom_bits::{EpochCandidates, BLOOM_BITS_SECTION_SIZE};
 use cfx_execute_helper::{
     exec_tracer::{BlockExecTraces, TransactionExecTraces},
     phantom_tx::build_bloom_and_recover_phantom,
@@ -49,6 +55,7 @@ use cfx_internal_common::{
     EpochExecutionCommitment, StateAvailabilityBoundary, StateRootWithAuxInfo,
 };
 use db_gc_manager::GCProgress;
+pub use freezer::FreezerConfig;
 use metrics::{register_meter_with_group, Meter, MeterTimer};
 use primitives::pos::PosBlockId;
 use std::{hash::Hash, path::Path, time::Duration};
@@ -60,6 +67,10 @@ lazy_static! {
 
 pub const NULLU64: u64 = !0;
 
+/// Maximum number of entries scanned for a page of the address transaction
+/// index, which bounds the work on addresses with many removed entries.
+const MAX_ADDRESS_TX_SCAN: u64 = 1000;
+
 #[derive(DeriveMallocSizeOf)]
 pub struct InvalidBlockSet {
     capacity: usize,
@@ -187,13 +198,23 @@ impl BlockDataManager {
             config.tx_cache_index_maintain_timeout,
             worker_pool,
         );
-        let db_manager = match config.db_type {
+        let mut db_manager = match config.db_type {
             DbType::Rocksdb => DBManager::new_from_rocksdb(db, pow.clone()),
             DbType::Sqlite => DBManager::new_from_sqlite(
                 Path::new("./sqlite_db"),
                 pow.clone(),
             ),
         };
+        if let Some(freezer_config) = &config.freezer {
+            db_manager
+                .open_freezer(&freezer_config.dir)
+                .expect("Failed to open the freezer");
+        } else if db_manager.has_frozen_data() {
+            panic!(
+                "Some block data has been moved into the freezer, which must \
+                 be configured to read it"
+            );
+        }
         let previous_db_progress =
             db_manager.gc_progress_from_db().unwrap_or(0);
 
@@ -851,6 +872,129 @@ impl BlockDataManager {
         }
     }
 
+    /// Record the log bloom of an executed pivot epoch in the bloom-bits
+    /// index, if enabled. If the execution result of any block is missing,
+    /// all bits are set so that log filtering never skips the epoch.
+    pub fn insert_epoch_bloom(
+        &self, epoch: u64, epoch_hash: &H256, epoch_block_hashes: &[H256],
+    ) {
+        if !self.config.persist_bloom_bits_index {
+            return;
+        }
+
+        let mut bloom = Bloom::zero();
+        for hash in epoch_block_hashes {
+            match self.block_execution_result_by_hash_with_epoch(
+                hash, epoch_hash, false, /* update_pivot_assumption */
+                false, /* update_cache */
+            ) {
+                Some(result) => bloom.accrue_bloom(&result.bloom),
+                None => {
+                    bloom = Bloom::repeat_byte(0xff);
+                    break;
+                }
+            }
+        }
+        self.db_manager.insert_epoch_bloom_to_index(epoch, &bloom);
+    }
+
+    /// Get the epochs in `[from_epoch, to_epoch]` that may contain logs
+    /// matching any of the given blooms. All epochs are candidates if the
+    /// bloom-bits index is disabled.
+    pub fn log_filter_candidate_epochs(
+        &self, from_epoch: u64, to_epoch: u64, bloom_possibilities: &[Bloom],
+    ) -> EpochCandidates {
+        if self.config.persist_bloom_bits_index {
+            self.db_manager.bloom_bits_candidates(
+                from_epoch,
+                to_epoch,
+                bloom_possibilities,
+            )
+        } else {
+            EpochCandidates::all(from_epoch, to_epoch)
+        }
+    }
+
+    /// Record the senders and recipients of the transactions in an executed
+    /// pivot epoch in the address transaction index, if enabled.
+    pub fn insert_epoch_address_txs(
+        &self, epoch: u64, epoch_hash: &H256, epoch_block_hashes: &[H256],
+    ) {
+        if !self.config.persist_address_tx_index {
+            return;
+        }
+
+        let mut blocks = Vec::with_capacity(epoch_block_hashes.len());
+        for hash in epoch_block_hashes {
+            let block = self.block_by_hash(hash, false /* update_cache */);
+            let result = self.block_execution_result_by_hash_with_epoch(
+                hash, epoch_hash, false, /* update_pivot_assumption */
+                false, /* update_cache */
+            );
+            match (block, result) {
+                (Some(block), Some(result)) => {
+                    blocks.push((block, result.block_receipts))
+                }
+                _ => {
+                    warn!(
+                        "Cannot index address transactions of epoch {}: \
+                         block {:?} or its execution result is missing",
+                        epoch, hash
+                    );
+                    return;
+                }
+            }
+        }
+
+        let entries = address_tx_index::collect_epoch_address_txs(
+            epoch, epoch_hash, &blocks,
+        );
+        self.db_manager
+            .insert_epoch_address_txs_to_index(epoch, epoch_hash, entries);
+    }
+
+    /// Get a page of the indexed transactions sent from or to an address,
+    /// from the newest to the oldest. `cursor` is the sequence number of the
+    /// entry to start from, which defaults to the newest one, and `is_valid`
+    /// decides if an entry is still on the pivot chain. At most
+    /// `MAX_ADDRESS_TX_SCAN` entries are scanned, so a page may be shorter
+    /// than `limit` if many entries are removed or invalid. Returns the
+    /// entries and the cursor of the next page, or `None` if the index is
+    /// disabled.
+    pub fn transactions_by_address<F>(
+        &self, address: &AddressWithSpace, cursor: Option<u64>, limit: usize,
+        is_valid: F,
+    ) -> Option<(Vec<AddressTransaction>, Option<u64>)>
+    where
+        F: Fn(&AddressTransaction) -> bool,
+    {
+        if !self.config.persist_address_tx_index {
+            return None;
+        }
+
+        let count = self.db_manager.address_tx_count(address);
+        let end = match cursor {
+            Some(cursor) => min(cursor.saturating_add(1), count),
+            None => count,
+        };
+
+        let mut transactions = Vec::new();
+        let mut seq = end;
+        let scan_end = end.saturating_sub(MAX_ADDRESS_TX_SCAN);
+        while seq > scan_end && transactions.len() < limit {
+            seq -= 1;
+            if let Some(entry) =
+                self.db_manager.address_tx_from_index(address, seq)
+            {
+                if is_valid(&entry) {
+                    transactions.push(entry);
+                }
+            }
+        }
+        let next_cursor = if seq > 0 { Some(seq - 1) } else { None };
+        Some((transactions, next_cursor))
+    }
+
     pub fn insert_local_block_info(&self, hash: &H256, info: LocalBlockInfo) {
         self.insert(
             *hash,
@@ -1763,11 +1907,38 @@ impl BlockDataManager {
             self.config.additional_maintained_trace_epoch_count,
             |h| self.db_manager.remove_block_trace_from_db(h),
         );
+        // Frozen data can still be garbage collected above, which only drops
+        // its freezer index entries.
+        self.gc_epoch_set_with_defer(
+            base_epoch,
+            self.config.freezer.as_ref().map(|f| f.defer_epoch_count),
+            |epoch_set| {
+                // The data stays in the database if it's not frozen, and it
+                // is not retried.
+                if let Err(e) = self.db_manager.freeze_block_data(epoch_set) {
+                    warn!("Failed to freeze block data: {}", e);
+                }
+            },
+        );
     }
 
     fn gc_epoch_with_defer<F>(
         &self, epoch_number: u64, maybe_defer_epochs: Option<usize>, gc_func: F,
     ) where F: Fn(&H256) -> () {
+        self.gc_epoch_set_with_defer(
+            epoch_number,
+            maybe_defer_epochs,
+            |epoch_set| {
+                for b in epoch_set {
+                    gc_func(b);
+                }
+            },
+        );
+    }
+
+    fn gc_epoch_set_with_defer<F>(
+        &self, epoch_number: u64, maybe_defer_epochs: Option<usize>, gc_func: F,
+    ) where F: Fn(&[H256]) -> () {
         if let Some(defer_epochs) = maybe_defer_epochs {
             if epoch_number > defer_epochs as u64 {
                 let epoch_to_remove = epoch_number - defer_epochs as u64;
@@ -1776,11 +1947,7 @@ impl BlockDataManager {
                         "GC epoch set is missing! epoch_to_remove: {}",
                         epoch_to_remove
                     ),
-                    Some(epoch_set) => {
-                        for b in epoch_set {
-                            gc_func(&b);
-                        }
-                    }
+                    Some(epoch_set) => gc_func(&epoch_set),
                 }
             }
         }
@@ -1796,6 +1963,12 @@ pub enum DbType {
 pub struct DataManagerConfiguration {
     pub persist_tx_index: bool,
     pub persist_block_number_index: bool,
+    /// Whether to maintain the bloom-bits index of epoch log blooms to
+    /// accelerate log filtering.
+    pub persist_bloom_bits_index: bool,
+    /// Whether to index executed transactions by sender and recipient
+    /// address.
+    pub persist_address_tx_index: bool,
     pub tx_cache_index_maintain_timeout: Duration,
     pub db_type: DbType,
     pub additional_maintained_block_body_epoch_count: Option<usize>,
@@ -1805,6 +1978,9 @@ pub struct DataManagerConfiguration {
     pub additional_maintained_transaction_index_epoch_count: Option<usize>,
     pub checkpoint_gc_time_in_epoch_count: usize,
     pub strict_tx_index_gc: bool,
+    /// If set, the block bodies, execution results and traces of ancient
+    /// epochs are moved out of the database into the freezer.
+    pub freezer: Option<FreezerConfig>,
 }
 
 impl MallocSizeOf for DataManagerConfiguration {
@@ -1819,6 +1995,8 @@ impl DataManagerConfiguration {
         Self {
             persist_tx_index,
             persist_block_number_index,
+            persist_bloom_bits_index: false,
+            persist_address_tx_index: false,
             tx_cache_index_maintain_timeout,
             db_type,
             additional_maintained_block_body_epoch_count: None,
@@ -1828,6 +2006,7 @@ impl DataManagerConfiguration {
             additional_maintained_transaction_index_epoch_count: None,
             checkpoint_gc_time_in_epoch_count: 1,
             strict_tx_index_gc: true,
+            freezer: None,
         }
     }
 }
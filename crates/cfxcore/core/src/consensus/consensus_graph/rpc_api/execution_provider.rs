@@ -2,13 +2,19 @@ use super::super::ConsensusGraph;
 
 use crate::{
     block_data_manager::BlockExecutionResultWithEpoch,
+    consensus::consensus_inner::consensus_executor::{
+        EpochExecutionTask, ExecutionWitness,
+    },
     errors::Result as CoreResult,
 };
 use cfx_execute_helper::estimation::{EstimateExt, EstimateRequest};
 use cfx_executor::executive::ExecutionOutcome;
-use cfx_parameters::rpc::{
-    GAS_PRICE_BLOCK_SAMPLE_SIZE, GAS_PRICE_DEFAULT_VALUE,
-    GAS_PRICE_TRANSACTION_SAMPLE_SIZE,
+use cfx_parameters::{
+    consensus::DEFERRED_STATE_EPOCH_COUNT,
+    rpc::{
+        GAS_PRICE_BLOCK_SAMPLE_SIZE, GAS_PRICE_DEFAULT_VALUE,
+        GAS_PRICE_TRANSACTION_SAMPLE_SIZE,
+    },
 };
 use cfx_rpc_eth_types::EvmOverrides;
 use cfx_types::{Space, H256, U256};
@@ -147,4 +153,89 @@ impl ConsensusGraph {
             evm_overrides,
         )
     }
+
+    /// Re-executes an executed epoch on the pivot chain to collect its
+    /// execution witness.
+    pub fn execution_witness(
+        &self, epoch_number: u64,
+    ) -> CoreResult<ExecutionWitness> {
+        self.validate_stated_epoch(&EpochNumber::Number(epoch_number))?;
+        if epoch_number == 0 {
+            bail!("Genesis epoch can not be re-executed");
+        }
+
+        let task = {
+            let inner = &mut *self.inner.write();
+            let epoch_hash =
+                inner.get_pivot_hash_from_epoch_number(epoch_number)?;
+            let arena_index = *inner
+                .hash_to_arena_indices
+                .get(&epoch_hash)
+                .ok_or("Epoch is too old to re-execute")?;
+            let reward_execution_info =
+                self.executor.get_reward_execution_info(inner, arena_index);
+            EpochExecutionTask::new(
+                arena_index,
+                inner,
+                reward_execution_info,
+                false, /* on_local_pivot */
+                false, /* force_recompute */
+            )
+        };
+        self.executor.collect_execution_witness(&task)
+    }
+
+    /// Checks the blocks and the state roots in `witness` against the pivot
+    /// chain, and re-executes the epoch with only the state entries in it to
+    /// check that the modifications are consistent with the resulting state
+    /// root. Returns the hash of the verified state root.
+    pub fn verify_execution_witness(
+        &self, witness: &ExecutionWitness,
+    ) -> CoreResult<H256> {
+        if witness.epoch_number == 0 {
+            bail!("Genesis epoch can not be re-executed");
+        }
+        let epoch_hashes = self.get_block_hashes_by_epoch(
+            EpochNumber::Number(witness.epoch_number),
+        )?;
+        if epoch_hashes != witness.epoch_hashes {
+            bail!(
+                "Blocks in the witness are not epoch {} on the pivot chain",
+                witness.epoch_number
+            );
+        }
+
+        // The state root of an epoch is committed by the pivot block
+        // `DEFERRED_STATE_EPOCH_COUNT` epochs later.
+        for (state_root, epoch_number) in [
+            (&witness.parent_state_root, witness.epoch_number - 1),
+            (&witness.state_root, witness.epoch_number),
+        ] {
+            let deferred_header = self
+                .get_hash_from_epoch_number(EpochNumber::Number(
+                    epoch_number + DEFERRED_STATE_EPOCH_COUNT,
+                ))
+                .ok()
+                .and_then(|hash| self.data_man.block_header_by_hash(&hash))
+                .ok_or_else(|| {
+                    format!(
+                        "State of epoch {} is not committed on the pivot \
+                         chain yet",
+                        epoch_number
+                    )
+                })?;
+            if *deferred_header.deferred_state_root()
+                != state_root.aux_info.state_root_hash
+            {
+                bail!(
+                    "State root of epoch {} in the witness is not on the \
+                     pivot chain",
+                    epoch_number
+                );
+            }
+        }
+
+        self.executor.check_execution_witness(witness)?;
+        Ok(witness.state_root.aux_info.state_root_hash)
+    }
 }
@@ -312,9 +312,13 @@ impl ConsensusGraph {
         Ok(epoch_batch_logs.into_iter().flatten().collect())
     }
 
+    /// Get the epochs to filter logs in reverse order. Epochs that cannot
+    /// contain logs matching `bloom_possibilities` according to the bloom-bits
+    /// index are skipped. The range limit is checked before the index is
+    /// loaded, so the work is bounded by the limit.
     pub fn get_log_filter_epoch_range(
         &self, from_epoch: EpochNumber, to_epoch: EpochNumber,
-        check_range: bool,
+        bloom_possibilities: &[Bloom], check_range: bool,
     ) -> Result<impl Iterator<Item = u64>, FilterError> {
         // lock so that we have a consistent view
         let _inner = self.inner.read_recursive();
@@ -350,7 +354,12 @@ impl ConsensusGraph {
             }
         }
 
-        return Ok((from_epoch..=to_epoch).rev());
+        let candidates = self.data_man.log_filter_candidate_epochs(
+            from_epoch,
+            to_epoch,
+            bloom_possibilities,
+        );
+        return Ok(candidates.into_iter_rev());
     }
 
     pub fn get_trace_filter_epoch_range(
@@ -392,7 +401,12 @@ impl ConsensusGraph {
 
         let mut logs = self
             // iterate over epochs in reverse order
-            .get_log_filter_epoch_range(from_epoch, to_epoch, check_range)?
+            .get_log_filter_epoch_range(
+                from_epoch,
+                to_epoch,
+                &bloom_possibilities,
+                check_range,
+            )?
             // we process epochs in each batch in parallel
             // but batches are processed one-by-one
             .chunks(self.config.get_logs_epoch_batch_size)
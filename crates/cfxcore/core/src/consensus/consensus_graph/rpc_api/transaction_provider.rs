@@ -1,4 +1,5 @@
-use cfx_types::{H256, U256};
+use crate::block_data_manager::AddressTransaction;
+use cfx_types::{AddressWithSpace, H256, U256};
 use primitives::{
     receipt::Receipt, SignedTransaction, TransactionIndex, TransactionStatus,
 };
@@ -88,4 +89,21 @@ impl ConsensusGraph {
             maybe_executed_extra_info,
         })
     }
+
+    /// Get a page of the transactions sent from or to an address from the
+    /// address transaction index, from the newest to the oldest, and the
+    /// cursor of the next page. Entries of epochs that are no longer on the
+    /// pivot chain are skipped. Returns `None` if the index is disabled.
+    pub fn get_transactions_by_address(
+        &self, address: &AddressWithSpace, cursor: Option<u64>, limit: usize,
+    ) -> Option<(Vec<AddressTransaction>, Option<u64>)> {
+        self.data_man
+            .transactions_by_address(address, cursor, limit, |entry| {
+                // Only lock for each check, not across the index reads.
+                self.inner
+                    .read_recursive()
+                    .get_pivot_hash_from_epoch_number(entry.epoch)
+                    .map_or(false, |hash| hash == entry.epoch_hash)
+            })
+    }
 }
@@ -4,6 +4,7 @@ use cfx_parameters::consensus_internal::REWARD_EPOCH_COUNT;
 use cfx_types::{H256, U256};
 use metrics::{register_meter_with_group, Meter, MeterTimer};
 use primitives::EpochId;
+use tracing::info_span;
 
 use std::{
     collections::HashSet,
@@ -155,6 +156,8 @@ impl ConsensusGraph {
     pub fn on_new_block(&self, hash: &H256) {
         let _timer =
             MeterTimer::time_func(CONSENSIS_ON_NEW_BLOCK_TIMER.as_ref());
+        let _span =
+            info_span!("consensus_on_new_block", block = ?hash).entered();
         self.statistics.inc_consensus_graph_processed_block_count();
 
         self.new_block_handler.on_new_block(
@@ -1,10 +1,17 @@
-use super::ConsensusExecutionHandler;
+use super::{
+    parallel_execution::SpeculativeDbPool, ConsensusExecutionConfiguration,
+    ConsensusExecutionHandler, RewardExecutionInfo,
+};
 use std::{collections::BTreeSet, convert::From, sync::Arc};
 
 use alloy_rpc_types_trace::geth::GethDebugTracingOptions;
 use cfx_parameters::genesis::GENESIS_ACCOUNT_ADDRESS;
+use cfx_storage::defaults::DEFAULT_EXECUTION_PREFETCH_THREADS;
+use diem_types::reward_distribution_event::RewardDistributionEventV2;
 use geth_tracer::{GethTraceWithHash, GethTracer, TxExecContext};
 use pow_types::StakingEvent;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde_derive::{Deserialize, Serialize};
 
 use cfx_statedb::{Error as DbErrorKind, Result as DbResult};
 use cfx_types::{AddressSpaceUtil, Space, SpaceMap, H256, U256};
@@ -14,28 +21,36 @@ use primitives::{
 };
 
 use crate::{
-    block_data_manager::BlockDataManager,
+    block_data_manager::{BlockRewardResult, PosRewardInfo},
     consensus::consensus_inner::consensus_executor::GOOD_TPS_METER,
+    pow::PowComputer,
 };
 use cfx_execute_helper::{
-    exec_tracer::TransactionExecTraces,
+    exec_tracer::{BlockExecTraces, TransactionExecTraces},
     observer::Observer,
     tx_outcome::{make_process_tx_outcome, ProcessTxOutcome},
 };
 use cfx_executor::{
-    executive::{ExecutiveContext, TransactOptions, TransactSettings},
+    executive::{
+        ExecutionOutcome, ExecutiveContext, TransactOptions, TransactSettings,
+    },
     internal_contract::{
         block_hash_slot, epoch_hash_slot, initialize_internal_contract_accounts,
     },
+    machine::Machine,
     state::{
-        initialize_cip107, initialize_cip137,
-        initialize_or_update_dao_voted_params, State,
+        distribute_pos_interest, initialize_cip107, initialize_cip137,
+        initialize_or_update_dao_voted_params, update_pos_status, State,
     },
 };
+use cfx_internal_common::debug::ComputeEpochDebugRecord;
 use cfx_vm_types::Env;
 
 pub enum VirtualCall<'a> {
     GethTrace(GethTask<'a>),
+    /// Re-executes an executed epoch without side effects other than the
+    /// state, e.g. to collect or verify an execution witness.
+    Replay,
 }
 
 pub struct GethTask<'a> {
@@ -44,12 +59,165 @@ pub struct GethTask<'a> {
     pub(super) answer: &'a mut Vec<GethTraceWithHash>,
 }
 
-impl ConsensusExecutionHandler {
+/// The PoS status in the environment of a block, which is decided by the PoS
+/// reference of the block executed before it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockPosEnv {
+    pub pos_view: Option<u64>,
+    pub finalized_epoch: Option<u64>,
+}
+
+/// The PoS decisions used in the execution of an epoch. The nodes unlocked
+/// and the interest distributed are committed by the PoS blocks between the
+/// PoS references of the pivot block and its parent.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochPosDecisions {
+    /// The PoS status in the environment of each block in the epoch.
+    pub block_envs: Vec<BlockPosEnv>,
+    /// The PoS nodes unlocked with their unlocked votes.
+    pub unlock_nodes: Vec<(H256, u64)>,
+    /// The PoS interest distribution with the PoS epoch deciding it.
+    pub reward_event: Option<(u64, RewardDistributionEventV2)>,
+}
+
+/// The inputs of executing an epoch other than the blocks and the state,
+/// which are loaded from the node databases or taken from a witness.
+pub struct EpochExecutionInputs {
+    pub pos_decisions: EpochPosDecisions,
+    /// The receipts of each block in the reward epoch, which is empty if
+    /// there is no reward to distribute.
+    pub reward_receipts: Vec<Arc<BlockReceipts>>,
+}
+
+/// Executes the blocks of an epoch upon a state. It doesn't access the node
+/// databases, so the other inputs are given by the caller, and the outcomes
+/// except the state are returned for the caller to persist.
+pub struct EpochExecutor {
+    pub(super) config: ConsensusExecutionConfiguration,
+    pub(super) machine: Arc<Machine>,
+    pub(super) transaction_epoch_bound: u64,
+    pub(super) pow: Arc<PowComputer>,
+    pub(super) execution_state_prefetcher: Option<ThreadPool>,
+    pub(super) parallel_execution_pool: Option<ThreadPool>,
+}
+
+impl EpochExecutor {
+    pub fn new(
+        config: ConsensusExecutionConfiguration, machine: Arc<Machine>,
+        transaction_epoch_bound: u64, pow: Arc<PowComputer>,
+    ) -> Self {
+        EpochExecutor {
+            machine,
+            transaction_epoch_bound,
+            pow,
+            execution_state_prefetcher: if DEFAULT_EXECUTION_PREFETCH_THREADS
+                > 0
+            {
+                Some(
+                    ThreadPoolBuilder::new()
+                        .num_threads(DEFAULT_EXECUTION_PREFETCH_THREADS)
+                        .build()
+                        .unwrap(),
+                )
+            } else {
+                None
+            },
+            parallel_execution_pool: if config.parallel_execution_threads > 0 {
+                Some(
+                    ThreadPoolBuilder::new()
+                        .num_threads(config.parallel_execution_threads)
+                        .build()
+                        .unwrap(),
+                )
+            } else {
+                None
+            },
+            config,
+        }
+    }
+
+    /// Executes the transactions, the rewards and the PoS decisions of an
+    /// epoch upon `state` without committing it.
+    pub(super) fn execute_epoch(
+        &self, state: &mut State, epoch_blocks: &Vec<Arc<Block>>,
+        start_block_number: u64,
+        reward_execution_info: &Option<RewardExecutionInfo>,
+        inputs: &EpochExecutionInputs, on_local_pivot: bool, replay: bool,
+        speculative_dbs: Option<SpeculativeDbPool>,
+        debug_record: Option<&mut ComputeEpochDebugRecord>,
+    ) -> DbResult<EpochProcessRecorder> {
+        let pivot_block = epoch_blocks.last().expect("Not empty");
+
+        let mut epoch_recorder = self.process_epoch_transactions(
+            state,
+            epoch_blocks,
+            start_block_number,
+            &inputs.pos_decisions.block_envs,
+            on_local_pivot,
+            replay.then_some(VirtualCall::Replay),
+            speculative_dbs,
+        )?;
+
+        let current_block_number =
+            start_block_number + epoch_recorder.receipts.len() as u64 - 1;
+
+        if let Some(reward_execution_info) = reward_execution_info {
+            let spec = self
+                .machine
+                .spec(current_block_number, pivot_block.block_header.height());
+            // Calculate the block reward for blocks inside the epoch
+            // All transaction fees are shared among blocks inside one epoch
+            epoch_recorder.block_rewards = self.process_rewards_and_fees(
+                state,
+                &reward_execution_info,
+                &inputs.reward_receipts,
+                debug_record,
+                spec,
+            )?;
+        }
+
+        epoch_recorder.pos_reward = self.process_pos_interest(
+            state,
+            pivot_block,
+            &inputs.pos_decisions,
+            current_block_number,
+        )?;
+
+        Ok(epoch_recorder)
+    }
+
+    fn process_pos_interest(
+        &self, state: &mut State, pivot_block: &Block,
+        pos_decisions: &EpochPosDecisions, current_block_number: u64,
+    ) -> DbResult<Option<(u64, PosRewardInfo)>> {
+        for (unlock_node_id, votes) in &pos_decisions.unlock_nodes {
+            debug!("unlock node: {:?} {}", unlock_node_id, votes);
+            update_pos_status(state, *unlock_node_id, *votes)?;
+        }
+        let Some((pos_epoch, reward_event)) = &pos_decisions.reward_event
+        else {
+            return Ok(None);
+        };
+        debug!("distribute_pos_interest: {:?}", reward_event);
+        let account_rewards = distribute_pos_interest(
+            state,
+            reward_event.rewards(),
+            current_block_number,
+        )?;
+        Ok(Some((
+            *pos_epoch,
+            PosRewardInfo::new(account_rewards, pivot_block.hash()),
+        )))
+    }
+
     pub(super) fn process_epoch_transactions<'a>(
         &self, state: &mut State, epoch_blocks: &Vec<Arc<Block>>,
-        start_block_number: u64, on_local_pivot: bool,
-        virtual_call: Option<VirtualCall<'a>>,
-    ) -> DbResult<Vec<Arc<BlockReceipts>>> {
+        start_block_number: u64, pos_envs: &[BlockPosEnv],
+        on_local_pivot: bool, virtual_call: Option<VirtualCall<'a>>,
+        speculative_dbs: Option<SpeculativeDbPool>,
+    ) -> DbResult<EpochProcessRecorder> {
         self.prefetch_storage_for_execution(state, epoch_blocks);
 
         let pivot_block = epoch_blocks.last().expect("Epoch not empty");
@@ -68,7 +236,9 @@ impl ConsensusExecutionHandler {
             executive_trace: self.config.executive_trace,
             dry_run,
             virtual_call,
+            speculative_dbs,
             pivot_block,
+            pos_envs,
             base_gas_price,
             burnt_gas_price,
         };
@@ -97,33 +267,8 @@ impl ConsensusExecutionHandler {
             std::mem::swap(&mut epoch_recorder.geth_traces, task.answer);
         }
 
-        if !dry_run && self.pos_verifier.pos_option().is_some() {
-            debug!(
-                "put_staking_events: {:?} height={} len={}",
-                pivot_block.hash(),
-                pivot_block.block_header.height(),
-                epoch_recorder.staking_events.len()
-            );
-            self.pos_verifier
-                .consensus_db()
-                .put_staking_events(
-                    pivot_block.block_header.height(),
-                    pivot_block.hash(),
-                    epoch_recorder.staking_events,
-                )
-                .map_err(|e| {
-                    cfx_statedb::Error::from(DbErrorKind::PosDatabaseError(
-                        format!("{:?}", e),
-                    ))
-                })?;
-        }
-
-        if !dry_run && on_local_pivot {
-            self.tx_pool.recycle_transactions(epoch_recorder.repack_tx);
-        }
-
         debug!("Finish processing tx for epoch");
-        Ok(epoch_recorder.receipts)
+        Ok(epoch_recorder)
     }
 
     fn prefetch_storage_for_execution(
@@ -169,26 +314,18 @@ impl ConsensusExecutionHandler {
             epoch_context:
                 &EpochProcessContext {
                     pivot_block,
+                    pos_envs,
                     base_gas_price,
                     burnt_gas_price,
                     ..
                 },
             block,
+            block_index,
             block_number,
             last_hash,
         } = *block_context;
 
-        let last_block_header = &self.data_man.block_header_by_hash(&last_hash);
-
-        let pos_id = last_block_header
-            .as_ref()
-            .and_then(|header| header.pos_reference().as_ref());
-        let pos_view_number =
-            pos_id.and_then(|id| self.pos_verifier.get_pos_view(id));
-        let pivot_decision_epoch = pos_id
-            .and_then(|id| self.pos_verifier.get_pivot_decision(id))
-            .and_then(|hash| self.data_man.block_header_by_hash(&hash))
-            .map(|header| header.height());
+        let pos_env = pos_envs[block_index];
 
         let epoch_height = pivot_block.block_header.height();
         let chain_id = self.machine.params().chain_id_map(epoch_height);
@@ -202,11 +339,9 @@ impl ConsensusExecutionHandler {
             last_hash,
             gas_limit: U256::from(block.block_header.gas_limit()),
             epoch_height,
-            pos_view: pos_view_number,
-            finalized_epoch: pivot_decision_epoch,
-            transaction_epoch_bound: self
-                .verification_config
-                .transaction_epoch_bound,
+            pos_view: pos_env.pos_view,
+            finalized_epoch: pos_env.finalized_epoch,
+            transaction_epoch_bound: self.transaction_epoch_bound,
             base_gas_price,
             burnt_gas_price,
             // Temporarily set `transaction_hash` to zero; it will be updated
@@ -246,20 +381,51 @@ impl ConsensusExecutionHandler {
         let mut block_recorder =
             BlockProcessRecorder::new(epoch_recorder.evm_tx_idx);
 
-        for (idx, transaction) in block.transactions.iter().enumerate() {
-            self.process_transaction(
-                idx,
-                transaction,
-                block_context,
-                state,
-                &mut env,
-                on_local_pivot,
-                &mut block_recorder,
-            )?;
+        let speculative_outcomes = match block_context
+            .epoch_context
+            .speculative_dbs
+        {
+            Some(ref dbs) if block.transactions.len() > 1 => {
+                self.execute_block_in_parallel(block_context, state, &env, dbs)?
+            }
+            _ => None,
+        };
+
+        if let Some(outcomes) = speculative_outcomes {
+            for (idx, (transaction, outcome)) in
+                block.transactions.iter().zip(outcomes).enumerate()
+            {
+                self.process_transaction_outcome(
+                    idx,
+                    transaction,
+                    outcome,
+                    block_context,
+                    &mut env,
+                    on_local_pivot,
+                    &mut block_recorder,
+                );
+            }
+        } else {
+            for (idx, transaction) in block.transactions.iter().enumerate() {
+                let outcome = self.execute_transaction(
+                    transaction,
+                    block_context,
+                    state,
+                    &mut env,
+                )?;
+                self.process_transaction_outcome(
+                    idx,
+                    transaction,
+                    outcome,
+                    block_context,
+                    &mut env,
+                    on_local_pivot,
+                    &mut block_recorder,
+                );
+            }
         }
 
         block_recorder.finish_block(
-            &self.data_man,
             epoch_recorder,
             block_context,
             secondary_reward,
@@ -268,16 +434,12 @@ impl ConsensusExecutionHandler {
         Ok(())
     }
 
-    fn process_transaction(
-        &self, idx: usize, transaction: &Arc<SignedTransaction>,
+    /// Executes a transaction and updates the state as the end of a
+    /// transaction, including burning the fee by CIP-1559.
+    pub(super) fn execute_transaction(
+        &self, transaction: &Arc<SignedTransaction>,
         block_context: &BlockProcessContext, state: &mut State, env: &mut Env,
-        on_local_pivot: bool, recorder: &mut BlockProcessRecorder,
-    ) -> DbResult<()> {
-        let rpc_index = recorder.tx_idx[transaction.space()];
-
-        let block = &block_context.block;
-        let dry_run = block_context.epoch_context.dry_run;
-
+    ) -> DbResult<ExecutionOutcome> {
         let machine = self.machine.as_ref();
 
         let spec = machine.spec(env.number, env.epoch_height);
@@ -292,7 +454,6 @@ impl ConsensusExecutionHandler {
             ExecutiveContext::new(state, env, machine, &spec)
                 .transact(transaction, options)?;
         state.update_state_post_tx_execution(!spec.cip645.fix_eip1153);
-        execution_outcome.log(transaction, &block_context.block.hash());
 
         if let Some(burnt_fee) = execution_outcome
             .try_as_executed()
@@ -301,6 +462,24 @@ impl ConsensusExecutionHandler {
             state.burn_by_cip1559(burnt_fee);
         };
 
+        Ok(execution_outcome)
+    }
+
+    fn process_transaction_outcome(
+        &self, idx: usize, transaction: &Arc<SignedTransaction>,
+        execution_outcome: ExecutionOutcome,
+        block_context: &BlockProcessContext, env: &mut Env,
+        on_local_pivot: bool, recorder: &mut BlockProcessRecorder,
+    ) {
+        let rpc_index = recorder.tx_idx[transaction.space()];
+
+        let block = &block_context.block;
+        let dry_run = block_context.epoch_context.dry_run;
+
+        let spec = self.machine.spec(env.number, env.epoch_height);
+
+        execution_outcome.log(transaction, &block_context.block.hash());
+
         let r = make_process_tx_outcome(
             execution_outcome,
             &mut env.accumulated_gas_used,
@@ -319,20 +498,20 @@ impl ConsensusExecutionHandler {
 
         if !on_local_pivot || tx_skipped || dry_run {
             // Skip transaction index persist
-            return Ok(());
+            return;
         }
 
         let hash = transaction.hash();
 
-        self.data_man.insert_transaction_index(
-            &hash,
-            &TransactionIndex {
+        recorder.tx_indexes.push((
+            hash,
+            TransactionIndex {
                 block_hash: block.hash(),
                 real_index: idx,
                 is_phantom: false,
                 rpc_index: Some(rpc_index),
             },
-        );
+        ));
 
         // persist tx index for phantom transactions.
         // note: in some cases, pivot chain reorgs will result in
@@ -344,20 +523,18 @@ impl ConsensusExecutionHandler {
         let evm_tx_index = &mut recorder.tx_idx[Space::Ethereum];
 
         for ptx in phantom_txs {
-            self.data_man.insert_transaction_index(
-                &ptx.into_eip155(evm_chain_id).hash(),
-                &TransactionIndex {
+            recorder.tx_indexes.push((
+                ptx.into_eip155(evm_chain_id).hash(),
+                TransactionIndex {
                     block_hash: block.hash(),
                     real_index: idx,
                     is_phantom: true,
                     rpc_index: Some(*evm_tx_index),
                 },
-            );
+            ));
 
             *evm_tx_index += 1;
         }
-
-        Ok(())
     }
 
     fn make_observer(
@@ -485,21 +662,157 @@ impl ConsensusExecutionHandler {
     }
 }
 
+impl ConsensusExecutionHandler {
+    /// Executes the transactions of an epoch upon `state`, and persists the
+    /// outcomes unless it's a virtual call.
+    pub(super) fn process_epoch_transactions<'a>(
+        &self, state: &mut State, epoch_blocks: &Vec<Arc<Block>>,
+        start_block_number: u64, on_local_pivot: bool,
+        virtual_call: Option<VirtualCall<'a>>,
+    ) -> DbResult<Vec<Arc<BlockReceipts>>> {
+        let dry_run = virtual_call.is_some();
+        let pos_envs = self.block_pos_envs(epoch_blocks);
+        let epoch_recorder = self.executor.process_epoch_transactions(
+            state,
+            epoch_blocks,
+            start_block_number,
+            &pos_envs,
+            on_local_pivot,
+            virtual_call,
+            self.speculative_dbs(epoch_blocks, dry_run),
+        )?;
+        if dry_run {
+            return Ok(epoch_recorder.receipts);
+        }
+        self.persist_epoch_transactions(
+            epoch_blocks,
+            on_local_pivot,
+            epoch_recorder,
+        )
+    }
+
+    pub(super) fn speculative_dbs(
+        &self, epoch_blocks: &Vec<Arc<Block>>, dry_run: bool,
+    ) -> Option<SpeculativeDbPool> {
+        let pivot_block = epoch_blocks.last().expect("Epoch not empty");
+        (self.executor.parallel_execution_pool.is_some() && !dry_run).then(
+            || {
+                self.speculative_db_pool(
+                    *pivot_block.block_header.parent_hash(),
+                )
+            },
+        )
+    }
+
+    /// Persists the outcomes of executing the transactions of an epoch, and
+    /// returns the receipts.
+    pub(super) fn persist_epoch_transactions(
+        &self, epoch_blocks: &Vec<Arc<Block>>, on_local_pivot: bool,
+        epoch_recorder: EpochProcessRecorder,
+    ) -> DbResult<Vec<Arc<BlockReceipts>>> {
+        let pivot_block = epoch_blocks.last().expect("Epoch not empty");
+
+        for (block, traces) in epoch_blocks.iter().zip(epoch_recorder.traces) {
+            self.data_man.insert_block_traces(
+                block.hash(),
+                traces,
+                pivot_block.hash(),
+                on_local_pivot,
+            );
+        }
+        for (block, block_receipts) in
+            epoch_blocks.iter().zip(&epoch_recorder.receipts)
+        {
+            self.data_man.insert_block_execution_result(
+                block.hash(),
+                pivot_block.hash(),
+                block_receipts.clone(),
+                on_local_pivot,
+            );
+        }
+        for (hash, tx_index) in &epoch_recorder.tx_indexes {
+            self.data_man.insert_transaction_index(hash, tx_index);
+        }
+
+        if self.pos_verifier.pos_option().is_some() {
+            debug!(
+                "put_staking_events: {:?} height={} len={}",
+                pivot_block.hash(),
+                pivot_block.block_header.height(),
+                epoch_recorder.staking_events.len()
+            );
+            self.pos_verifier
+                .consensus_db()
+                .put_staking_events(
+                    pivot_block.block_header.height(),
+                    pivot_block.hash(),
+                    epoch_recorder.staking_events,
+                )
+                .map_err(|e| {
+                    cfx_statedb::Error::from(DbErrorKind::PosDatabaseError(
+                        format!("{:?}", e),
+                    ))
+                })?;
+        }
+
+        if on_local_pivot {
+            self.tx_pool.recycle_transactions(epoch_recorder.repack_tx);
+        }
+
+        Ok(epoch_recorder.receipts)
+    }
+
+    /// Loads the PoS status in the environment of each block in an epoch.
+    pub(super) fn block_pos_envs(
+        &self, epoch_blocks: &Vec<Arc<Block>>,
+    ) -> Vec<BlockPosEnv> {
+        let pivot_block = epoch_blocks.last().expect("Epoch not empty");
+        let mut last_hash = *pivot_block.block_header.parent_hash();
+        epoch_blocks
+            .iter()
+            .map(|block| {
+                let last_block_header =
+                    &self.data_man.block_header_by_hash(&last_hash);
+                last_hash = block.hash();
+
+                let pos_id = last_block_header
+                    .as_ref()
+                    .and_then(|header| header.pos_reference().as_ref());
+                BlockPosEnv {
+                    pos_view: pos_id
+                        .and_then(|id| self.pos_verifier.get_pos_view(id)),
+                    finalized_epoch: pos_id
+                        .and_then(|id| self.pos_verifier.get_pivot_decision(id))
+                        .and_then(|hash| {
+                            self.data_man.block_header_by_hash(&hash)
+                        })
+                        .map(|header| header.height()),
+                }
+            })
+            .collect()
+    }
+}
+
 struct EpochProcessContext<'a> {
     on_local_pivot: bool,
     executive_trace: bool,
     virtual_call: Option<VirtualCall<'a>>,
     dry_run: bool,
+    /// The databases for executing transactions in parallel, if enabled.
+    speculative_dbs: Option<SpeculativeDbPool>,
 
     pivot_block: &'a Block,
+    /// The PoS status of each block in the epoch.
+    pos_envs: &'a [BlockPosEnv],
 
     base_gas_price: SpaceMap<U256>,
     burnt_gas_price: SpaceMap<U256>,
 }
 
-struct BlockProcessContext<'a, 'b> {
+pub(super) struct BlockProcessContext<'a, 'b> {
     epoch_context: &'b EpochProcessContext<'a>,
-    block: &'b Block,
+    pub(super) block: &'b Block,
+    block_index: usize,
     block_number: u64,
     last_hash: H256,
 }
@@ -514,6 +827,7 @@ impl<'a, 'b> BlockProcessContext<'a, 'b> {
         Self {
             epoch_context,
             block,
+            block_index: 0,
             block_number: start_block_number,
             last_hash,
         }
@@ -521,17 +835,27 @@ impl<'a, 'b> BlockProcessContext<'a, 'b> {
 
     fn next_block(&mut self, block: &'b Block) {
         self.last_hash = self.block.hash();
+        self.block_index += 1;
         self.block_number += 1;
         self.block = block;
     }
 }
 
+/// The outcomes of executing an epoch except the state, which are persisted
+/// by `ConsensusExecutionHandler` unless it's a dry run.
 #[derive(Default)]
-struct EpochProcessRecorder {
-    receipts: Vec<Arc<BlockReceipts>>,
-    staking_events: Vec<StakingEvent>,
-    repack_tx: Vec<Arc<SignedTransaction>>,
+pub(super) struct EpochProcessRecorder {
+    pub(super) receipts: Vec<Arc<BlockReceipts>>,
+    /// The traces of each block, if `executive_trace` is enabled.
+    pub(super) traces: Vec<BlockExecTraces>,
+    pub(super) tx_indexes: Vec<(H256, TransactionIndex)>,
+    pub(super) staking_events: Vec<StakingEvent>,
+    pub(super) repack_tx: Vec<Arc<SignedTransaction>>,
     geth_traces: Vec<GethTraceWithHash>,
+    /// The rewards of each block in the reward epoch.
+    pub(super) block_rewards: Vec<(H256, BlockRewardResult)>,
+    /// The PoS interest distributed with the PoS epoch deciding it.
+    pub(super) pos_reward: Option<(u64, PosRewardInfo)>,
 
     evm_tx_idx: usize,
 }
@@ -547,6 +871,7 @@ struct BlockProcessRecorder {
     geth_traces: Vec<GethTraceWithHash>,
     repack_tx: Vec<Arc<SignedTransaction>>,
     staking_events: Vec<StakingEvent>,
+    tx_indexes: Vec<(H256, TransactionIndex)>,
 
     tx_idx: SpaceMap<usize>,
 }
@@ -562,6 +887,7 @@ impl BlockProcessRecorder {
             geth_traces: vec![],
             repack_tx: vec![],
             staking_events: vec![],
+            tx_indexes: vec![],
             tx_idx,
         }
     }
@@ -610,20 +936,14 @@ impl BlockProcessRecorder {
     }
 
     fn finish_block(
-        self, data_man: &BlockDataManager,
-        epoch_recorder: &mut EpochProcessRecorder,
+        self, epoch_recorder: &mut EpochProcessRecorder,
         block_context: &BlockProcessContext, secondary_reward: U256,
     ) {
         let BlockProcessContext {
             epoch_context:
                 &EpochProcessContext {
-                    on_local_pivot,
-                    executive_trace,
-                    pivot_block,
-                    dry_run,
-                    ..
+                    executive_trace, ..
                 },
-            block,
             block_number,
             ..
         } = *block_context;
@@ -637,31 +957,15 @@ impl BlockProcessRecorder {
             tx_execution_error_messages: self.tx_error_msg,
         });
 
-        epoch_recorder.receipts.push(block_receipts.clone());
+        epoch_recorder.receipts.push(block_receipts);
+        if executive_trace {
+            epoch_recorder.traces.push(self.traces.into());
+        }
+        epoch_recorder.tx_indexes.extend(self.tx_indexes);
         epoch_recorder.staking_events.extend(self.staking_events);
         epoch_recorder.repack_tx.extend(self.repack_tx);
         epoch_recorder.geth_traces.extend(self.geth_traces);
 
         epoch_recorder.evm_tx_idx = self.tx_idx[Space::Ethereum];
-
-        if dry_run {
-            return;
-        }
-
-        if executive_trace {
-            data_man.insert_block_traces(
-                block.hash(),
-                self.traces.into(),
-                pivot_block.hash(),
-                on_local_pivot,
-            );
-        }
-
-        data_man.insert_block_execution_result(
-            block.hash(),
-            pivot_block.hash(),
-            block_receipts.clone(),
-            on_local_pivot,
-        );
     }
 }
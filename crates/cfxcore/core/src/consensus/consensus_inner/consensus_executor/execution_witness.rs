@@ -0,0 +1,447 @@
+use super::{
+    epoch_execution::{EpochExecutionInputs, EpochExecutor, EpochPosDecisions},
+    ConsensusExecutionHandler, EpochExecutionTask, RewardExecutionInfo,
+};
+use cfx_bytes::Bytes;
+use cfx_executor::state::State;
+use cfx_internal_common::StateRootWithAuxInfo;
+use cfx_statedb::StateDb;
+use cfx_storage::{
+    RecordingStorage, StateIndex, StateProofMerger, StateProofNodes,
+    StorageState, StorageStateTraitExt, WitnessStorage,
+};
+use cfx_types::{H256, U512};
+use primitives::{
+    receipt::BlockReceipts, Block, CodeInfo, SignedTransaction, SkipInputCheck,
+    StorageKey, StorageKeyWithSpace,
+};
+use rustc_hex::ToHex;
+use serde_derive::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{
+    errors::Result as CoreResult, verification::compute_transaction_root,
+};
+
+/// The data to re-execute an epoch without the node databases: the blocks,
+/// the inputs loaded from the node databases, and the state entries accessed
+/// by the execution with their proof against the state root of the parent
+/// epoch, and the proof of the entries modified against the resulting state
+/// root.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionWitness {
+    pub epoch_number: u64,
+    /// The blocks in the epoch, with the pivot block at last.
+    pub epoch_hashes: Vec<H256>,
+    /// The RLP encoded blocks with the hashes in `epoch_hashes`.
+    pub blocks: Vec<Bytes>,
+    pub start_block_number: u64,
+    pub reward: Option<RewardWitness>,
+    pub pos_decisions: EpochPosDecisions,
+    pub parent_state_root: StateRootWithAuxInfo,
+    pub state_root: StateRootWithAuxInfo,
+    /// The keys of the state entries accessed, which are proved by `proof`
+    /// against `parent_state_root`.
+    pub keys: Vec<Bytes>,
+    pub proof: StateProofNodes,
+    /// The contract codes accessed, which are also included in `proof`.
+    pub codes: Vec<Bytes>,
+    /// The proof of the entries modified against `state_root`.
+    pub post_proof: StateProofNodes,
+}
+
+/// The rewards of an earlier epoch which are distributed in the epoch, see
+/// `RewardExecutionInfo`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardWitness {
+    pub past_block_count: u64,
+    /// The RLP encoded blocks in the reward epoch, with the pivot block at
+    /// last.
+    pub blocks: Vec<Bytes>,
+    /// The RLP encoded receipts of each block in the reward epoch.
+    pub receipts: Vec<Bytes>,
+    pub no_reward: Vec<bool>,
+    pub anticone_difficulties: Vec<U512>,
+}
+
+impl RewardWitness {
+    fn new(
+        info: &RewardExecutionInfo, receipts: &[Arc<BlockReceipts>],
+    ) -> Self {
+        RewardWitness {
+            past_block_count: info.past_block_count,
+            blocks: info
+                .epoch_blocks
+                .iter()
+                .map(|block| rlp::encode(&**block))
+                .collect(),
+            receipts: receipts
+                .iter()
+                .map(|receipts| rlp::encode(&**receipts))
+                .collect(),
+            no_reward: info.epoch_block_no_reward.clone(),
+            anticone_difficulties: info
+                .epoch_block_anticone_difficulties
+                .clone(),
+        }
+    }
+
+    fn decode(
+        &self,
+    ) -> CoreResult<(RewardExecutionInfo, Vec<Arc<BlockReceipts>>)> {
+        let epoch_blocks = self
+            .blocks
+            .iter()
+            .map(|block| decode_block(block))
+            .collect::<CoreResult<Vec<_>>>()?;
+        let reward_epoch_hash =
+            epoch_blocks.last().ok_or("empty reward epoch")?.hash();
+        if self.no_reward.len() != epoch_blocks.len()
+            || self.anticone_difficulties.len() != epoch_blocks.len()
+            || self.receipts.len() != epoch_blocks.len()
+        {
+            bail!("Inconsistent reward info for epoch {:?}", reward_epoch_hash);
+        }
+
+        let mut receipts = Vec::with_capacity(epoch_blocks.len());
+        for (block, block_receipts) in epoch_blocks.iter().zip(&self.receipts) {
+            let block_receipts: BlockReceipts = rlp::decode(block_receipts)?;
+            if block_receipts.receipts.len() != block.transactions.len() {
+                bail!("Inconsistent receipts of block {:?}", block.hash());
+            }
+            receipts.push(Arc::new(block_receipts));
+        }
+
+        let info = RewardExecutionInfo {
+            past_block_count: self.past_block_count,
+            epoch_blocks,
+            epoch_block_no_reward: self.no_reward.clone(),
+            epoch_block_anticone_difficulties: self
+                .anticone_difficulties
+                .clone(),
+        };
+        Ok((info, receipts))
+    }
+}
+
+impl ExecutionWitness {
+    /// Decodes the blocks in the epoch and checks them against
+    /// `epoch_hashes`.
+    fn epoch_blocks(&self) -> CoreResult<Vec<Arc<Block>>> {
+        if self.blocks.len() != self.epoch_hashes.len() {
+            bail!("Inconsistent blocks of epoch {}", self.epoch_number);
+        }
+        let mut epoch_blocks = Vec::with_capacity(self.blocks.len());
+        for (block, hash) in self.blocks.iter().zip(&self.epoch_hashes) {
+            let block = decode_block(block)?;
+            if block.hash() != *hash {
+                bail!("Block {:?} in the witness is inconsistent", hash);
+            }
+            epoch_blocks.push(block);
+        }
+
+        let pivot_block = epoch_blocks.last().ok_or("empty epoch")?;
+        if pivot_block.block_header.height() != self.epoch_number {
+            bail!(
+                "Pivot block {:?} is not in epoch {}",
+                pivot_block.hash(),
+                self.epoch_number
+            );
+        }
+        if self.pos_decisions.block_envs.len() != epoch_blocks.len() {
+            bail!("Inconsistent PoS decisions of epoch {}", self.epoch_number);
+        }
+        Ok(epoch_blocks)
+    }
+}
+
+/// Decodes a block and recovers the senders of its transactions, which are
+/// checked against the transaction root in the header.
+fn decode_block(bytes: &[u8]) -> CoreResult<Arc<Block>> {
+    let block: Block = rlp::decode(bytes)?;
+    let mut transactions = Vec::with_capacity(block.transactions.len());
+    for tx in &block.transactions {
+        let public = tx.transaction.recover_public().map_err(|e| {
+            format!("Fail to recover the sender of tx {:?}: {:?}", tx.hash(), e)
+        })?;
+        transactions.push(Arc::new(SignedTransaction::new(
+            public,
+            tx.transaction.clone(),
+        )));
+    }
+    if compute_transaction_root(&transactions)
+        != *block.block_header.transactions_root()
+    {
+        bail!("Inconsistent transactions of block {:?}", block.hash());
+    }
+    Ok(Arc::new(Block::new(block.block_header, transactions)))
+}
+
+impl EpochExecutor {
+    /// Re-executes an epoch upon the blocks and the state entries in
+    /// `witness`, which doesn't need the node databases, and checks that
+    /// every modification is proved against the resulting state root claimed
+    /// by `witness`.
+    ///
+    /// This is a consistency check: the resulting state root is not
+    /// recomputed, so a claimed root with extra modifications is not
+    /// detected. The caller should check both state roots in `witness`
+    /// against the pivot chain, and trusts the reward info and the PoS
+    /// decisions in `witness`. The blocks are checked against
+    /// `epoch_hashes`.
+    pub fn check_execution_witness(
+        &self, witness: &ExecutionWitness,
+    ) -> CoreResult<()> {
+        for root in [&witness.parent_state_root, &witness.state_root] {
+            if root.state_root.compute_state_root_hash()
+                != root.aux_info.state_root_hash
+            {
+                bail!("Inconsistent state root {:?}", root);
+            }
+        }
+
+        let epoch_blocks = witness.epoch_blocks()?;
+        let (reward_info, reward_receipts) = match &witness.reward {
+            Some(reward) => {
+                let (info, receipts) = reward.decode()?;
+                (Some(info), receipts)
+            }
+            None => (None, vec![]),
+        };
+        let inputs = EpochExecutionInputs {
+            pos_decisions: witness.pos_decisions.clone(),
+            reward_receipts,
+        };
+
+        let storage = WitnessStorage::new(
+            &witness.parent_state_root,
+            witness.keys.clone(),
+            &witness.proof.clone().into_proof()?,
+            witness.state_root.clone(),
+            witness.post_proof.clone().into_proof()?,
+        )?;
+        let mut state = State::new(StateDb::new(Box::new(storage)))?;
+        self.execute_epoch(
+            &mut state,
+            &epoch_blocks,
+            witness.start_block_number,
+            &reward_info,
+            &inputs,
+            /* on_local_pivot = */ false,
+            /* replay = */ true,
+            /* speculative_dbs = */ None,
+            /* debug_record = */ None,
+        )?;
+        // Checks the modifications against the claimed state root.
+        state.compute_state_root(None)?;
+        Ok(())
+    }
+}
+
+impl ConsensusExecutionHandler {
+    /// Re-executes an executed epoch upon the state of its parent, and
+    /// records the state entries accessed as an execution witness.
+    ///
+    /// Fails if the execution iterates keys by prefix, since a witness can't
+    /// prove that it has all the keys with the prefix. This happens when a
+    /// contract is killed, whose storage and code are removed, and when the
+    /// sponsor whitelist of a contract is cleared.
+    pub(super) fn collect_execution_witness(
+        &self, task: &EpochExecutionTask,
+    ) -> CoreResult<ExecutionWitness> {
+        let epoch_blocks = self
+            .data_man
+            .blocks_by_hash_list(
+                &task.epoch_block_hashes,
+                false, /* update_cache */
+            )
+            .ok_or("blocks in the epoch are not found")?;
+        let pivot_header =
+            &epoch_blocks.last().expect("Not empty").block_header;
+        let epoch_number = pivot_header.height();
+        let parent_state_root =
+            self.executed_state_root(pivot_header.parent_hash())?;
+        let state_root = self.executed_state_root(&task.epoch_hash)?;
+
+        let parent_state = self.open_readonly_state(
+            pivot_header.parent_hash(),
+            epoch_number - 1,
+            &parent_state_root,
+        )?;
+        let recording = RecordingStorage::new(parent_state);
+        let record = recording.access_record();
+        let mut state = State::new(StateDb::new(Box::new(recording)))?;
+        let inputs =
+            self.epoch_execution_inputs(&epoch_blocks, &task.reward_info)?;
+        self.execute_epoch_on_state(
+            &mut state,
+            &task.epoch_hash,
+            &epoch_blocks,
+            task.start_block_number,
+            &task.reward_info,
+            &inputs,
+            /* on_local_pivot = */ false,
+            /* replay = */ true,
+            /* debug_record = */ None,
+        )?;
+        // Flush the modifications into the recording storage.
+        state.compute_state_root(None)?;
+        drop(state);
+
+        let mut record = record.lock();
+        if let Some(prefix) = record.prefixes.first() {
+            bail!(
+                "Epoch {:?} iterates the keys with prefix {}, which can not be \
+                 proved complete by an execution witness",
+                task.epoch_hash,
+                prefix.to_hex::<String>()
+            );
+        }
+        let proof = record.take_proof()?;
+
+        let mut codes = vec![];
+        for (key, value) in &record.reads {
+            let storage_key =
+                StorageKeyWithSpace::from_key_bytes::<SkipInputCheck>(key);
+            if let (StorageKey::CodeKey { .. }, Some(value)) =
+                (storage_key.key, value)
+            {
+                let code_info: CodeInfo = rlp::decode(&value[..])?;
+                codes.push((*code_info.code).clone());
+            }
+        }
+
+        // The modifications should be the same as the committed state.
+        let post_state = self.open_readonly_state(
+            &task.epoch_hash,
+            epoch_number,
+            &state_root,
+        )?;
+        let mut post_proof = StateProofMerger::default();
+        for (key, value) in &record.writes {
+            let (committed, proof) = post_state.get_with_proof(
+                StorageKeyWithSpace::from_key_bytes::<SkipInputCheck>(key),
+            )?;
+            if committed != *value {
+                bail!(
+                    "Re-execution of epoch {:?} results in a different \
+                     state, key={:?}",
+                    task.epoch_hash,
+                    key
+                );
+            }
+            post_proof.merge(proof);
+        }
+
+        Ok(ExecutionWitness {
+            epoch_number,
+            epoch_hashes: task.epoch_block_hashes.clone(),
+            blocks: epoch_blocks
+                .iter()
+                .map(|block| rlp::encode(&**block))
+                .collect(),
+            start_block_number: task.start_block_number,
+            reward: task
+                .reward_info
+                .as_ref()
+                .map(|info| RewardWitness::new(info, &inputs.reward_receipts)),
+            pos_decisions: inputs.pos_decisions,
+            parent_state_root,
+            state_root,
+            keys: record.reads.keys().cloned().collect(),
+            proof: proof.into(),
+            codes,
+            post_proof: post_proof.finish()?.into(),
+        })
+    }
+
+    /// Checks `witness` like `EpochExecutor::check_execution_witness`, after
+    /// checking the reward receipts and the PoS decisions in `witness`
+    /// against the local databases.
+    pub(super) fn check_execution_witness(
+        &self, witness: &ExecutionWitness,
+    ) -> CoreResult<()> {
+        let epoch_blocks = witness.epoch_blocks()?;
+        if self.pos_decisions(&epoch_blocks) != witness.pos_decisions {
+            bail!(
+                "PoS decisions of epoch {} are different from the local PoS \
+                 ledger",
+                witness.epoch_number
+            );
+        }
+
+        if let Some(reward) = &witness.reward {
+            let (reward_info, receipts) = reward.decode()?;
+            let reward_epoch_hash =
+                reward_info.epoch_blocks.last().expect("Not empty").hash();
+            for (block, block_receipts) in
+                reward_info.epoch_blocks.iter().zip(&receipts)
+            {
+                let local_receipts = self
+                    .data_man
+                    .block_execution_result_by_hash_with_epoch(
+                        &block.hash(),
+                        &reward_epoch_hash,
+                        false, /* update_pivot_assumption */
+                        false, /* update_cache */
+                    )
+                    .ok_or_else(|| {
+                        format!(
+                            "Receipts of block {:?} in the reward epoch are \
+                             not found",
+                            block.hash()
+                        )
+                    })?
+                    .block_receipts;
+                if local_receipts != *block_receipts {
+                    bail!(
+                        "Receipts of block {:?} in the reward epoch are \
+                         different from the local ones",
+                        block.hash()
+                    );
+                }
+            }
+        }
+
+        self.executor.check_execution_witness(witness)
+    }
+
+    fn executed_state_root(
+        &self, epoch_hash: &H256,
+    ) -> CoreResult<StateRootWithAuxInfo> {
+        Ok(self
+            .data_man
+            .get_epoch_execution_commitment_with_db(epoch_hash)
+            .ok_or_else(|| format!("Epoch {:?} is not executed", epoch_hash))?
+            .state_root_with_aux_info)
+    }
+
+    fn open_readonly_state(
+        &self, epoch_hash: &H256, epoch_number: u64,
+        state_root: &StateRootWithAuxInfo,
+    ) -> CoreResult<StorageState> {
+        // Keep the lock until we get the state, otherwise the state may
+        // expire.
+        let state_availability_boundary =
+            self.data_man.state_availability_boundary.read();
+        if !state_availability_boundary.check_read_availability(
+            epoch_number,
+            epoch_hash,
+            None,
+        ) {
+            bail!("State of epoch {} is not available", epoch_number);
+        }
+
+        Ok(self
+            .data_man
+            .storage_manager
+            .get_state_no_commit_inner(
+                StateIndex::new_for_readonly(epoch_hash, state_root),
+                /* try_open = */ true,
+                /* open_mpt_snapshot = */ false,
+            )?
+            .ok_or("state deleted")?)
+    }
+}
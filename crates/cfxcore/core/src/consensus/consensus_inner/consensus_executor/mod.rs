@@ -4,6 +4,20 @@
 
 mod epoch_execution;
 
+/// Re-execution of an epoch with only the state entries it accesses.
+mod execution_witness;
+
+/// Optimistic parallel execution of the transactions in a block.
+mod parallel_execution;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    epoch_execution::{BlockPosEnv, EpochExecutor, EpochPosDecisions},
+    execution_witness::{ExecutionWitness, RewardWitness},
+};
+
 use core::convert::TryFrom;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
@@ -19,7 +33,6 @@ use std::{
 
 use crate::hash::KECCAK_EMPTY_LIST_RLP;
 use parking_lot::{Mutex, RwLock};
-use rayon::{ThreadPool, ThreadPoolBuilder};
 use rustc_hex::ToHex;
 
 use cfx_internal_common::{
@@ -27,10 +40,7 @@ use cfx_internal_common::{
 };
 use cfx_parameters::consensus::*;
 use cfx_statedb::{Result as DbResult, StateDb};
-use cfx_storage::{
-    defaults::DEFAULT_EXECUTION_PREFETCH_THREADS, StateIndex,
-    StorageManagerTrait,
-};
+use cfx_storage::{StateIndex, StorageManagerTrait};
 use cfx_types::{
     address_util::AddressUtil, AddressSpaceUtil, AllChainID, BigEndianHash,
     Space, H160, H256, KECCAK_EMPTY_BLOOM, U256, U512,
@@ -42,7 +52,7 @@ use primitives::{
 };
 
 use crate::{
-    block_data_manager::{BlockDataManager, BlockRewardResult, PosRewardInfo},
+    block_data_manager::{BlockDataManager, BlockRewardResult},
     consensus::{
         consensus_inner::{
             consensus_new_block_handler::ConsensusNewBlockHandler,
@@ -64,17 +74,16 @@ use cfx_execute_helper::estimation::{
 use cfx_executor::{
     executive::{ExecutionOutcome, ExecutiveContext},
     machine::Machine,
-    state::{
-        distribute_pos_interest, update_pos_status, State, StateCommitResult,
-    },
+    state::{State, StateCommitResult},
 };
 use cfx_vm_types::{Env, Spec};
 use geth_tracer::GethTraceWithHash;
+use tracing::{info_span, Span};
 
 use alloy_rpc_types_trace::geth::GethDebugTracingOptions;
 use cfx_rpc_eth_types::EvmOverrides;
 
-use self::epoch_execution::{GethTask, VirtualCall};
+use self::epoch_execution::{EpochExecutionInputs, GethTask, VirtualCall};
 
 lazy_static! {
     static ref CONSENSIS_EXECUTION_TIMER: Arc<dyn Meter> =
@@ -125,6 +134,18 @@ enum ExecutionTask {
     Stop,
 }
 
+/// The span of executing the epoch `epoch_hash` of `blocks` blocks.
+pub fn epoch_execution_span(
+    epoch_hash: &H256, blocks: usize, start_block_number: u64,
+) -> Span {
+    info_span!(
+        "epoch_execution",
+        epoch = ?epoch_hash,
+        blocks,
+        start_block_number,
+    )
+}
+
 /// The EpochExecutionTask struct includes all the information needed to execute
 /// an epoch
 #[derive(Debug)]
@@ -656,6 +677,22 @@ impl ConsensusExecutor {
         )
     }
 
+    /// Re-executes an executed epoch to collect its execution witness.
+    pub fn collect_execution_witness(
+        &self, task: &EpochExecutionTask,
+    ) -> CoreResult<ExecutionWitness> {
+        self.handler.collect_execution_witness(task)
+    }
+
+    /// Re-executes an epoch with the state entries in `witness`, and checks
+    /// that the modifications are consistent with the resulting state root
+    /// claimed by `witness`.
+    pub fn check_execution_witness(
+        &self, witness: &ExecutionWitness,
+    ) -> CoreResult<()> {
+        self.handler.check_execution_witness(witness)
+    }
+
     pub fn stop(&self) {
         // `stopped` is used to allow the execution thread to stopped even the
         // queue is not empty and `ExecutionTask::Stop` has not been
@@ -829,11 +866,10 @@ impl ConsensusExecutor {
 pub struct ConsensusExecutionHandler {
     tx_pool: SharedTransactionPool,
     data_man: Arc<BlockDataManager>,
-    config: ConsensusExecutionConfiguration,
     verification_config: VerificationConfig,
     machine: Arc<Machine>,
     pos_verifier: Arc<PosVerifier>,
-    execution_state_prefetcher: Option<ThreadPool>,
+    executor: EpochExecutor,
 }
 
 impl ConsensusExecutionHandler {
@@ -843,25 +879,19 @@ impl ConsensusExecutionHandler {
         verification_config: VerificationConfig, machine: Arc<Machine>,
         pos_verifier: Arc<PosVerifier>,
     ) -> Self {
+        let executor = EpochExecutor::new(
+            config,
+            machine.clone(),
+            verification_config.transaction_epoch_bound,
+            data_man.pow.clone(),
+        );
         ConsensusExecutionHandler {
             tx_pool,
             data_man,
-            config,
             verification_config,
             machine,
             pos_verifier,
-            execution_state_prefetcher: if DEFAULT_EXECUTION_PREFETCH_THREADS
-                > 0
-            {
-                Some(
-                    ThreadPoolBuilder::new()
-                        .num_threads(DEFAULT_EXECUTION_PREFETCH_THREADS)
-                        .build()
-                        .unwrap(),
-                )
-            } else {
-                None
-            },
+            executor,
         }
     }
 
@@ -884,6 +914,12 @@ impl ConsensusExecutionHandler {
         recover_mpt_during_construct_pivot_state: bool,
     ) {
         let _timer = MeterTimer::time_func(CONSENSIS_EXECUTION_TIMER.as_ref());
+        let _span = epoch_execution_span(
+            &task.epoch_hash,
+            task.epoch_block_hashes.len(),
+            task.start_block_number,
+        )
+        .entered();
         self.compute_epoch(
             &task.epoch_hash,
             &task.epoch_block_hashes,
@@ -967,7 +1003,7 @@ impl ConsensusExecutionHandler {
             &epoch_hash,
             &epoch_block_hashes,
             on_local_pivot,
-            self.config.executive_trace,
+            self.executor.config.executive_trace,
             reward_execution_info,
             self.pos_verifier.as_ref(),
             evm_chain_id,
@@ -1030,6 +1066,18 @@ impl ConsensusExecutionHandler {
                 &pivot_block_header,
                 on_local_pivot,
             );
+            if on_local_pivot {
+                self.data_man.insert_epoch_bloom(
+                    pivot_block_header.height(),
+                    epoch_hash,
+                    epoch_block_hashes,
+                );
+                self.data_man.insert_epoch_address_txs(
+                    pivot_block_header.height(),
+                    epoch_hash,
+                    epoch_block_hashes,
+                );
+            }
             return;
         }
 
@@ -1054,49 +1102,40 @@ impl ConsensusExecutionHandler {
             .expect("Cannot init state");
 
         let epoch_receipts = self
-            .process_epoch_transactions(
-                &mut state,
-                &epoch_blocks,
-                start_block_number,
-                on_local_pivot,
-                /* virtual_call */ None,
-            )
+            .epoch_execution_inputs(&epoch_blocks, reward_execution_info)
+            .and_then(|inputs| {
+                self.execute_epoch_on_state(
+                    &mut state,
+                    epoch_hash,
+                    &epoch_blocks,
+                    start_block_number,
+                    reward_execution_info,
+                    &inputs,
+                    on_local_pivot,
+                    /* replay = */ false,
+                    debug_record.as_deref_mut(),
+                )
+            })
             // TODO: maybe propagate the error all the way up so that the
             // program may restart by itself.
             .expect("Can not handle db error in consensus, crashing.");
 
-        let current_block_number =
-            start_block_number + epoch_receipts.len() as u64 - 1;
-
-        if let Some(reward_execution_info) = reward_execution_info {
-            let spec = self
-                .machine
-                .spec(current_block_number, pivot_block.block_header.height());
-            // Calculate the block reward for blocks inside the epoch
-            // All transaction fees are shared among blocks inside one epoch
-            self.process_rewards_and_fees(
-                &mut state,
-                &reward_execution_info,
-                epoch_hash,
-                on_local_pivot,
-                debug_record.as_deref_mut(),
-                spec,
-            );
-        }
-
-        self.process_pos_interest(
-            &mut state,
-            pivot_block,
-            current_block_number,
-        )
-        .expect("db error");
-
         let commit_result = state
             .commit(*epoch_hash, debug_record.as_deref_mut())
             .expect(&concat!(file!(), ":", line!(), ":", column!()));
 
         if on_local_pivot {
             self.notify_txpool(&commit_result, epoch_hash);
+            self.data_man.insert_epoch_bloom(
+                pivot_block.block_header.height(),
+                epoch_hash,
+                epoch_block_hashes,
+            );
+            self.data_man.insert_epoch_address_txs(
+                pivot_block.block_header.height(),
+                epoch_hash,
+                epoch_block_hashes,
+            );
         };
 
         self.data_man.insert_epoch_execution_commitment(
@@ -1120,6 +1159,76 @@ impl ConsensusExecutionHandler {
             .adjust_upper_bound(&pivot_block.block_header);
     }
 
+    /// Loads the inputs of executing an epoch from the node databases.
+    fn epoch_execution_inputs(
+        &self, epoch_blocks: &Vec<Arc<Block>>,
+        reward_execution_info: &Option<RewardExecutionInfo>,
+    ) -> DbResult<EpochExecutionInputs> {
+        Ok(EpochExecutionInputs {
+            pos_decisions: self.pos_decisions(epoch_blocks),
+            reward_receipts: match reward_execution_info {
+                Some(reward_info) => self.reward_epoch_receipts(reward_info)?,
+                None => vec![],
+            },
+        })
+    }
+
+    /// Executes the transactions, the rewards and the PoS interest of an
+    /// epoch upon `state` without committing it. In a replay, nothing except
+    /// `state` is modified, e.g. the receipts and the PoS rewards are not
+    /// persisted.
+    fn execute_epoch_on_state(
+        &self, state: &mut State, epoch_hash: &H256,
+        epoch_blocks: &Vec<Arc<Block>>, start_block_number: u64,
+        reward_execution_info: &Option<RewardExecutionInfo>,
+        inputs: &EpochExecutionInputs, on_local_pivot: bool, replay: bool,
+        debug_record: Option<&mut ComputeEpochDebugRecord>,
+    ) -> DbResult<Vec<Arc<BlockReceipts>>> {
+        let mut epoch_recorder = self.executor.execute_epoch(
+            state,
+            epoch_blocks,
+            start_block_number,
+            reward_execution_info,
+            inputs,
+            on_local_pivot,
+            replay,
+            self.speculative_dbs(epoch_blocks, replay),
+            debug_record,
+        )?;
+        if replay {
+            return Ok(epoch_recorder.receipts);
+        }
+
+        if let Some(reward_info) =
+            reward_execution_info.as_ref().filter(|_| on_local_pivot)
+        {
+            let reward_epoch_hash =
+                reward_info.epoch_blocks.last().expect("Not empty").hash();
+            for (block_hash, block_reward) in
+                epoch_recorder.block_rewards.drain(..)
+            {
+                self.data_man.insert_block_reward_result(
+                    block_hash,
+                    epoch_hash,
+                    block_reward,
+                    true,
+                );
+                self.data_man
+                    .receipts_retain_epoch(&block_hash, &reward_epoch_hash);
+            }
+        }
+        if let Some((pos_epoch, pos_reward)) = epoch_recorder.pos_reward.take()
+        {
+            self.data_man.insert_pos_reward(pos_epoch, &pos_reward);
+        }
+
+        self.persist_epoch_transactions(
+            epoch_blocks,
+            on_local_pivot,
+            epoch_recorder,
+        )
+    }
+
     fn update_on_skipped_execution(
         &self, epoch_hash: &H256, pivot_block_header: &BlockHeader,
         on_local_pivot: bool,
@@ -1155,10 +1264,16 @@ impl ConsensusExecutionHandler {
         debug!("Skip execution in prefix {:?}", epoch_hash);
     }
 
-    fn process_pos_interest(
-        &self, state: &mut State, pivot_block: &Block,
-        current_block_number: u64,
-    ) -> DbResult<()> {
+    /// Loads the PoS decisions used in the execution of an epoch.
+    fn pos_decisions(
+        &self, epoch_blocks: &Vec<Arc<Block>>,
+    ) -> EpochPosDecisions {
+        let pivot_block = epoch_blocks.last().expect("Not empty");
+        let mut pos_decisions = EpochPosDecisions {
+            block_envs: self.block_pos_envs(epoch_blocks),
+            ..Default::default()
+        };
+
         // TODO(peilun): Specify if we unlock before or after executing the
         // transactions.
         let maybe_parent_pos_ref = self
@@ -1180,33 +1295,15 @@ impl ConsensusExecutionHandler {
             // The pos_reference is continuous, so after seeing a new
             // pos_reference, we only need to process the new
             // unlock_txs in it.
-            for (unlock_node_id, votes) in self
+            pos_decisions.unlock_nodes = self
                 .pos_verifier
-                .get_unlock_nodes(current_pos_ref, parent_pos_ref)
-            {
-                debug!("unlock node: {:?} {}", unlock_node_id, votes);
-                update_pos_status(state, unlock_node_id, votes)?;
-            }
-            if let Some((pos_epoch, reward_event)) = self
+                .get_unlock_nodes(current_pos_ref, parent_pos_ref);
+            pos_decisions.reward_event = self
                 .pos_verifier
                 .get_reward_distribution_event(current_pos_ref, parent_pos_ref)
-                .as_ref()
-                .and_then(|x| x.first())
-            {
-                debug!("distribute_pos_interest: {:?}", reward_event);
-                let account_rewards: Vec<(H160, H256, U256)> =
-                    distribute_pos_interest(
-                        state,
-                        reward_event.rewards(),
-                        current_block_number,
-                    )?;
-                self.data_man.insert_pos_reward(
-                    *pos_epoch,
-                    &PosRewardInfo::new(account_rewards, pivot_block.hash()),
-                )
-            }
+                .and_then(|events| events.into_iter().next());
         }
-        Ok(())
+        pos_decisions
     }
 
     fn notify_txpool(
@@ -1235,7 +1332,9 @@ impl ConsensusExecutionHandler {
             ))
             .expect(&concat!(file!(), ":", line!(), ":", column!()));
     }
+}
 
+impl EpochExecutor {
     fn compute_block_base_reward(
         &self, past_block_count: u64, pivot_height: u64,
     ) -> U512 {
@@ -1245,12 +1344,13 @@ impl ConsensusExecutionHandler {
     }
 
     /// `epoch_block_states` includes if a block is partial invalid and its
-    /// anticone difficulty
+    /// anticone difficulty. `reward_receipts` are the receipts of the blocks
+    /// in the reward epoch. Returns the reward of each block.
     fn process_rewards_and_fees(
         &self, state: &mut State, reward_info: &RewardExecutionInfo,
-        epoch_later: &H256, on_local_pivot: bool,
+        reward_receipts: &[Arc<BlockReceipts>],
         mut debug_record: Option<&mut ComputeEpochDebugRecord>, spec: Spec,
-    ) {
+    ) -> DbResult<Vec<(H256, BlockRewardResult)>> {
         /// (Fee, SetOfPackingBlockHash)
         struct TxExecutionInfo(U256, BTreeSet<H256>);
 
@@ -1288,7 +1388,7 @@ impl ConsensusExecutionHandler {
             } else {
                 let pow_quality =
                     VerificationConfig::get_or_compute_header_pow_quality(
-                        &self.data_man.pow,
+                        &self.pow,
                         &block.block_header,
                     );
                 let mut reward = if pow_quality >= *epoch_difficulty {
@@ -1361,46 +1461,11 @@ impl ConsensusExecutionHandler {
 
         // Compute tx_fee of each block based on gas_used and gas_price of every
         // tx
-        let mut epoch_receipts = None;
         let mut secondary_reward = U256::zero();
-        for (enum_idx, block) in epoch_blocks.iter().enumerate() {
+        for (enum_idx, (block, block_receipts)) in
+            epoch_blocks.iter().zip(reward_receipts).enumerate()
+        {
             let block_hash = block.hash();
-            // TODO: better redesign to avoid recomputation.
-            // FIXME: check state availability boundary here. Actually, it seems
-            // FIXME: we should never recompute states here.
-            let block_receipts = match self
-                .data_man
-                .block_execution_result_by_hash_with_epoch(
-                    &block_hash,
-                    &reward_epoch_hash,
-                    false, /* update_pivot_assumption */
-                    true,  /* update_cache */
-                ) {
-                Some(block_exec_result) => block_exec_result.block_receipts,
-                None => {
-                    let ctx = self
-                        .data_man
-                        .get_epoch_execution_context(&reward_epoch_hash)
-                        .expect("epoch_execution_context should exists here");
-
-                    // We need to return receipts instead of getting it through
-                    // function get_receipts, because it's
-                    // possible that the computed receipts is deleted by garbage
-                    // collection before we try get it
-                    if epoch_receipts.is_none() {
-                        epoch_receipts = Some(self.recompute_states(
-                            &reward_epoch_hash,
-                            &epoch_blocks,
-                            ctx.start_block_number,
-                        )
-                            // TODO: maybe propagate the error all the way up so that the
-                            // program may restart by itself.
-                            .expect("Can not handle db error in consensus, crashing."));
-                    }
-                    epoch_receipts.as_ref().unwrap()[enum_idx].clone()
-                }
-            };
-
             secondary_reward += block_receipts.secondary_reward;
             debug_assert!(
                 block_receipts.receipts.len() == block.transactions.len()
@@ -1459,6 +1524,7 @@ impl ConsensusExecutionHandler {
         }
 
         let mut merged_rewards = BTreeMap::new();
+        let mut block_rewards = Vec::with_capacity(epoch_size);
         // Here is the exact secondary reward allocated in total
         let mut allocated_secondary_reward = U256::from(0);
 
@@ -1508,29 +1574,21 @@ impl ConsensusExecutionHandler {
                     total_reward,
                 ));
             }
-            if on_local_pivot {
-                self.data_man.insert_block_reward_result(
-                    block_hash,
-                    epoch_later,
-                    BlockRewardResult {
-                        total_reward,
-                        tx_fee,
-                        base_reward,
-                    },
-                    true,
-                );
-                self.data_man
-                    .receipts_retain_epoch(&block_hash, &reward_epoch_hash);
-            }
+            block_rewards.push((
+                block_hash,
+                BlockRewardResult {
+                    total_reward,
+                    tx_fee,
+                    base_reward,
+                },
+            ));
         }
 
         debug!("Give rewards merged_reward={:?}", merged_rewards);
 
         for (address, reward) in merged_rewards {
             if spec.is_valid_address(&address) {
-                state
-                    .add_balance(&address.with_native_space(), &reward)
-                    .unwrap();
+                state.add_balance(&address.with_native_space(), &reward)?;
             }
 
             if let Some(debug_out) = &mut debug_record {
@@ -1555,6 +1613,51 @@ impl ConsensusExecutionHandler {
             // The very unlikely case
             state.sub_total_issued(burnt_fee - new_mint);
         }
+        Ok(block_rewards)
+    }
+}
+
+impl ConsensusExecutionHandler {
+    /// Loads the receipts of the blocks in the reward epoch, which are
+    /// recomputed if missing.
+    fn reward_epoch_receipts(
+        &self, reward_info: &RewardExecutionInfo,
+    ) -> DbResult<Vec<Arc<BlockReceipts>>> {
+        let epoch_blocks = &reward_info.epoch_blocks;
+        let reward_epoch_hash = epoch_blocks.last().expect("Not empty").hash();
+        let mut epoch_receipts = Vec::with_capacity(epoch_blocks.len());
+        for block in epoch_blocks {
+            // TODO: better redesign to avoid recomputation.
+            // FIXME: check state availability boundary here. Actually, it
+            // FIXME: seems we should never recompute states here.
+            match self.data_man.block_execution_result_by_hash_with_epoch(
+                &block.hash(),
+                &reward_epoch_hash,
+                false, /* update_pivot_assumption */
+                true,  /* update_cache */
+            ) {
+                Some(block_exec_result) => {
+                    epoch_receipts.push(block_exec_result.block_receipts)
+                }
+                None => {
+                    let ctx = self
+                        .data_man
+                        .get_epoch_execution_context(&reward_epoch_hash)
+                        .ok_or("epoch_execution_context should exists here")?;
+
+                    // We need to return receipts instead of getting it through
+                    // function get_receipts, because it's possible that the
+                    // computed receipts is deleted by garbage collection
+                    // before we try get it
+                    return self.recompute_states(
+                        &reward_epoch_hash,
+                        epoch_blocks,
+                        ctx.start_block_number,
+                    );
+                }
+            }
+        }
+        Ok(epoch_receipts)
     }
 
     fn recompute_states(
@@ -1774,4 +1877,10 @@ impl ConsensusExecutionHandler {
 
 pub struct ConsensusExecutionConfiguration {
     pub executive_trace: bool,
+    /// The number of threads to execute the transactions of a block in
+    /// parallel. Transactions are executed sequentially if it is zero.
+    pub parallel_execution_threads: usize,
+    /// Whether to also execute each block sequentially in the parallel
+    /// execution mode and compare the results.
+    pub parallel_execution_cross_check: bool,
 }
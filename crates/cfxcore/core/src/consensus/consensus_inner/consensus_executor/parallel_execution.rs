@@ -0,0 +1,268 @@
+use super::{
+    epoch_execution::{BlockProcessContext, EpochExecutor},
+    ConsensusExecutionHandler,
+};
+use std::sync::Arc;
+
+use cfx_executor::{
+    executive::ExecutionOutcome,
+    state::{AccountAccess, SpeculationBase, SpeculativeOutput, State},
+};
+use cfx_statedb::{Result as DbResult, StateDb};
+use cfx_types::H256;
+use cfx_vm_types::Env;
+use metrics::{register_meter_with_group, Meter};
+use parking_lot::Mutex;
+use primitives::SignedTransaction;
+use rayon::prelude::*;
+
+lazy_static! {
+    static ref SPECULATIVE_TX_METER: Arc<dyn Meter> =
+        register_meter_with_group("parallel_execution", "speculative_txs");
+    static ref REEXECUTED_TX_METER: Arc<dyn Meter> =
+        register_meter_with_group("parallel_execution", "reexecuted_txs");
+    static ref CROSS_CHECK_MISMATCH_METER: Arc<dyn Meter> =
+        register_meter_with_group(
+            "parallel_execution",
+            "cross_check_mismatches"
+        );
+}
+
+/// Read-only databases opened at the parent state of an epoch, which are
+/// reused by the speculative executions of its blocks.
+pub(super) struct SpeculativeDbPool {
+    open: Box<dyn Fn() -> DbResult<StateDb> + Send + Sync>,
+    dbs: Mutex<Vec<StateDb>>,
+}
+
+impl SpeculativeDbPool {
+    fn take(&self) -> DbResult<StateDb> {
+        match self.dbs.lock().pop() {
+            Some(db) => Ok(db),
+            None => (self.open)(),
+        }
+    }
+}
+
+impl ConsensusExecutionHandler {
+    /// Makes the pool of databases opened at the state of `parent_hash`,
+    /// which is executed.
+    pub(super) fn speculative_db_pool(
+        &self, parent_hash: H256,
+    ) -> SpeculativeDbPool {
+        let data_man = self.data_man.clone();
+        let open = move || -> DbResult<StateDb> {
+            let state_index = data_man
+                .get_state_readonly_index(&parent_hash)
+                .ok_or("parent state index not found")?;
+            Ok(StateDb::new(
+                data_man
+                    .storage_manager
+                    .get_state_no_commit(
+                        state_index,
+                        /* try_open = */ false,
+                        None,
+                    )?
+                    .ok_or("parent state deleted")?,
+            ))
+        };
+        SpeculativeDbPool {
+            open: Box::new(open),
+            dbs: Default::default(),
+        }
+    }
+}
+
+impl EpochExecutor {
+    /// Executes the transactions of a block upon `state`, which has finished
+    /// `before_block_execution`, in the parallel execution mode. The state
+    /// after execution and the outcomes are the same as the sequential
+    /// execution. Returns `None` if the transactions have not been executed
+    /// and should be executed sequentially.
+    ///
+    /// In the cross-check mode, the block is executed both sequentially and in
+    /// parallel, and the sequential result is kept if they differ.
+    pub(super) fn execute_block_in_parallel(
+        &self, block_context: &BlockProcessContext, state: &mut State,
+        env: &Env, dbs: &SpeculativeDbPool,
+    ) -> DbResult<Option<Vec<ExecutionOutcome>>> {
+        if !self.config.parallel_execution_cross_check {
+            return self.execute_block_speculatively(
+                block_context,
+                state,
+                env,
+                dbs,
+            );
+        }
+
+        let block = block_context.block;
+        let base = state.save();
+        let mut sequential_env = env.clone();
+        let expected_outcomes = block
+            .transactions
+            .iter()
+            .map(|transaction| {
+                self.execute_transaction(
+                    transaction,
+                    block_context,
+                    state,
+                    &mut sequential_env,
+                )
+            })
+            .collect::<DbResult<Vec<_>>>()?;
+        let expected_digest = state.committed_digest();
+        let expected = state.save();
+
+        state.restore(base);
+        match self.execute_block_speculatively(
+            block_context,
+            state,
+            env,
+            dbs,
+        )? {
+            Some(outcomes)
+                if state.committed_digest() == expected_digest
+                    && outcomes
+                        .iter()
+                        .map(describe_outcome)
+                        .eq(expected_outcomes.iter().map(describe_outcome)) =>
+            {
+                Ok(Some(outcomes))
+            }
+            outcomes => {
+                if outcomes.is_some() {
+                    CROSS_CHECK_MISMATCH_METER.mark(1);
+                    error!(
+                        "Parallel execution of block {:?} differs from \
+                         sequential execution, use the sequential result",
+                        block.hash()
+                    );
+                }
+                state.restore(expected);
+                Ok(Some(expected_outcomes))
+            }
+        }
+    }
+
+    /// Executes all the transactions speculatively upon the same state in
+    /// parallel, then applies the results in order. A transaction is
+    /// re-executed on `state` if it accessed an account written by an earlier
+    /// transaction in the block, or read a global statistic variable changed
+    /// by them.
+    fn execute_block_speculatively(
+        &self, block_context: &BlockProcessContext, state: &mut State,
+        env: &Env, dbs: &SpeculativeDbPool,
+    ) -> DbResult<Option<Vec<ExecutionOutcome>>> {
+        let Some(pool) = self.parallel_execution_pool.as_ref() else {
+            return Ok(None);
+        };
+        let transactions = &block_context.block.transactions;
+
+        // Make sure the parent state could be opened before touching `state`.
+        match dbs.take() {
+            Ok(db) => dbs.dbs.lock().push(db),
+            Err(e) => {
+                warn!("Fail to open state for parallel execution: {:?}", e);
+                return Ok(None);
+            }
+        }
+
+        let base = state.start_speculation();
+        let speculative_results: Vec<_> = pool.install(|| {
+            transactions
+                .par_iter()
+                .map(|transaction| {
+                    self.execute_speculatively(
+                        transaction,
+                        block_context,
+                        env,
+                        &base,
+                        dbs,
+                    )
+                })
+                .collect()
+        });
+        state.end_speculation(base);
+        SPECULATIVE_TX_METER.mark(transactions.len());
+
+        let mut accessed = AccountAccess::default();
+        let mut env = env.clone();
+        let mut outcomes = Vec::with_capacity(transactions.len());
+        for (transaction, result) in
+            transactions.iter().zip(speculative_results)
+        {
+            if let Some((outcome, output)) = result {
+                if state.apply_speculative(output, &mut accessed) {
+                    outcomes.push(outcome);
+                    continue;
+                }
+            }
+
+            REEXECUTED_TX_METER.mark(1);
+            state.start_recording_access();
+            let outcome = self.execute_transaction(
+                transaction,
+                block_context,
+                state,
+                &mut env,
+            )?;
+            accessed.extend(state.finish_recording_access());
+            outcomes.push(outcome);
+        }
+        Ok(Some(outcomes))
+    }
+
+    /// Executes a transaction upon `base`. Returns `None` if it fails, and the
+    /// transaction will be re-executed.
+    fn execute_speculatively(
+        &self, transaction: &Arc<SignedTransaction>,
+        block_context: &BlockProcessContext, env: &Env,
+        base: &Arc<SpeculationBase>, dbs: &SpeculativeDbPool,
+    ) -> Option<(ExecutionOutcome, SpeculativeOutput)> {
+        let db = dbs
+            .take()
+            .map_err(|e| debug!("Fail to open speculative state: {:?}", e))
+            .ok()?;
+        let mut state = State::new_speculative(db, base.clone());
+        let mut env = env.clone();
+        let outcome = self
+            .execute_transaction(
+                transaction,
+                block_context,
+                &mut state,
+                &mut env,
+            )
+            .map_err(|e| debug!("Speculative execution fails: {:?}", e))
+            .ok()?;
+
+        let (output, db) = state.finish_speculation();
+        dbs.dbs.lock().push(db);
+        Some((outcome, output))
+    }
+}
+
+/// Formats an outcome for comparison. The traces in `Executed::ext_result` are
+/// excluded, since their debug format is not in a deterministic order.
+fn describe_outcome(outcome: &ExecutionOutcome) -> String {
+    match outcome.try_as_executed() {
+        None => format!("{:?}", outcome),
+        Some(executed) => format!(
+            "{} {:?}",
+            outcome.error_message(),
+            (
+                executed.base_gas,
+                executed.gas_used,
+                executed.fee,
+                executed.burnt_fee,
+                executed.gas_charged,
+                executed.gas_sponsor_paid,
+                &executed.logs,
+                executed.storage_sponsor_paid,
+                &executed.storage_collateralized,
+                &executed.storage_released,
+                &executed.contracts_created,
+                &executed.output,
+            )
+        ),
+    }
+}
@@ -0,0 +1,361 @@
+use super::{
+    ConsensusExecutionConfiguration, ConsensusExecutionHandler,
+    EpochExecutionTask,
+};
+use crate::{
+    block_data_manager::{BlockDataManager, DbType},
+    genesis_block::{GenesisAccount, GenesisAlloc},
+    keylib::{Generator, KeyPair, Random},
+    pow::PowComputer,
+    sync::utils::{
+        initialize_data_manager_with_genesis_alloc,
+        initialize_disabled_pos_verifier,
+    },
+    transaction_pool::TxPoolConfig,
+    verification::{compute_transaction_root, VerificationConfig},
+    TransactionPool,
+};
+use cfx_executor::machine::{Machine, VmFactory};
+use cfx_internal_common::StateRootWithAuxInfo;
+use cfx_parameters::{
+    block::{MAX_BLOCK_SIZE_IN_BYTES, REFEREE_DEFAULT_BOUND},
+    consensus::{
+        GENESIS_GAS_LIMIT, ONE_CFX_IN_DRIP, TRANSACTION_DEFAULT_EPOCH_BOUND,
+    },
+    tx_pool::TXPOOL_DEFAULT_NONCE_BITS,
+};
+use cfx_types::{Address, AddressSpaceUtil, SpaceMap, U256};
+use primitives::{
+    receipt::BlockReceipts, transaction::Eip155Transaction, Action, Block,
+    BlockHeaderBuilder, SignedTransaction, Transaction,
+};
+use rustc_hex::FromHex;
+use std::sync::Arc;
+use tempfile::{tempdir, TempDir};
+
+/// An eSpace contract which increases the value in slot 0 by one.
+const COUNTER_CODE: &str = "60005460010160005500";
+
+/// A chain with only the genesis block, whose state includes the accounts in
+/// a genesis alloc.
+struct TestChain {
+    data_man: Arc<BlockDataManager>,
+    genesis: Arc<Block>,
+    machine: Arc<Machine>,
+    _dir: TempDir,
+}
+
+impl TestChain {
+    fn new(genesis_alloc: &GenesisAlloc) -> Self {
+        let dir = tempdir().unwrap();
+        let machine = Arc::new(Machine::new_with_builtin(
+            Default::default(),
+            VmFactory::new(1024 * 32),
+        ));
+        let (data_man, genesis) = initialize_data_manager_with_genesis_alloc(
+            dir.path().to_str().unwrap(),
+            DbType::Rocksdb,
+            Arc::new(PowComputer::new(true)),
+            machine.clone(),
+            genesis_alloc,
+        );
+        TestChain {
+            data_man,
+            genesis,
+            machine,
+            _dir: dir,
+        }
+    }
+
+    fn handler(
+        &self, parallel_execution_threads: usize,
+        parallel_execution_cross_check: bool,
+    ) -> ConsensusExecutionHandler {
+        let pos_verifier = initialize_disabled_pos_verifier();
+        let verification_config = VerificationConfig::new(
+            true, /* test_mode */
+            REFEREE_DEFAULT_BOUND,
+            MAX_BLOCK_SIZE_IN_BYTES,
+            TRANSACTION_DEFAULT_EPOCH_BOUND,
+            TXPOOL_DEFAULT_NONCE_BITS,
+            pos_verifier.enable_height(),
+            self.machine.clone(),
+        );
+        let txpool = Arc::new(TransactionPool::new(
+            TxPoolConfig::default(),
+            verification_config.clone(),
+            self.data_man.clone(),
+            self.machine.clone(),
+        ));
+        ConsensusExecutionHandler::new(
+            txpool,
+            self.data_man.clone(),
+            ConsensusExecutionConfiguration {
+                executive_trace: false,
+                parallel_execution_threads,
+                parallel_execution_cross_check,
+            },
+            verification_config,
+            self.machine.clone(),
+            pos_verifier,
+        )
+    }
+
+    /// Makes a block upon the genesis block, which is the pivot block of
+    /// epoch 1.
+    fn child_block(
+        &self, transactions: Vec<Arc<SignedTransaction>>,
+        base_price: Option<SpaceMap<U256>>,
+    ) -> Arc<Block> {
+        let mut header = BlockHeaderBuilder::new()
+            .with_parent_hash(self.genesis.hash())
+            .with_height(1)
+            .with_gas_limit(GENESIS_GAS_LIMIT.into())
+            .with_base_price(base_price)
+            .with_transactions_root(compute_transaction_root(&transactions))
+            .build();
+        header.compute_hash();
+        let block = Arc::new(Block::new(header, transactions));
+        self.data_man
+            .insert_block(block.clone(), true /* persistent */);
+        block
+    }
+}
+
+/// Executes the epoch of `pivot_block` upon the state of its parent without
+/// committing it.
+fn execute_epoch(
+    handler: &ConsensusExecutionHandler, pivot_block: &Arc<Block>,
+) -> (Vec<Arc<BlockReceipts>>, StateRootWithAuxInfo) {
+    let epoch_blocks = vec![pivot_block.clone()];
+    let inputs = handler
+        .epoch_execution_inputs(&epoch_blocks, &None)
+        .unwrap();
+    let mut state = handler.new_state(pivot_block, false).unwrap();
+    let receipts = handler
+        .execute_epoch_on_state(
+            &mut state,
+            &pivot_block.hash(),
+            &epoch_blocks,
+            1, /* start_block_number */
+            &None,
+            &inputs,
+            false, /* on_local_pivot */
+            false, /* replay */
+            None,
+        )
+        .unwrap();
+    (receipts, state.compute_state_root(None).unwrap())
+}
+
+fn espace_tx(
+    sender: &KeyPair, nonce: u64, action: Action, value: u64,
+) -> Arc<SignedTransaction> {
+    let tx: Transaction = Eip155Transaction {
+        nonce: nonce.into(),
+        gas_price: 1.into(),
+        gas: 100_000.into(),
+        action,
+        value: value.into(),
+        chain_id: Some(1),
+        data: vec![],
+    }
+    .into();
+    Arc::new(tx.sign(sender.secret()))
+}
+
+#[test]
+fn test_parallel_execution_same_as_sequential() {
+    let senders: Vec<KeyPair> =
+        (0..4).map(|_| Random.generate().unwrap()).collect();
+    let counter = Address::from_low_u64_be(0x1000);
+    let receivers = [
+        Address::from_low_u64_be(0x2000),
+        Address::from_low_u64_be(0x3000),
+    ];
+
+    let mut genesis_alloc = GenesisAlloc::new();
+    for sender in &senders {
+        genesis_alloc.insert(
+            sender.evm_address().with_evm_space(),
+            GenesisAccount {
+                balance: ONE_CFX_IN_DRIP.into(),
+                ..Default::default()
+            },
+        );
+    }
+    genesis_alloc.insert(
+        counter.with_evm_space(),
+        GenesisAccount {
+            code: Some(COUNTER_CODE.from_hex().unwrap()),
+            ..Default::default()
+        },
+    );
+    let chain = TestChain::new(&genesis_alloc);
+
+    let transactions = vec![
+        espace_tx(&senders[0], 0, Action::Call(counter), 0),
+        // The same sender as the previous one.
+        espace_tx(&senders[0], 1, Action::Call(receivers[0]), 100),
+        // The same contract storage as the first one.
+        espace_tx(&senders[1], 0, Action::Call(counter), 0),
+        espace_tx(&senders[2], 0, Action::Call(counter), 0),
+        // Independent of the others.
+        espace_tx(&senders[3], 0, Action::Call(receivers[1]), 100),
+    ];
+    // With a base price, each transaction burns some fee and reads the total
+    // issued tokens decreased by the earlier ones.
+    let blocks = [
+        chain.child_block(transactions.clone(), None),
+        chain.child_block(
+            transactions,
+            Some(SpaceMap::new(U256::one(), U256::one())),
+        ),
+    ];
+
+    let sequential = chain.handler(0, false);
+    let parallel = chain.handler(4, false);
+    let cross_check = chain.handler(4, true);
+    for block in &blocks {
+        let (receipts, state_root) = execute_epoch(&sequential, block);
+        assert!(receipts[0].receipts.iter().all(|r| r.tx_success()));
+
+        for handler in [&parallel, &cross_check] {
+            let (parallel_receipts, parallel_state_root) =
+                execute_epoch(handler, block);
+            assert_eq!(parallel_receipts, receipts);
+            assert_eq!(parallel_state_root, state_root);
+        }
+    }
+}
+
+#[test]
+fn test_execution_witness_round_trip() {
+    let sender = Random.generate().unwrap();
+    let counter = Address::from_low_u64_be(0x1000);
+
+    let mut genesis_alloc = GenesisAlloc::new();
+    genesis_alloc.insert(
+        sender.evm_address().with_evm_space(),
+        GenesisAccount {
+            balance: ONE_CFX_IN_DRIP.into(),
+            ..Default::default()
+        },
+    );
+    genesis_alloc.insert(
+        counter.with_evm_space(),
+        GenesisAccount {
+            code: Some(COUNTER_CODE.from_hex().unwrap()),
+            ..Default::default()
+        },
+    );
+    let chain = TestChain::new(&genesis_alloc);
+    let block = chain.child_block(
+        vec![espace_tx(&sender, 0, Action::Call(counter), 0)],
+        None,
+    );
+
+    let handler = chain.handler(0, false);
+    let task = EpochExecutionTask {
+        epoch_hash: block.hash(),
+        epoch_block_hashes: vec![block.hash()],
+        start_block_number: 1,
+        reward_info: None,
+        on_local_pivot: false,
+        force_recompute: false,
+    };
+    handler.compute_epoch(
+        &task.epoch_hash,
+        &task.epoch_block_hashes,
+        task.start_block_number,
+        &task.reward_info,
+        task.on_local_pivot,
+        None,
+        task.force_recompute,
+        false,
+    );
+    {
+        // Make the executed state readable, as the consensus graph does for
+        // an epoch on the pivot chain.
+        let mut boundary = chain.data_man.state_availability_boundary.write();
+        boundary.pivot_chain.push(block.hash());
+        boundary.adjust_upper_bound(&block.block_header);
+    }
+
+    let witness = handler.collect_execution_witness(&task).unwrap();
+    handler.check_execution_witness(&witness).unwrap();
+    handler.executor.check_execution_witness(&witness).unwrap();
+
+    // The modifications are not in a state root claiming no change.
+    let mut unchanged = witness.clone();
+    unchanged.state_root = unchanged.parent_state_root.clone();
+    assert!(handler
+        .executor
+        .check_execution_witness(&unchanged)
+        .is_err());
+
+    // Every key in the witness is read by the execution.
+    let mut truncated = witness.clone();
+    truncated.keys.pop();
+    assert!(handler
+        .executor
+        .check_execution_witness(&truncated)
+        .is_err());
+}
+
+#[test]
+fn test_execution_witness_rejects_killed_contract() {
+    let sender = Random.generate().unwrap();
+
+    let mut genesis_alloc = GenesisAlloc::new();
+    genesis_alloc.insert(
+        sender.evm_address().with_evm_space(),
+        GenesisAccount {
+            balance: ONE_CFX_IN_DRIP.into(),
+            ..Default::default()
+        },
+    );
+    let chain = TestChain::new(&genesis_alloc);
+    // The init code `CALLER SELFDESTRUCT` kills the contract being created,
+    // whose storage is then removed by prefix.
+    let tx: Transaction = Eip155Transaction {
+        nonce: 0.into(),
+        gas_price: 1.into(),
+        gas: 100_000.into(),
+        action: Action::Create,
+        value: 0.into(),
+        chain_id: Some(1),
+        data: "33ff".from_hex().unwrap(),
+    }
+    .into();
+    let block =
+        chain.child_block(vec![Arc::new(tx.sign(sender.secret()))], None);
+
+    let handler = chain.handler(0, false);
+    let task = EpochExecutionTask {
+        epoch_hash: block.hash(),
+        epoch_block_hashes: vec![block.hash()],
+        start_block_number: 1,
+        reward_info: None,
+        on_local_pivot: false,
+        force_recompute: false,
+    };
+    handler.compute_epoch(
+        &task.epoch_hash,
+        &task.epoch_block_hashes,
+        task.start_block_number,
+        &task.reward_info,
+        task.on_local_pivot,
+        None,
+        task.force_recompute,
+        false,
+    );
+    {
+        let mut boundary = chain.data_man.state_availability_boundary.write();
+        boundary.pivot_chain.push(block.hash());
+        boundary.adjust_upper_bound(&block.block_header);
+    }
+
+    let err = handler.collect_execution_witness(&task).unwrap_err();
+    assert!(err.to_string().contains("can not be proved complete"));
+}
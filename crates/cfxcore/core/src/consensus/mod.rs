@@ -13,6 +13,7 @@ pub mod pos_handler;
 mod statistics;
 
 pub use crate::consensus::consensus_inner::{
+    consensus_executor::{EpochExecutor, ExecutionWitness, RewardWitness},
     ConsensusGraphInner, ConsensusInnerConfig,
 };
 
@@ -43,8 +43,14 @@ pub const COL_BLOCK_TRACES: u32 = 5;
 pub const COL_HASH_BY_BLOCK_NUMBER: u32 = 6;
 /// Column for PoS interest reward info.
 pub const COL_REWARD_BY_POS_EPOCH: u32 = 7;
+/// Column for bloom-bits index of epoch log blooms
+pub const COL_BLOOM_BITS: u32 = 8;
+/// Column for transactions indexed by sender and recipient address
+pub const COL_ADDRESS_TX_INDEX: u32 = 9;
+/// Column for the locations of block data moved into the freezer
+pub const COL_FREEZER_INDEX: u32 = 10;
 /// Number of columns in DB
-pub const NUM_COLUMNS: u32 = 8;
+pub const NUM_COLUMNS: u32 = 11;
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
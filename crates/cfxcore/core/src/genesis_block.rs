@@ -3,9 +3,10 @@
 // See http://www.gnu.org/licenses/
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::File,
     io::{BufRead, BufReader, Read},
+    str::FromStr,
     sync::Arc,
 };
 
@@ -14,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use toml::Value;
 
 use crate::keylib::KeyPair;
+use cfx_bytes::Bytes;
 use cfx_executor::internal_contract::initialize_internal_contract_accounts;
 use cfx_internal_common::debug::ComputeEpochDebugRecord;
 use cfx_parameters::{
@@ -22,9 +24,12 @@ use cfx_parameters::{
         GENESIS_TOKEN_COUNT_IN_CFX, TWO_YEAR_UNLOCK_TOKEN_COUNT_IN_CFX,
     },
     genesis::*,
-    staking::POS_VOTE_PRICE,
+    staking::{
+        code_collateral_units, COLLATERAL_UNITS_PER_STORAGE_KEY,
+        DRIPS_PER_STORAGE_COLLATERAL_UNIT, POS_VOTE_PRICE,
+    },
 };
-use cfx_statedb::StateDb;
+use cfx_statedb::{Result as DbResult, StateDb};
 use cfx_storage::{StorageManager, StorageManagerTrait};
 use cfx_types::{
     address_util::AddressUtil, Address, AddressSpaceUtil, AddressWithSpace,
@@ -35,7 +40,8 @@ use diem_crypto::{
 };
 use diem_types::validator_config::{ConsensusPublicKey, ConsensusVRFPublicKey};
 use primitives::{
-    Action, Block, BlockHeaderBuilder, BlockReceipts, SignedTransaction,
+    storage::STORAGE_LAYOUT_REGULAR_V0, Action, Block, BlockHeaderBuilder,
+    BlockReceipts, SignedTransaction,
 };
 use secret_store::SecretStore;
 
@@ -45,9 +51,10 @@ use cfx_executor::{
         contract_address, ExecutionOutcome, ExecutiveContext, TransactOptions,
     },
     machine::Machine,
-    state::State,
+    state::{settle_collateral_for_all, State},
+    substate::Substate,
 };
-use cfx_vm_types::{CreateContractAddress, Env};
+use cfx_vm_types::{CreateContractAddress, Env, Spec};
 use diem_types::account_address::AccountAddress;
 use primitives::transaction::native_transaction::NativeTransaction;
 
@@ -116,14 +123,15 @@ pub fn load_secrets_file(
 }
 
 /// ` test_net_version` is used to update the genesis author so that after
-/// resetting, the chain of the older version will be discarded
+/// resetting, the chain of the older version will be discarded. Fails if the
+/// accounts in `genesis_alloc` can't be initialized.
 pub fn genesis_block(
     storage_manager: &Arc<StorageManager>,
     genesis_accounts: HashMap<AddressWithSpace, U256>,
-    test_net_version: Address, initial_difficulty: U256, machine: Arc<Machine>,
-    need_to_execute: bool, genesis_chain_id: Option<u32>,
-    initial_nodes: &Option<GenesisPosState>,
-) -> Block {
+    genesis_alloc: &GenesisAlloc, test_net_version: Address,
+    initial_difficulty: U256, machine: Arc<Machine>, need_to_execute: bool,
+    genesis_chain_id: Option<u32>, initial_nodes: &Option<GenesisPosState>,
+) -> Result<Block, String> {
     let mut state =
         State::new(StateDb::new(storage_manager.get_state_for_genesis_write()))
             .expect("Failed to initialize state");
@@ -136,6 +144,8 @@ pub fn genesis_block(
         machine.internal_contracts().initialized_at_genesis(),
     )
     .expect("no db error");
+    initialize_genesis_alloc(&mut state, genesis_alloc, &machine.spec(0, 0))
+        .map_err(|e| format!("failed to initialize genesis alloc: {}", e))?;
     trace!("genesis_accounts: {:?}", genesis_accounts);
     for (addr, balance) in genesis_accounts {
         state.add_balance(&addr, &balance).unwrap();
@@ -417,7 +427,7 @@ pub fn genesis_block(
         "genesis debug_record {}",
         serde_json::to_string(&debug_record).unwrap()
     );
-    genesis
+    Ok(genesis)
 }
 
 pub fn register_transaction(
@@ -555,6 +565,404 @@ pub fn load_file(
     Ok(accounts)
 }
 
+/// The accounts allocated in the genesis state, keyed by address in both
+/// spaces, as loaded from the `genesis_alloc` file.
+///
+/// ```toml
+/// [native."0x8000000000000000000000000000000000000001"]
+/// balance = "1000000000000000000"
+/// code = "0x6080..."
+/// admin = "0x1000000000000000000000000000000000000001"
+///
+/// [native."0x8000000000000000000000000000000000000001".storage]
+/// "0x0000000000000000000000000000000000000000000000000000000000000000" = "0x1"
+///
+/// [native."0x8000000000000000000000000000000000000001".sponsor]
+/// collateral_sponsor = "0x1000000000000000000000000000000000000001"
+/// collateral_balance = "1000000000000000000000"
+///
+/// [evm."0x0000000000000000000000000000000000001000"]
+/// balance = "0x10"
+/// nonce = 1
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisAllocConfig {
+    pub native: BTreeMap<String, GenesisAccountConfig>,
+    pub evm: BTreeMap<String, GenesisAccountConfig>,
+}
+
+/// An account in `GenesisAllocConfig`. Amounts are decimal or `0x` prefixed
+/// hex strings.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisAccountConfig {
+    pub balance: Option<String>,
+    pub nonce: Option<u64>,
+    pub code: Option<String>,
+    pub storage: BTreeMap<String, String>,
+    /// Core space contracts only.
+    pub admin: Option<String>,
+    /// Core space contracts only.
+    pub sponsor: Option<GenesisSponsorConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisSponsorConfig {
+    pub gas_sponsor: Option<String>,
+    pub gas_balance: Option<String>,
+    pub gas_upper_bound: Option<String>,
+    pub collateral_sponsor: Option<String>,
+    pub collateral_balance: Option<String>,
+    /// The users sponsored by the contract, where the zero address means all
+    /// the users.
+    pub whitelist: Vec<String>,
+}
+
+pub type GenesisAlloc = BTreeMap<AddressWithSpace, GenesisAccount>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenesisAccount {
+    pub balance: U256,
+    pub nonce: Option<U256>,
+    pub code: Option<Bytes>,
+    pub storage: BTreeMap<H256, U256>,
+    pub admin: Address,
+    pub sponsor: Option<GenesisSponsor>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenesisSponsor {
+    pub gas_sponsor: Address,
+    pub gas_balance: U256,
+    pub gas_upper_bound: U256,
+    pub collateral_sponsor: Address,
+    pub collateral_balance: U256,
+    pub whitelist: Vec<Address>,
+}
+
+impl GenesisAllocConfig {
+    /// Validates the accounts, where `address_parser` parses the core space
+    /// addresses.
+    pub fn validate(
+        &self, address_parser: impl Fn(&str) -> Result<Address, String>,
+    ) -> Result<GenesisAlloc, String> {
+        let mut alloc = GenesisAlloc::new();
+        for (space, accounts) in
+            [(Space::Native, &self.native), (Space::Ethereum, &self.evm)]
+        {
+            for (key, config) in accounts {
+                let address = match space {
+                    Space::Native => address_parser(key),
+                    Space::Ethereum => parse_evm_address(key),
+                }
+                .map_err(|e| {
+                    format!(
+                        "failed to parse address: value = {}, error = {:?}",
+                        key, e
+                    )
+                })?
+                .with_space(space);
+                let account =
+                    config.validate(&address, &address_parser).map_err(
+                        |e| format!("invalid genesis account {}: {}", key, e),
+                    )?;
+                if alloc.insert(address, account).is_some() {
+                    return Err(format!("duplicated genesis account {}", key));
+                }
+            }
+        }
+        Ok(alloc)
+    }
+}
+
+impl GenesisAccountConfig {
+    fn validate(
+        &self, address: &AddressWithSpace,
+        address_parser: &impl Fn(&str) -> Result<Address, String>,
+    ) -> Result<GenesisAccount, String> {
+        let is_native = address.space == Space::Native;
+        if is_native && address.address.is_builtin_address() {
+            return Err("builtin address is reserved".into());
+        }
+
+        let code = self.code.as_deref().map(parse_bytes).transpose()?;
+        if code.as_ref().map_or(false, |code| code.is_empty()) {
+            return Err("code should not be empty".into());
+        }
+        if is_native && code.is_some() != address.address.is_contract_address()
+        {
+            return Err(if code.is_some() {
+                "account with code requires a contract address".into()
+            } else {
+                "account without code requires a user address".into()
+            });
+        }
+        if code.is_none()
+            && (!self.storage.is_empty()
+                || self.admin.is_some()
+                || self.sponsor.is_some())
+        {
+            return Err(
+                "storage, admin and sponsor are only allowed for contracts"
+                    .into(),
+            );
+        }
+        if !is_native && (self.admin.is_some() || self.sponsor.is_some()) {
+            return Err("admin and sponsor are not supported in eSpace".into());
+        }
+
+        let mut storage = BTreeMap::new();
+        for (key, value) in &self.storage {
+            let key = H256::from_str(key.trim_start_matches("0x"))
+                .map_err(|e| format!("invalid storage key {}: {:?}", key, e))?;
+            let value = parse_u256(value)?;
+            if value.is_zero() {
+                return Err(format!("storage value of {:?} is zero", key));
+            }
+            storage.insert(key, value);
+        }
+
+        let admin = self
+            .admin
+            .as_deref()
+            .map(address_parser)
+            .transpose()?
+            .unwrap_or_default();
+        let sponsor = self
+            .sponsor
+            .as_ref()
+            .map(|sponsor| sponsor.validate(address_parser))
+            .transpose()?;
+
+        // Core space contracts pay the collateral for code and storage from
+        // the sponsor balance for collateral. With CIP-107, a part of it is
+        // converted into storage points, which pay for the collateral as well,
+        // so the total is the same.
+        if let Some(code) = code.as_ref().filter(|_| is_native) {
+            let whitelist_len = sponsor
+                .as_ref()
+                .map_or(0, |sponsor| sponsor.whitelist.len());
+            let collateral = *DRIPS_PER_STORAGE_COLLATERAL_UNIT
+                * (code_collateral_units(code.len())
+                    + (storage.len() + whitelist_len) as u64
+                        * COLLATERAL_UNITS_PER_STORAGE_KEY);
+            let sponsor_balance = sponsor
+                .as_ref()
+                .map_or(U256::zero(), |sponsor| sponsor.collateral_balance);
+            if sponsor_balance < collateral {
+                return Err(format!(
+                    "collateral_balance of sponsor should cover the storage \
+                     collateral {} of code and storage",
+                    collateral
+                ));
+            }
+        }
+
+        Ok(GenesisAccount {
+            balance: self
+                .balance
+                .as_deref()
+                .map(parse_u256)
+                .transpose()?
+                .unwrap_or_default(),
+            nonce: self.nonce.map(U256::from),
+            code,
+            storage,
+            admin,
+            sponsor,
+        })
+    }
+}
+
+impl GenesisSponsorConfig {
+    fn validate(
+        &self, address_parser: &impl Fn(&str) -> Result<Address, String>,
+    ) -> Result<GenesisSponsor, String> {
+        let parse_amount = |amount: &Option<String>| {
+            amount
+                .as_deref()
+                .map(parse_u256)
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        let sponsor = GenesisSponsor {
+            gas_sponsor: self
+                .gas_sponsor
+                .as_deref()
+                .map(address_parser)
+                .transpose()?
+                .unwrap_or_default(),
+            gas_balance: parse_amount(&self.gas_balance)?,
+            gas_upper_bound: parse_amount(&self.gas_upper_bound)?,
+            collateral_sponsor: self
+                .collateral_sponsor
+                .as_deref()
+                .map(address_parser)
+                .transpose()?
+                .unwrap_or_default(),
+            collateral_balance: parse_amount(&self.collateral_balance)?,
+            whitelist: self
+                .whitelist
+                .iter()
+                .map(|user| address_parser(user))
+                .collect::<Result<_, _>>()?,
+        };
+        if sponsor.gas_sponsor.is_zero()
+            && !(sponsor.gas_balance.is_zero()
+                && sponsor.gas_upper_bound.is_zero())
+        {
+            return Err("gas_sponsor is required for gas sponsorship".into());
+        }
+        // The same as the check in `SponsorWhitelistControl`.
+        if sponsor.gas_balance / U256::from(1000) < sponsor.gas_upper_bound {
+            return Err(
+                "gas_balance should be at least gas_upper_bound * 1000".into(),
+            );
+        }
+        if sponsor.collateral_sponsor.is_zero()
+            && !sponsor.collateral_balance.is_zero()
+        {
+            return Err(
+                "collateral_sponsor is required for collateral sponsorship"
+                    .into(),
+            );
+        }
+        Ok(sponsor)
+    }
+}
+
+fn parse_u256(value: &str) -> Result<U256, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => {
+            U256::from_str_radix(hex, 16).map_err(|e| format!("{:?}", e))
+        }
+        None => U256::from_dec_str(value).map_err(|e| format!("{:?}", e)),
+    }
+    .map_err(|e| format!("failed to parse number {}: {}", value, e))
+}
+
+fn parse_bytes(value: &str) -> Result<Bytes, String> {
+    value
+        .trim_start_matches("0x")
+        .from_hex()
+        .map_err(|e| format!("failed to parse hex {}: {:?}", value, e))
+}
+
+fn parse_evm_address(value: &str) -> Result<Address, String> {
+    Address::from_str(value.trim_start_matches("0x"))
+        .map_err(|e| format!("{:?}", e))
+}
+
+pub fn load_alloc_config(path: &String) -> Result<GenesisAllocConfig, String> {
+    let mut content = String::new();
+    let mut file = File::open(path)
+        .map_err(|e| format!("failed to open file: {:?}", e))?;
+    file.read_to_string(&mut content)
+        .map_err(|e| format!("failed to read file content: {:?}", e))?;
+    toml::from_str(&content)
+        .map_err(|e| format!("failed to parse toml file: {:?}", e))
+}
+
+pub fn load_alloc_file(
+    path: &String, address_parser: impl Fn(&str) -> Result<Address, String>,
+) -> Result<GenesisAlloc, String> {
+    load_alloc_config(path)?.validate(address_parser)
+}
+
+/// Writes the accounts in `genesis_alloc` into the state. The collateral for
+/// code and storage of core space contracts is charged to themselves, which
+/// is paid by the sponsor balance for collateral.
+fn initialize_genesis_alloc(
+    state: &mut State, genesis_alloc: &GenesisAlloc, spec: &Spec,
+) -> DbResult<()> {
+    for (address, account) in genesis_alloc {
+        let mut substate = Substate::new();
+        let storage_owner = match address.space {
+            Space::Native => address.address,
+            Space::Ethereum => Address::zero(),
+        };
+
+        if let Some(code) = &account.code {
+            state.new_contract_with_admin(
+                address,
+                &account.admin,
+                U256::zero(),
+                Some(STORAGE_LAYOUT_REGULAR_V0),
+                spec.cip107,
+            )?;
+            if address.space == Space::Native {
+                substate.record_storage_occupy(
+                    &storage_owner,
+                    code_collateral_units(code.len()),
+                );
+            }
+            state.init_code(
+                address,
+                code.clone(),
+                storage_owner,
+                H256::zero(),
+            )?;
+            for (key, value) in &account.storage {
+                state.set_storage(
+                    address,
+                    key.as_bytes().to_vec(),
+                    *value,
+                    storage_owner,
+                    &mut substate,
+                )?;
+            }
+        }
+
+        state.add_balance(address, &account.balance)?;
+        state.add_total_issued(account.balance);
+        if address.space == Space::Ethereum {
+            state.add_total_evm_tokens(account.balance);
+        }
+        if let Some(nonce) = &account.nonce {
+            state.set_nonce(address, nonce)?;
+        }
+
+        if let Some(sponsor) = &account.sponsor {
+            state.set_sponsor_for_gas(
+                &address.address,
+                &sponsor.gas_sponsor,
+                &sponsor.gas_balance,
+                &sponsor.gas_upper_bound,
+            )?;
+            state.set_sponsor_for_collateral(
+                &address.address,
+                &sponsor.collateral_sponsor,
+                &sponsor.collateral_balance,
+                spec.cip107,
+            )?;
+            state.add_total_issued(
+                sponsor.gas_balance + sponsor.collateral_balance,
+            );
+            for user in &sponsor.whitelist {
+                state.add_to_contract_whitelist(
+                    address.address,
+                    storage_owner,
+                    *user,
+                    &mut substate,
+                )?;
+            }
+        }
+
+        settle_collateral_for_all(state, &substate, &mut (), spec, false)?
+            .map_err(|e| {
+                format!(
+                    "failed to settle collateral for genesis account {:?}: \
+                     {:?}",
+                    address, e
+                )
+            })?;
+        state.commit_cache(false);
+    }
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GenesisPosNodeInfo {
     pub address: Address,
@@ -570,3 +978,240 @@ pub struct GenesisPosState {
     pub initial_committee: Vec<(AccountAddress, u64)>,
     pub initial_seed: H256,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block_data_manager::DbType, pow::PowComputer};
+    use cfx_executor::{machine::VmFactory, spec::CommonParams};
+
+    fn parse_alloc(content: &str) -> Result<GenesisAlloc, String> {
+        let config: GenesisAllocConfig =
+            toml::from_str(content).map_err(|e| format!("{:?}", e))?;
+        config.validate(parse_evm_address)
+    }
+
+    #[test]
+    fn test_genesis_alloc() {
+        let alloc = parse_alloc(
+            r#"
+            [native."0x1000000000000000000000000000000000000001"]
+            balance = "100"
+
+            [native."0x8000000000000000000000000000000000000001"]
+            balance = "0x10"
+            code = "0x12345678"
+            admin = "0x1000000000000000000000000000000000000001"
+            storage = { "0x0000000000000000000000000000000000000000000000000000000000000001" = "2" }
+            sponsor = { collateral_sponsor = "0x1000000000000000000000000000000000000001", collateral_balance = "1000000000000000000", whitelist = ["0x0000000000000000000000000000000000000000"] }
+
+            [evm."0x0000000000000000000000000000000000001000"]
+            nonce = 3
+            code = "0x00"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(alloc.len(), 3);
+
+        let mut user_address = Address::from_low_u64_be(1);
+        user_address.set_user_account_type_bits();
+        assert_eq!(
+            alloc[&user_address.with_native_space()].balance,
+            U256::from(100)
+        );
+
+        let mut contract = Address::from_low_u64_be(1);
+        contract.set_contract_type_bits();
+        let account = &alloc[&contract.with_native_space()];
+        assert_eq!(account.balance, U256::from(16));
+        assert_eq!(account.code, Some(vec![0x12, 0x34, 0x56, 0x78]));
+        assert_eq!(account.admin, user_address);
+        assert_eq!(account.storage[&H256::from_low_u64_be(1)], U256::from(2));
+        let sponsor = account.sponsor.as_ref().unwrap();
+        assert_eq!(sponsor.collateral_sponsor, user_address);
+        assert_eq!(sponsor.whitelist, vec![Address::zero()]);
+
+        let account =
+            &alloc[&Address::from_low_u64_be(0x1000).with_evm_space()];
+        assert_eq!(account.nonce, Some(U256::from(3)));
+        assert_eq!(account.code, Some(vec![0]));
+    }
+
+    #[test]
+    fn test_invalid_genesis_alloc() {
+        for content in [
+            // Code for a user address.
+            r#"native."0x1000000000000000000000000000000000000001".code = "0x00""#,
+            // A contract address without code.
+            r#"native."0x8000000000000000000000000000000000000001".balance = "1""#,
+            // Builtin address.
+            r#"native."0x0000000000000000000000000000000000000001".balance = "1""#,
+            // Collateral not covered.
+            r#"native."0x8000000000000000000000000000000000000001".code = "0x00""#,
+            // Admin in eSpace.
+            r#"
+            [evm."0x0000000000000000000000000000000000001000"]
+            code = "0x00"
+            admin = "0x1000000000000000000000000000000000000001"
+            "#,
+            // Storage without code.
+            r#"
+            [evm."0x0000000000000000000000000000000000001000".storage]
+            "0x0000000000000000000000000000000000000000000000000000000000000001" = "1"
+            "#,
+            // Unknown field.
+            r#"evm."0x0000000000000000000000000000000000001000".value = "1""#,
+        ] {
+            assert!(parse_alloc(content).is_err(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_initialize_genesis_alloc_with_cip107() {
+        let alloc_with_sponsor_balance = |balance: U256| {
+            parse_alloc(&format!(
+                r#"
+                [native."0x8000000000000000000000000000000000000001"]
+                code = "0x12345678"
+                storage = {{ "0x0000000000000000000000000000000000000000000000000000000000000001" = "2" }}
+                sponsor = {{ collateral_sponsor = "0x1000000000000000000000000000000000000001", collateral_balance = "{}" }}
+                "#,
+                balance
+            ))
+        };
+        let collateral = *DRIPS_PER_STORAGE_COLLATERAL_UNIT
+            * (code_collateral_units(4) + COLLATERAL_UNITS_PER_STORAGE_KEY);
+        assert!(alloc_with_sponsor_balance(collateral - 1).is_err());
+        let alloc = alloc_with_sponsor_balance(collateral).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut params = CommonParams::default();
+        params.transition_numbers.cip107 = 0;
+        let machine = Arc::new(Machine::new_with_builtin(
+            params,
+            VmFactory::new(1024 * 32),
+        ));
+        let (data_man, genesis) =
+            crate::sync::utils::initialize_data_manager_with_genesis_alloc(
+                dir.path().to_str().unwrap(),
+                DbType::Rocksdb,
+                Arc::new(PowComputer::new(true)),
+                machine,
+                &alloc,
+            );
+        let state_index =
+            data_man.get_state_readonly_index(&genesis.hash()).unwrap();
+        let state = State::new(StateDb::new(
+            data_man
+                .storage_manager
+                .get_state_no_commit(state_index, false, None)
+                .unwrap()
+                .unwrap(),
+        ))
+        .unwrap();
+
+        // The sponsor balance and the storage points are used up.
+        let mut contract = Address::from_low_u64_be(1);
+        contract.set_contract_type_bits();
+        assert_eq!(
+            state.collateral_for_storage(&contract).unwrap(),
+            collateral
+        );
+        assert!(state
+            .sponsor_balance_for_collateral(&contract)
+            .unwrap()
+            .is_zero());
+        assert!(state
+            .available_storage_points_for_collateral(&contract)
+            .unwrap()
+            .is_zero());
+    }
+
+    #[test]
+    fn test_initialize_genesis_alloc() {
+        let alloc = parse_alloc(
+            r#"
+            [native."0x8000000000000000000000000000000000000001"]
+            balance = "0x10"
+            code = "0x12345678"
+            admin = "0x1000000000000000000000000000000000000001"
+            storage = { "0x0000000000000000000000000000000000000000000000000000000000000001" = "2" }
+            sponsor = { collateral_sponsor = "0x1000000000000000000000000000000000000001", collateral_balance = "1000000000000000000", whitelist = ["0x0000000000000000000000000000000000000000"] }
+
+            [evm."0x0000000000000000000000000000000000001000"]
+            nonce = 3
+            code = "0x00"
+            "#,
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let machine = Arc::new(Machine::new_with_builtin(
+            Default::default(),
+            VmFactory::new(1024 * 32),
+        ));
+        let (data_man, genesis) =
+            crate::sync::utils::initialize_data_manager_with_genesis_alloc(
+                dir.path().to_str().unwrap(),
+                DbType::Rocksdb,
+                Arc::new(PowComputer::new(true)),
+                machine,
+                &alloc,
+            );
+        let state_index =
+            data_man.get_state_readonly_index(&genesis.hash()).unwrap();
+        let state = State::new(StateDb::new(
+            data_man
+                .storage_manager
+                .get_state_no_commit(state_index, false, None)
+                .unwrap()
+                .unwrap(),
+        ))
+        .unwrap();
+
+        let mut user_address = Address::from_low_u64_be(1);
+        user_address.set_user_account_type_bits();
+        let mut contract = Address::from_low_u64_be(1);
+        contract.set_contract_type_bits();
+        let native_contract = contract.with_native_space();
+        assert_eq!(state.balance(&native_contract).unwrap(), U256::from(16));
+        assert_eq!(
+            *state.code(&native_contract).unwrap().unwrap(),
+            vec![0x12, 0x34, 0x56, 0x78]
+        );
+        assert_eq!(state.code_owner(&native_contract).unwrap(), contract);
+        assert_eq!(state.admin(&contract).unwrap(), user_address);
+        let entry = state
+            .storage_entry_at(
+                &native_contract,
+                H256::from_low_u64_be(1).as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(entry.value, U256::from(2));
+        assert_eq!(entry.owner, Some(contract));
+
+        // The code, the storage entry and the whitelist entry are charged to
+        // the contract and paid by its sponsor.
+        let collateral = *DRIPS_PER_STORAGE_COLLATERAL_UNIT
+            * (code_collateral_units(4) + 2 * COLLATERAL_UNITS_PER_STORAGE_KEY);
+        assert_eq!(
+            state.collateral_for_storage(&contract).unwrap(),
+            collateral
+        );
+        assert_eq!(
+            state.sponsor_for_collateral(&contract).unwrap(),
+            Some(user_address)
+        );
+        assert_eq!(
+            state.sponsor_balance_for_collateral(&contract).unwrap(),
+            U256::from(ONE_CFX_IN_DRIP) - collateral
+        );
+        assert!(state
+            .check_contract_whitelist(&contract, &Address::zero())
+            .unwrap());
+
+        let evm_contract = Address::from_low_u64_be(0x1000).with_evm_space();
+        assert_eq!(state.nonce(&evm_contract).unwrap(), U256::from(3));
+        assert_eq!(*state.code(&evm_contract).unwrap().unwrap(), vec![0]);
+    }
+}
@@ -39,7 +39,10 @@ pub mod verification;
 pub use crate::{
     block_data_manager::BlockDataManager,
     channel::Notifications,
-    consensus::{BestInformation, ConsensusGraph, SharedConsensusGraph},
+    consensus::{
+        BestInformation, ConsensusGraph, EpochExecutor, ExecutionWitness,
+        SharedConsensusGraph,
+    },
     light_protocol::{
         Handler as LightHandler, Provider as LightProvider,
         QueryService as LightQueryService,
@@ -9,7 +9,9 @@ use crate::{
 };
 use cfx_internal_common::ChainIdParamsOneChainInner;
 use cfx_types::{H160, H256};
-use network::{node_table::NodeId, NetworkContext, UpdateNodeOperation};
+use network::{
+    node_table::NodeId, NetworkContext, ReputationEvent, UpdateNodeOperation,
+};
 use parking_lot::Mutex;
 use primitives::{account::AccountError, filter::FilterError, StateRoot};
 use rlp::DecoderError;
@@ -163,6 +165,10 @@ pub fn handle(
     let reason = format!("{}", e);
     let mut op = None;
 
+    if let Some(event) = reputation_event(e) {
+        io.report_peer(peer, event);
+    }
+
     // NOTE: do not use wildcard; this way, the compiler
     // will help covering all the cases.
     match &e {
@@ -255,6 +261,7 @@ pub fn handle(
             | network::Error::Auth
             | network::Error::BadAddr
             | network::Error::Disconnect(_)
+            | network::Error::DnsDiscovery(_)
             | network::Error::Expired
             | network::Error::InvalidNodeId
             | network::Error::Io(_)
@@ -282,6 +289,39 @@ pub fn handle(
     }
 }
 
+/// The misbehavior of the peer indicated by an error of its message. Wrapped
+/// errors are reported in the recursive call of `handle`.
+fn reputation_event(e: &Error) -> Option<ReputationEvent> {
+    match e {
+        Error::InvalidHeader => Some(ReputationEvent::InvalidBlock),
+        Error::InvalidMessageFormat
+        | Error::Decoder(_)
+        | Error::UnexpectedMessage { .. }
+        | Error::Network(network::Error::BadProtocol)
+        | Error::Network(network::Error::Decoder(_))
+        | Error::Network(network::Error::MessageDeprecated { .. }) => {
+            Some(ReputationEvent::InvalidMessage)
+        }
+        Error::UnexpectedResponse { .. }
+        | Error::InvalidBloom { .. }
+        | Error::InvalidLedgerProofSize { .. }
+        | Error::InvalidPreviousStateRoot { .. }
+        | Error::InvalidReceipts { .. }
+        | Error::InvalidStateProof { .. }
+        | Error::InvalidStateRoot { .. }
+        | Error::InvalidStorageRootProof { .. }
+        | Error::InvalidTxInfo { .. }
+        | Error::InvalidTxRoot { .. }
+        | Error::InvalidTxSignature { .. }
+        | Error::InvalidWitnessRoot { .. } => {
+            Some(ReputationEvent::UselessResponse)
+        }
+        Error::AlreadyThrottled(_) => Some(ReputationEvent::Spam),
+        Error::Timeout(_) => Some(ReputationEvent::Timeout),
+        _ => None,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ClonableError(Arc<Mutex<Error>>);
 
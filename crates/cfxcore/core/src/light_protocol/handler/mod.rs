@@ -27,7 +27,10 @@ use crate::{
         LIGHT_PROTOCOL_VERSION, LIGHT_PROTO_V1,
     },
     message::{decode_msg, decode_rlp_and_check_deprecation, Message, MsgId},
-    sync::{message::Throttled, SynchronizationGraph},
+    sync::{
+        message::Throttled, msg_sender::metric_received_message,
+        SynchronizationGraph,
+    },
     Notifications, UniqueId,
 };
 use cfx_internal_common::ChainIdParamsDeprecated;
@@ -969,6 +972,7 @@ impl NetworkProtocolHandler for Handler {
         };
 
         trace!("on_message: peer={:?}, msgid={:?}", peer, msg_id);
+        metric_received_message(io.get_protocol(), msg_id, raw.len());
 
         if let Err(e) = self.dispatch_message(io, peer, msg_id.into(), rlp) {
             handle_error(io, peer, msg_id.into(), &e);
@@ -35,7 +35,10 @@ use crate::{
         LIGHT_PROTOCOL_VERSION, LIGHT_PROTO_V1,
     },
     message::{decode_msg, decode_rlp_and_check_deprecation, Message, MsgId},
-    sync::{message::Throttled, SynchronizationGraph},
+    sync::{
+        message::Throttled, msg_sender::metric_received_message,
+        SynchronizationGraph,
+    },
     verification::{compute_epoch_receipt_proof, compute_transaction_proof},
     TransactionPool,
 };
@@ -51,7 +54,7 @@ use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use network::{
     node_table::NodeId, service::ProtocolVersion,
     throttling::THROTTLING_SERVICE, NetworkContext, NetworkProtocolHandler,
-    NetworkService, UpdateNodeOperation,
+    NetworkService, ReputationEvent, UpdateNodeOperation,
 };
 use parking_lot::RwLock;
 use primitives::{
@@ -1001,6 +1004,7 @@ impl Provider {
         for peer in self.peers.all_peers_satisfying(|p| {
             p.handshake_completed && p.last_heartbeat.elapsed() >= timeout
         }) {
+            io.report_peer(&peer, ReputationEvent::Timeout);
             io.disconnect_peer(
                 &peer,
                 Some(UpdateNodeOperation::Failure),
@@ -1041,6 +1045,7 @@ impl NetworkProtocolHandler for Provider {
         };
 
         debug!("on_message: peer={:?}, msgid={:?}", peer, msg_id);
+        metric_received_message(io.get_protocol(), msg_id, raw.len());
 
         if let Err(e) = self.dispatch_message(io, peer, msg_id.into(), rlp) {
             handle_error(io, peer, msg_id.into(), &e);
@@ -10,7 +10,7 @@ pub use cfx_bytes::Bytes;
 pub use priority_send_queue::SendQueuePriority;
 use rlp::{Decodable, Rlp};
 
-use crate::sync::msg_sender::metric_message;
+use crate::sync::msg_sender::{metric_message, metric_sent_message};
 use network::{
     node_table::NodeId, parse_msg_id_leb128_2_bytes_at_most,
     service::ProtocolVersion, ProtocolId,
@@ -118,6 +118,7 @@ pub trait Message:
 
         if !io.is_peer_self(node_id) {
             metric_message(self.msg_id(), size);
+            metric_sent_message(io.get_protocol(), self.msg_id(), size);
         }
 
         Ok(())
@@ -42,7 +42,10 @@ use crate::{
             },
         },
     },
-    sync::{Error, ProtocolConfiguration, CHECK_RPC_REQUEST_TIMER},
+    sync::{
+        msg_sender::metric_received_message, Error, ProtocolConfiguration,
+        CHECK_RPC_REQUEST_TIMER,
+    },
 };
 
 use super::{HSB_PROTOCOL_ID, HSB_PROTOCOL_VERSION};
@@ -325,6 +328,7 @@ impl HotStuffSynchronizationProtocol {
                     op = Some(UpdateNodeOperation::Remove)
                 }
                 network::Error::BadAddr => disconnect = false,
+                network::Error::DnsDiscovery(_) => disconnect = false,
                 network::Error::Decoder(_) => {
                     op = Some(UpdateNodeOperation::Remove)
                 }
@@ -495,6 +499,7 @@ impl NetworkProtocolHandler for HotStuffSynchronizationProtocol {
 
         let msg_id = raw[len - 1];
         debug!("on_message: peer={:?}, msgid={:?}", peer, msg_id);
+        metric_received_message(io.get_protocol(), msg_id.into(), len);
 
         let msg = &raw[0..raw.len() - 1];
         self.dispatch_message(io, peer, msg_id.into(), msg)
@@ -35,6 +35,11 @@ impl CacheBuilder {
         self.seedhash.lock().hash_block_height(block_height)
     }
 
+    /// The seed hash of the cache used at `block_height`.
+    pub fn seed_hash(&self, block_height: u64) -> H256 {
+        self.block_height_to_ident(block_height)
+    }
+
     #[allow(dead_code)]
     fn stage_to_ident(&self, stage: u64) -> H256 {
         self.seedhash.lock().hash_stage(stage)
@@ -68,6 +68,7 @@ pub struct ProofOfWorkSolution {
 #[derive(Debug, Clone, DeriveMallocSizeOf)]
 pub enum MiningType {
     Stratum,
+    GetWork,
     CPU,
     Disable,
 }
@@ -78,6 +79,7 @@ impl FromStr for MiningType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mining_type = match s {
             "stratum" => Self::Stratum,
+            "getwork" => Self::GetWork,
             "cpu" => Self::CPU,
             "disable" => Self::Disable,
             _ => return Err("invalid mining type".into()),
@@ -86,6 +88,17 @@ impl FromStr for MiningType {
     }
 }
 
+/// Share difficulty adjustment ("vardiff") of Stratum workers.
+#[derive(Debug, Clone, PartialEq, DeriveMallocSizeOf)]
+pub struct StratumVardiffConfig {
+    /// The lowest share difficulty assigned to a worker.
+    pub min_share_difficulty: u64,
+    /// The expected time between two accepted shares of a worker.
+    pub target_share_interval_ms: u64,
+    /// How often the share difficulty of a worker is re-evaluated.
+    pub retarget_interval_ms: u64,
+}
+
 #[derive(Debug, Clone, DeriveMallocSizeOf)]
 pub struct ProofOfWorkConfig {
     pub test_mode: bool,
@@ -96,6 +109,9 @@ pub struct ProofOfWorkConfig {
     pub stratum_listen_addr: String,
     pub stratum_port: u16,
     pub stratum_secret: Option<H256>,
+    /// If set, Stratum workers mine against their own share difficulty
+    /// instead of the block difficulty.
+    pub stratum_vardiff: Option<StratumVardiffConfig>,
     pub pow_problem_window_size: usize,
     pub cip86_height: u64,
 }
@@ -105,6 +121,7 @@ impl ProofOfWorkConfig {
         test_mode: bool, use_octopus_in_test_mode: bool, mining_type: &str,
         initial_difficulty: Option<u64>, stratum_listen_addr: String,
         stratum_port: u16, stratum_secret: Option<H256>,
+        stratum_vardiff: Option<StratumVardiffConfig>,
         pow_problem_window_size: usize, cip86_height: u64,
     ) -> Self {
         if test_mode {
@@ -117,6 +134,7 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_vardiff,
                 pow_problem_window_size,
                 cip86_height,
             }
@@ -130,6 +148,7 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_vardiff,
                 pow_problem_window_size,
                 cip86_height,
             }
@@ -156,6 +175,10 @@ impl ProofOfWorkConfig {
         matches!(self.mining_type, MiningType::Stratum)
     }
 
+    pub fn use_get_work(&self) -> bool {
+        matches!(self.mining_type, MiningType::GetWork)
+    }
+
     pub fn enable_mining(&self) -> bool {
         !matches!(self.mining_type, MiningType::Disable)
     }
@@ -270,6 +293,28 @@ pub fn compute_inv_x_times_2_pow_256_floor(x: &U256) -> U256 {
     }
 }
 
+/// Interface for remote miners which poll for work instead of subscribing to
+/// it, e.g. through `eth_getWork` and `eth_submitWork`.
+pub trait PowWorkProvider: Send + Sync {
+    /// Return the latest PoW problem together with the seed hash of its PoW
+    /// cache, or `None` if there is no work to be served.
+    fn work(&self) -> Option<(ProofOfWorkProblem, H256)>;
+
+    /// Submit a solution to the recent problem identified by `pow_hash`.
+    /// Return `true` if the solution is valid and accepted.
+    fn submit_work(&self, pow_hash: H256, nonce: U256) -> bool;
+
+    /// Record the hashrate reported by the remote miner `id`. Return `false`
+    /// if remote mining is not enabled.
+    fn submit_hashrate(&self, hashrate: U256, id: H256) -> bool;
+
+    /// The total hashrate recently reported by remote miners.
+    fn hashrate(&self) -> U256;
+
+    /// Whether the node serves work to remote miners.
+    fn is_mining(&self) -> bool;
+}
+
 pub struct PowComputer {
     use_octopus: bool,
     cache_builder: CacheBuilder,
@@ -305,6 +350,16 @@ impl PowComputer {
                 .into()
         }
     }
+
+    /// The seed hash of the PoW cache used at `block_height`. It is zero if
+    /// octopus is not used.
+    pub fn seed_hash(&self, block_height: u64) -> H256 {
+        if !self.use_octopus {
+            H256::zero()
+        } else {
+            self.cache_builder.seed_hash(block_height).into()
+        }
+    }
 }
 
 pub fn validate(
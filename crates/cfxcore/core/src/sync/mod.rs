@@ -31,8 +31,9 @@ pub use self::{
         SynchronizationPhaseManager, SynchronizationPhaseTrait,
     },
     synchronization_protocol_handler::{
-        LocalMessageTask, ProtocolConfiguration, SyncHandlerWorkType,
-        SynchronizationProtocolHandler, CHECK_RPC_REQUEST_TIMER,
+        sync_message_span, LocalMessageTask, ProtocolConfiguration,
+        SyncHandlerWorkType, SynchronizationProtocolHandler,
+        CHECK_RPC_REQUEST_TIMER,
     },
     synchronization_service::{
         SharedSynchronizationService, SynchronizationService,
@@ -69,11 +70,67 @@ pub mod random {
 pub mod msg_sender {
     use super::message::msgid;
     use crate::message::MsgId;
-    use metrics::{register_meter_with_group, Meter};
+    use metrics::{
+        is_enabled, register_meter_with_group, Counter, CounterVec, Meter,
+    };
+    use network::ProtocolId;
     use std::sync::Arc;
 
     pub const NULL: usize = !0;
 
+    lazy_static! {
+        static ref SENT_MESSAGE_COUNTER: Arc<CounterVec> =
+            CounterVec::register_with_group(
+                "network_message",
+                "sent_total",
+                &["protocol", "msg_id"]
+            );
+        static ref SENT_MESSAGE_BYTES: Arc<CounterVec> =
+            CounterVec::register_with_group(
+                "network_message",
+                "sent_bytes_total",
+                &["protocol", "msg_id"]
+            );
+        static ref RECEIVED_MESSAGE_COUNTER: Arc<CounterVec> =
+            CounterVec::register_with_group(
+                "network_message",
+                "received_total",
+                &["protocol", "msg_id"]
+            );
+        static ref RECEIVED_MESSAGE_BYTES: Arc<CounterVec> =
+            CounterVec::register_with_group(
+                "network_message",
+                "received_bytes_total",
+                &["protocol", "msg_id"]
+            );
+    }
+
+    /// Counts the messages sent to peers by protocol and message id.
+    pub fn metric_sent_message(
+        protocol: ProtocolId, msg_id: MsgId, size: usize,
+    ) {
+        if is_enabled() {
+            let protocol = String::from_utf8_lossy(&protocol);
+            let msg_id = msg_id.to_string();
+            let labels = [protocol.as_ref(), msg_id.as_str()];
+            SENT_MESSAGE_COUNTER.with_label_values(&labels).inc(1);
+            SENT_MESSAGE_BYTES.with_label_values(&labels).inc(size);
+        }
+    }
+
+    /// Counts the messages received from peers by protocol and message id.
+    pub fn metric_received_message(
+        protocol: ProtocolId, msg_id: MsgId, size: usize,
+    ) {
+        if is_enabled() {
+            let protocol = String::from_utf8_lossy(&protocol);
+            let msg_id = msg_id.to_string();
+            let labels = [protocol.as_ref(), msg_id.as_str()];
+            RECEIVED_MESSAGE_COUNTER.with_label_values(&labels).inc(1);
+            RECEIVED_MESSAGE_BYTES.with_label_values(&labels).inc(size);
+        }
+    }
+
     lazy_static! {
         static ref GET_BLOCK_TXN_RESPOPNSE_METER: Arc<dyn Meter> =
             register_meter_with_group(
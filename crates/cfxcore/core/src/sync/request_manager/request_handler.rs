@@ -16,7 +16,7 @@ use cfx_parameters::sync::FAILED_REQUEST_RESEND_WAIT;
 use malloc_size_of::MallocSizeOf;
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use network::{
-    node_table::NodeId, Error as NetworkError, NetworkContext,
+    node_table::NodeId, Error as NetworkError, NetworkContext, ReputationEvent,
     UpdateNodeOperation,
 };
 use parking_lot::Mutex;
@@ -193,6 +193,7 @@ impl RequestHandler {
         for peer_id in peers_to_disconnect {
             // Note `self.peers` will be used in `disconnect_peer`, so we must
             // call it without locking `self.peers`.
+            io.report_peer(&peer_id, ReputationEvent::Timeout);
             io.disconnect_peer(
                 &peer_id,
                 op,
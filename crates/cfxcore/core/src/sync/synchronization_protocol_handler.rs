@@ -16,6 +16,7 @@ use crate::{
             GetBlockHeadersResponse, Heartbeat, NewBlockHashes, StatusV2,
             StatusV3, TransactionDigests,
         },
+        msg_sender::metric_received_message,
         request_manager::{try_get_block_hashes, Request},
         state::SnapshotChunkSync,
         synchronization_phases::{SyncPhaseType, SynchronizationPhaseManager},
@@ -37,7 +38,8 @@ use metrics::{register_meter_with_group, Meter, MeterTimer};
 use network::{
     node_table::NodeId, service::ProtocolVersion,
     throttling::THROTTLING_SERVICE, Error as NetworkError, HandlerWorkType,
-    NetworkContext, NetworkProtocolHandler, UpdateNodeOperation,
+    NetworkContext, NetworkProtocolHandler, ReputationEvent,
+    UpdateNodeOperation,
 };
 use parking_lot::{Mutex, RwLock};
 use primitives::{Block, BlockHeader, EpochId, SignedTransaction};
@@ -49,6 +51,7 @@ use std::{
     sync::Arc,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use tracing::{info_span, Span};
 
 lazy_static! {
     static ref TX_PROPAGATE_METER: Arc<dyn Meter> =
@@ -583,6 +586,7 @@ impl SynchronizationProtocolHandler {
             warn!("Unknown message: peer={:?} msgid={:?}", peer, msg_id);
             let reason =
                 format!("unknown sync protocol message id {:?}", msg_id);
+            io.report_peer(peer, ReputationEvent::InvalidMessage);
             io.disconnect_peer(
                 peer,
                 Some(UpdateNodeOperation::Remove),
@@ -602,6 +606,7 @@ impl SynchronizationProtocolHandler {
         let reason = format!("{}", e);
         let error_reason = format!("{:?}", e);
         let mut op = None;
+        let event = Self::reputation_event(&e);
 
         // NOTE, DO NOT USE WILDCARD IN THE FOLLOWING MATCH STATEMENT!
         // COMPILER WILL HELP TO FIND UNHANDLED ERROR CASES.
@@ -679,6 +684,7 @@ impl SynchronizationProtocolHandler {
                     op = Some(UpdateNodeOperation::Remove)
                 }
                 network::Error::BadAddr => disconnect = false,
+                network::Error::DnsDiscovery(_) => disconnect = false,
                 network::Error::Decoder(_) => {
                     op = Some(UpdateNodeOperation::Remove)
                 }
@@ -718,11 +724,41 @@ impl SynchronizationProtocolHandler {
             );
         }
 
+        if let Some(event) = event {
+            io.report_peer(peer, event);
+        }
         if disconnect {
             io.disconnect_peer(peer, op, reason.as_str());
         }
     }
 
+    /// The misbehavior of the peer indicated by an error of its message.
+    fn reputation_event(e: &Error) -> Option<ReputationEvent> {
+        match e {
+            Error::InvalidBlock => Some(ReputationEvent::InvalidBlock),
+            Error::InvalidGetBlockTxn(_)
+            | Error::InvalidStatus(_)
+            | Error::InvalidMessageFormat
+            | Error::Decoder(_)
+            | Error::UnexpectedMessage(_)
+            | Error::Network(NetworkError::BadProtocol)
+            | Error::Network(NetworkError::Decoder(_))
+            | Error::Network(NetworkError::MessageDeprecated { .. }) => {
+                Some(ReputationEvent::InvalidMessage)
+            }
+            Error::UnexpectedResponse
+            | Error::InvalidTimestamp
+            | Error::InvalidSnapshotManifest(_)
+            | Error::InvalidSnapshotChunk(_) => {
+                Some(ReputationEvent::UselessResponse)
+            }
+            Error::TooManyTrans | Error::AlreadyThrottled(_) => {
+                Some(ReputationEvent::Spam)
+            }
+            _ => None,
+        }
+    }
+
     pub fn start_sync(&self, io: &dyn NetworkContext) {
         let current_phase_type =
             self.phase_manager.get_current_phase().phase_type();
@@ -1770,6 +1806,11 @@ impl SynchronizationProtocolHandler {
     }
 }
 
+/// The span of handling a synchronization message from `peer`.
+pub fn sync_message_span(peer: &NodeId, msg_id: MsgId) -> Span {
+    info_span!("sync_message", peer = %peer, msg_id = ?msg_id)
+}
+
 impl NetworkProtocolHandler for SynchronizationProtocolHandler {
     fn minimum_supported_version(&self) -> ProtocolVersion {
         let my_version = self.protocol_version.0;
@@ -1845,6 +1886,8 @@ impl NetworkProtocolHandler for SynchronizationProtocolHandler {
         };
 
         debug!("on_message: peer={}, msgid={:?}", peer, msg_id);
+        metric_received_message(io.get_protocol(), msg_id, raw.len());
+        let _span = sync_message_span(peer, msg_id).entered();
 
         self.dispatch_message(io, peer, msg_id.into(), rlp)
             .unwrap_or_else(|e| self.handle_error(io, peer, msg_id.into(), e));
@@ -1937,6 +1980,7 @@ impl NetworkProtocolHandler for SynchronizationProtocolHandler {
                     self.protocol_config.heartbeat_timeout,
                 );
                 for peer in timeout_peers {
+                    io.report_peer(&peer, ReputationEvent::Timeout);
                     io.disconnect_peer(
                         &peer,
                         Some(UpdateNodeOperation::Failure),
@@ -34,7 +34,7 @@ use crate::{
         ConsensusConfig, ConsensusInnerConfig,
     },
     db::NUM_COLUMNS,
-    genesis_block::{genesis_block, GenesisPosState},
+    genesis_block::{genesis_block, GenesisAlloc, GenesisPosState},
     pow::{self, PowComputer, ProofOfWorkConfig},
     statistics::Statistics,
     sync::{SyncGraphConfig, SynchronizationGraph},
@@ -109,6 +109,22 @@ pub fn create_simple_block(
 
 pub fn initialize_data_manager(
     db_dir: &str, dbtype: DbType, pow: Arc<PowComputer>, vm: VmFactory,
+) -> (Arc<BlockDataManager>, Arc<Block>) {
+    let machine = Arc::new(Machine::new_with_builtin(Default::default(), vm));
+    initialize_data_manager_with_genesis_alloc(
+        db_dir,
+        dbtype,
+        pow,
+        machine,
+        &Default::default(),
+    )
+}
+
+/// Initializes the data manager with the accounts in `genesis_alloc` written
+/// into the genesis state.
+pub fn initialize_data_manager_with_genesis_alloc(
+    db_dir: &str, dbtype: DbType, pow: Arc<PowComputer>, machine: Arc<Machine>,
+    genesis_alloc: &GenesisAlloc,
 ) -> (Arc<BlockDataManager>, Arc<Block>) {
     let ledger_db = db::open_database(
         db_dir,
@@ -145,22 +161,25 @@ pub fn initialize_data_manager(
         U256::from(0),
     );
 
-    let machine = Arc::new(Machine::new_with_builtin(Default::default(), vm));
-
-    let genesis_block = Arc::new(genesis_block(
-        &storage_manager,
-        genesis_accounts,
-        Address::from_str("1000000000000000000000000000000000000008").unwrap(),
-        U256::from(10),
-        machine.clone(),
-        false, /* need_to_execute */
-        None,
-        &Some(GenesisPosState {
-            initial_nodes: vec![],
-            initial_committee: vec![],
-            initial_seed: Default::default(),
-        }),
-    ));
+    let genesis_block = Arc::new(
+        genesis_block(
+            &storage_manager,
+            genesis_accounts,
+            genesis_alloc,
+            Address::from_str("1000000000000000000000000000000000000008")
+                .unwrap(),
+            U256::from(10),
+            machine.clone(),
+            false, /* need_to_execute */
+            None,
+            &Some(GenesisPosState {
+                initial_nodes: vec![],
+                initial_committee: vec![],
+                initial_seed: Default::default(),
+            }),
+        )
+        .expect("Failed to initialize genesis block."),
+    );
 
     let data_man = Arc::new(BlockDataManager::new(
         CacheConfig::default(),
@@ -180,15 +199,10 @@ pub fn initialize_data_manager(
     (data_man, genesis_block)
 }
 
-pub fn initialize_synchronization_graph_with_data_manager(
-    data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
-    era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
-) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>) {
-    let mut params = CommonParams::default();
-    params.transition_heights.cip1559 = u64::MAX;
-    let machine = Arc::new(Machine::new_with_builtin(params.clone(), vm));
+/// Makes a PoS verifier which is never enabled.
+pub fn initialize_disabled_pos_verifier() -> Arc<PosVerifier> {
     let mut rng = StdRng::from_seed([0u8; 32]);
-    let pos_verifier = Arc::new(PosVerifier::new(
+    Arc::new(PosVerifier::new(
         None,
         // These configurations will not be used.
         PosConfiguration {
@@ -201,7 +215,17 @@ pub fn initialize_synchronization_graph_with_data_manager(
             pos_state_config: Default::default(),
         },
         u64::MAX,
-    ));
+    ))
+}
+
+pub fn initialize_synchronization_graph_with_data_manager(
+    data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
+    era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
+) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>) {
+    let mut params = CommonParams::default();
+    params.transition_heights.cip1559 = u64::MAX;
+    let machine = Arc::new(Machine::new_with_builtin(params.clone(), vm));
+    let pos_verifier = initialize_disabled_pos_verifier();
 
     let verification_config = VerificationConfig::new(
         true, /* test_mode */
@@ -229,6 +253,7 @@ pub fn initialize_synchronization_graph_with_data_manager(
         String::from(""), /* stratum_listen_addr */
         0,                /* stratum_port */
         None,             /* stratum_secret */
+        None,             /* stratum_vardiff */
         1,                /* pow_problem_window_size */
         0,                /* cip_height */
     );
@@ -278,6 +303,8 @@ pub fn initialize_synchronization_graph_with_data_manager(
         notifications.clone(),
         ConsensusExecutionConfiguration {
             executive_trace: false,
+            parallel_execution_threads: 0,
+            parallel_execution_cross_check: false,
         },
         verification_config.clone(),
         NodeType::Archive,
@@ -60,8 +60,8 @@ use transaction_pool_inner::TransactionPoolInner;
 
 pub struct TxPoolConfig {
     pub capacity: usize,
-    pub min_native_tx_price: u64,
-    pub min_eth_tx_price: u64,
+    pub min_native_tx_price: RwLock<u64>,
+    pub min_eth_tx_price: RwLock<u64>,
     pub half_block_gas_limit: RwLock<U256>,
     pub allow_gas_over_half_block: bool,
     pub target_block_gas_limit: u64,
@@ -78,8 +78,8 @@ impl Default for TxPoolConfig {
     fn default() -> Self {
         TxPoolConfig {
             capacity: 500_000,
-            min_native_tx_price: 1,
-            min_eth_tx_price: 1,
+            min_native_tx_price: RwLock::new(1),
+            min_eth_tx_price: RwLock::new(1),
             half_block_gas_limit: RwLock::new(U256::from(
                 DEFAULT_TARGET_BLOCK_GAS_LIMIT,
             )),
@@ -93,6 +93,13 @@ impl Default for TxPoolConfig {
 }
 
 impl TxPoolConfig {
+    pub fn min_tx_price(&self, space: Space) -> u64 {
+        match space {
+            Space::Native => *self.min_native_tx_price.read(),
+            Space::Ethereum => *self.min_eth_tx_price.read(),
+        }
+    }
+
     pub fn check_gas_price_and_limit(
         &self, tx: &TransactionWithSignature,
     ) -> Result<(), TransactionPoolError> {
@@ -112,10 +119,7 @@ impl TxPoolConfig {
         // differences.
         let block_gas_target = half_block_gas_limit;
 
-        let min_tx_price = match tx.space() {
-            Space::Native => self.min_native_tx_price,
-            Space::Ethereum => self.min_eth_tx_price,
-        };
+        let min_tx_price = self.min_tx_price(tx.space());
 
         let space_gas_target: U256 = match tx.space() {
             Space::Native => {
@@ -435,6 +439,7 @@ impl TransactionPool {
 
         if transactions.is_empty() {
             INSERT_TXS_SUCCESS_TPS.mark(passed_transactions.len());
+            mark_inserted_txs(&passed_transactions);
             INSERT_TXS_FAILURE_TPS.mark(failure.len());
             return (passed_transactions, failure);
         }
@@ -494,6 +499,7 @@ impl TransactionPool {
         TX_POOL_READY_GAUGE.update(self.total_ready_accounts());
 
         INSERT_TXS_SUCCESS_TPS.mark(passed_transactions.len());
+        mark_inserted_txs(&passed_transactions);
         INSERT_TXS_FAILURE_TPS.mark(failure.len());
 
         (passed_transactions, failure)
@@ -566,6 +572,7 @@ impl TransactionPool {
 
         if signed_transactions.is_empty() {
             INSERT_TXS_SUCCESS_TPS.mark(passed_transactions.len());
+            mark_inserted_txs(&passed_transactions);
             INSERT_TXS_FAILURE_TPS.mark(failure.len());
             return (passed_transactions, failure);
         }
@@ -608,6 +615,7 @@ impl TransactionPool {
         TX_POOL_READY_GAUGE.update(self.total_ready_accounts());
 
         INSERT_TXS_SUCCESS_TPS.mark(passed_transactions.len());
+        mark_inserted_txs(&passed_transactions);
         INSERT_TXS_FAILURE_TPS.mark(failure.len());
 
         (passed_transactions, failure)
@@ -1,4 +1,9 @@
-use metrics::{register_meter_with_group, Gauge, GaugeUsize, Lock, Meter};
+use cfx_types::Space;
+use metrics::{
+    register_meter_with_group, Counter, CounterVec, Gauge, GaugeUsize, Lock,
+    Meter,
+};
+use primitives::SignedTransaction;
 use std::sync::Arc;
 
 // Metrics for transaction pool.
@@ -17,6 +22,12 @@ lazy_static! {
         register_meter_with_group("txpool", "insert_txs_success_tps");
     pub static ref INSERT_TXS_FAILURE_TPS: Arc<dyn Meter> =
         register_meter_with_group("txpool", "insert_txs_failure_tps");
+    pub static ref INSERT_TXS_BY_SPACE: Arc<CounterVec> =
+        CounterVec::register_with_group(
+            "txpool",
+            "inserted_txs_total",
+            &["space"]
+        );
     pub static ref TX_POOL_INSERT_TIMER: Arc<dyn Meter> =
         register_meter_with_group("timer", "tx_pool::insert_new_tx");
     pub static ref TX_POOL_VERIFY_TIMER: Arc<dyn Meter> =
@@ -35,6 +46,20 @@ lazy_static! {
         Lock::register("txpool_notify_modified_info");
 }
 
+/// Counts the inserted transactions by space.
+pub fn mark_inserted_txs(txs: &[Arc<SignedTransaction>]) {
+    for space in [Space::Native, Space::Ethereum] {
+        let count = txs.iter().filter(|tx| tx.space() == space).count();
+        if count > 0 {
+            let label = match space {
+                Space::Native => "native",
+                Space::Ethereum => "evm",
+            };
+            INSERT_TXS_BY_SPACE.with_label_values(&[label]).inc(count);
+        }
+    }
+}
+
 // Metrics for transaction pool inner.
 pub mod pool_inner_metrics {
     use metrics::{register_meter_with_group, Counter, CounterUsize, Meter};
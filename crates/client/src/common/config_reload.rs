@@ -0,0 +1,177 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::sync::Arc;
+
+use cfx_config::{Configuration, RawConfiguration};
+use cfx_types::Space;
+use cfxcore::TransactionPool;
+use log::info;
+use network::NetworkService;
+use parking_lot::Mutex;
+use serde::Serialize;
+use throttling::token_bucket::reload_managers;
+
+use super::logger::{reload_logger, reloaded_log_config};
+
+const LOG_KEYS: [&str; 2] = ["log_file", "log_level"];
+const TXPOOL_KEYS: [&str; 2] = [
+    "tx_pool_min_eth_tx_gas_price",
+    "tx_pool_min_native_tx_gas_price",
+];
+const PEER_LIMIT_KEYS: [&str; 3] = [
+    "max_incoming_peers",
+    "max_outgoing_peers",
+    "max_outgoing_peers_archive",
+];
+
+/// The changed configurations found by a reload.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReloadReport {
+    /// Configurations changed since the last reload, which are applied.
+    pub applied: Vec<String>,
+    /// Configurations changed since startup, which take effect only after
+    /// restart.
+    pub restart_required: Vec<String>,
+}
+
+/// Reloads the configuration file, on SIGHUP or RPC `admin_reloadConfig`,
+/// and applies the changes which are safe without restart:
+/// * The throttling file of RPCs is read again even if it is not changed in the
+///   configuration. Its path is only read at startup. Note, the P2P protocols
+///   read the throttling file whenever a peer connects.
+/// * `log_level` and `log_file`, unless the logger is configured by `log_conf`,
+///   which could be reloaded by log4rs with `refresh_rate`.
+/// * The minimum gas prices of the transaction pool.
+/// * The limits of P2P peers. Existing peers are kept if the limits decrease.
+pub struct ConfigReloader {
+    /// The configuration at startup, or `None` if it is not parsed from
+    /// command line and thus could not be reloaded.
+    startup: Option<Configuration>,
+    /// The configuration applied by the last reload.
+    last: Mutex<RawConfiguration>,
+    txpool: Arc<TransactionPool>,
+    network: Arc<NetworkService>,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        conf: &Configuration, txpool: Arc<TransactionPool>,
+        network: Arc<NetworkService>,
+    ) -> Self {
+        // Parse the configuration again because `conf` is modified during
+        // startup, e.g. to fill in the defaults of the node type.
+        let startup = conf.reparse().ok();
+        let last = startup
+            .as_ref()
+            .map_or_else(Default::default, |c| c.raw_conf.clone());
+
+        ConfigReloader {
+            startup,
+            last: Mutex::new(last),
+            txpool,
+            network,
+        }
+    }
+
+    pub fn reload(&self) -> Result<ConfigReloadReport, String> {
+        let startup = self.startup.as_ref().ok_or_else(|| {
+            "configuration is not parsed from command line".to_string()
+        })?;
+        let conf = startup.reparse()?;
+
+        let mut last = self.last.lock();
+        let changed = conf.raw_conf.changed_keys(&last);
+        let changed_any =
+            |keys: &[&str]| changed.iter().any(|key| keys.contains(key));
+
+        // Check everything which could fail before applying any change, so
+        // that a failed reload changes nothing and `last` stays the applied
+        // configuration.
+        let log_config = if changed_any(&LOG_KEYS) {
+            reloaded_log_config(&conf)?
+        } else {
+            None
+        };
+        if changed_any(&PEER_LIMIT_KEYS) && self.network.inner.is_none() {
+            return Err("network service not started yet".into());
+        }
+        // The throttling managers are changed only if all of them are
+        // reloaded.
+        reload_managers(startup.raw_conf.throttling_conf.as_deref())?;
+
+        if let Some(log_config) = log_config {
+            reload_logger(log_config);
+        }
+
+        if changed_any(&TXPOOL_KEYS) {
+            let txpool_config = conf.txpool_config();
+            *self.txpool.config.min_native_tx_price.write() =
+                txpool_config.min_tx_price(Space::Native);
+            *self.txpool.config.min_eth_tx_price.write() =
+                txpool_config.min_tx_price(Space::Ethereum);
+        }
+
+        if changed_any(&PEER_LIMIT_KEYS) {
+            self.network
+                .set_peer_limits(
+                    conf.raw_conf.max_incoming_peers,
+                    conf.raw_conf.max_outgoing_peers,
+                    conf.raw_conf.max_outgoing_peers_archive,
+                )
+                .map_err(|e| e.to_string())?;
+        }
+
+        let report = ConfigReloadReport {
+            applied: changed
+                .into_iter()
+                .filter(|key| is_live(key))
+                .map(Into::into)
+                .collect(),
+            restart_required: conf
+                .raw_conf
+                .changed_keys(&startup.raw_conf)
+                .into_iter()
+                .filter(|key| !is_live(key))
+                .map(Into::into)
+                .collect(),
+        };
+        *last = conf.raw_conf;
+
+        info!("Configuration reloaded: {:?}", report);
+        Ok(report)
+    }
+}
+
+fn is_live(key: &str) -> bool {
+    LOG_KEYS.contains(&key)
+        || TXPOOL_KEYS.contains(&key)
+        || PEER_LIMIT_KEYS.contains(&key)
+}
+
+/// Reloads the configuration whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub fn reload_on_sighup(
+    reloader: Arc<ConfigReloader>, runtime: &tokio::runtime::Runtime,
+) {
+    use log::warn;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    runtime.spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!("Failed to listen to SIGHUP: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading configuration");
+            if let Err(e) = reloader.reload() {
+                warn!("Failed to reload configuration: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,350 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::sync::OnceLock;
+
+use cfx_config::Configuration;
+use log::{warn, LevelFilter};
+use log4rs::{
+    append::{console::ConsoleAppender, file::FileAppender},
+    config::{Appender, Config as LogConfig, Logger, Root},
+    encode::pattern::PatternEncoder,
+    Handle,
+};
+
+/// Handle of the logger configured by `log_file` and `log_level`, which is
+/// used to apply the changes of them without restart.
+static LOG_HANDLE: OnceLock<Handle> = OnceLock::new();
+
+// If log_conf is provided, use it for log configuration and ignore
+// log_file and log_level. Otherwise, set stdout to INFO and set
+// all our crate log to log_level.
+pub fn setup_logger(conf: &Configuration) -> Result<(), String> {
+    match conf.raw_conf.log_conf {
+        Some(ref log_conf) => {
+            // The changes of the log config file are applied by log4rs if
+            // `refresh_rate` is set in the file.
+            log4rs::init_file(log_conf, Default::default()).map_err(|e| {
+                format!(
+                    "failed to initialize log with log config file: {:?}",
+                    e
+                )
+            })?;
+        }
+        None => {
+            let handle =
+                log4rs::init_config(log_config(conf)?).map_err(|e| {
+                    format!("failed to initialize log with config: {:?}", e)
+                })?;
+            LOG_HANDLE
+                .set(handle)
+                .map_err(|_| "logger is already initialized".to_string())?;
+        }
+    };
+
+    Ok(())
+}
+
+/// Builds the logger configuration of `log_file` and `log_level` of `conf`,
+/// which is applied by `reload_logger`. Returns `None` if the logger is
+/// configured by `log_conf`, which ignores them.
+pub fn reloaded_log_config(
+    conf: &Configuration,
+) -> Result<Option<LogConfig>, String> {
+    match LOG_HANDLE.get() {
+        Some(_) => log_config(conf).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Applies the configuration built by `reloaded_log_config` to the logger.
+pub fn reload_logger(config: LogConfig) {
+    if let Some(handle) = LOG_HANDLE.get() {
+        handle.set_config(config);
+    }
+}
+
+fn log_config(conf: &Configuration) -> Result<LogConfig, String> {
+    let mut conf_builder = LogConfig::builder().appender(
+        Appender::builder()
+            .build("stdout", Box::new(ConsoleAppender::builder().build())),
+    );
+    let mut root_builder = Root::builder().appender("stdout");
+    if let Some(ref log_file) = conf.raw_conf.log_file {
+        conf_builder = conf_builder.appender(
+            Appender::builder().build(
+                "logfile",
+                Box::new(
+                    FileAppender::builder()
+                        .encoder(Box::new(PatternEncoder::new(
+                            "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}",
+                        )))
+                        .build(log_file)
+                        .map_err(|e| {
+                            format!("failed to build log pattern: {:?}", e)
+                        })?,
+                ),
+            ),
+        );
+        root_builder = root_builder.appender("logfile");
+    };
+    // Should add new crate names here
+    for crate_name in [
+        "blockgen",
+        "cfxcore",
+        "cfx_statedb",
+        "cfx_storage",
+        "conflux",
+        "db",
+        "keymgr",
+        "network",
+        "txgen",
+        "client",
+        "primitives",
+        "io",
+    ]
+    .iter()
+    {
+        conf_builder = conf_builder.logger(
+            Logger::builder().build(*crate_name, conf.raw_conf.log_level),
+        );
+    }
+    conf_builder
+        .build(root_builder.build(LevelFilter::Info))
+        .map_err(|e| format!("failed to build log config: {:?}", e))
+}
+
+/// The crates whose tracing spans are exported.
+#[cfg(any(test, feature = "otlp"))]
+const TRACING_TARGETS: [&str; 3] = ["cfxcore", "client", "cfx_rpc_middlewares"];
+
+#[cfg(feature = "otlp")]
+struct OtlpTracing {
+    provider: opentelemetry_sdk::trace::TracerProvider,
+    /// The runtime of the batch span processor and the gRPC client, which
+    /// is separated from the runtimes of the client so that spans are
+    /// exported since startup.
+    _runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "otlp")]
+static OTLP_TRACING: OnceLock<OtlpTracing> = OnceLock::new();
+
+/// Exports the tracing spans to the OpenTelemetry collector at
+/// `otlp_endpoint`, if it is set. The spans are filtered by `log_level`.
+#[cfg(feature = "otlp")]
+pub fn setup_tracing(conf: &Configuration) -> Result<(), String> {
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let endpoint = match conf.raw_conf.otlp_endpoint {
+        Some(ref endpoint) => endpoint.clone(),
+        None => return Ok(()),
+    };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("otlp-exporter")
+        .enable_all()
+        .build()
+        .map_err(|e| format!("failed to create OTLP runtime: {:?}", e))?;
+    let provider = {
+        let _guard = runtime.enter();
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| format!("failed to build OTLP exporter: {:?}", e))?;
+        TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                conf.raw_conf.otlp_service_name.clone(),
+            )]))
+            .build()
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_filter(conf.raw_conf.log_level))
+        .with(
+            tracing_opentelemetry::layer()
+                .with_tracer(provider.tracer("conflux")),
+        );
+    // Do not use `try_init`, which also redirects `log` records to `tracing`
+    // and conflicts with log4rs.
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| format!("failed to set tracing subscriber: {:?}", e))?;
+
+    OTLP_TRACING
+        .set(OtlpTracing {
+            provider,
+            _runtime: runtime,
+        })
+        .map_err(|_| "tracing is already initialized".to_string())
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn setup_tracing(conf: &Configuration) -> Result<(), String> {
+    if conf.raw_conf.otlp_endpoint.is_some() {
+        warn!(
+            "otlp_endpoint is ignored because the client is built without \
+             feature `otlp`"
+        );
+    }
+    Ok(())
+}
+
+/// Exports the remaining spans before exit.
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otlp")]
+    if let Some(otlp) = OTLP_TRACING.get() {
+        if let Err(e) = otlp.provider.shutdown() {
+            warn!("failed to shutdown OTLP exporter: {:?}", e);
+        }
+    }
+}
+
+/// Filters the spans of `TRACING_TARGETS` by `level`.
+#[cfg(any(test, feature = "otlp"))]
+fn tracing_filter(level: LevelFilter) -> tracing_subscriber::filter::Targets {
+    let level = tracing_level(level);
+    tracing_subscriber::filter::Targets::new()
+        .with_targets(TRACING_TARGETS.iter().map(|target| (*target, level)))
+}
+
+#[cfg(any(test, feature = "otlp"))]
+fn tracing_level(level: LevelFilter) -> tracing::level_filters::LevelFilter {
+    use tracing::level_filters::LevelFilter as TracingLevelFilter;
+    match level {
+        LevelFilter::Off => TracingLevelFilter::OFF,
+        LevelFilter::Error => TracingLevelFilter::ERROR,
+        LevelFilter::Warn => TracingLevelFilter::WARN,
+        LevelFilter::Info => TracingLevelFilter::INFO,
+        LevelFilter::Debug => TracingLevelFilter::DEBUG,
+        LevelFilter::Trace => TracingLevelFilter::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tracing_filter;
+    use crate::rpc::interceptor::{RpcInterceptor, RpcProxy};
+    use cfx_types::H256;
+    use cfxcore::{
+        consensus::consensus_inner::consensus_executor::epoch_execution_span,
+        sync::sync_message_span,
+    };
+    use futures::future;
+    use jsonrpc_core::{
+        MetaIoHandler, Params, RemoteProcedure, Result as RpcResult, Value,
+    };
+    use log::LevelFilter;
+    use network::node_table::NodeId;
+    use parking_lot::Mutex;
+    use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+    use tracing::{
+        field::{Field, Visit},
+        info_span,
+        span::{Attributes, Id},
+        Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        Layer,
+    };
+
+    /// Records the name and the fields of the created spans.
+    #[derive(Clone, Default)]
+    struct SpanCapture {
+        spans: Arc<Mutex<Vec<(&'static str, BTreeMap<String, String>)>>>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.insert(field.name().into(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanCapture {
+        fn on_new_span(
+            &self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>,
+        ) {
+            let mut fields = BTreeMap::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            self.spans.lock().push((attrs.metadata().name(), fields));
+        }
+    }
+
+    struct NoopInterceptor;
+
+    impl RpcInterceptor for NoopInterceptor {
+        fn before(&self, _name: &String) -> RpcResult<()> { Ok(()) }
+    }
+
+    fn epoch_number(_: Params, _: ()) -> future::Ready<RpcResult<Value>> {
+        future::ready(Ok(Value::from(1)))
+    }
+
+    fn fields(fields: &[(&str, String)]) -> BTreeMap<String, String> {
+        fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tracing_spans() {
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_filter(LevelFilter::Info))
+            .with(capture.clone());
+
+        let epoch = H256::repeat_byte(1);
+        let peer = NodeId::repeat_byte(2);
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = epoch_execution_span(&epoch, 3, 10).entered();
+            let _span = sync_message_span(&peer, 0x12).entered();
+
+            let methods = vec![(
+                "cfx_epochNumber".to_string(),
+                RemoteProcedure::Method(Arc::new(epoch_number)),
+            )];
+            let mut handler: MetaIoHandler<()> = MetaIoHandler::default();
+            handler.extend_with(RpcProxy::new(methods, NoopInterceptor));
+            let request = r#"{"jsonrpc": "2.0", "method": "cfx_epochNumber", "params": [], "id": 1}"#;
+            assert!(handler.handle_request_sync(request, ()).is_some());
+
+            // The spans of other crates or below `log_level` are not
+            // exported.
+            let _span = info_span!(target: "network", "ignored").entered();
+            let _span = tracing::debug_span!("ignored").entered();
+        });
+
+        assert_eq!(
+            *capture.spans.lock(),
+            vec![
+                (
+                    "epoch_execution",
+                    fields(&[
+                        ("epoch", format!("{:?}", epoch)),
+                        ("blocks", "3".into()),
+                        ("start_block_number", "10".into()),
+                    ])
+                ),
+                (
+                    "sync_message",
+                    fields(&[
+                        ("peer", format!("{}", peer)),
+                        ("msg_id", "18".into()),
+                    ])
+                ),
+                ("rpc", fields(&[("method", "cfx_epochNumber".into())])),
+            ]
+        );
+    }
+}
@@ -15,6 +15,7 @@ use std::{
 use cfx_rpc_builder::RpcServerHandle;
 use cfx_util_macros::bail;
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WSServer;
 use parking_lot::{Condvar, Mutex};
@@ -38,7 +39,7 @@ use cfxcore::{
         pos_handler::{PosConfiguration, PosVerifier},
     },
     genesis_block::{self as genesis, genesis_block},
-    pow::PowComputer,
+    pow::{PowComputer, PowWorkProvider},
     statistics::Statistics,
     sync::SyncPhaseType,
     ConsensusGraph, LightProvider, NodeType, Notifications, Stopable,
@@ -62,6 +63,7 @@ use tokio::runtime::Runtime as TokioRuntime;
 use txgen::{DirectTransactionGenerator, TransactionGenerator};
 
 use cfx_config::{parse_config_address_string, Configuration};
+use config_reload::ConfigReloader;
 
 use crate::{
     accounts::{account_provider, keys_path},
@@ -78,7 +80,9 @@ use crate::{
 use cfxcore::consensus::pos_handler::read_initial_nodes_from_file;
 use std::net::SocketAddr;
 
+pub mod config_reload;
 pub mod delegate_convert;
+pub mod logger;
 pub mod shutdown_handler;
 
 /// Hold all top-level components for a type of client.
@@ -161,6 +165,7 @@ pub fn initialize_common_modules(
     String,
 > {
     info!("Working directory: {:?}", std::env::current_dir());
+    conf.check_rpc_authentication()?;
 
     // TODO(lpl): Keep it properly and allow not running pos.
     let (self_pos_private_key, self_vrf_private_key) = {
@@ -299,6 +304,38 @@ pub fn initialize_common_modules(
         }
     };
 
+    let address_parser = |addr_str: &str| {
+        parse_config_address_string(addr_str, network_config.get_network_type())
+    };
+    let chain_spec_genesis = conf
+        .chain_spec
+        .as_ref()
+        .map(|spec| &spec.genesis)
+        .filter(|genesis| {
+            !genesis.native.is_empty() || !genesis.evm.is_empty()
+        });
+    // The exported chain spec already includes the accounts in
+    // `genesis_accounts`.
+    if chain_spec_genesis.is_some() && conf.raw_conf.genesis_accounts.is_some()
+    {
+        return Err("genesis_accounts can not be set with a chain spec \
+                    containing genesis accounts"
+            .into());
+    }
+    let genesis_alloc = match (&conf.raw_conf.genesis_alloc, chain_spec_genesis)
+    {
+        (Some(_), Some(_)) => {
+            return Err(
+                "genesis_alloc can not be set with a chain spec containing \
+                 genesis accounts"
+                    .into(),
+            );
+        }
+        (Some(file), None) => genesis::load_alloc_file(file, address_parser)?,
+        (None, Some(genesis)) => genesis.validate(address_parser)?,
+        (None, None) => Default::default(),
+    };
+
     // Only try to setup PoW genesis block if pos is enabled from genesis.
     let initial_nodes = if conf.raw_conf.pos_reference_enable_height == 0 {
         Some(
@@ -318,13 +355,14 @@ pub fn initialize_common_modules(
     let genesis_block = genesis_block(
         &storage_manager,
         genesis_accounts.clone(),
+        &genesis_alloc,
         GENESIS_ACCOUNT_ADDRESS,
         U256::zero(),
         machine.clone(),
         conf.raw_conf.execute_genesis, /* need_to_execute */
         conf.raw_conf.chain_id,
         &initial_nodes,
-    );
+    )?;
     storage_manager.notify_genesis_hash(genesis_block.hash());
     let mut genesis_accounts = genesis_accounts;
     let genesis_accounts = genesis_accounts
@@ -450,6 +488,9 @@ pub fn initialize_common_modules(
         .expect("failed to initialize account provider"),
     );
 
+    let config_reloader =
+        Arc::new(ConfigReloader::new(conf, txpool.clone(), network.clone()));
+
     let common_impl = Arc::new(CommonRpcImpl::new(
         exit,
         consensus.clone(),
@@ -457,9 +498,12 @@ pub fn initialize_common_modules(
         txpool.clone(),
         accounts.clone(),
         pos_verifier.clone(),
+        config_reloader.clone(),
     ));
     let tokio_runtime =
         Arc::new(TokioRuntime::new().map_err(|e| e.to_string())?);
+    #[cfg(unix)]
+    config_reload::reload_on_sighup(config_reloader, &tokio_runtime);
 
     let pubsub = PubSubClient::new(
         tokio_runtime.clone(),
@@ -514,6 +558,8 @@ pub fn initialize_not_light_node_modules(
         Arc<PosVerifier>,
         Option<HttpServer>,
         Option<WSServer>,
+        Option<IpcServer>,
+        Option<IpcServer>,
         Arc<TokioRuntime>,
         Option<RpcServerHandle>,
         TaskManager,
@@ -751,6 +797,29 @@ pub fn initialize_not_light_node_modules(
         RpcExtractor,
     )?;
 
+    let rpc_ipc_server = super::rpc::start_ipc(
+        conf.ipc_config()?,
+        setup_public_rpc_apis(
+            common_impl.clone(),
+            rpc_impl.clone(),
+            pubsub.clone(),
+            eth_pubsub.clone(),
+            &conf,
+        ),
+        RpcExtractor,
+    )?;
+
+    let eth_rpc_ipc_server = super::rpc::start_ipc(
+        conf.eth_ipc_config()?,
+        setup_public_eth_rpc_apis(
+            rpc_impl.clone(),
+            eth_pubsub.clone(),
+            &conf,
+            task_executor.clone(),
+        ),
+        RpcExtractor,
+    )?;
+
     let rpc_http_server = super::rpc::start_http(
         conf.http_config(),
         setup_public_rpc_apis(
@@ -780,6 +849,7 @@ pub fn initialize_not_light_node_modules(
             txpool.clone(),
             eth_rpc_http_server_addr,
             task_executor.clone(),
+            Some(blockgen.clone() as Arc<dyn PowWorkProvider>),
         ))?;
 
     metrics::initialize(conf.metrics_config(), task_executor.clone());
@@ -800,6 +870,8 @@ pub fn initialize_not_light_node_modules(
         pos_verifier,
         eth_rpc_http_server,
         eth_rpc_ws_server,
+        rpc_ipc_server,
+        eth_rpc_ipc_server,
         tokio_runtime,
         async_eth_rpc_http_server,
         task_manager,
@@ -3,6 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
@@ -35,6 +36,8 @@ pub struct ArchiveClientExtraComponents {
     pub pow: Arc<PowComputer>,
     pub eth_rpc_http_server: Option<HttpServer>,
     pub eth_rpc_ws_server: Option<WsServer>,
+    pub rpc_ipc_server: Option<IpcServer>,
+    pub eth_rpc_ipc_server: Option<IpcServer>,
     /// Handle to the started ETH RPC server. This is version 2 of the ETH RPC.
     /// Which use Rust async I/O
     pub eth_rpc_server_handle: Option<RpcServerHandle>,
@@ -79,6 +82,8 @@ impl ArchiveClient {
             pos_handler,
             eth_rpc_http_server,
             eth_rpc_ws_server,
+            rpc_ipc_server,
+            eth_rpc_ipc_server,
             tokio_runtime,
             eth_rpc_server_handle,
             task_manager,
@@ -104,6 +109,8 @@ impl ArchiveClient {
                 pow,
                 eth_rpc_http_server,
                 eth_rpc_ws_server,
+                rpc_ipc_server,
+                eth_rpc_ipc_server,
                 eth_rpc_server_handle,
                 tokio_runtime,
                 task_manager,
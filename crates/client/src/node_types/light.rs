@@ -8,6 +8,7 @@ use parking_lot::{Condvar, Mutex};
 use secret_store::SecretStore;
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
@@ -33,6 +34,7 @@ pub struct LightClientExtraComponents {
     pub debug_rpc_ws_server: Option<WsServer>,
     pub light: Arc<LightQueryService>,
     pub rpc_http_server: Option<HttpServer>,
+    pub rpc_ipc_server: Option<IpcServer>,
     pub rpc_tcp_server: Option<TcpServer>,
     pub rpc_ws_server: Option<WsServer>,
     pub secret_store: Arc<SecretStore>,
@@ -150,6 +152,17 @@ impl LightClient {
             RpcExtractor,
         )?;
 
+        let rpc_ipc_server = crate::rpc::start_ipc(
+            conf.ipc_config()?,
+            setup_public_rpc_apis_light(
+                common_impl.clone(),
+                rpc_impl.clone(),
+                pubsub.clone(),
+                &conf,
+            ),
+            RpcExtractor,
+        )?;
+
         let rpc_http_server = crate::rpc::start_http(
             conf.http_config(),
             setup_public_rpc_apis_light(
@@ -173,6 +186,7 @@ impl LightClient {
                 debug_rpc_ws_server,
                 light,
                 rpc_http_server,
+                rpc_ipc_server,
                 rpc_tcp_server,
                 rpc_ws_server,
                 secret_store,
@@ -16,8 +16,11 @@
 
 //! Parity-specific metadata extractors.
 
-use crate::rpc::{http_common::HttpMetaExtractor, Metadata, Origin};
+use crate::rpc::{
+    http_common::HttpMetaExtractor, jwt::JwtGrant, Metadata, Origin,
+};
 use cfx_types::H256;
+use jsonrpc_ipc_server as ipc;
 use jsonrpc_pubsub::Session;
 use jsonrpc_tcp_server as tcp;
 use jsonrpc_ws_server as ws;
@@ -32,6 +35,7 @@ impl HttpMetaExtractor for RpcExtractor {
 
     fn read_metadata(
         &self, origin: Option<String>, user_agent: Option<String>,
+        auth: Option<Arc<JwtGrant>>,
     ) -> Metadata {
         Metadata {
             origin: Origin::Rpc(format!(
@@ -40,6 +44,7 @@ impl HttpMetaExtractor for RpcExtractor {
                 user_agent.unwrap_or_else(|| "unknown agent".to_string())
             )),
             session: None,
+            auth,
         }
     }
 }
@@ -49,6 +54,17 @@ impl tcp::MetaExtractor<Metadata> for RpcExtractor {
         Metadata {
             origin: Origin::Tcp(req.peer_addr),
             session: Some(Arc::new(Session::new(req.sender.clone()))),
+            auth: None,
+        }
+    }
+}
+
+impl ipc::MetaExtractor<Metadata> for RpcExtractor {
+    fn extract(&self, req: &ipc::RequestContext) -> Metadata {
+        Metadata {
+            origin: Origin::Ipc(H256::from_low_u64_be(req.session_id)),
+            session: Some(Arc::new(Session::new(req.sender.clone()))),
+            auth: None,
         }
     }
 }
@@ -60,6 +76,7 @@ impl ws::MetaExtractor<Metadata> for RpcExtractor {
                 session: H256::from_low_u64_be(req.session_id),
             },
             session: Some(Arc::new(Session::new(req.sender()))),
+            auth: None,
         }
     }
 }
@@ -255,7 +272,10 @@ impl ws::MetaExtractor<Metadata> for RpcExtractor {
 
 #[cfg(test)]
 mod tests {
-    use super::{HttpMetaExtractor, Origin, RpcExtractor};
+    use super::{ipc, HttpMetaExtractor, Origin, RpcExtractor};
+    use cfx_types::H256;
+    use jsonrpc_core::futures::channel::mpsc;
+    use std::path::Path;
 
     #[test]
     fn should_extract_rpc_origin() {
@@ -263,11 +283,17 @@ mod tests {
         let extractor = RpcExtractor;
 
         // when
-        let meta1 = extractor.read_metadata(None, None);
-        let meta2 = extractor
-            .read_metadata(None, Some("https://conflux-chain.org".to_owned()));
-        let meta3 = extractor
-            .read_metadata(None, Some("https://conflux-chain.org".to_owned()));
+        let meta1 = extractor.read_metadata(None, None, None);
+        let meta2 = extractor.read_metadata(
+            None,
+            Some("https://conflux-chain.org".to_owned()),
+            None,
+        );
+        let meta3 = extractor.read_metadata(
+            None,
+            Some("https://conflux-chain.org".to_owned()),
+            None,
+        );
 
         // then
         assert_eq!(
@@ -283,4 +309,19 @@ mod tests {
             Origin::Rpc("unknown origin / https://conflux-chain.org".into())
         );
     }
+
+    #[test]
+    fn should_extract_ipc_origin() {
+        let (sender, _receiver) = mpsc::unbounded();
+        let context = ipc::RequestContext {
+            session_id: 5,
+            endpoint_addr: Path::new("conflux.ipc"),
+            sender,
+        };
+
+        let meta = ipc::MetaExtractor::extract(&RpcExtractor, &context);
+
+        assert_eq!(meta.origin, Origin::Ipc(H256::from_low_u64_be(5)));
+        assert!(meta.session.is_some());
+    }
 }
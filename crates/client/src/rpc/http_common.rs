@@ -17,8 +17,10 @@
 
 //! Transport-specific metadata extractors.
 
+use super::jwt::JwtGrant;
 use jsonrpc_core;
 use jsonrpc_http_server::{self as http, hyper};
+use std::sync::Arc;
 
 /// HTTP RPC server impl-independent metadata extractor
 pub trait HttpMetaExtractor: Send + Sync + 'static {
@@ -27,6 +29,7 @@ pub trait HttpMetaExtractor: Send + Sync + 'static {
     /// Extracts metadata from given params.
     fn read_metadata(
         &self, origin: Option<String>, user_agent: Option<String>,
+        auth: Option<Arc<JwtGrant>>,
     ) -> Self::Metadata;
 }
 
@@ -50,6 +53,8 @@ where
 
         let origin = as_string(req.headers().get("origin"));
         let user_agent = as_string(req.headers().get("user-agent"));
-        self.extractor.read_metadata(origin, user_agent)
+        // Set by `HttpJwtMiddleware` once the token is validated.
+        let auth = req.extensions().get::<Arc<JwtGrant>>().cloned();
+        self.extractor.read_metadata(origin, user_agent, auth)
     }
 }
@@ -0,0 +1,43 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    common::config_reload::ConfigReloadReport,
+    rpc::{
+        impls::common::RpcImpl as CommonImpl,
+        traits::admin::Admin,
+        types::{NodeInfo, PeerReputation},
+    },
+};
+use delegate::delegate;
+use jsonrpc_core::Result as JsonRpcResult;
+use network::{node_table::NodeId, SessionDetails};
+use std::sync::Arc;
+
+pub struct AdminHandler {
+    common: Arc<CommonImpl>,
+}
+
+impl AdminHandler {
+    pub fn new(common: Arc<CommonImpl>) -> Self { AdminHandler { common } }
+}
+
+impl Admin for AdminHandler {
+    delegate! {
+        to self.common {
+            fn admin_peers(&self) -> JsonRpcResult<Vec<SessionDetails>>;
+            fn admin_add_peer(&self, url: String) -> JsonRpcResult<bool>;
+            fn admin_add_static_peer(&self, url: String) -> JsonRpcResult<bool>;
+            fn admin_remove_static_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn admin_static_peers(&self) -> JsonRpcResult<Vec<String>>;
+            fn admin_remove_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn admin_ban_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn admin_unban_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn admin_peer_reputations(&self) -> JsonRpcResult<Vec<PeerReputation>>;
+            fn admin_banned_peers(&self) -> JsonRpcResult<Vec<NodeId>>;
+            fn admin_node_info(&self) -> JsonRpcResult<NodeInfo>;
+            fn admin_reload_config(&self) -> JsonRpcResult<ConfigReloadReport>;
+        }
+    }
+}
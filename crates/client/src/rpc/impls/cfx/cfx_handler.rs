@@ -43,7 +43,7 @@ use cfxcore::{
     state_exposer::STATE_EXPOSER,
     transaction_pool::TransactionPoolError,
     verification::{compute_epoch_receipt_proof, EpochReceiptProof},
-    ConsensusGraph, PeerInfo, SharedConsensusGraph,
+    ConsensusGraph, ExecutionWitness, PeerInfo, SharedConsensusGraph,
     SharedSynchronizationService, SharedTransactionPool,
 };
 use cfxcore_accounts::AccountProvider;
@@ -74,8 +74,9 @@ use crate::{
     common::delegate_convert,
     rpc::{
         errors::{
-            call_execution_error, internal_error, invalid_params,
-            pivot_assumption_failed, request_rejected_in_catch_up_mode,
+            build_rpc_server_error, call_execution_error, codes,
+            internal_error, invalid_params, pivot_assumption_failed,
+            request_rejected_in_catch_up_mode,
         },
         impls::{
             common::{self, RpcImpl as CommonImpl},
@@ -91,8 +92,9 @@ use crate::{
             EpochNumber, EstimateGasAndCollateralResponse, Log as RpcLog,
             PackedOrExecuted, Receipt as RpcReceipt,
             RewardInfo as RpcRewardInfo, Status as RpcStatus,
-            StorageCollateralInfo, SyncGraphStates,
+            StorageCollateralInfo, StratumWorker, SyncGraphStates,
             Transaction as RpcTransaction, TransactionRequest,
+            TransactionsByAddress,
         },
         CoreResult,
     },
@@ -105,6 +107,10 @@ use cfx_parameters::{
     genesis::{
         genesis_contract_address_four_year, genesis_contract_address_two_year,
     },
+    rpc::{
+        TRANSACTIONS_BY_ADDRESS_DEFAULT_LIMIT,
+        TRANSACTIONS_BY_ADDRESS_MAX_LIMIT,
+    },
     staking::{BLOCKS_PER_YEAR, DRIPS_PER_STORAGE_COLLATERAL_UNIT},
 };
 use cfx_storage::state::StateDbGetOriginalMethods;
@@ -128,7 +134,7 @@ pub struct RpcImpl {
     pub config: RpcImplConfiguration,
     pub consensus: SharedConsensusGraph,
     pub sync: SharedSynchronizationService,
-    block_gen: Arc<BlockGenerator>,
+    pub block_gen: Arc<BlockGenerator>,
     pub tx_pool: SharedTransactionPool,
     maybe_txgen: Option<Arc<TransactionGenerator>>,
     maybe_direct_txgen: Option<Arc<Mutex<DirectTransactionGenerator>>>,
@@ -675,6 +681,51 @@ impl RpcImpl {
         Ok(Some(root))
     }
 
+    fn transactions_by_address(
+        &self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>,
+    ) -> CoreResult<TransactionsByAddress> {
+        info!(
+            "RPC Request: cfx_getTransactionsByAddress address={:?} cursor={:?} limit={:?}",
+            address, cursor, limit
+        );
+        self.check_address_network(address.network)?;
+
+        let limit = match limit {
+            None => TRANSACTIONS_BY_ADDRESS_DEFAULT_LIMIT,
+            Some(limit)
+                if limit.as_usize() <= TRANSACTIONS_BY_ADDRESS_MAX_LIMIT =>
+            {
+                limit.as_usize()
+            }
+            Some(_) => bail!(invalid_params(
+                "limit",
+                format!(
+                    "limit should not exceed {}",
+                    TRANSACTIONS_BY_ADDRESS_MAX_LIMIT
+                )
+            )),
+        };
+
+        let (transactions, next_cursor) = match self
+            .consensus
+            .get_transactions_by_address(
+                &address.hex_address.with_native_space(),
+                cursor.map(|cursor| cursor.as_u64()),
+                limit,
+            ) {
+            Some(page) => page,
+            None => bail!(build_rpc_server_error(
+                codes::INCAPABLE,
+                "Address transaction index is not enabled, set persist_address_tx_index to true.".into()
+            )),
+        };
+
+        Ok(TransactionsByAddress {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            next_cursor: next_cursor.map(Into::into),
+        })
+    }
+
     fn send_usable_genesis_accounts(
         &self, account_start_index: usize,
     ) -> CoreResult<Bytes> {
@@ -1538,6 +1589,15 @@ impl RpcImpl {
         Ok(SyncGraphStates::new(sync_graph_states))
     }
 
+    pub fn stratum_workers(&self) -> CoreResult<Vec<StratumWorker>> {
+        Ok(self
+            .block_gen
+            .stratum_worker_stats()
+            .into_iter()
+            .map(StratumWorker::from)
+            .collect())
+    }
+
     /// Return (block_info.status, state_valid)
     /// Return Error if either field is missing
     pub fn get_block_status(&self, block_hash: H256) -> CoreResult<(u8, bool)> {
@@ -2088,6 +2148,20 @@ impl RpcImpl {
         self.get_transactions(&block, pivot, epoch_number)
     }
 
+    fn execution_witness(
+        &self, epoch_number: U64,
+    ) -> CoreResult<ExecutionWitness> {
+        debug!("debug_executionWitness {}", epoch_number);
+        self.consensus.execution_witness(epoch_number.as_u64())
+    }
+
+    fn verify_execution_witness(
+        &self, witness: ExecutionWitness,
+    ) -> CoreResult<H256> {
+        debug!("debug_verifyExecutionWitness {}", witness.epoch_number);
+        self.consensus.verify_execution_witness(&witness)
+    }
+
     fn get_transactions(
         &self, blocks: &Vec<Arc<Block>>, pivot: &Arc<Block>, epoch_number: u64,
     ) -> JsonRpcResult<Vec<WrapTransaction>> {
@@ -2332,6 +2406,7 @@ impl Cfx for CfxHandler {
             fn storage_at(&self, addr: RpcAddress, pos: U256, block_hash_or_epoch_number: Option<BlockHashOrEpochNumber>)
                 -> BoxFuture<JsonRpcResult<Option<H256>>>;
             fn transaction_by_hash(&self, hash: H256) -> BoxFuture<JsonRpcResult<Option<RpcTransaction>>>;
+            fn transactions_by_address(&self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>) -> BoxFuture<JsonRpcResult<TransactionsByAddress>>;
             fn transaction_receipt(&self, tx_hash: H256) -> BoxFuture<JsonRpcResult<Option<RpcReceipt>>>;
             fn storage_root(&self, address: RpcAddress, epoch_num: Option<EpochNumber>) -> BoxFuture<JsonRpcResult<Option<StorageRoot>>>;
             fn get_supply_info(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<TokenSupplyInfo>;
@@ -2457,11 +2532,14 @@ impl LocalRpc for LocalRpcImpl {
             fn epoch_receipt_proof_by_transaction(&self, tx_hash: H256) -> JsonRpcResult<Option<EpochReceiptProof>>;
             fn stat_on_gas_load(&self, last_epoch: EpochNumber, time_window: U64) -> JsonRpcResult<Option<StatOnGasLoad>>;
             fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
+            fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
             fn send_transaction(
                 &self, tx: TransactionRequest, password: Option<String>) -> BoxFuture<JsonRpcResult<H256>>;
             fn sign_transaction(&self, tx: TransactionRequest, password: Option<String>) -> JsonRpcResult<String>;
             fn transactions_by_epoch(&self, epoch_number: U64) -> JsonRpcResult<Vec<WrapTransaction>>;
             fn transactions_by_block(&self, block_hash: H256) -> JsonRpcResult<Vec<WrapTransaction>>;
+            fn execution_witness(&self, epoch_number: U64) -> JsonRpcResult<ExecutionWitness>;
+            fn verify_execution_witness(&self, witness: ExecutionWitness) -> JsonRpcResult<H256>;
         }
     }
 }
@@ -6,23 +6,28 @@ use cfx_util_macros::bail;
 use std::{
     collections::{BTreeMap, HashSet},
     net::SocketAddr,
+    str::FromStr,
     sync::Arc,
     time::Duration,
 };
 
-use crate::rpc::{
-    errors::invalid_params_check,
-    helpers::MAX_FEE_HISTORY_CACHE_BLOCK_COUNT,
-    impls::pos::{convert_to_pos_epoch_reward, hash_value_to_h256},
-    types::{
-        cfx::check_rpc_address_network, pos::PoSEpochReward,
-        AccountPendingInfo, AccountPendingTransactions, Block as RpcBlock,
-        BlockHashOrEpochNumber, Bytes, CfxFeeHistory,
-        CheckBalanceAgainstTransactionResponse, EpochNumber, FeeHistory,
-        RpcAddress, Status as RpcStatus, Transaction as RpcTransaction,
-        TxPoolPendingNonceRange, TxPoolStatus, TxWithPoolInfo, U64 as HexU64,
+use crate::{
+    common::config_reload::{ConfigReloadReport, ConfigReloader},
+    rpc::{
+        errors::invalid_params_check,
+        helpers::MAX_FEE_HISTORY_CACHE_BLOCK_COUNT,
+        impls::pos::{convert_to_pos_epoch_reward, hash_value_to_h256},
+        types::{
+            cfx::check_rpc_address_network, pos::PoSEpochReward,
+            AccountPendingInfo, AccountPendingTransactions, Block as RpcBlock,
+            BlockHashOrEpochNumber, Bytes, CfxFeeHistory,
+            CheckBalanceAgainstTransactionResponse, EpochNumber, FeeHistory,
+            NodeInfo, PeerReputation, RpcAddress, Status as RpcStatus,
+            Transaction as RpcTransaction, TxPoolPendingNonceRange,
+            TxPoolStatus, TxWithPoolInfo, U64 as HexU64,
+        },
+        CoreResult,
     },
-    CoreResult,
 };
 use log::{debug, info, warn};
 
@@ -162,6 +167,7 @@ pub struct RpcImpl {
     tx_pool: SharedTransactionPool,
     accounts: Arc<AccountProvider>,
     pub pos_handler: Arc<PosVerifier>,
+    config_reloader: Arc<ConfigReloader>,
 }
 
 impl RpcImpl {
@@ -169,6 +175,7 @@ impl RpcImpl {
         exit: Arc<(Mutex<bool>, Condvar)>, consensus: SharedConsensusGraph,
         network: Arc<NetworkService>, tx_pool: SharedTransactionPool,
         accounts: Arc<AccountProvider>, pos_verifier: Arc<PosVerifier>,
+        config_reloader: Arc<ConfigReloader>,
     ) -> Self {
         let data_man = consensus.data_manager().clone();
 
@@ -180,6 +187,7 @@ impl RpcImpl {
             tx_pool,
             accounts,
             pos_handler: pos_verifier,
+            config_reloader,
         }
     }
 
@@ -213,7 +221,7 @@ impl RpcImpl {
             .into();
         Ok(std::cmp::max(
             consensus_gas_price,
-            self.tx_pool.config.min_native_tx_price.into(),
+            self.tx_pool.config.min_tx_price(Space::Native).into(),
         ))
     }
 
@@ -1045,6 +1053,130 @@ impl RpcImpl {
     }
 }
 
+// Admin RPC implementation
+impl RpcImpl {
+    pub fn admin_peers(&self) -> JsonRpcResult<Vec<SessionDetails>> {
+        info!("RPC Request: admin_peers");
+        Ok(self.network.get_detailed_sessions(None).unwrap_or_default())
+    }
+
+    pub fn admin_add_peer(&self, url: String) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_addPeer({})", url);
+        let node = Self::parse_node_url(&url)?;
+        self.network
+            .add_peer(node)
+            .map_err(|e| internal_rpc_err(e.to_string()))?;
+        Ok(true)
+    }
+
+    pub fn admin_add_static_peer(&self, url: String) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_addStaticPeer({})", url);
+        let node = Self::parse_node_url(&url)?;
+        self.network
+            .add_static_peer(node)
+            .map_err(|e| internal_rpc_err(e.to_string()))?;
+        Ok(true)
+    }
+
+    pub fn admin_remove_static_peer(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_removeStaticPeer({:?})", id);
+        self.network
+            .remove_static_peer(&id)
+            .map_err(|e| internal_rpc_err(e.to_string()))
+    }
+
+    pub fn admin_static_peers(&self) -> JsonRpcResult<Vec<String>> {
+        Ok(self
+            .network
+            .get_static_peers()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|n| Node::new(n.id, n.endpoint).to_string())
+            .collect())
+    }
+
+    pub fn admin_remove_peer(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_removePeer({:?})", id);
+        self.network
+            .remove_peer(&id)
+            .map_err(|e| internal_rpc_err(e.to_string()))
+    }
+
+    pub fn admin_ban_peer(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_banPeer({:?})", id);
+        self.network
+            .ban_peer(&id)
+            .map_err(|e| internal_rpc_err(e.to_string()))
+    }
+
+    pub fn admin_unban_peer(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: admin_unbanPeer({:?})", id);
+        self.network
+            .unban_peer(&id)
+            .map_err(|e| internal_rpc_err(e.to_string()))
+    }
+
+    pub fn admin_peer_reputations(&self) -> JsonRpcResult<Vec<PeerReputation>> {
+        let mut reputations: Vec<PeerReputation> = self
+            .network
+            .get_peer_reputations()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, reputation)| PeerReputation {
+                id,
+                score: reputation.score,
+                banned_seconds: reputation.banned_for.map(|d| d.as_secs()),
+                backoff_seconds: reputation.backoff_for.map(|d| d.as_secs()),
+            })
+            .collect();
+        reputations.sort_by(|a, b| a.score.total_cmp(&b.score));
+        Ok(reputations)
+    }
+
+    pub fn admin_banned_peers(&self) -> JsonRpcResult<Vec<NodeId>> {
+        let mut banned = self.network.get_banned_peers().unwrap_or_default();
+        banned.sort();
+        Ok(banned)
+    }
+
+    pub fn admin_node_info(&self) -> JsonRpcResult<NodeInfo> {
+        let id = *self
+            .network
+            .net_key_pair()
+            .map_err(|e| internal_rpc_err(e.to_string()))?
+            .public();
+        let endpoint = self
+            .network
+            .public_endpoint()
+            .ok_or_else(|| internal_rpc_err("network service not started"))?;
+
+        Ok(NodeInfo {
+            id,
+            url: Node::new(id, endpoint.clone()).to_string(),
+            listen_address: self.network.local_addr(),
+            public_address: endpoint.address,
+            udp_port: endpoint.udp_port,
+            protocols: self.network.protocols().unwrap_or_default(),
+        })
+    }
+
+    pub fn admin_reload_config(&self) -> JsonRpcResult<ConfigReloadReport> {
+        self.config_reloader.reload().map_err(internal_rpc_err)
+    }
+
+    fn parse_node_url(url: &str) -> JsonRpcResult<NodeEntry> {
+        let node = Node::from_str(url)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+        if node.id.is_zero() {
+            return Err(RpcError::invalid_params("node id is missing"));
+        }
+        Ok(NodeEntry {
+            id: node.id,
+            endpoint: node.endpoint,
+        })
+    }
+}
+
 // Debug RPC implementation
 impl RpcImpl {
     pub fn txpool_clear(&self) -> JsonRpcResult<()> {
@@ -11,7 +11,8 @@ use cfxcore::{
     errors::account_result_to_rpc_result,
     light_protocol::{self, query_service::TxInfo, Error as LightError},
     verification::EpochReceiptProof,
-    ConsensusGraph, LightQueryService, PeerInfo, SharedConsensusGraph,
+    ConsensusGraph, ExecutionWitness, LightQueryService, PeerInfo,
+    SharedConsensusGraph,
 };
 use cfxcore_accounts::AccountProvider;
 use delegate::delegate;
@@ -47,8 +48,10 @@ use crate::{
             Log as RpcLog, PoSEconomics, Receipt as RpcReceipt,
             RewardInfo as RpcRewardInfo, RpcAddress, SponsorInfo,
             StatOnGasLoad, Status as RpcStatus, StorageCollateralInfo,
-            SyncGraphStates, TokenSupplyInfo, Transaction as RpcTransaction,
-            TransactionRequest, VoteParamsInfo, WrapTransaction, U64 as HexU64,
+            StratumWorker, SyncGraphStates, TokenSupplyInfo,
+            Transaction as RpcTransaction, TransactionRequest,
+            TransactionsByAddress, VoteParamsInfo, WrapTransaction,
+            U64 as HexU64,
         },
         CoreBoxFuture, CoreResult,
     },
@@ -1231,6 +1234,7 @@ impl Cfx for CfxHandler {
         fn get_pos_reward_by_epoch(&self, epoch: EpochNumber) -> JsonRpcResult<Option<PoSEpochReward>>;
         fn get_fee_burnt(&self, epoch: Option<EpochNumber>) -> JsonRpcResult<U256>;
         fn max_priority_fee_per_gas(&self) -> BoxFuture<JsonRpcResult<U256>>;
+        fn transactions_by_address(&self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>) -> BoxFuture<JsonRpcResult<TransactionsByAddress>>;
     }
 }
 
@@ -1340,7 +1344,10 @@ impl LocalRpc for DebugRpcImpl {
         fn stat_on_gas_load(&self, epoch: EpochNumber, time_window: U64) -> JsonRpcResult<Option<StatOnGasLoad>>;
         fn sign_transaction(&self, tx: TransactionRequest, password: Option<String>) -> JsonRpcResult<String>;
         fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
+        fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
         fn transactions_by_epoch(&self, epoch_number: U64) -> JsonRpcResult<Vec<WrapTransaction>>;
         fn transactions_by_block(&self, block_hash: H256) -> JsonRpcResult<Vec<WrapTransaction>>;
+        fn execution_witness(&self, epoch_number: U64) -> JsonRpcResult<ExecutionWitness>;
+        fn verify_execution_witness(&self, witness: ExecutionWitness) -> JsonRpcResult<H256>;
     }
 }
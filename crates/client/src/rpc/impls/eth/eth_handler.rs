@@ -10,21 +10,24 @@ use crate::rpc::{
         eth::{
             AccountPendingTransactions, Block as RpcBlock, BlockNumber,
             BlockOverrides, EthRpcLogFilter, Log, Receipt, RpcStateOverride,
-            SyncStatus, Transaction, TransactionRequest,
+            SyncStatus, Transaction, TransactionRequest, TransactionsByAddress,
+            Work,
         },
         Bytes, FeeHistory, Index, U64 as HexU64,
     },
 };
 use cfx_rpc::EthApi;
 use cfx_tasks::TaskExecutor;
-use cfx_types::{Address, AddressSpaceUtil, Space, H160, H256, U256, U64};
+use cfx_types::{Address, AddressSpaceUtil, Space, H160, H256, H64, U256, U64};
 use cfx_util_macros::bail;
 use cfxcore::{
-    SharedConsensusGraph, SharedSynchronizationService, SharedTransactionPool,
+    pow::PowWorkProvider, SharedConsensusGraph, SharedSynchronizationService,
+    SharedTransactionPool,
 };
 use jsonrpc_core::Result as RpcResult;
 use log::debug;
 use primitives::TransactionWithSignature;
+use std::sync::Arc;
 
 pub struct EthHandler {
     inner: EthApi,
@@ -35,9 +38,17 @@ impl EthHandler {
         config: RpcImplConfiguration, consensus: SharedConsensusGraph,
         sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
         executor: TaskExecutor,
+        work_provider: Option<Arc<dyn PowWorkProvider>>,
     ) -> Self {
         EthHandler {
-            inner: EthApi::new(config, consensus, sync, tx_pool, executor),
+            inner: EthApi::new(
+                config,
+                consensus,
+                sync,
+                tx_pool,
+                executor,
+                work_provider,
+            ),
         }
     }
 }
@@ -66,8 +77,7 @@ impl Eth for EthHandler {
 
     fn hashrate(&self) -> RpcResult<U256> {
         debug!("RPC Request: eth_hashrate()");
-        // We do not mine
-        Ok(U256::zero())
+        Ok(self.inner.hashrate())
     }
 
     fn author(&self) -> RpcResult<H160> {
@@ -78,8 +88,7 @@ impl Eth for EthHandler {
 
     fn is_mining(&self) -> RpcResult<bool> {
         debug!("RPC Request: eth_mining()");
-        // We do not mine from ETH perspective
-        Ok(false)
+        Ok(self.inner.is_mining())
     }
 
     fn chain_id(&self) -> RpcResult<Option<U64>> {
@@ -381,10 +390,26 @@ impl Eth for EthHandler {
         self.inner.logs(filter).map_err(|err| err.into())
     }
 
-    fn submit_hashrate(&self, _: U256, _: H256) -> RpcResult<bool> {
+    fn work(&self) -> RpcResult<Work> {
+        debug!("RPC Request: eth_getWork()");
+        self.inner.get_work().map_err(|err| err.into())
+    }
+
+    fn submit_work(
+        &self, nonce: H64, pow_hash: H256, _mix_digest: H256,
+    ) -> RpcResult<bool> {
+        debug!(
+            "RPC Request: eth_submitWork(nonce={:?}, pow_hash={:?})",
+            nonce, pow_hash
+        );
+        self.inner
+            .submit_work(nonce, pow_hash)
+            .map_err(|err| err.into())
+    }
+
+    fn submit_hashrate(&self, hashrate: U256, id: H256) -> RpcResult<bool> {
         debug!("RPC Request: eth_submitHashrate()");
-        // We do not care mining
-        Ok(false)
+        Ok(self.inner.submit_hashrate(hashrate, id))
     }
 
     fn eth_block_receipts(
@@ -444,4 +469,17 @@ impl Eth for EthHandler {
             pending_count: pending_count.into(),
         })
     }
+
+    fn transactions_by_address(
+        &self, address: H160, cursor: Option<U64>, limit: Option<U64>,
+    ) -> RpcResult<TransactionsByAddress> {
+        debug!(
+            "RPC Request: eth_getTransactionsByAddress(addr={:?}, cursor={:?}, limit={:?})",
+            address, cursor, limit
+        );
+
+        self.inner
+            .transactions_by_address(address, cursor, limit)
+            .map_err(|err| err.into())
+    }
 }
@@ -6,6 +6,6 @@ pub mod cfx;
 pub mod eth;
 pub mod pos;
 
-pub use cfx::{cfx_filter, common, light, pool, pubsub};
+pub use cfx::{admin, cfx_filter, common, light, pool, pubsub};
 pub use cfx_rpc_cfx_types::{FeeHistoryCacheEntry, RpcImplConfiguration};
 pub use eth::{debug, eth_filter, eth_handler::EthHandler, eth_pubsub};
@@ -3,35 +3,39 @@
 // See http://www.gnu.org/licenses/
 
 use crate::rpc::errors::request_rejected_too_many_request_error;
+use cfx_rpc_middlewares::RpcCallMetrics;
+use cfx_types::Space;
 use cfx_util_macros::bail;
 use futures::{future::lazy, FutureExt, TryFutureExt};
 use jsonrpc_core::{
     BoxFuture, Metadata, Params, RemoteProcedure, Result as RpcResult,
     RpcMethod,
 };
-use lazy_static::lazy_static;
 use log::debug;
-use metrics::{register_timer_with_group, ScopeTimer, Timer};
-use parking_lot::Mutex;
 use serde_json::Value;
 use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 use throttling::token_bucket::{ThrottleResult, TokenBucketManager};
-
-lazy_static! {
-    static ref METRICS_INTERCEPTOR_TIMERS: Mutex<HashMap<String, Arc<dyn Timer>>> =
-        Default::default();
-}
+use tracing::{info_span, Instrument};
 
 pub trait RpcInterceptor: Send + Sync + 'static {
     fn before(&self, _name: &String) -> RpcResult<()>;
 
+    /// Wraps the call of method `_name` requested through `_transport`.
     fn around(
-        &self, _name: &String, method_call: BoxFuture<RpcResult<Value>>,
+        &self, _name: &String, _transport: &'static str,
+        method_call: BoxFuture<RpcResult<Value>>,
     ) -> BoxFuture<RpcResult<Value>> {
         method_call
     }
 }
 
+/// The metadata which knows the transport of requests, e.g. "http" or "ws".
+pub trait RequestTransport {
+    fn transport(&self) -> &'static str { "unknown" }
+}
+
+impl RequestTransport for () {}
+
 pub struct RpcProxy<M, T, I>
 where
     M: Metadata,
@@ -60,7 +64,7 @@ where
 
 impl<M, T, I> IntoIterator for RpcProxy<M, T, I>
 where
-    M: Metadata,
+    M: Metadata + RequestTransport,
     T: IntoIterator<Item = (String, RemoteProcedure<M>)>,
     I: RpcInterceptor,
 {
@@ -116,7 +120,7 @@ where
 
 impl<M, I> RpcMethod<M> for RpcMethodWithInterceptor<M, I>
 where
-    M: Metadata,
+    M: Metadata + RequestTransport,
     I: RpcInterceptor,
 {
     fn call(&self, params: Params, meta: M) -> BoxFuture<RpcResult<Value>> {
@@ -125,13 +129,17 @@ where
         let before_future = lazy(move |_| interceptor.before(&name));
 
         let method = self.method.clone();
+        let transport = meta.transport();
         let method_call = self.interceptor.around(
             &self.name,
+            transport,
             lazy(move |_| method.call(params, meta)).flatten().boxed(),
         );
         let method_future = before_future.and_then(move |_| method_call);
 
-        method_future.boxed()
+        method_future
+            .instrument(info_span!("rpc", method = %self.name))
+            .boxed()
     }
 }
 
@@ -141,11 +149,9 @@ pub struct ThrottleInterceptor {
 
 impl ThrottleInterceptor {
     pub fn new(file: &Option<String>, section: &str) -> Self {
-        let manager = match file {
-            Some(file) => TokenBucketManager::load(file, Some(section))
-                .expect("invalid throttling configuration file"),
-            None => TokenBucketManager::default(),
-        };
+        let manager =
+            TokenBucketManager::load_reloadable(file.as_deref(), section)
+                .expect("invalid throttling configuration file");
 
         ThrottleInterceptor { manager }
     }
@@ -179,48 +185,46 @@ impl RpcInterceptor for ThrottleInterceptor {
     }
 }
 
+/// Records the metrics of RPC calls, which are reported in the same way as
+/// the calls of the async RPC server.
 pub struct MetricsInterceptor {
     // TODO: Chain interceptors instead of wrapping up.
     throttle_interceptor: ThrottleInterceptor,
+    space: Space,
 }
 
 impl MetricsInterceptor {
-    pub fn new(throttle_interceptor: ThrottleInterceptor) -> Self {
+    pub fn new(
+        throttle_interceptor: ThrottleInterceptor, space: Space,
+    ) -> Self {
         Self {
             throttle_interceptor,
+            space,
         }
     }
 }
 
 impl RpcInterceptor for MetricsInterceptor {
     fn before(&self, name: &String) -> RpcResult<()> {
-        self.throttle_interceptor.before(name)?;
-        // Use a global variable here because `http` and `web3` setup different
-        // interceptors for the same RPC API.
-        let mut timers = METRICS_INTERCEPTOR_TIMERS.lock();
-        if !timers.contains_key(name) {
-            let timer = register_timer_with_group("rpc", name.as_str());
-            timers.insert(name.clone(), timer);
-        }
-        Ok(())
+        self.throttle_interceptor.before(name)
     }
 
     fn around(
-        &self, name: &String, method_call: BoxFuture<RpcResult<Value>>,
+        &self, name: &String, transport: &'static str,
+        method_call: BoxFuture<RpcResult<Value>>,
     ) -> BoxFuture<RpcResult<Value>> {
-        let maybe_timer = METRICS_INTERCEPTOR_TIMERS
-            .lock()
-            .get(name)
-            .map(|timer| timer.clone());
-        let setup = lazy(move |_| {
-            Ok(maybe_timer
-                .as_ref()
-                .map(|timer| ScopeTimer::time_scope(timer.clone())))
-        });
-        setup
-            .then(|timer: Result<_, ()>| {
-                method_call.then(|r| async {
-                    drop(timer);
+        // Start recording on the first poll so that the throttled calls are
+        // not recorded, which is the same as the async RPC server.
+        let space: &'static str = self.space.into();
+        let name = name.clone();
+        lazy(move |_| RpcCallMetrics::start(space, transport, &name))
+            .then(move |call| {
+                method_call.map(move |r| {
+                    let (response_size, error_code) = match &r {
+                        Ok(value) => (serialized_size(value), None),
+                        Err(e) => (serialized_size(e), Some(e.code.code())),
+                    };
+                    call.finish(response_size, error_code);
                     r
                 })
             })
@@ -228,9 +232,16 @@ impl RpcInterceptor for MetricsInterceptor {
     }
 }
 
+fn serialized_size<T: serde::Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::rpc::interceptor::{RpcInterceptor, RpcProxy};
+    use crate::rpc::interceptor::{
+        MetricsInterceptor, RpcInterceptor, RpcProxy, ThrottleInterceptor,
+    };
+    use cfx_types::Space;
     use jsonrpc_core::{
         BoxFuture, Error as RpcError, MetaIoHandler, Params, RemoteProcedure,
         Result as RpcResult, RpcMethod, Value,
@@ -334,4 +345,24 @@ mod tests {
         assert_eq!(interceptor_1_handled.load(Ordering::SeqCst), true);
         assert_eq!(interceptor_2_handled.load(Ordering::SeqCst), true);
     }
+
+    #[test]
+    fn test_metrics_interceptor() {
+        let mock_method = RemoteProcedure::Method(Arc::new(MockRpcMethod));
+        let methods: Vec<(String, RemoteProcedure<()>)> =
+            vec![("cfx_balance".to_string(), mock_method)];
+
+        let interceptor = MetricsInterceptor::new(
+            ThrottleInterceptor::new(&None, "rpc"),
+            Space::Native,
+        );
+        let mut handler: MetaIoHandler<()> = MetaIoHandler::default();
+        handler.extend_with(RpcProxy::new(methods, interceptor));
+
+        let request = r#"{"jsonrpc": "2.0", "method": "cfx_balance", "params": [8], "id": 1}"#;
+        assert_eq!(
+            handler.handle_request_sync(request, ()),
+            Some(r#"{"jsonrpc":"2.0","result":8,"id":1}"#.to_string()),
+        );
+    }
 }
@@ -0,0 +1,615 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! JWT authentication for the HTTP and WebSocket RPC servers.
+//!
+//! Tokens are signed with HS256 by a 32 bytes secret shared with the clients,
+//! which is stored hex encoded in a file, in the same way as the `jwtsecret`
+//! of the Ethereum engine API. Besides the issued-at time, a token may grant
+//! the namespaces its holder is allowed to call, in the same format as
+//! `public_rpc_apis` and `public_evm_rpc_apis`:
+//!
+//! ```json
+//! { "iat": 1700000000, "apis": "safe,debug,trace", "eth_apis": "all" }
+//! ```
+
+use crate::rpc::{
+    apis::{Api, ApiSet, EthApi, EthApiSet},
+    errors::request_rejected_unauthorized_error,
+    Metadata,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::{future, FutureExt};
+use hmac::{Hmac, Mac};
+use jsonrpc_core::{
+    BoxFuture, MetaIoHandler, Params, RemoteProcedure, Result as RpcResult,
+    RpcMethod, Value,
+};
+use jsonrpc_http_server::{self as http, hyper};
+use jsonrpc_ws_server as ws;
+use log::debug;
+use parking_lot::Mutex;
+use rand::{rngs::OsRng, RngCore};
+use rustc_hex::{FromHex, ToHex};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const JWT_SECRET_LEN: usize = 32;
+
+/// The maximum difference allowed between the `iat` claim and the local
+/// time, as required by the engine API.
+const IAT_LEEWAY_SECS: u64 = 60;
+
+#[derive(Clone)]
+pub struct JwtSecret([u8; JWT_SECRET_LEN]);
+
+impl JwtSecret {
+    pub fn random() -> Self {
+        let mut secret = [0u8; JWT_SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+        JwtSecret(secret)
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let bytes: Vec<u8> = hex
+            .from_hex()
+            .map_err(|e| format!("invalid JWT secret: {}", e))?;
+        if bytes.len() != JWT_SECRET_LEN {
+            return Err(format!(
+                "invalid JWT secret: expect {} bytes, got {}",
+                JWT_SECRET_LEN,
+                bytes.len()
+            ));
+        }
+        let mut secret = [0u8; JWT_SECRET_LEN];
+        secret.copy_from_slice(&bytes);
+        Ok(JwtSecret(secret))
+    }
+
+    /// Load the secret from the file, or generate a random one and save it
+    /// to the file if it does not exist yet.
+    pub fn load_or_create(path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            let hex = fs::read_to_string(path).map_err(|e| {
+                format!("failed to read JWT secret {:?}: {}", path, e)
+            })?;
+            return Self::from_hex(&hex);
+        }
+
+        let secret = Self::random();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                format!("failed to create directory {:?}: {}", dir, e)
+            })?;
+        }
+        fs::write(path, secret.to_hex()).map_err(|e| {
+            format!("failed to write JWT secret {:?}: {}", path, e)
+        })?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+                .map_err(|e| {
+                    format!("failed to protect JWT secret {:?}: {}", path, e)
+                })?;
+        }
+        Ok(secret)
+    }
+
+    pub fn to_hex(&self) -> String { self.0.to_hex() }
+
+    /// Sign the claims into a token.
+    pub fn encode(&self, claims: &JwtClaims) -> String {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(
+            serde_json::to_vec(claims).expect("claims are serializable"),
+        );
+        let message = format!("{}.{}", header, payload);
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.mac(&message).finalize().into_bytes());
+        format!("{}.{}", message, signature)
+    }
+
+    /// Verify the signature and the time claims of the token, and return the
+    /// namespaces it grants.
+    pub fn validate(&self, token: &str, now: u64) -> Result<JwtGrant, String> {
+        let mut parts = token.split('.');
+        let (header, payload, signature) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(h), Some(p), Some(s), None) => (h, p, s),
+                _ => return Err("malformed token".into()),
+            };
+
+        let header_len = header.len();
+        let header: JwtHeader = decode_part(header)?;
+        if header.alg != "HS256" {
+            return Err(format!("unsupported algorithm {}", header.alg));
+        }
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "malformed signature".to_string())?;
+        let message = &token[..header_len + 1 + payload.len()];
+        self.mac(message)
+            .verify_slice(&signature)
+            .map_err(|_| "invalid signature".to_string())?;
+
+        let claims: JwtClaims = decode_part(payload)?;
+        if claims.iat.abs_diff(now) > IAT_LEEWAY_SECS {
+            return Err("stale token".into());
+        }
+        if let Some(exp) = claims.exp {
+            if exp <= now {
+                return Err("expired token".into());
+            }
+        }
+        claims.grant()
+    }
+
+    fn mac(&self, message: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0)
+            .expect("HMAC accepts keys of any size");
+        mac.update(message.as_bytes());
+        mac
+    }
+}
+
+fn decode_part<T: for<'a> Deserialize<'a>>(part: &str) -> Result<T, String> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| "malformed token".to_string())?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| format!("malformed token: {}", e))
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JwtClaims {
+    /// Issued-at time, in seconds since the epoch.
+    pub iat: u64,
+    /// Expiration time, in seconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    /// Core space namespaces, `safe` if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apis: Option<String>,
+    /// eSpace namespaces, `evm` if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eth_apis: Option<String>,
+}
+
+impl JwtClaims {
+    fn grant(&self) -> Result<JwtGrant, String> {
+        let apis = match &self.apis {
+            Some(apis) => apis.parse::<ApiSet>()?,
+            None => ApiSet::Safe,
+        };
+        let eth_apis = match &self.eth_apis {
+            Some(apis) => apis.parse::<EthApiSet>()?,
+            None => EthApiSet::Evm,
+        };
+        Ok(JwtGrant {
+            apis: apis.list_apis(),
+            eth_apis: eth_apis.list_apis(),
+        })
+    }
+}
+
+/// The namespaces granted to an authenticated connection. The default grant
+/// allows nothing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JwtGrant {
+    pub apis: HashSet<Api>,
+    pub eth_apis: HashSet<EthApi>,
+}
+
+pub trait GrantedNamespace: Display + Send + Sync + 'static {
+    fn is_granted(&self, grant: &JwtGrant) -> bool;
+}
+
+impl GrantedNamespace for Api {
+    fn is_granted(&self, grant: &JwtGrant) -> bool { grant.apis.contains(self) }
+}
+
+impl GrantedNamespace for EthApi {
+    fn is_granted(&self, grant: &JwtGrant) -> bool {
+        grant.eth_apis.contains(self)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn bearer_token(authorization: &str) -> Option<&str> {
+    authorization
+        .strip_prefix("Bearer ")
+        .or_else(|| authorization.strip_prefix("bearer "))
+        .map(str::trim)
+}
+
+/// Restrict the methods of each namespace to the requests whose token grants
+/// the namespace. Requests from servers without JWT authentication carry no
+/// grant and are not restricted.
+pub fn restrict_namespaces<N: GrantedNamespace + Clone>(
+    handler: MetaIoHandler<Metadata>, namespaces: &HashMap<String, N>,
+) -> MetaIoHandler<Metadata> {
+    let mut restricted = MetaIoHandler::default();
+    restricted.extend_with(handler.into_iter().map(|(name, rp)| {
+        let rp = match (rp, namespaces.get(&name)) {
+            (RemoteProcedure::Method(method), Some(namespace)) => {
+                RemoteProcedure::Method(Arc::new(RestrictedMethod {
+                    namespace: namespace.clone(),
+                    method,
+                }))
+            }
+            (rp, _) => rp,
+        };
+        (name, rp)
+    }));
+    restricted
+}
+
+struct RestrictedMethod<N> {
+    namespace: N,
+    method: Arc<dyn RpcMethod<Metadata>>,
+}
+
+impl<N: GrantedNamespace> RpcMethod<Metadata> for RestrictedMethod<N> {
+    fn call(
+        &self, params: Params, meta: Metadata,
+    ) -> BoxFuture<RpcResult<Value>> {
+        if let Some(grant) = &meta.auth {
+            if !self.namespace.is_granted(grant) {
+                return future::ready(Err(
+                    request_rejected_unauthorized_error(Some(format!(
+                        "namespace {} is not granted by the token",
+                        self.namespace
+                    ))),
+                ))
+                .boxed();
+            }
+        }
+        self.method.call(params, meta)
+    }
+}
+
+/// Rejects HTTP requests without a valid bearer token, and hands the grant
+/// of the token to the metadata extractor through the request extensions.
+pub struct HttpJwtMiddleware {
+    secret: Arc<JwtSecret>,
+}
+
+impl HttpJwtMiddleware {
+    pub fn new(secret: Arc<JwtSecret>) -> Self { HttpJwtMiddleware { secret } }
+}
+
+impl http::RequestMiddleware for HttpJwtMiddleware {
+    fn on_request(
+        &self, mut request: hyper::Request<hyper::Body>,
+    ) -> http::RequestMiddlewareAction {
+        // CORS preflight requests never carry the credentials.
+        if request.method() == hyper::Method::OPTIONS {
+            return request.into();
+        }
+
+        let token = request
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token);
+        match token.map(|token| self.secret.validate(token, unix_now())) {
+            Some(Ok(grant)) => {
+                request.extensions_mut().insert(Arc::new(grant));
+                request.into()
+            }
+            Some(Err(e)) => {
+                debug!("Rejected HTTP RPC request: {}", e);
+                http_unauthorized(e).into()
+            }
+            None => http_unauthorized("missing bearer token".into()).into(),
+        }
+    }
+}
+
+fn http_unauthorized(message: String) -> http::Response {
+    http::Response {
+        code: hyper::StatusCode::UNAUTHORIZED,
+        content_type: hyper::header::HeaderValue::from_static(
+            "text/plain; charset=utf-8",
+        ),
+        content: message,
+    }
+}
+
+/// The grant of the WebSocket handshake being processed, which is passed from
+/// [`WsJwtMiddleware`] to [`WsJwtExtractor`] of the same server. The metadata
+/// extractor has no access to the request headers, but the server calls both
+/// of them for a handshake one after another on its event loop.
+type HandshakeGrant = Arc<Mutex<Option<Arc<JwtGrant>>>>;
+
+/// Creates the request middleware and the metadata extractor of a WebSocket
+/// server, which attach the grant of the bearer token of a handshake to the
+/// metadata of its session. `extractor` provides the rest of the metadata.
+pub fn ws_jwt_auth<T>(
+    secret: Arc<JwtSecret>, extractor: T,
+) -> (WsJwtMiddleware, WsJwtExtractor<T>) {
+    let grant = HandshakeGrant::default();
+    (
+        WsJwtMiddleware {
+            secret,
+            grant: grant.clone(),
+        },
+        WsJwtExtractor { extractor, grant },
+    )
+}
+
+/// Rejects WebSocket handshakes without a valid bearer token.
+pub struct WsJwtMiddleware {
+    secret: Arc<JwtSecret>,
+    grant: HandshakeGrant,
+}
+
+impl ws::RequestMiddleware for WsJwtMiddleware {
+    fn process(&self, req: &ws::ws::Request) -> ws::MiddlewareAction {
+        let token = req
+            .header("authorization")
+            .and_then(|value| std::str::from_utf8(value).ok())
+            .and_then(bearer_token);
+        let result = match token {
+            Some(token) => self.secret.validate(token, unix_now()),
+            None => Err("missing bearer token".into()),
+        };
+        // The grant of the previous handshake is always replaced.
+        *self.grant.lock() = result.as_ref().ok().cloned().map(Arc::new);
+        match result {
+            Ok(_) => ws::MiddlewareAction::Proceed,
+            Err(e) => {
+                debug!("Rejected WS RPC handshake: {}", e);
+                ws::MiddlewareAction::Respond {
+                    response: ws::ws::Response::new(
+                        401,
+                        "Unauthorized",
+                        e.into_bytes(),
+                    ),
+                    validate_origin: false,
+                    validate_hosts: false,
+                }
+            }
+        }
+    }
+}
+
+/// Attaches the grant of the handshake to the metadata of a WebSocket
+/// session authenticated by [`WsJwtMiddleware`].
+pub struct WsJwtExtractor<T> {
+    extractor: T,
+    grant: HandshakeGrant,
+}
+
+impl<T: ws::MetaExtractor<Metadata>> ws::MetaExtractor<Metadata>
+    for WsJwtExtractor<T>
+{
+    fn extract(&self, req: &ws::RequestContext) -> Metadata {
+        let mut meta = self.extractor.extract(req);
+        // Sessions are never left unrestricted, even if the grant is missing.
+        meta.auth = Some(self.grant.lock().take().unwrap_or_default());
+        meta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpStream},
+    };
+
+    fn claims(iat: u64) -> JwtClaims {
+        JwtClaims {
+            iat,
+            ..Default::default()
+        }
+    }
+
+    /// Calls `debug_foo` in a new WebSocket session authenticated by `token`.
+    /// Returns the status line if the handshake is rejected.
+    fn ws_call(
+        addr: &SocketAddr, token: Option<&str>,
+    ) -> Result<String, String> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let authorization = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\n\
+             Connection: Upgrade\r\nSec-WebSocket-Version: 13\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n{}\r\n",
+            addr, authorization
+        )
+        .unwrap();
+
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        if !status.starts_with("HTTP/1.1 101") {
+            return Err(status);
+        }
+        let mut line = String::new();
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        // A text frame, masked with a zero key as required for clients.
+        let request =
+            br#"{"jsonrpc":"2.0","method":"debug_foo","params":[],"id":1}"#;
+        let mut frame = vec![0x81, 0x80 | request.len() as u8, 0, 0, 0, 0];
+        frame.extend_from_slice(request);
+        stream.write_all(&frame).unwrap();
+
+        let mut header = [0u8; 2];
+        reader.read_exact(&mut header).unwrap();
+        let len = match header[1] {
+            126 => {
+                let mut len = [0u8; 2];
+                reader.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload).unwrap();
+        Ok(String::from_utf8(payload).unwrap())
+    }
+
+    #[test]
+    fn test_validate_token() {
+        let secret = JwtSecret::random();
+        let now = 1_700_000_000;
+
+        let grant = secret.validate(&secret.encode(&claims(now)), now).unwrap();
+        assert_eq!(grant.apis, ApiSet::Safe.list_apis());
+        assert_eq!(grant.eth_apis, EthApiSet::Evm.list_apis());
+
+        // Tokens issued too long ago or in the future.
+        let token = secret.encode(&claims(now - 61));
+        assert!(secret.validate(&token, now).is_err());
+        let token = secret.encode(&claims(now + 61));
+        assert!(secret.validate(&token, now).is_err());
+
+        let token = secret.encode(&JwtClaims {
+            exp: Some(now),
+            ..claims(now)
+        });
+        assert!(secret.validate(&token, now).is_err());
+
+        // Signed by another secret.
+        let token = JwtSecret::random().encode(&claims(now));
+        assert!(secret.validate(&token, now).is_err());
+        assert!(secret.validate("a.b.c.d", now).is_err());
+    }
+
+    #[test]
+    fn test_token_grant() {
+        let secret = JwtSecret::from_hex(
+            "0x7365637265747365637265747365637265747365637265747365637265747365",
+        )
+        .unwrap();
+        let now = 1_700_000_000;
+        let token = secret.encode(&JwtClaims {
+            apis: Some("safe,debug,-txpool".into()),
+            eth_apis: Some("debug".into()),
+            ..claims(now)
+        });
+
+        let grant = secret.validate(&token, now).unwrap();
+        assert!(Api::Debug.is_granted(&grant));
+        assert!(Api::Cfx.is_granted(&grant));
+        assert!(!Api::TxPool.is_granted(&grant));
+        assert!(!Api::Admin.is_granted(&grant));
+        assert!(EthApi::Debug.is_granted(&grant));
+        assert!(!EthApi::Eth.is_granted(&grant));
+
+        let token = secret.encode(&JwtClaims {
+            apis: Some("unknown".into()),
+            ..claims(now)
+        });
+        assert!(secret.validate(&token, now).is_err());
+    }
+
+    #[test]
+    fn test_restrict_namespaces() {
+        let mut handler = MetaIoHandler::<Metadata>::default();
+        handler.add_sync_method("debug_foo", |_| Ok(Value::from(1)));
+        let namespaces: HashMap<String, Api> =
+            [("debug_foo".to_string(), Api::Debug)]
+                .into_iter()
+                .collect();
+        let handler = restrict_namespaces(handler, &namespaces);
+
+        let request =
+            r#"{"jsonrpc":"2.0","method":"debug_foo","params":[],"id":1}"#;
+        let ok = Some(r#"{"jsonrpc":"2.0","result":1,"id":1}"#.to_string());
+        assert_eq!(
+            handler.handle_request_sync(request, Metadata::default()),
+            ok
+        );
+
+        let meta = Metadata {
+            auth: Some(Arc::new(JwtGrant::default())),
+            ..Default::default()
+        };
+        let response = handler.handle_request_sync(request, meta).unwrap();
+        assert!(response.contains("-32079"));
+
+        let meta = Metadata {
+            auth: Some(Arc::new(JwtGrant {
+                apis: [Api::Debug].into_iter().collect(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        assert_eq!(handler.handle_request_sync(request, meta), ok);
+    }
+
+    #[test]
+    fn test_ws_session_grant() {
+        let mut handler = MetaIoHandler::<Metadata>::default();
+        handler.add_sync_method("debug_foo", |_| Ok(Value::from(1)));
+        let namespaces: HashMap<String, Api> =
+            [("debug_foo".to_string(), Api::Debug)]
+                .into_iter()
+                .collect();
+        let handler = restrict_namespaces(handler, &namespaces);
+
+        let secret = Arc::new(JwtSecret::random());
+        let (middleware, extractor) =
+            ws_jwt_auth(secret.clone(), |_: &ws::RequestContext| {
+                Metadata::default()
+            });
+        let server = ws::ServerBuilder::with_meta_extractor(handler, extractor)
+            .request_middleware(middleware)
+            .start(&"127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        let addr = server.addr();
+
+        let now = unix_now();
+        let debug_token = secret.encode(&JwtClaims {
+            apis: Some("debug".into()),
+            ..claims(now)
+        });
+        let safe_token = secret.encode(&claims(now));
+        let ok = r#"{"jsonrpc":"2.0","result":1,"id":1}"#.to_string();
+
+        // Each session is restricted by the grant of its own token.
+        assert_eq!(ws_call(addr, Some(&debug_token)), Ok(ok.clone()));
+        assert!(ws_call(addr, Some(&safe_token)).unwrap().contains("-32079"));
+        assert_eq!(ws_call(addr, Some(&debug_token)), Ok(ok));
+
+        assert!(ws_call(addr, None).unwrap_err().contains("401"));
+        let token = JwtSecret::random().encode(&claims(now));
+        assert!(ws_call(addr, Some(&token)).unwrap_err().contains("401"));
+
+        server.close();
+    }
+}
@@ -15,7 +15,7 @@
 // along with Parity Ethereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Parity RPC requests Metadata.
-use super::types::Origin;
+use super::{interceptor::RequestTransport, jwt::JwtGrant, types::Origin};
 use jsonrpc_core;
 use jsonrpc_pubsub::{PubSubMetadata, Session};
 use std::sync::Arc;
@@ -27,6 +27,9 @@ pub struct Metadata {
     pub origin: Origin,
     /// Request PubSub Session
     pub session: Option<Arc<Session>>,
+    /// Namespaces granted by the JWT of the request. Requests to servers
+    /// without JWT authentication have none and are not restricted.
+    pub auth: Option<Arc<JwtGrant>>,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -34,3 +37,7 @@ impl jsonrpc_core::Metadata for Metadata {}
 impl PubSubMetadata for Metadata {
     fn session(&self) -> Option<Arc<Session>> { self.session.clone() }
 }
+
+impl RequestTransport for Metadata {
+    fn transport(&self) -> &'static str { self.origin.transport() }
+}
@@ -7,13 +7,19 @@ use cfx_rpc_builder::{
     TransportRpcModuleConfig,
 };
 use cfx_tasks::TaskExecutor;
+use cfx_types::Space;
 use cfxcore::{
-    SharedConsensusGraph, SharedSynchronizationService, SharedTransactionPool,
+    pow::PowWorkProvider, SharedConsensusGraph, SharedSynchronizationService,
+    SharedTransactionPool,
 };
 use jsonrpc_core::{MetaIoHandler, RemoteProcedure, Value};
 use jsonrpc_http_server::{
     Server as HttpServer, ServerBuilder as HttpServerBuilder,
 };
+use jsonrpc_ipc_server::{
+    MetaExtractor as IpcMetaExtractor, SecurityAttributes, Server as IpcServer,
+    ServerBuilder as IpcServerBuilder,
+};
 use jsonrpc_tcp_server::{
     MetaExtractor as TpcMetaExtractor, Server as TcpServer,
     ServerBuilder as TcpServerBuilder,
@@ -34,7 +40,8 @@ mod helpers;
 mod http_common;
 pub mod impls;
 pub mod informant;
-mod interceptor;
+pub(crate) mod interceptor;
+pub mod jwt;
 pub mod metadata;
 mod traits;
 pub mod types;
@@ -46,6 +53,7 @@ pub use errors::{error_codes, invalid_params};
 
 use self::{
     impls::{
+        admin::AdminHandler,
         cfx::{CfxHandler, LocalRpcImpl, RpcImpl, TestRpcImpl, TraceHandler},
         cfx_filter::CfxFilterClient,
         common::RpcImpl as CommonImpl,
@@ -59,6 +67,7 @@ use self::{
         pubsub::PubSubClient,
     },
     traits::{
+        admin::Admin,
         cfx::Cfx,
         cfx_filter::CfxFilter,
         debug::LocalRpc,
@@ -79,6 +88,8 @@ use crate::{
     configuration::Configuration,
     rpc::{
         apis::{Api, ApiSet, EthApi},
+        extractor::RpcExtractor,
+        http_common::MetaExtractor as HttpMetaExtractor,
         impls::{
             eth::{EthHandler, EthTraceHandler, GethDebugHandler},
             eth_filter::EthFilterHelper as EthFilterClient,
@@ -89,11 +100,15 @@ use crate::{
     },
 };
 pub use cfx_config::rpc_server_config::{
-    HttpConfiguration, TcpConfiguration, WsConfiguration,
+    HttpConfiguration, IpcConfiguration, TcpConfiguration, WsConfiguration,
 };
 use interceptor::{MetricsInterceptor, ThrottleInterceptor};
+use jwt::{restrict_namespaces, ws_jwt_auth, HttpJwtMiddleware, JwtSecret};
 pub use metadata::Metadata;
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 pub fn setup_public_rpc_apis(
     common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: PubSubClient,
@@ -145,8 +160,15 @@ fn setup_rpc_apis(
     throttling_section: &str, apis: HashSet<Api>,
 ) -> MetaIoHandler<Metadata> {
     let mut handler = MetaIoHandler::default();
+    let mut namespaces = HashMap::new();
     for api in &apis {
+        let known = method_names(&handler);
         match api {
+            Api::Admin => {
+                handler.extend_with(
+                    AdminHandler::new(common.clone()).to_delegate(),
+                );
+            }
             Api::Cfx => {
                 let cfx =
                     CfxHandler::new(common.clone(), rpc.clone()).to_delegate();
@@ -156,6 +178,7 @@ fn setup_rpc_apis(
                     cfx,
                     throttling_conf,
                     throttling_section,
+                    Space::Native,
                 );
 
                 if let Some(poll_lifetime) = rpc.config.poll_lifetime_in_seconds
@@ -178,6 +201,7 @@ fn setup_rpc_apis(
                             filter_client,
                             throttling_conf,
                             throttling_section,
+                            Space::Native,
                         );
                     }
                 }
@@ -195,6 +219,7 @@ fn setup_rpc_apis(
                     pubsub.clone().to_delegate(),
                     throttling_conf,
                     throttling_section,
+                    Space::Native,
                 );
             }
             Api::Test => {
@@ -214,6 +239,7 @@ fn setup_rpc_apis(
                     trace,
                     throttling_conf,
                     throttling_section,
+                    Space::Native,
                 );
             }
             Api::TxPool => {
@@ -225,6 +251,7 @@ fn setup_rpc_apis(
                     txpool,
                     throttling_conf,
                     throttling_section,
+                    Space::Native,
                 );
             }
             Api::Pos => {
@@ -240,8 +267,10 @@ fn setup_rpc_apis(
                 handler.extend_with(RpcProxy::new(pos, pos_interceptor));
             }
         }
+        record_namespace(&handler, &known, api, &mut namespaces);
     }
 
+    let handler = restrict_namespaces(handler, &namespaces);
     add_meta_rpc_methods(handler, apis)
 }
 
@@ -251,7 +280,9 @@ fn setup_evm_rpc_apis(
     apis: HashSet<EthApi>, executor: TaskExecutor,
 ) -> MetaIoHandler<Metadata> {
     let mut handler = MetaIoHandler::default();
+    let mut namespaces = HashMap::new();
     for api in &apis {
+        let known = method_names(&handler);
         match api {
             EthApi::Eth => {
                 info!("Add EVM RPC");
@@ -261,6 +292,7 @@ fn setup_evm_rpc_apis(
                     rpc.sync.clone(),
                     rpc.tx_pool.clone(),
                     executor.clone(),
+                    Some(rpc.block_gen.clone() as Arc<dyn PowWorkProvider>),
                 )
                 .to_delegate();
                 let evm_trace_handler = EthTraceHandler {
@@ -276,6 +308,7 @@ fn setup_evm_rpc_apis(
                     evm,
                     throttling_conf,
                     throttling_section,
+                    Space::Ethereum,
                 );
                 handler.extend_with(evm_trace_handler);
 
@@ -297,6 +330,7 @@ fn setup_evm_rpc_apis(
                         filter_client,
                         throttling_conf,
                         throttling_section,
+                        Space::Ethereum,
                     );
                 }
             }
@@ -308,6 +342,7 @@ fn setup_evm_rpc_apis(
                     eth_pubsub.clone().to_delegate(),
                     throttling_conf,
                     throttling_section,
+                    Space::Ethereum,
                 );
             }
             EthApi::Debug => {
@@ -322,12 +357,14 @@ fn setup_evm_rpc_apis(
                     geth_debug.to_delegate(),
                     throttling_conf,
                     throttling_section,
+                    Space::Ethereum,
                 );
             }
         }
+        record_namespace(&handler, &known, api, &mut namespaces);
     }
 
-    handler
+    restrict_namespaces(handler, &namespaces)
 }
 
 pub fn extend_with_interceptor<
@@ -335,19 +372,37 @@ pub fn extend_with_interceptor<
 >(
     handler: &mut MetaIoHandler<Metadata>, rpc_conf: &RpcImplConfiguration,
     rpc_impl: T, throttling_conf: &Option<String>, throttling_section: &str,
+    space: Space,
 ) {
     let interceptor =
         ThrottleInterceptor::new(throttling_conf, throttling_section);
     if rpc_conf.enable_metrics {
         handler.extend_with(RpcProxy::new(
             rpc_impl,
-            MetricsInterceptor::new(interceptor),
+            MetricsInterceptor::new(interceptor, space),
         ));
     } else {
         handler.extend_with(RpcProxy::new(rpc_impl, interceptor));
     }
 }
 
+fn method_names(handler: &MetaIoHandler<Metadata>) -> HashSet<String> {
+    handler.iter().map(|(method, _)| method.clone()).collect()
+}
+
+/// Map the methods added to the handler since `known` to the namespace, so
+/// that they can be restricted to the tokens granting it.
+fn record_namespace<N: Clone>(
+    handler: &MetaIoHandler<Metadata>, known: &HashSet<String>, namespace: &N,
+    namespaces: &mut HashMap<String, N>,
+) {
+    for (method, _) in handler.iter() {
+        if !known.contains(method) {
+            namespaces.insert(method.clone(), namespace.clone());
+        }
+    }
+}
+
 fn add_meta_rpc_methods(
     mut handler: MetaIoHandler<Metadata>, apis: HashSet<Api>,
 ) -> MetaIoHandler<Metadata> {
@@ -414,8 +469,15 @@ fn setup_rpc_apis_light(
     apis: HashSet<Api>,
 ) -> MetaIoHandler<Metadata> {
     let mut handler = MetaIoHandler::default();
+    let mut namespaces = HashMap::new();
     for api in apis {
+        let known = method_names(&handler);
         match api {
+            Api::Admin => {
+                handler.extend_with(
+                    AdminHandler::new(common.clone()).to_delegate(),
+                );
+            }
             Api::Cfx => {
                 let cfx = LightCfxHandler::new(common.clone(), rpc.clone())
                     .to_delegate();
@@ -448,8 +510,9 @@ fn setup_rpc_apis_light(
                 warn!("Light nodes do not support PoS RPC");
             }
         }
+        record_namespace(&handler, &known, &api, &mut namespaces);
     }
-    handler
+    restrict_namespaces(handler, &namespaces)
 }
 
 pub fn start_tcp<H, T>(
@@ -479,10 +542,18 @@ pub fn start_http(
     if !conf.enabled {
         return Ok(None);
     }
-    let mut builder = HttpServerBuilder::new(handler);
+    let mut builder = HttpServerBuilder::with_meta_extractor(
+        handler,
+        HttpMetaExtractor::new(RpcExtractor),
+    );
     if let Some(threads) = conf.threads {
         builder = builder.threads(threads);
     }
+    if let Some(path) = &conf.jwt_secret_path {
+        let secret = JwtSecret::load_or_create(Path::new(path))?;
+        builder = builder
+            .request_middleware(HttpJwtMiddleware::new(Arc::new(secret)));
+    }
 
     match builder
         .keep_alive(conf.keep_alive)
@@ -508,7 +579,17 @@ where
         return Ok(None);
     }
 
-    match WsServerBuilder::with_meta_extractor(handler, extractor)
+    let builder = match &conf.jwt_secret_path {
+        Some(path) => {
+            let secret = JwtSecret::load_or_create(Path::new(path))?;
+            let (middleware, extractor) =
+                ws_jwt_auth(Arc::new(secret), extractor);
+            WsServerBuilder::with_meta_extractor(handler, extractor)
+                .request_middleware(middleware)
+        }
+        None => WsServerBuilder::with_meta_extractor(handler, extractor),
+    };
+    match builder
         .max_payload(conf.max_payload_bytes)
         .start(&conf.address)
     {
@@ -519,12 +600,38 @@ where
     }
 }
 
+pub fn start_ipc<H, T>(
+    conf: IpcConfiguration, handler: H, extractor: T,
+) -> Result<Option<IpcServer>, String>
+where
+    H: Into<MetaIoHandler<Metadata>>,
+    T: IpcMetaExtractor<Metadata> + 'static,
+{
+    if !conf.enabled {
+        return Ok(None);
+    }
+
+    let security_attributes = SecurityAttributes::empty()
+        .set_mode(conf.permissions)
+        .map_err(|e| format!("IPC error: {} (path = {})", e, conf.path))?;
+    match IpcServerBuilder::with_meta_extractor(handler, extractor)
+        .set_security_attributes(security_attributes)
+        .start(&conf.path)
+    {
+        Ok(server) => Ok(Some(server)),
+        Err(io_error) => {
+            Err(format!("IPC error: {} (path = {})", io_error, conf.path))
+        }
+    }
+}
+
 // start espace rpc server v2(async)
 pub async fn launch_async_rpc_servers(
     rpc_conf: RpcImplConfiguration, throttling_conf_file: Option<String>,
     apis: RpcModuleSelection, consensus: SharedConsensusGraph,
     sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
     addr: Option<SocketAddr>, executor: TaskExecutor,
+    work_provider: Option<Arc<dyn PowWorkProvider>>,
 ) -> Result<Option<RpcServerHandle>, String> {
     if addr.is_none() {
         return Ok(None);
@@ -532,8 +639,14 @@ pub async fn launch_async_rpc_servers(
 
     let enable_metrics = rpc_conf.enable_metrics;
 
-    let rpc_module_builder =
-        RpcModuleBuilder::new(rpc_conf, consensus, sync, tx_pool, executor);
+    let rpc_module_builder = RpcModuleBuilder::new(
+        rpc_conf,
+        consensus,
+        sync,
+        tx_pool,
+        executor,
+        work_provider,
+    );
 
     info!(
         "Enabled evm async rpc modules: {:?}",
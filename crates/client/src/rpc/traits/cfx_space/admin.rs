@@ -0,0 +1,71 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    common::config_reload::ConfigReloadReport,
+    rpc::types::{NodeInfo, PeerReputation},
+};
+use jsonrpc_core::Result as JsonRpcResult;
+use jsonrpc_derive::rpc;
+use network::{node_table::NodeId, SessionDetails};
+
+/// Peer management RPCs for node operators.
+#[rpc(server)]
+pub trait Admin {
+    /// Returns the sessions of connected peers.
+    #[rpc(name = "admin_peers")]
+    fn admin_peers(&self) -> JsonRpcResult<Vec<SessionDetails>>;
+
+    /// Adds a peer with node URL `cfxnode://<id>@<ip>:<port>` to the trusted
+    /// nodes, which will be connected if there are free peer slots.
+    #[rpc(name = "admin_addPeer")]
+    fn admin_add_peer(&self, url: String) -> JsonRpcResult<bool>;
+
+    /// Adds a peer with node URL `cfxnode://<id>@<ip>:<port>` to the static
+    /// peers, which are always connected regardless of the peer limits.
+    #[rpc(name = "admin_addStaticPeer", alias("admin_addTrustedPeer"))]
+    fn admin_add_static_peer(&self, url: String) -> JsonRpcResult<bool>;
+
+    /// Removes a peer from the static peers without disconnecting it.
+    /// Returns false if it is not a static peer.
+    #[rpc(name = "admin_removeStaticPeer")]
+    fn admin_remove_static_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    /// Returns the URLs of the static peers.
+    #[rpc(name = "admin_staticPeers")]
+    fn admin_static_peers(&self) -> JsonRpcResult<Vec<String>>;
+
+    /// Removes a peer from the node database and static peers, and
+    /// disconnects it. Returns false if the peer is unknown.
+    #[rpc(name = "admin_removePeer")]
+    fn admin_remove_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    /// Bans a peer permanently and disconnects it. The peer need not be known
+    /// to the node database. The ban is persisted and survives restarts, and
+    /// is only lifted by `admin_unbanPeer`. Returns false if already banned.
+    #[rpc(name = "admin_banPeer")]
+    fn admin_ban_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    /// Lifts the ban of a peer. Returns false if the peer is not banned.
+    #[rpc(name = "admin_unbanPeer")]
+    fn admin_unban_peer(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    /// Returns the reputation of peers which have misbehaved recently,
+    /// ordered from the worst.
+    #[rpc(name = "admin_peerReputations")]
+    fn admin_peer_reputations(&self) -> JsonRpcResult<Vec<PeerReputation>>;
+
+    /// Returns the ids of peers banned by `admin_banPeer`.
+    #[rpc(name = "admin_bannedPeers")]
+    fn admin_banned_peers(&self) -> JsonRpcResult<Vec<NodeId>>;
+
+    /// Returns the information of the local node.
+    #[rpc(name = "admin_nodeInfo")]
+    fn admin_node_info(&self) -> JsonRpcResult<NodeInfo>;
+
+    /// Reloads the configuration file and applies the changes which do not
+    /// require restart, as on SIGHUP. Returns the changed configurations.
+    #[rpc(name = "admin_reloadConfig")]
+    fn admin_reload_config(&self) -> JsonRpcResult<ConfigReloadReport>;
+}
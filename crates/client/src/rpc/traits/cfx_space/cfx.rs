@@ -9,7 +9,8 @@ use crate::rpc::types::{
     EpochNumber, EstimateGasAndCollateralResponse, Log as RpcLog, PoSEconomics,
     Receipt as RpcReceipt, RewardInfo as RpcRewardInfo, RpcAddress,
     SponsorInfo, Status as RpcStatus, StorageCollateralInfo, TokenSupplyInfo,
-    Transaction, TransactionRequest, VoteParamsInfo, U64 as HexU64,
+    Transaction, TransactionRequest, TransactionsByAddress, VoteParamsInfo,
+    U64 as HexU64,
 };
 use cfx_types::{H256, U256, U64};
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
@@ -196,6 +197,13 @@ pub trait Cfx {
         maybe_limit: Option<U64>,
     ) -> BoxFuture<JsonRpcResult<AccountPendingTransactions>>;
 
+    /// Get the executed transactions sent from or to an address, from the
+    /// newest to the oldest. Requires `persist_address_tx_index`.
+    #[rpc(name = "cfx_getTransactionsByAddress")]
+    fn transactions_by_address(
+        &self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>,
+    ) -> BoxFuture<JsonRpcResult<TransactionsByAddress>>;
+
     /// Return estimated gas and collateral usage.
     #[rpc(name = "cfx_estimateGasAndCollateral")]
     fn estimate_gas_and_collateral(
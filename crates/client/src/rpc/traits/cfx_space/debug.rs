@@ -5,11 +5,11 @@
 use crate::rpc::types::{
     BlockHashOrEpochNumber, Bytes as RpcBytes, ConsensusGraphStates,
     EpochNumber, Receipt as RpcReceipt, RpcAddress, StatOnGasLoad,
-    SyncGraphStates, Transaction as RpcTransaction, TransactionRequest,
-    WrapTransaction,
+    StratumWorker, SyncGraphStates, Transaction as RpcTransaction,
+    TransactionRequest, WrapTransaction,
 };
 use cfx_types::{H256, H520, U128, U64};
-use cfxcore::verification::EpochReceiptProof;
+use cfxcore::{verification::EpochReceiptProof, ExecutionWitness};
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
 use jsonrpc_derive::rpc;
 use network::{
@@ -74,6 +74,10 @@ pub trait LocalRpc {
     #[rpc(name = "debug_syncGraphState")]
     fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
 
+    /// Returns the share statistics of the connected Stratum workers.
+    #[rpc(name = "debug_getStratumWorkers")]
+    fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
+
     #[rpc(name = "cfx_sendTransaction")]
     fn send_transaction(
         &self, tx: TransactionRequest, password: Option<String>,
@@ -132,4 +136,21 @@ pub trait LocalRpc {
     fn transactions_by_block(
         &self, block_hash: H256,
     ) -> JsonRpcResult<Vec<WrapTransaction>>;
+
+    /// Re-executes an epoch and returns the state entries it accesses, with
+    /// their proof and the contract codes, to re-execute it statelessly.
+    ///
+    /// Fails for an epoch which kills a contract or clears a sponsor
+    /// whitelist, since the keys removed by prefix can't be proved complete.
+    #[rpc(name = "debug_executionWitness")]
+    fn execution_witness(
+        &self, epoch_number: U64,
+    ) -> JsonRpcResult<ExecutionWitness>;
+
+    /// Re-executes the epoch in a witness without the local state, and
+    /// returns the resulting state root hash if it's on the pivot chain.
+    #[rpc(name = "debug_verifyExecutionWitness")]
+    fn verify_execution_witness(
+        &self, witness: ExecutionWitness,
+    ) -> JsonRpcResult<H256>;
 }
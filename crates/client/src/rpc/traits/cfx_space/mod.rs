@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod cfx;
 pub mod cfx_filter;
 pub mod debug;
@@ -20,7 +20,7 @@
 
 //! Eth rpc interface.
 use crate::rpc::types::U64 as HexU64;
-use cfx_types::{H160, H256, U256, U64};
+use cfx_types::{H160, H256, H64, U256, U64};
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 
@@ -28,7 +28,7 @@ use crate::rpc::types::{
     eth::{
         AccountPendingTransactions, Block, BlockNumber, BlockOverrides,
         EthRpcLogFilter, Log, Receipt, RpcStateOverride, SyncStatus,
-        Transaction, TransactionRequest,
+        Transaction, TransactionRequest, TransactionsByAddress, Work,
     },
     Bytes, FeeHistory, Index,
 };
@@ -215,6 +215,15 @@ pub trait Eth {
     #[rpc(name = "eth_getLogs")]
     fn logs(&self, filter: EthRpcLogFilter) -> Result<Vec<Log>>;
 
+    /// Returns the hash of the current block, the seedHash, and the boundary
+    /// condition to be met.
+    #[rpc(name = "eth_getWork")]
+    fn work(&self) -> Result<Work>;
+
+    /// Used for submitting a proof-of-work solution.
+    #[rpc(name = "eth_submitWork")]
+    fn submit_work(&self, _: H64, _: H256, _: H256) -> Result<bool>;
+
     /// Used for submitting mining hashrate.
     #[rpc(name = "eth_submitHashrate")]
     fn submit_hashrate(&self, _: U256, _: H256) -> Result<bool>;
@@ -232,4 +241,11 @@ pub trait Eth {
         &self, address: H160, maybe_start_nonce: Option<U256>,
         maybe_limit: Option<U64>,
     ) -> Result<AccountPendingTransactions>;
+
+    /// Returns the executed transactions sent from or to an address, from the
+    /// newest to the oldest. Requires `persist_address_tx_index`.
+    #[rpc(name = "eth_getTransactionsByAddress")]
+    fn transactions_by_address(
+        &self, address: H160, cursor: Option<U64>, limit: Option<U64>,
+    ) -> Result<TransactionsByAddress>;
 }
@@ -0,0 +1,40 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H256, U64};
+use cfxcore::block_data_manager::AddressTransaction as PrimitiveAddressTransaction;
+use serde::Serialize;
+
+/// A transaction sent from or to an address.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransaction {
+    pub transaction_hash: H256,
+    pub block_hash: H256,
+    pub epoch_number: U64,
+    pub transaction_index: U64,
+}
+
+impl From<PrimitiveAddressTransaction> for AddressTransaction {
+    fn from(tx: PrimitiveAddressTransaction) -> Self {
+        AddressTransaction {
+            transaction_hash: tx.hash,
+            block_hash: tx.block_hash,
+            epoch_number: tx.epoch.into(),
+            transaction_index: tx.index.into(),
+        }
+    }
+}
+
+/// A page of the transactions of an address, from the newest to the oldest.
+/// The number of entries scanned for a page is bounded, so a page may have
+/// fewer transactions than the limit even if there are more transactions.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsByAddress {
+    pub transactions: Vec<AddressTransaction>,
+    /// The cursor to get the next page with, or `None` if there are no more
+    /// transactions.
+    pub next_cursor: Option<U64>,
+}
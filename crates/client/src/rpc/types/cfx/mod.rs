@@ -1,10 +1,12 @@
 mod access_list;
 pub mod account;
+pub mod address_transactions;
 pub mod blame_info;
 pub mod block;
 pub mod consensus_graph_states;
 pub mod filter;
 pub mod log;
+pub mod node_info;
 pub mod pos_economics;
 pub mod pubsub;
 pub mod receipt;
@@ -13,6 +15,7 @@ pub mod sponsor_info;
 pub mod stat_on_gas_load;
 pub mod status;
 pub mod storage_collateral_info;
+pub mod stratum_worker;
 pub mod sync_graph_states;
 pub mod token_supply_info;
 pub mod transaction;
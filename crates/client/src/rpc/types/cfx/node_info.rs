@@ -0,0 +1,35 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use network::{node_table::NodeId, ProtocolInfo};
+use serde::Serialize;
+use std::net::SocketAddr;
+
+/// Information of the local node that other nodes use to connect to it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    pub id: NodeId,
+    /// Node URL in the format of `cfxnode://<id>@<ip>:<port>`, which could
+    /// be added as a peer by other nodes.
+    pub url: String,
+    pub listen_address: Option<SocketAddr>,
+    pub public_address: SocketAddr,
+    pub udp_port: u16,
+    pub protocols: Vec<ProtocolInfo>,
+}
+
+/// Reputation of a peer which has misbehaved recently.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerReputation {
+    pub id: NodeId,
+    /// The score decays towards zero over time, and the peer is banned once
+    /// it drops to the ban threshold.
+    pub score: f64,
+    /// The remaining seconds of the ban, if the peer is banned.
+    pub banned_seconds: Option<u64>,
+    /// The remaining seconds before the peer could be connected again.
+    pub backoff_seconds: Option<u64>,
+}
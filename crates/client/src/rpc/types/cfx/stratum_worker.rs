@@ -0,0 +1,35 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use blockgen::StratumWorkerStats;
+use cfx_types::{U256, U64};
+use serde::Serialize;
+use std::net::SocketAddr;
+
+/// Share statistics of a worker connected to the Stratum server.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StratumWorker {
+    pub worker_id: String,
+    pub addr: SocketAddr,
+    pub share_difficulty: U256,
+    pub accepted_shares: U64,
+    pub rejected_shares: U64,
+    pub stale_shares: U64,
+    pub mined_blocks: U64,
+}
+
+impl From<StratumWorkerStats> for StratumWorker {
+    fn from(stats: StratumWorkerStats) -> Self {
+        StratumWorker {
+            worker_id: stats.worker_id,
+            addr: stats.addr,
+            share_difficulty: stats.share_difficulty,
+            accepted_shares: stats.accepted_shares.into(),
+            rejected_shares: stats.rejected_shares.into(),
+            stale_shares: stats.stale_shares.into(),
+            mined_blocks: stats.mined_blocks.into(),
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub use cfx_rpc_eth_types::{
     AccountPendingTransactions, Block, BlockNumber, BlockOverrides,
     EthRpcLogFilter, EvmOverrides, FilterChanges, Header, Log, Receipt,
     RpcStateOverride, SyncInfo, SyncStatus, Transaction, TransactionRequest,
+    TransactionsByAddress, Work,
 };
 
 pub use cfx_rpc_cfx_types::trace_eth::{LocalizedTrace, Res};
@@ -14,12 +14,14 @@ pub use self::{
     cfx::{
         address,
         address::{check_two_rpc_address_network_match, RpcAddress},
+        address_transactions::{AddressTransaction, TransactionsByAddress},
         blame_info::BlameInfo,
         block::{Block, BlockTransactions, Header},
         consensus_graph_states::ConsensusGraphStates,
         epoch_number::{BlockHashOrEpochNumber, EpochNumber},
         filter::{CfxFilterChanges, CfxFilterLog, CfxRpcLogFilter, RevertTo},
         log::Log,
+        node_info::{NodeInfo, PeerReputation},
         pos_economics::PoSEconomics,
         pubsub,
         receipt::Receipt,
@@ -27,6 +29,7 @@ pub use self::{
         stat_on_gas_load::StatOnGasLoad,
         status::Status,
         storage_collateral_info::StorageCollateralInfo,
+        stratum_worker::StratumWorker,
         sync_graph_states::SyncGraphStates,
         token_supply_info::TokenSupplyInfo,
         transaction::{PackedOrExecuted, Transaction, WrapTransaction},
@@ -29,6 +29,8 @@ pub enum Origin {
     Rpc(String),
     /// TCP server (includes peer address)
     Tcp(SocketAddr),
+    /// IPC server (includes session hash)
+    Ipc(H256),
     /// WS server
     Ws {
         /// Session id
@@ -49,11 +51,26 @@ impl Default for Origin {
     fn default() -> Self { Origin::Unknown }
 }
 
+impl Origin {
+    /// The transport of the request, which is used as a label of metrics.
+    pub fn transport(&self) -> &'static str {
+        match *self {
+            Origin::Rpc(_) => "http",
+            Origin::Tcp(_) => "tcp",
+            Origin::Ipc(_) => "ipc",
+            Origin::Ws { .. } | Origin::Signer { .. } => "ws",
+            Origin::CApi => "capi",
+            Origin::Unknown => "unknown",
+        }
+    }
+}
+
 impl fmt::Display for Origin {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Origin::Rpc(ref origin) => write!(f, "{} via RPC", origin),
             Origin::Tcp(ref address) => write!(f, "TCP (address: {})", address),
+            Origin::Ipc(ref session) => write!(f, "IPC (session: {})", session),
             Origin::Ws { ref session } => {
                 write!(f, "WebSocket (session: {})", session)
             }
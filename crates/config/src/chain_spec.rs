@@ -0,0 +1,374 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::{collections::BTreeMap, fs::File, io::Read};
+
+use cfx_executor::spec::{
+    CommonParams, TransitionsBlockNumber, TransitionsEpochHeight,
+};
+use cfx_parameters::consensus_internal::{
+    INITIAL_BASE_MINING_REWARD_IN_UCFX, MINING_REWARD_TANZANITE_IN_UCFX,
+};
+use cfx_types::{Space, SpaceMap, U256};
+use cfxcore::genesis_block::{
+    load_alloc_config, load_file, GenesisAccountConfig, GenesisAllocConfig,
+};
+use network::NetworkConfiguration;
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_config_address_string, Configuration, RawConfiguration};
+
+/// The version of the chain specification format. It should be increased
+/// when the fields are changed, e.g. a new hardfork is added.
+pub const CHAIN_SPEC_VERSION: u32 = 2;
+
+/// The built-in chains which could be exported as chain specifications.
+pub const BUILTIN_CHAINS: [&str; 1] = ["mainnet"];
+
+/// The configuration files shipped for the built-in chains, from which their
+/// chain specifications are derived.
+const BUILTIN_CHAIN_CONFIGS: [(&str, &str); 1] =
+    [("mainnet", include_str!("../../../run/hydra.toml"))];
+
+/// The parameters of a chain in one JSON file: chain ids, hardfork
+/// activation points, PoS parameters and genesis accounts. If configured by
+/// `chain_spec`, it overrides the corresponding configuration items.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+    pub version: u32,
+    pub chain_id: u32,
+    pub evm_chain_id: u32,
+    pub params: ChainParams,
+    pub transition_numbers: TransitionsBlockNumber,
+    pub transition_heights: TransitionsEpochHeight,
+    pub pos: PosParams,
+    #[serde(default)]
+    pub genesis: GenesisAllocConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ChainParams {
+    pub anticone_penalty_ratio: u64,
+    pub evm_transaction_block_ratio: u64,
+    pub evm_transaction_gas_ratio: u64,
+    pub params_dao_vote_period: u64,
+    pub min_native_base_price: u64,
+    pub min_eth_base_price: u64,
+    /// Set the internal contracts to state at the genesis block.
+    pub early_set_internal_contracts_states: bool,
+    /// The PoW difficulty of the blocks right after the genesis block.
+    pub initial_difficulty: Option<u64>,
+}
+
+/// The PoS parameters, see the `pos_*` configuration items.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PosParams {
+    pub reference_enable_height: u64,
+    pub pivot_decision_defer_epoch_count: u64,
+    pub cip113_pivot_decision_defer_epoch_count: u64,
+    pub cip113_transition_height: u64,
+    pub round_per_term: u64,
+    pub term_max_size: usize,
+    pub term_elected_size: usize,
+    pub in_queue_locked_views: u64,
+    pub out_queue_locked_views: u64,
+    pub cip99_transition_view: u64,
+    pub cip99_in_queue_locked_views: u64,
+    pub cip99_out_queue_locked_views: u64,
+    pub nonce_limit_transition_view: u64,
+    pub cip136_transition_view: u64,
+    pub cip136_in_queue_locked_views: u64,
+    pub cip136_out_queue_locked_views: u64,
+    pub cip136_round_per_term: u64,
+    pub cip156_transition_view: u64,
+    pub cip156_dispute_locked_views: u64,
+}
+
+impl ChainSpec {
+    pub fn load_file(path: &str) -> Result<Self, String> {
+        let mut content = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut content))
+            .map_err(|e| format!("failed to read chain spec file: {:?}", e))?;
+        let spec: ChainSpec = serde_json::from_str(&content)
+            .map_err(|e| format!("failed to parse chain spec file: {}", e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn builtin(name: &str) -> Result<Self, String> {
+        let (_, config) = BUILTIN_CHAIN_CONFIGS
+            .iter()
+            .find(|(chain, _)| *chain == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown chain {}, expected one of {:?}",
+                    name, BUILTIN_CHAINS
+                )
+            })?;
+        let conf = Configuration {
+            raw_conf: RawConfiguration::from_toml(config)?,
+            ..Default::default()
+        };
+        Self::from_configuration(&conf)
+    }
+
+    /// Exports the chain specification used by `conf`.
+    pub fn from_configuration(conf: &Configuration) -> Result<Self, String> {
+        if let Some(spec) = &conf.chain_spec {
+            return Ok(spec.clone());
+        }
+
+        let raw_conf = &conf.raw_conf;
+        let chain_id = raw_conf.chain_id.ok_or("chain_id is not set")?;
+        let mut genesis = match &raw_conf.genesis_alloc {
+            Some(path) => load_alloc_config(path)?,
+            None => Default::default(),
+        };
+        // The accounts in `genesis_accounts` only have balances in core
+        // space, so they are exported as genesis alloc accounts.
+        if let Some(path) = &raw_conf.genesis_accounts {
+            let network = NetworkConfiguration::network_id_to_known_cfx_network(
+                conf.network_id(),
+            );
+            let accounts = load_file(path, |addr| {
+                parse_config_address_string(addr, &network)
+            })?;
+            for (address, balance) in accounts {
+                let key = format!("0x{:x}", address.address);
+                let account = GenesisAccountConfig {
+                    balance: Some(balance.to_string()),
+                    ..Default::default()
+                };
+                if genesis.native.insert(key.clone(), account).is_some() {
+                    return Err(format!(
+                        "genesis account {} is in both genesis_accounts and \
+                         genesis_alloc",
+                        key
+                    ));
+                }
+            }
+        }
+        let params = conf.common_params();
+
+        Ok(ChainSpec {
+            version: CHAIN_SPEC_VERSION,
+            chain_id,
+            evm_chain_id: raw_conf.evm_chain_id.unwrap_or(chain_id),
+            params: ChainParams {
+                anticone_penalty_ratio: params.anticone_penalty_ratio,
+                evm_transaction_block_ratio: params.evm_transaction_block_ratio,
+                evm_transaction_gas_ratio: params.evm_transaction_gas_ratio,
+                params_dao_vote_period: params.params_dao_vote_period,
+                min_native_base_price: params
+                    .min_base_price
+                    .in_space(Space::Native)
+                    .as_u64(),
+                min_eth_base_price: params
+                    .min_base_price
+                    .in_space(Space::Ethereum)
+                    .as_u64(),
+                early_set_internal_contracts_states: params
+                    .early_set_internal_contracts_states,
+                initial_difficulty: raw_conf.initial_difficulty,
+            },
+            transition_numbers: params.transition_numbers,
+            transition_heights: params.transition_heights,
+            pos: PosParams {
+                reference_enable_height: raw_conf.pos_reference_enable_height,
+                pivot_decision_defer_epoch_count: raw_conf
+                    .pos_pivot_decision_defer_epoch_count,
+                cip113_pivot_decision_defer_epoch_count: raw_conf
+                    .cip113_pivot_decision_defer_epoch_count,
+                cip113_transition_height: raw_conf.cip113_transition_height,
+                round_per_term: raw_conf.pos_round_per_term,
+                term_max_size: raw_conf.pos_term_max_size,
+                term_elected_size: raw_conf.pos_term_elected_size,
+                in_queue_locked_views: raw_conf.pos_in_queue_locked_views,
+                out_queue_locked_views: raw_conf.pos_out_queue_locked_views,
+                cip99_transition_view: raw_conf.pos_cip99_transition_view,
+                cip99_in_queue_locked_views: raw_conf
+                    .pos_cip99_in_queue_locked_views,
+                cip99_out_queue_locked_views: raw_conf
+                    .pos_cip99_out_queue_locked_views,
+                nonce_limit_transition_view: raw_conf
+                    .nonce_limit_transition_view,
+                cip136_transition_view: raw_conf.pos_cip136_transition_view,
+                cip136_in_queue_locked_views: raw_conf
+                    .pos_cip136_in_queue_locked_views,
+                cip136_out_queue_locked_views: raw_conf
+                    .pos_cip136_out_queue_locked_views,
+                cip136_round_per_term: raw_conf.pos_cip136_round_per_term,
+                cip156_transition_view: raw_conf.pos_cip156_transition_view,
+                cip156_dispute_locked_views: raw_conf
+                    .pos_cip156_dispute_locked_views,
+            },
+            genesis,
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.version != CHAIN_SPEC_VERSION {
+            return Err(format!(
+                "unsupported chain spec version {}, expected {}",
+                self.version, CHAIN_SPEC_VERSION
+            ));
+        }
+        if self.transition_heights.cip1559 < self.pos.reference_enable_height {
+            return Err(format!(
+                "1559 can not be activated earlier than pos reference: 1559 \
+                 (epoch {}), pos (epoch {})",
+                self.transition_heights.cip1559,
+                self.pos.reference_enable_height
+            ));
+        }
+        // The current implementation requires the round number to be an even
+        // number.
+        if self.pos.round_per_term % 2 != 0
+            || self.pos.cip136_round_per_term % 2 != 0
+        {
+            return Err("PoS round_per_term should be an even number".into());
+        }
+        Ok(())
+    }
+
+    /// Overrides the configuration items defined by the chain specification,
+    /// so that they are consistent with `CommonParams`.
+    pub(crate) fn apply_to_raw_conf(&self, raw_conf: &mut RawConfiguration) {
+        raw_conf.chain_id = Some(self.chain_id);
+        raw_conf.evm_chain_id = Some(self.evm_chain_id);
+        raw_conf.anticone_penalty_ratio = self.params.anticone_penalty_ratio;
+        raw_conf.evm_transaction_block_ratio =
+            self.params.evm_transaction_block_ratio;
+        raw_conf.evm_transaction_gas_ratio =
+            self.params.evm_transaction_gas_ratio;
+        raw_conf.params_dao_vote_period = self.params.params_dao_vote_period;
+        raw_conf.min_native_base_price =
+            Some(self.params.min_native_base_price);
+        raw_conf.min_eth_base_price = Some(self.params.min_eth_base_price);
+        raw_conf.initial_difficulty = self.params.initial_difficulty;
+
+        raw_conf.tanzanite_transition_height = self.transition_heights.cip40;
+        raw_conf.cip90_transition_height = Some(self.transition_heights.cip90a);
+        raw_conf.cip112_transition_height =
+            Some(self.transition_heights.cip112);
+        raw_conf.align_evm_transition_height =
+            self.transition_heights.align_evm;
+
+        let pos = &self.pos;
+        raw_conf.pos_reference_enable_height = pos.reference_enable_height;
+        raw_conf.pos_pivot_decision_defer_epoch_count =
+            pos.pivot_decision_defer_epoch_count;
+        raw_conf.cip113_pivot_decision_defer_epoch_count =
+            pos.cip113_pivot_decision_defer_epoch_count;
+        raw_conf.cip113_transition_height = pos.cip113_transition_height;
+        raw_conf.pos_round_per_term = pos.round_per_term;
+        raw_conf.pos_term_max_size = pos.term_max_size;
+        raw_conf.pos_term_elected_size = pos.term_elected_size;
+        raw_conf.pos_in_queue_locked_views = pos.in_queue_locked_views;
+        raw_conf.pos_out_queue_locked_views = pos.out_queue_locked_views;
+        raw_conf.pos_cip99_transition_view = pos.cip99_transition_view;
+        raw_conf.pos_cip99_in_queue_locked_views =
+            pos.cip99_in_queue_locked_views;
+        raw_conf.pos_cip99_out_queue_locked_views =
+            pos.cip99_out_queue_locked_views;
+        raw_conf.nonce_limit_transition_view = pos.nonce_limit_transition_view;
+        raw_conf.pos_cip136_transition_view = pos.cip136_transition_view;
+        raw_conf.pos_cip136_in_queue_locked_views =
+            pos.cip136_in_queue_locked_views;
+        raw_conf.pos_cip136_out_queue_locked_views =
+            pos.cip136_out_queue_locked_views;
+        raw_conf.pos_cip136_round_per_term = pos.cip136_round_per_term;
+        raw_conf.pos_cip156_transition_view = pos.cip156_transition_view;
+        raw_conf.pos_cip156_dispute_locked_views =
+            pos.cip156_dispute_locked_views;
+    }
+
+    pub(crate) fn apply_to_common_params(&self, params: &mut CommonParams) {
+        params.anticone_penalty_ratio = self.params.anticone_penalty_ratio;
+        params.evm_transaction_block_ratio =
+            self.params.evm_transaction_block_ratio;
+        params.evm_transaction_gas_ratio =
+            self.params.evm_transaction_gas_ratio;
+        params.params_dao_vote_period = self.params.params_dao_vote_period;
+        params.min_base_price = SpaceMap::new(
+            U256::from(self.params.min_native_base_price),
+            U256::from(self.params.min_eth_base_price),
+        );
+        params.early_set_internal_contracts_states =
+            self.params.early_set_internal_contracts_states;
+        params.transition_numbers = self.transition_numbers.clone();
+        params.transition_heights = self.transition_heights.clone();
+
+        let mut base_block_rewards = BTreeMap::new();
+        base_block_rewards.insert(0, INITIAL_BASE_MINING_REWARD_IN_UCFX.into());
+        base_block_rewards.insert(
+            self.transition_heights.cip40,
+            MINING_REWARD_TANZANITE_IN_UCFX.into(),
+        );
+        params.base_block_rewards = base_block_rewards;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mainnet_chain_spec() {
+        let spec = ChainSpec::builtin("mainnet").unwrap();
+        assert_eq!(spec.chain_id, 1029);
+        assert_eq!(spec.evm_chain_id, 1030);
+        assert_eq!(spec.transition_numbers.cip43a, 92060600);
+        assert_eq!(spec.transition_numbers.cip43b, 92751800);
+        assert_eq!(spec.transition_heights.cip90a, 36935000);
+        assert_eq!(spec.pos.reference_enable_height, 37400000);
+        spec.validate().unwrap();
+
+        // The exported spec is loaded into the same parameters.
+        let json = serde_json::to_string_pretty(&spec).unwrap();
+        let loaded: ChainSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, spec);
+
+        let mut conf = Configuration::default();
+        loaded.apply_to_raw_conf(&mut conf.raw_conf);
+        conf.chain_spec = Some(loaded);
+        let params = conf.common_params();
+        assert_eq!(params.transition_numbers, spec.transition_numbers);
+        assert_eq!(params.transition_heights, spec.transition_heights);
+        assert_eq!(ChainSpec::from_configuration(&conf).unwrap(), spec);
+    }
+
+    #[test]
+    fn test_genesis_accounts_chain_spec() {
+        let path = std::env::temp_dir().join(format!(
+            "chain_spec_genesis_accounts_{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#""0x1000000000000000000000000000000000000001" = "100""#,
+        )
+        .unwrap();
+        let mut conf = Configuration::default();
+        conf.raw_conf.chain_id = Some(10);
+        conf.raw_conf.initial_difficulty = Some(4);
+        conf.raw_conf.genesis_accounts = Some(path.to_str().unwrap().into());
+        let spec = ChainSpec::from_configuration(&conf);
+        std::fs::remove_file(&path).unwrap();
+
+        let spec = spec.unwrap();
+        assert_eq!(spec.params.initial_difficulty, Some(4));
+        let account =
+            &spec.genesis.native["0x1000000000000000000000000000000000000001"];
+        assert_eq!(account.balance.as_deref(), Some("100"));
+
+        let mut raw_conf = RawConfiguration::default();
+        spec.apply_to_raw_conf(&mut raw_conf);
+        assert_eq!(raw_conf.initial_difficulty, Some(4));
+    }
+}
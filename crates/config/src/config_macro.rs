@@ -93,9 +93,6 @@ macro_rules! build_config{
                 Ok(config)
             }
             pub fn from_file(config_path: &str) ->  Result<RawConfiguration, String> {
-
-                let mut config = RawConfiguration::default();
-
                 let mut config_file = File::open(config_path)
                 .map_err(|e| format!("failed to open configuration file: {:?}", e))?;
 
@@ -104,6 +101,13 @@ macro_rules! build_config{
                     .read_to_string(&mut config_str)
                     .map_err(|e| format!("failed to read configuration file: {:?}", e))?;
 
+                RawConfiguration::from_toml(&config_str)
+            }
+
+            /// Parses the content of a configuration file.
+            pub fn from_toml(config_str: &str) -> Result<RawConfiguration, String> {
+                let mut config = RawConfiguration::default();
+
                 let config_value = config_str.parse::<toml::Value>()
                     .map_err(|e| format!("failed to parse configuration file: {:?}", e))?;
                 $(
@@ -127,6 +131,23 @@ macro_rules! build_config{
                 )*
                 Ok(config)
          }
+
+            /// Returns the names of the configurations whose values differ
+            /// from `other`.
+            pub fn changed_keys(&self, other: &RawConfiguration) -> Vec<&'static str> {
+                let mut keys = Vec::new();
+                $(
+                    if self.$name != other.$name {
+                        keys.push(stringify!($name));
+                    }
+                )*
+                $(
+                    if self.$c_name != other.$c_name {
+                        keys.push(stringify!($c_name));
+                    }
+                )*
+                keys
+            }
         }
     }
 }
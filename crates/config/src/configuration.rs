@@ -2,7 +2,13 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use std::{collections::BTreeMap, convert::TryInto, path::PathBuf, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use cfx_rpc_builder::RpcModuleSelection;
 use lazy_static::*;
@@ -29,7 +35,7 @@ use cfx_types::{
     parse_hex_string, Address, AllChainID, Space, SpaceMap, H256, U256,
 };
 use cfxcore::{
-    block_data_manager::{DataManagerConfiguration, DbType},
+    block_data_manager::{DataManagerConfiguration, DbType, FreezerConfig},
     block_parameters::*,
     cache_config::{
         DEFAULT_INVALID_BLOCK_HASH_CACHE_SIZE_IN_COUNT,
@@ -43,6 +49,7 @@ use cfxcore::{
     consensus_internal_parameters::*,
     consensus_parameters::*,
     light_protocol::LightNodeConfiguration,
+    pow::StratumVardiffConfig,
     sync::{ProtocolConfiguration, StateSyncConfiguration, SyncGraphConfig},
     sync_parameters::*,
     transaction_pool::TxPoolConfig,
@@ -53,16 +60,22 @@ use diem_types::term_state::{
     OUT_QUEUE_LOCKED_VIEWS, ROUND_PER_TERM, TERM_ELECTED_SIZE, TERM_MAX_SIZE,
 };
 use metrics::MetricsConfiguration;
-use network::DiscoveryConfiguration;
+use network::{
+    dns_discovery::TreeUrl, DiscoveryConfiguration, NatType, ReputationConfig,
+};
 use primitives::block_header::CIP112_TRANSITION_HEIGHT;
 use txgen::TransactionGeneratorConfig;
 
-use crate::{HttpConfiguration, TcpConfiguration, WsConfiguration};
+use crate::{
+    chain_spec::ChainSpec, HttpConfiguration, IpcConfiguration,
+    TcpConfiguration, WsConfiguration,
+};
 
 lazy_static! {
     pub static ref CHAIN_ID: RwLock<Option<ChainIdParams>> = Default::default();
 }
 const BLOCK_DB_DIR_NAME: &str = "blockchain_db";
+const FREEZER_DIR_NAME: &str = "freezer";
 const NET_CONFIG_DB_DIR_NAME: &str = "net_config";
 
 // usage:
@@ -134,6 +147,8 @@ build_config! {
         (metrics_output_file, (Option<String>), None)
         (metrics_report_interval_ms, (u64), 3_000)
         (metrics_prometheus_listen_addr, (Option<String>), None)
+        (otlp_endpoint, (Option<String>), None)
+        (otlp_service_name, (String), "conflux".into())
         (rocksdb_disable_wal, (bool), false)
         (txgen_account_count, (usize), 10)
 
@@ -142,6 +157,9 @@ build_config! {
         (anticone_penalty_ratio, (u64), ANTICONE_PENALTY_RATIO)
         (chain_id, (Option<u32>), None)
         (evm_chain_id, (Option<u32>), None)
+        // The JSON chain specification file, which overrides the chain ids,
+        // the hardfork and PoS parameters, and the genesis accounts.
+        (chain_spec, (Option<String>), None)
         (execute_genesis, (bool), true)
         (default_transition_time, (Option<u64>), None)
         // Snapshot Epoch Count is a consensus parameter. This flag overrides
@@ -150,6 +168,7 @@ build_config! {
         (era_epoch_count, (u64), ERA_DEFAULT_EPOCH_COUNT)
         (heavy_block_difficulty_ratio, (u64), HEAVY_BLOCK_DEFAULT_DIFFICULTY_RATIO)
         (genesis_accounts, (Option<String>), None)
+        (genesis_alloc, (Option<String>), None)
         (genesis_evm_secrets, (Option<String>), None)
         (genesis_secrets, (Option<String>), None)
         (pivot_hint_path, (Option<String>), None)
@@ -206,6 +225,9 @@ build_config! {
         (stratum_listen_address, (String), "127.0.0.1".into())
         (stratum_port, (u16), 32525)
         (stratum_secret, (Option<String>), None)
+        (stratum_min_share_difficulty, (Option<u64>), None)
+        (stratum_target_share_interval_ms, (u64), 10_000)
+        (stratum_vardiff_retarget_interval_ms, (u64), 60_000)
         (use_octopus_in_test_mode, (bool), false)
         (pow_problem_window_size, (usize), 1)
 
@@ -223,6 +245,10 @@ build_config! {
         (jsonrpc_http_eth_port, (Option<u16>), None)
         (jsonrpc_ws_eth_port, (Option<u16>), None)
         (jsonrpc_http_eth_port_v2, (Option<u16>), None)
+        (jsonrpc_ipc_path, (Option<String>), None)
+        (jsonrpc_ipc_eth_path, (Option<String>), None)
+        (jsonrpc_ipc_permissions, (String), "600".into())
+        (jsonrpc_jwt_secret_path, (Option<String>), None)
         // The network_id, if unset, defaults to the chain_id.
         // Only override the network_id for local experiments,
         // when user would like to keep the existing blockchain data
@@ -232,6 +258,8 @@ build_config! {
         (tcp_port, (u16), 32323)
         (public_tcp_port, (Option<u16>), None)
         (public_address, (Option<String>), None)
+        (public_address_v6, (Option<String>), None)
+        (listen_ipv6, (bool), false)
         (udp_port, (Option<u16>), Some(32323))
         (max_estimation_gas_limit, (Option<u64>), None)
 
@@ -288,12 +316,23 @@ build_config! {
         (discovery_throttling_interval_ms, (u64), 1_000)
         (discovery_throttling_limit_ping, (usize), 20)
         (discovery_throttling_limit_find_nodes, (usize), 10)
+        (dns_discovery_nameserver, (Option<String>), None)
+        (dns_discovery_refresh_interval_s, (u64), 1800)
+        (dns_discovery_urls, (Option<String>), None)
         (enable_discovery, (bool), true)
+        (enable_session_compression, (bool), false)
+        (enable_session_encryption, (bool), false)
+        (nat_type, (String), "any".into())
         (netconf_dir, (Option<String>), None)
         (net_key, (Option<String>), None)
         (node_table_timeout_s, (u64), 300)
         (node_table_promotion_timeout_s, (u64), 3 * 24 * 3600)
+        (peer_reputation_ban_duration_s, (u64), 3600)
+        (peer_reputation_ban_threshold, (f64), -100.0)
+        (peer_reputation_half_life_s, (u64), 1800)
         (session_ip_limits, (String), "1,8,4,2".into())
+        (static_peers, (Option<String>), None)
+        (static_peers_only, (bool), false)
         (subnet_quota, (usize), 128)
 
         // Transaction cache/transaction pool section.
@@ -323,6 +362,9 @@ build_config! {
         // The conflux data dir, if unspecified, is the workdir where conflux is started.
         (conflux_data_dir, (String), "./blockchain_data".to_string())
         (enable_single_mpt_storage, (bool), false)
+        (freezer_dir, (Option<String>), None)
+        // Block data older than this number of eras before the checkpoint is moved into the freezer.
+        (freezer_era_count, (Option<u64>), None)
         (ledger_cache_size, (usize), DEFAULT_LEDGER_CACHE_SIZE)
         (invalid_block_hash_cache_size_in_count, (usize), DEFAULT_INVALID_BLOCK_HASH_CACHE_SIZE_IN_COUNT)
         (rocksdb_cache_size, (Option<usize>), Some(128))
@@ -354,9 +396,13 @@ build_config! {
         (max_trans_count_received_in_catch_up, (u64), 60_000)
         (persist_tx_index, (bool), false)
         (persist_block_number_index, (bool), true)
+        (persist_bloom_bits_index, (bool), false)
+        (persist_address_tx_index, (bool), false)
         (print_memory_usage_period_s, (Option<u64>), None)
         (target_block_gas_limit, (u64), DEFAULT_TARGET_BLOCK_GAS_LIMIT)
         (executive_trace, (bool), false)
+        (parallel_execution_threads, (usize), 0)
+        (parallel_execution_cross_check, (bool), false)
         (check_status_genesis, (bool), true)
         (packing_gas_limit_block_count, (u64), 10)
         (poll_lifetime_in_seconds, (Option<u32>), None)
@@ -460,20 +506,42 @@ build_config! {
 #[derive(Debug)]
 pub struct Configuration {
     pub raw_conf: RawConfiguration,
+    /// The chain specification loaded from `chain_spec`, which has been
+    /// applied to `raw_conf`.
+    pub chain_spec: Option<ChainSpec>,
+    /// The command line arguments, kept to parse the configuration again when
+    /// it is reloaded.
+    matches: Option<clap::ArgMatches>,
 }
 
 impl Default for Configuration {
     fn default() -> Self {
         Configuration {
             raw_conf: Default::default(),
+            chain_spec: None,
+            matches: None,
         }
     }
 }
 
 impl Configuration {
     pub fn parse(matches: &clap::ArgMatches) -> Result<Configuration, String> {
+        let config = Self::parse_matches(matches)?;
+
+        CIP112_TRANSITION_HEIGHT
+            .set(config.raw_conf.cip112_transition_height.unwrap_or(u64::MAX))
+            .expect("called once");
+
+        Ok(config)
+    }
+
+    fn parse_matches(
+        matches: &clap::ArgMatches,
+    ) -> Result<Configuration, String> {
         let mut config = Configuration::default();
         config.raw_conf = RawConfiguration::parse(matches)?;
+        config.matches = Some(matches.clone());
+        config.load_chain_spec()?;
 
         if matches.get_flag("archive") {
             config.raw_conf.node_type = Some(NodeType::Archive);
@@ -483,20 +551,39 @@ impl Configuration {
             config.raw_conf.node_type = Some(NodeType::Light);
         }
 
-        CIP112_TRANSITION_HEIGHT
-            .set(config.raw_conf.cip112_transition_height.unwrap_or(u64::MAX))
-            .expect("called once");
-
         Ok(config)
     }
 
+    /// Parses the configuration file and command line arguments again, so
+    /// that the changes of the configuration file could be applied without
+    /// restart. The command line arguments still override the file.
+    pub fn reparse(&self) -> Result<Configuration, String> {
+        match self.matches {
+            Some(ref matches) => Self::parse_matches(matches),
+            None => Err("configuration is not parsed from command line".into()),
+        }
+    }
+
     pub fn from_file(config_path: &str) -> Result<Configuration, String> {
-        Ok(Configuration {
+        let mut config = Configuration {
             raw_conf: RawConfiguration::from_file(config_path)?,
-        })
+            chain_spec: None,
+            matches: None,
+        };
+        config.load_chain_spec()?;
+        Ok(config)
     }
 
-    fn network_id(&self) -> u64 {
+    fn load_chain_spec(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.raw_conf.chain_spec {
+            let chain_spec = ChainSpec::load_file(path)?;
+            chain_spec.apply_to_raw_conf(&mut self.raw_conf);
+            self.chain_spec = Some(chain_spec);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn network_id(&self) -> u64 {
         match self.raw_conf.network_id {
             Some(x) => x,
             // If undefined, the network id is set to the native space chain_id
@@ -510,6 +597,26 @@ impl Configuration {
         }
     }
 
+    /// Resolve the configured public address with the public TCP port.
+    fn public_socket_addr(&self, addr: &str) -> Option<SocketAddr> {
+        let port = self
+            .raw_conf
+            .public_tcp_port
+            .unwrap_or(self.raw_conf.tcp_port);
+        // IPv6 addresses contain ':', so they are parsed before looking for
+        // the port.
+        if let Ok(ip) = addr.trim_matches(&['[', ']'][..]).parse::<IpAddr>() {
+            return Some(SocketAddr::new(ip, port));
+        }
+        let host = if let Some(idx) = addr.find(":") {
+            warn!("Public address configuration should not contain port! (val = {}). Content after ':' is ignored.", addr);
+            &addr[0..idx]
+        } else {
+            addr
+        };
+        (host, port).to_socket_addrs().ok()?.next()
+    }
+
     pub fn net_config(&self) -> Result<NetworkConfiguration, String> {
         let mut network_config = NetworkConfiguration::new_with_port(
             self.network_id(),
@@ -518,9 +625,43 @@ impl Configuration {
         );
 
         network_config.is_consortium = self.raw_conf.is_consortium;
+        network_config.session_encryption =
+            self.raw_conf.enable_session_encryption;
+        network_config.session_compression =
+            self.raw_conf.enable_session_compression;
         network_config.discovery_enabled = self.raw_conf.enable_discovery;
+        match self.raw_conf.nat_type.as_str() {
+            "any" => network_config.nat_type = NatType::Any,
+            "upnp" => network_config.nat_type = NatType::UPnP,
+            "natpmp" => network_config.nat_type = NatType::NatPMP,
+            "none" => network_config.nat_enabled = false,
+            other => {
+                return Err(format!(
+                    "invalid nat_type {}, expected any, upnp, natpmp or none",
+                    other
+                ))
+            }
+        }
         network_config.boot_nodes = to_bootnodes(&self.raw_conf.bootnodes)
             .map_err(|e| format!("failed to parse bootnodes: {}", e))?;
+        network_config.dns_discovery_urls =
+            to_dns_discovery_urls(&self.raw_conf.dns_discovery_urls)?;
+        network_config.static_peers = to_bootnodes(&self.raw_conf.static_peers)
+            .map_err(|e| format!("failed to parse static_peers: {}", e))?;
+        network_config.static_peers_only = self.raw_conf.static_peers_only;
+        if let Some(addr) = &self.raw_conf.dns_discovery_nameserver {
+            network_config.dns_discovery_nameserver = Some(
+                addr.parse::<SocketAddr>()
+                    .or_else(|_| {
+                        addr.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53))
+                    })
+                    .map_err(|_| {
+                        format!("invalid dns_discovery_nameserver {}", addr)
+                    })?,
+            );
+        }
+        network_config.dns_discovery_refresh_interval =
+            Duration::from_secs(self.raw_conf.dns_discovery_refresh_interval_s);
         network_config.config_path = Some(match &self.raw_conf.netconf_dir {
             Some(dir) => dir.clone(),
             None => Path::new(&self.raw_conf.conflux_data_dir)
@@ -534,25 +675,26 @@ impl Configuration {
                 parse_hex_string(sec_str)
                     .expect("net_key is not a valid secret string")
             });
-        if let Some(addr) = self.raw_conf.public_address.clone() {
-            let addr_ip = if let Some(idx) = addr.find(":") {
-                warn!("Public address configuration should not contain port! (val = {}). Content after ':' is ignored.", &addr);
-                (&addr[0..idx]).to_string()
-            } else {
-                addr
-            };
-            let addr_with_port = match self.raw_conf.public_tcp_port {
-                Some(port) => addr_ip + ":" + &port.to_string(),
-                None => addr_ip + ":" + &self.raw_conf.tcp_port.to_string(),
-            };
-            network_config.public_address =
-                match addr_with_port.to_socket_addrs().map(|mut i| i.next()) {
-                    Ok(sock_addr) => sock_addr,
-                    Err(_e) => {
-                        warn!("public_address in config is invalid");
-                        None
-                    }
-                };
+        if self.raw_conf.listen_ipv6 {
+            network_config.listen_address = Some(SocketAddr::new(
+                Ipv6Addr::UNSPECIFIED.into(),
+                self.raw_conf.tcp_port,
+            ));
+        }
+        if let Some(addr) = &self.raw_conf.public_address {
+            network_config.public_address = self.public_socket_addr(addr);
+            if network_config.public_address.is_none() {
+                warn!("public_address in config is invalid");
+            }
+        }
+        if let Some(addr) = &self.raw_conf.public_address_v6 {
+            network_config.public_address_v6 =
+                self.public_socket_addr(addr).filter(|addr| addr.is_ipv6());
+            if network_config.public_address_v6.is_none() {
+                warn!(
+                    "public_address_v6 in config is not a valid IPv6 address"
+                );
+            }
         }
         network_config.node_table_timeout =
             Duration::from_secs(self.raw_conf.node_table_timeout_s);
@@ -564,6 +706,16 @@ impl Configuration {
             self.raw_conf.session_ip_limits.clone().try_into().map_err(
                 |e| format!("failed to parse session ip limit config: {}", e),
             )?;
+        network_config.reputation_config = ReputationConfig {
+            half_life: Duration::from_secs(
+                self.raw_conf.peer_reputation_half_life_s.max(1),
+            ),
+            ban_threshold: self.raw_conf.peer_reputation_ban_threshold,
+            ban_duration: Duration::from_secs(
+                self.raw_conf.peer_reputation_ban_duration_s,
+            ),
+            ..Default::default()
+        };
         network_config.fast_discovery_refresh_timeout = Duration::from_millis(
             self.raw_conf.discovery_fast_refresh_timeout_ms,
         );
@@ -734,6 +886,18 @@ impl Configuration {
                 parse_hex_string(hex_str)
                     .expect("Stratum secret should be 64-digit hex string")
             });
+        let stratum_vardiff = self.raw_conf.stratum_min_share_difficulty.map(
+            |min_share_difficulty| StratumVardiffConfig {
+                min_share_difficulty: min_share_difficulty.max(1),
+                target_share_interval_ms: self
+                    .raw_conf
+                    .stratum_target_share_interval_ms
+                    .max(1),
+                retarget_interval_ms: self
+                    .raw_conf
+                    .stratum_vardiff_retarget_interval_ms,
+            },
+        );
 
         ProofOfWorkConfig::new(
             self.is_test_or_dev_mode(),
@@ -753,6 +917,7 @@ impl Configuration {
             self.raw_conf.stratum_listen_address.clone(),
             self.raw_conf.stratum_port,
             stratum_secret,
+            stratum_vardiff,
             self.raw_conf.pow_problem_window_size,
             self.common_params().transition_heights.cip86,
         )
@@ -991,6 +1156,8 @@ impl Configuration {
             persist_block_number_index: self
                 .raw_conf
                 .persist_block_number_index,
+            persist_bloom_bits_index: self.raw_conf.persist_bloom_bits_index,
+            persist_address_tx_index: self.raw_conf.persist_address_tx_index,
             tx_cache_index_maintain_timeout: Duration::from_millis(
                 self.raw_conf.tx_cache_index_maintain_timeout_ms,
             ),
@@ -1020,6 +1187,18 @@ impl Configuration {
                 * self.raw_conf.era_epoch_count as f64)
                 as usize,
             strict_tx_index_gc: self.raw_conf.strict_tx_index_gc,
+            freezer: self.raw_conf.freezer_era_count.map(|era_count| {
+                FreezerConfig {
+                    dir: match &self.raw_conf.freezer_dir {
+                        Some(dir) => dir.into(),
+                        None => Path::new(&self.raw_conf.conflux_data_dir)
+                            .join(FREEZER_DIR_NAME),
+                    },
+                    defer_epoch_count: (era_count
+                        * self.raw_conf.era_epoch_count)
+                        as usize,
+                }
+            }),
         };
 
         // By default, we do not keep the block data for additional period,
@@ -1102,18 +1281,20 @@ impl Configuration {
             half_block_gas_limit: RwLock::new(U256::from(
                 DEFAULT_TARGET_BLOCK_GAS_LIMIT / 2,
             )),
-            min_native_tx_price: self
-                .raw_conf
-                .tx_pool_min_native_tx_gas_price
-                .unwrap_or(min_native_tx_price_default),
+            min_native_tx_price: RwLock::new(
+                self.raw_conf
+                    .tx_pool_min_native_tx_gas_price
+                    .unwrap_or(min_native_tx_price_default),
+            ),
             allow_gas_over_half_block: self
                 .raw_conf
                 .tx_pool_allow_gas_over_half_block,
             target_block_gas_limit: self.raw_conf.target_block_gas_limit,
-            min_eth_tx_price: self
-                .raw_conf
-                .tx_pool_min_eth_tx_gas_price
-                .unwrap_or(min_eth_tx_price_default),
+            min_eth_tx_price: RwLock::new(
+                self.raw_conf
+                    .tx_pool_min_eth_tx_gas_price
+                    .unwrap_or(min_eth_tx_price_default),
+            ),
             max_packing_batch_gas_limit: self
                 .raw_conf
                 .max_packing_batch_gas_limit,
@@ -1149,6 +1330,7 @@ impl Configuration {
             self.raw_conf.jsonrpc_cors.clone(),
             self.raw_conf.jsonrpc_http_keep_alive,
             self.raw_conf.jsonrpc_http_threads,
+            None,
         )
     }
 
@@ -1159,6 +1341,7 @@ impl Configuration {
             self.raw_conf.jsonrpc_cors.clone(),
             self.raw_conf.jsonrpc_http_keep_alive,
             self.raw_conf.jsonrpc_http_threads,
+            self.raw_conf.jsonrpc_jwt_secret_path.clone(),
         )
     }
 
@@ -1169,6 +1352,7 @@ impl Configuration {
             self.raw_conf.jsonrpc_cors.clone(),
             self.raw_conf.jsonrpc_http_keep_alive,
             self.raw_conf.jsonrpc_http_threads,
+            self.raw_conf.jsonrpc_jwt_secret_path.clone(),
         )
     }
 
@@ -1177,9 +1361,63 @@ impl Configuration {
             None,
             self.raw_conf.jsonrpc_ws_eth_port,
             self.raw_conf.jsonrpc_ws_max_payload_bytes,
+            self.raw_conf.jsonrpc_jwt_secret_path.clone(),
         )
     }
 
+    pub fn ipc_config(&self) -> Result<IpcConfiguration, String> {
+        Ok(IpcConfiguration::new(
+            self.raw_conf.jsonrpc_ipc_path.clone(),
+            self.ipc_permissions()?,
+        ))
+    }
+
+    pub fn eth_ipc_config(&self) -> Result<IpcConfiguration, String> {
+        Ok(IpcConfiguration::new(
+            self.raw_conf.jsonrpc_ipc_eth_path.clone(),
+            self.ipc_permissions()?,
+        ))
+    }
+
+    fn ipc_permissions(&self) -> Result<u16, String> {
+        u16::from_str_radix(&self.raw_conf.jsonrpc_ipc_permissions, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+            .ok_or_else(|| {
+                format!(
+                    "invalid jsonrpc_ipc_permissions {}, expected octal file \
+                     permissions like 600",
+                    self.raw_conf.jsonrpc_ipc_permissions
+                )
+            })
+    }
+
+    /// JWT authentication only covers the public HTTP and WebSocket servers,
+    /// so the other public servers, which listen on all interfaces, must be
+    /// disabled if it is enabled.
+    pub fn check_rpc_authentication(&self) -> Result<(), String> {
+        if self.raw_conf.jsonrpc_jwt_secret_path.is_none() {
+            return Ok(());
+        }
+        let unauthenticated = [
+            ("jsonrpc_tcp_port", self.raw_conf.jsonrpc_tcp_port),
+            (
+                "jsonrpc_http_eth_port_v2",
+                self.raw_conf.jsonrpc_http_eth_port_v2,
+            ),
+        ];
+        for (name, port) in unauthenticated {
+            if port.is_some() {
+                return Err(format!(
+                    "{} cannot be set with jsonrpc_jwt_secret_path, because \
+                     the server does not support JWT authentication",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn local_tcp_config(&self) -> TcpConfiguration {
         TcpConfiguration::new(
             Some((127, 0, 0, 1)),
@@ -1196,6 +1434,7 @@ impl Configuration {
             Some((127, 0, 0, 1)),
             self.raw_conf.jsonrpc_local_ws_port,
             self.raw_conf.jsonrpc_ws_max_payload_bytes,
+            None,
         )
     }
 
@@ -1204,12 +1443,19 @@ impl Configuration {
             None,
             self.raw_conf.jsonrpc_ws_port,
             self.raw_conf.jsonrpc_ws_max_payload_bytes,
+            self.raw_conf.jsonrpc_jwt_secret_path.clone(),
         )
     }
 
     pub fn execution_config(&self) -> ConsensusExecutionConfiguration {
         ConsensusExecutionConfiguration {
             executive_trace: self.raw_conf.executive_trace,
+            parallel_execution_threads: self
+                .raw_conf
+                .parallel_execution_threads,
+            parallel_execution_cross_check: self
+                .raw_conf
+                .parallel_execution_cross_check,
         }
     }
 
@@ -1323,7 +1569,10 @@ impl Configuration {
 
         params.params_dao_vote_period = self.raw_conf.params_dao_vote_period;
 
-        self.set_cips(&mut params);
+        match &self.chain_spec {
+            Some(chain_spec) => chain_spec.apply_to_common_params(&mut params),
+            None => self.set_cips(&mut params),
+        }
 
         params
     }
@@ -1469,7 +1718,7 @@ impl Configuration {
             .cip107_transition_number
             .unwrap_or(default_transition_time);
         params.transition_heights.cip112 =
-            *CIP112_TRANSITION_HEIGHT.get().expect("initialized");
+            self.raw_conf.cip112_transition_height.unwrap_or(u64::MAX);
         params.transition_numbers.cip118 = self
             .raw_conf
             .cip118_transition_number
@@ -1558,6 +1807,24 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
     }
 }
 
+/// Validates and formats dns_discovery_urls option.
+pub fn to_dns_discovery_urls(
+    urls: &Option<String>,
+) -> Result<Vec<String>, String> {
+    urls.iter()
+        .flat_map(|urls| urls.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<TreeUrl>()
+                .map(|url| url.to_string())
+                .map_err(|e| {
+                    format!("failed to parse dns_discovery_urls: {}", e)
+                })
+        })
+        .collect()
+}
+
 pub fn parse_config_address_string(
     addr: &str, network: &Network,
 ) -> Result<Address, String> {
@@ -1592,8 +1859,40 @@ pub fn parse_config_address_string(
 #[cfg(test)]
 mod tests {
     use cfx_addr::Network;
+    use log::LevelFilter;
 
-    use crate::configuration::parse_config_address_string;
+    use crate::configuration::{
+        parse_config_address_string, Configuration, RawConfiguration,
+    };
+
+    #[test]
+    fn test_check_rpc_authentication() {
+        let mut conf = Configuration::default();
+        conf.raw_conf.jsonrpc_tcp_port = Some(12536);
+        conf.raw_conf.jsonrpc_http_eth_port_v2 = Some(8545);
+        assert!(conf.check_rpc_authentication().is_ok());
+
+        conf.raw_conf.jsonrpc_jwt_secret_path = Some("jwt.hex".into());
+        assert!(conf.check_rpc_authentication().is_err());
+        conf.raw_conf.jsonrpc_tcp_port = None;
+        assert!(conf.check_rpc_authentication().is_err());
+        conf.raw_conf.jsonrpc_http_eth_port_v2 = None;
+        assert!(conf.check_rpc_authentication().is_ok());
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let conf = RawConfiguration::default();
+        let mut changed = conf.clone();
+        assert!(changed.changed_keys(&conf).is_empty());
+
+        changed.max_incoming_peers += 1;
+        changed.log_level = LevelFilter::Trace;
+        assert_eq!(
+            changed.changed_keys(&conf),
+            vec!["max_incoming_peers", "log_level"]
+        );
+    }
 
     #[test]
     fn test_config_address_string() {
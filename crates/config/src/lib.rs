@@ -1,8 +1,10 @@
 #[macro_use]
 mod config_macro;
+pub mod chain_spec;
 mod configuration;
 pub mod rpc_server_config;
 
+pub use chain_spec::ChainSpec;
 pub use configuration::{
     parse_config_address_string, Configuration, RawConfiguration,
 };
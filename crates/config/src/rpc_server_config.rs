@@ -29,12 +29,16 @@ pub struct HttpConfiguration {
     // If it's Some, we will manually set the number of threads of HTTP RPC
     // server
     pub threads: Option<usize>,
+    /// If it's Some, requests must carry a JWT signed by the secret in the
+    /// file.
+    pub jwt_secret_path: Option<String>,
 }
 
 impl HttpConfiguration {
     pub fn new(
         ip: Option<(u8, u8, u8, u8)>, port: Option<u16>, cors: Option<String>,
         keep_alive: bool, threads: Option<usize>,
+        jwt_secret_path: Option<String>,
     ) -> Self {
         let ipv4 = match ip {
             Some(ip) => Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
@@ -57,6 +61,26 @@ impl HttpConfiguration {
             },
             keep_alive,
             threads,
+            jwt_secret_path,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IpcConfiguration {
+    pub enabled: bool,
+    pub path: String,
+    /// Unix file permissions of the socket file, e.g. 0o600 only allows
+    /// the user of the node to connect.
+    pub permissions: u16,
+}
+
+impl IpcConfiguration {
+    pub fn new(path: Option<String>, permissions: u16) -> Self {
+        IpcConfiguration {
+            enabled: path.is_some(),
+            path: path.unwrap_or_default(),
+            permissions,
         }
     }
 }
@@ -66,12 +90,15 @@ pub struct WsConfiguration {
     pub enabled: bool,
     pub address: SocketAddr,
     pub max_payload_bytes: usize,
+    /// If it's Some, handshakes must carry a JWT signed by the secret in the
+    /// file.
+    pub jwt_secret_path: Option<String>,
 }
 
 impl WsConfiguration {
     pub fn new(
         ip: Option<(u8, u8, u8, u8)>, port: Option<u16>,
-        max_payload_bytes: usize,
+        max_payload_bytes: usize, jwt_secret_path: Option<String>,
     ) -> Self {
         let ipv4 = match ip {
             Some(ip) => Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
@@ -81,6 +108,7 @@ impl WsConfiguration {
             enabled: port.is_some(),
             address: SocketAddr::V4(SocketAddrV4::new(ipv4, port.unwrap_or(0))),
             max_payload_bytes,
+            jwt_secret_path,
         }
     }
 }
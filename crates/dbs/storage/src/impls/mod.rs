@@ -16,6 +16,7 @@ pub(super) mod state_manager;
 pub(super) mod state_proof;
 pub(super) mod storage_db;
 pub(super) mod storage_manager;
+pub(super) mod witness_storage;
 
 pub(super) use cfx_db_errors::storage as errors;
 
@@ -5,42 +5,108 @@
 // `RecordingStorage` is a wrapper around other storage implementations that
 // tracks all read accesses. It can then be turned into a `StateProof` that is
 // able to prove all key-value accesses.
+//
+// Modifications are not applied to the underlying storage, which is usually a
+// read-only state. They are kept in the record instead, e.g. for proving them
+// against the state after the same execution is committed.
 
 pub struct RecordingStorage<Storage: StateTrait> {
     storage: Storage,
 
     // note: we need interior mutability so that we can record accesses and we
     // need to use Mutex for this as State implementations need to be Send and
-    // Sync. However, the current execution logic is single-threaded.
-    proof_merger: Mutex<StateProofMerger>,
+    // Sync. However, the current execution logic is single-threaded. The
+    // record is shared so that it's still accessible after the storage is
+    // moved into a StateDb.
+    record: Arc<Mutex<StorageAccessRecord>>,
+}
+
+/// The key-value accesses recorded by a `RecordingStorage`, by key bytes.
+#[derive(Debug, Default)]
+pub struct StorageAccessRecord {
+    proof_merger: StateProofMerger,
+    /// The values read from the underlying storage, which are proved by the
+    /// proof.
+    pub reads: BTreeMap<Vec<u8>, Option<Box<[u8]>>>,
+    /// The latest values set, where `None` means deleted.
+    pub writes: BTreeMap<Vec<u8>, Option<Box<[u8]>>>,
+    /// The prefixes of the keys iterated. The proof only proves the keys
+    /// found, but not that there is no other key with the prefix.
+    pub prefixes: BTreeSet<Vec<u8>>,
+}
+
+impl StorageAccessRecord {
+    /// Takes the proof of all the values read.
+    pub fn take_proof(&mut self) -> Result<StateProof> {
+        mem::take(&mut self.proof_merger).finish()
+    }
 }
 
 impl<Storage: StateTrait> RecordingStorage<Storage> {
     pub fn new(storage: Storage) -> Self {
         Self {
             storage,
-            proof_merger: Default::default(),
+            record: Default::default(),
         }
     }
 
+    pub fn access_record(&self) -> Arc<Mutex<StorageAccessRecord>> {
+        self.record.clone()
+    }
+
     pub fn try_into_proof(self) -> Result<StateProof> {
-        self.proof_merger.into_inner().finish()
+        self.record.lock().take_proof()
     }
 }
 
 impl<Storage: StateTrait + StateTraitExt> RecordingStorage<Storage> {
     fn record_kvs(&self, kvs: &Vec<MptKeyValue>) -> Result<()> {
-        let mut proof_merger = self.proof_merger.lock();
+        let mut record = self.record.lock();
 
-        for (k, _) in kvs {
+        for (k, v) in kvs {
+            if record.reads.contains_key(k) {
+                continue;
+            }
             let access_key =
                 StorageKeyWithSpace::from_key_bytes::<CheckInput>(k)?;
             let (_, proof) = self.storage.get_with_proof(access_key)?;
-            proof_merger.merge(proof);
+            record.proof_merger.merge(proof);
+            record.reads.insert(k.clone(), Some(v.clone()));
         }
 
         Ok(())
     }
+
+    /// Reads the key-values with `access_key_prefix` from the underlying
+    /// storage, overridden by the modifications.
+    fn read_prefixed(
+        &self, access_key_prefix: StorageKeyWithSpace,
+    ) -> Result<Option<Vec<MptKeyValue>>> {
+        let mut kvs: BTreeMap<Vec<u8>, Option<Box<[u8]>>> = BTreeMap::new();
+        if let Some(stored) = self.storage.read_all(access_key_prefix)? {
+            self.record_kvs(&stored)?;
+            kvs.extend(stored.into_iter().map(|(k, v)| (k, Some(v))));
+        }
+
+        let prefix = access_key_prefix.to_key_bytes();
+        let mut record = self.record.lock();
+        record.prefixes.insert(prefix.clone());
+        kvs.extend(
+            record
+                .writes
+                .range::<[u8], _>((Included(&*prefix), Unbounded))
+                .take_while(|(k, _)| k.starts_with(&prefix))
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+
+        let kvs: Vec<MptKeyValue> =
+            kvs.into_iter().filter_map(|(k, v)| Some((k, v?))).collect();
+        if kvs.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(kvs))
+        }
+    }
 }
 
 impl<Storage: StateTrait + StateTraitExt> StateTrait
@@ -48,46 +114,77 @@ impl<Storage: StateTrait + StateTraitExt> StateTrait
 {
     delegate! {
         to self.storage {
-            fn set(&mut self, access_key: StorageKeyWithSpace, value: Box<[u8]>) -> Result<()>;
-            fn delete(&mut self, access_key: StorageKeyWithSpace) -> Result<()>;
-            fn delete_test_only(&mut self, access_key: StorageKeyWithSpace) -> Result<Option<Box<[u8]>>>;
-            fn compute_state_root(&mut self) -> Result<StateRootWithAuxInfo>;
             fn get_state_root(&self) -> Result<StateRootWithAuxInfo>;
-            fn commit(&mut self, epoch_id: EpochId) -> Result<StateRootWithAuxInfo>;
         }
     }
 
+    /// Returns the state root of the underlying storage, which doesn't
+    /// include the modifications.
+    fn compute_state_root(&mut self) -> Result<StateRootWithAuxInfo> {
+        self.storage.get_state_root()
+    }
+
     // we need to record `get` operations
     fn get(
         &self, access_key: StorageKeyWithSpace,
     ) -> Result<Option<Box<[u8]>>> {
+        let key = access_key.to_key_bytes();
+        if let Some(value) = self.record.lock().writes.get(&key) {
+            return Ok(value.clone());
+        }
+
         let (val, proof) = self.storage.get_with_proof(access_key)?;
-        self.proof_merger.lock().merge(proof);
+        let mut record = self.record.lock();
+        record.proof_merger.merge(proof);
+        record.reads.entry(key).or_insert_with(|| val.clone());
         Ok(val)
     }
 
+    fn set(
+        &mut self, access_key: StorageKeyWithSpace, value: Box<[u8]>,
+    ) -> Result<()> {
+        self.record
+            .lock()
+            .writes
+            .insert(access_key.to_key_bytes(), Some(value));
+        Ok(())
+    }
+
+    fn delete(&mut self, access_key: StorageKeyWithSpace) -> Result<()> {
+        self.record
+            .lock()
+            .writes
+            .insert(access_key.to_key_bytes(), None);
+        Ok(())
+    }
+
+    fn delete_test_only(
+        &mut self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        let value = self.get(access_key)?;
+        self.delete(access_key)?;
+        Ok(value)
+    }
+
     fn delete_all(
         &mut self, access_key_prefix: StorageKeyWithSpace,
     ) -> Result<Option<Vec<MptKeyValue>>> {
-        match self.storage.delete_all(access_key_prefix)? {
-            None => Ok(None),
-            Some(kvs) => {
-                self.record_kvs(&kvs)?;
-                Ok(Some(kvs))
-            }
+        let deleted = self.read_prefixed(access_key_prefix)?;
+        let mut record = self.record.lock();
+        for (k, _) in deleted.iter().flatten() {
+            record.writes.insert(k.clone(), None);
         }
+        Ok(deleted)
     }
 
     fn read_all(
         &mut self, access_key_prefix: StorageKeyWithSpace,
     ) -> Result<Option<Vec<MptKeyValue>>> {
-        match self.storage.read_all(access_key_prefix)? {
-            None => Ok(None),
-            Some(kvs) => {
-                self.record_kvs(&kvs)?;
-                Ok(Some(kvs))
-            }
-        }
+        self.read_prefixed(access_key_prefix)
+    }
+
+    fn commit(&mut self, _epoch_id: EpochId) -> Result<StateRootWithAuxInfo> {
+        bail!(Error::Msg("Recording storage can not be committed".into()))
     }
 }
 
@@ -102,3 +199,9 @@ use cfx_internal_common::StateRootWithAuxInfo;
 use delegate::delegate;
 use parking_lot::Mutex;
 use primitives::{CheckInput, EpochId, StorageKeyWithSpace};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+    ops::Bound::{Included, Unbounded},
+    sync::Arc,
+};
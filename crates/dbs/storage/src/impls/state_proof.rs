@@ -136,11 +136,125 @@ impl StateProof {
             Some(proof) => proof.is_valid_kv(key, value, snapshot_root),
         }
     }
+
+    /// Get the value under `key` proved against `root`, following the same
+    /// lookup order as `is_valid_kv`. Returns `None` if the proof is invalid
+    /// or incomplete for the key.
+    pub fn get_value(
+        &self, key: &Vec<u8>, root: &StateRoot,
+        maybe_intermediate_padding: Option<&DeltaMptKeyPadding>,
+    ) -> Option<Option<Box<[u8]>>> {
+        let storage_key =
+            StorageKeyWithSpace::from_key_bytes::<CheckInput>(&key).ok()?;
+        let delta_mpt_padding = StorageKeyWithSpace::delta_mpt_padding(
+            &root.snapshot_root,
+            &root.intermediate_delta_root,
+        );
+
+        // A tombstone in the delta tries is an empty value, which means that
+        // the key is deleted.
+        let delta_value = |value: &[u8]| {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.into())
+            }
+        };
+
+        match &self.delta_proof {
+            Some(proof) => {
+                let (proves, value) = proof.get_value(
+                    &storage_key.to_delta_mpt_key_bytes(&delta_mpt_padding),
+                    &root.delta_root,
+                );
+                if !proves {
+                    return None;
+                }
+                if let Some(value) = value {
+                    return Some(delta_value(value));
+                }
+            }
+            None => {
+                if root.delta_root.ne(&MERKLE_NULL_NODE) {
+                    return None;
+                }
+            }
+        }
+
+        match (&self.intermediate_proof, maybe_intermediate_padding) {
+            (Some(proof), Some(padding)) => {
+                let (proves, value) = proof.get_value(
+                    &storage_key.to_delta_mpt_key_bytes(padding),
+                    &root.intermediate_delta_root,
+                );
+                if !proves {
+                    return None;
+                }
+                if let Some(value) = value {
+                    return Some(delta_value(value));
+                }
+            }
+            (Some(_), None) => return None,
+            (None, _) => {
+                if root.intermediate_delta_root.ne(&MERKLE_NULL_NODE) {
+                    return None;
+                }
+            }
+        }
+
+        let (proves, value) = self
+            .snapshot_proof
+            .as_ref()?
+            .get_value(key, &root.snapshot_root);
+        proves.then(|| value.map(Into::into))
+    }
+}
+
+/// The nodes of a `StateProof` by tries. Unlike `StateProof`, the number of
+/// nodes is not limited in deserialization, so that it could carry a proof
+/// merged from many keys, e.g. all the keys accessed by an epoch.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateProofNodes {
+    pub delta_nodes: Option<Vec<TrieProofNode>>,
+    pub intermediate_nodes: Option<Vec<TrieProofNode>>,
+    pub snapshot_nodes: Option<Vec<TrieProofNode>>,
 }
 
-use crate::impls::merkle_patricia_trie::TrieProof;
+impl StateProofNodes {
+    pub fn into_proof(self) -> Result<StateProof> {
+        let into_trie_proof =
+            |nodes: Option<Vec<TrieProofNode>>| nodes.map(TrieProof::new);
+        Ok(StateProof {
+            delta_proof: into_trie_proof(self.delta_nodes).transpose()?,
+            intermediate_proof: into_trie_proof(self.intermediate_nodes)
+                .transpose()?,
+            snapshot_proof: into_trie_proof(self.snapshot_nodes).transpose()?,
+        })
+    }
+}
+
+impl From<StateProof> for StateProofNodes {
+    fn from(proof: StateProof) -> Self {
+        StateProofNodes {
+            delta_nodes: proof.delta_proof.map(TrieProof::into_proof_nodes),
+            intermediate_nodes: proof
+                .intermediate_proof
+                .map(TrieProof::into_proof_nodes),
+            snapshot_nodes: proof
+                .snapshot_proof
+                .map(TrieProof::into_proof_nodes),
+        }
+    }
+}
+
+use crate::impls::{
+    errors::*,
+    merkle_patricia_trie::{trie_proof::TrieProofNode, TrieProof},
+};
 use primitives::{
     CheckInput, DeltaMptKeyPadding, MptValue, StateRoot, StorageKeyWithSpace,
     MERKLE_NULL_NODE,
 };
 use rlp_derive::{RlpDecodable, RlpEncodable};
+use serde::{Deserialize, Serialize};
@@ -0,0 +1,169 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+// `WitnessStorage` serves the key-value accesses of an execution purely from a
+// witness, i.e. the keys recorded by `RecordingStorage` with their proof
+// against the original state root. Instead of updating the tries, the
+// modifications are checked against a proof of the resulting state root
+// claimed by the witness when the state root is computed. The resulting state
+// root is not recomputed, so the check only shows that the modifications are
+// consistent with the claimed root, which should be checked against a trusted
+// source, e.g. the pivot chain.
+//
+// Prefix iterations are not supported: the proof of the keys in the witness
+// can't prove that no other key has the prefix, so they fail instead of
+// returning a result which may be incomplete.
+
+pub struct WitnessStorage {
+    /// The original values of the keys in the witness.
+    values: BTreeMap<Vec<u8>, Option<Box<[u8]>>>,
+    /// The latest values set, where `None` means deleted.
+    writes: BTreeMap<Vec<u8>, Option<Box<[u8]>>>,
+    state_root: StateRootWithAuxInfo,
+    state_proof: StateProof,
+}
+
+impl WitnessStorage {
+    /// Proves the values of `keys` with `proof` against `parent_state_root`.
+    /// The modifications are checked with `state_proof` against `state_root`.
+    pub fn new(
+        parent_state_root: &StateRootWithAuxInfo, keys: Vec<Vec<u8>>,
+        proof: &StateProof, state_root: StateRootWithAuxInfo,
+        state_proof: StateProof,
+    ) -> Result<Self> {
+        let maybe_intermediate_padding = parent_state_root
+            .aux_info
+            .maybe_intermediate_mpt_key_padding
+            .as_ref();
+
+        let mut values = BTreeMap::new();
+        for key in keys {
+            let value = proof
+                .get_value(
+                    &key,
+                    &parent_state_root.state_root,
+                    maybe_intermediate_padding,
+                )
+                .ok_or_else(|| {
+                    Error::Msg(format!(
+                        "Key {:?} is not proved by the witness",
+                        key.to_hex::<String>()
+                    ))
+                })?;
+            values.insert(key, value);
+        }
+
+        Ok(Self {
+            values,
+            writes: Default::default(),
+            state_root,
+            state_proof,
+        })
+    }
+
+    fn current_value(&self, key: &[u8]) -> Result<Option<Box<[u8]>>> {
+        match self.writes.get(key).or_else(|| self.values.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None => bail!(Error::Msg(format!(
+                "Key {:?} is not in the witness",
+                key.to_hex::<String>()
+            ))),
+        }
+    }
+
+    fn unprovable_prefix(access_key_prefix: StorageKeyWithSpace) -> Error {
+        Error::Msg(format!(
+            "Keys with prefix {:?} can not be proved complete by the witness",
+            access_key_prefix.to_key_bytes().to_hex::<String>()
+        ))
+    }
+}
+
+impl StateTrait for WitnessStorage {
+    fn get(
+        &self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        self.current_value(&access_key.to_key_bytes())
+    }
+
+    fn set(
+        &mut self, access_key: StorageKeyWithSpace, value: Box<[u8]>,
+    ) -> Result<()> {
+        self.writes.insert(access_key.to_key_bytes(), Some(value));
+        Ok(())
+    }
+
+    fn delete(&mut self, access_key: StorageKeyWithSpace) -> Result<()> {
+        self.writes.insert(access_key.to_key_bytes(), None);
+        Ok(())
+    }
+
+    fn delete_test_only(
+        &mut self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        let key = access_key.to_key_bytes();
+        let value = self.current_value(&key)?;
+        self.writes.insert(key, None);
+        Ok(value)
+    }
+
+    fn delete_all(
+        &mut self, access_key_prefix: StorageKeyWithSpace,
+    ) -> Result<Option<Vec<MptKeyValue>>> {
+        bail!(Self::unprovable_prefix(access_key_prefix))
+    }
+
+    fn read_all(
+        &mut self, access_key_prefix: StorageKeyWithSpace,
+    ) -> Result<Option<Vec<MptKeyValue>>> {
+        bail!(Self::unprovable_prefix(access_key_prefix))
+    }
+
+    fn compute_state_root(&mut self) -> Result<StateRootWithAuxInfo> {
+        self.get_state_root()
+    }
+
+    /// Returns the resulting state root claimed by the witness if all the
+    /// modifications are proved against it. Modifications in the claimed
+    /// root that are not made by the execution are not detected.
+    fn get_state_root(&self) -> Result<StateRootWithAuxInfo> {
+        let maybe_intermediate_padding = self
+            .state_root
+            .aux_info
+            .maybe_intermediate_mpt_key_padding
+            .clone();
+
+        for (key, value) in &self.writes {
+            if !self.state_proof.is_valid_kv(
+                key,
+                value.as_deref(),
+                self.state_root.state_root.clone(),
+                maybe_intermediate_padding.clone(),
+            ) {
+                bail!(Error::Msg(format!(
+                    "Modification of key {:?} is not proved by the resulting \
+                     state root",
+                    key.to_hex::<String>()
+                )));
+            }
+        }
+
+        Ok(self.state_root.clone())
+    }
+
+    fn commit(&mut self, _epoch: EpochId) -> Result<StateRootWithAuxInfo> {
+        bail!(Error::Msg("Witness storage can not be committed".into()))
+    }
+}
+
+use crate::{
+    impls::{
+        errors::*, merkle_patricia_trie::MptKeyValue, state_proof::StateProof,
+    },
+    state::*,
+};
+use cfx_internal_common::StateRootWithAuxInfo;
+use primitives::{EpochId, StorageKeyWithSpace};
+use rustc_hex::ToHex;
+use std::collections::BTreeMap;
@@ -207,15 +207,16 @@ pub use self::{
         },
         node_merkle_proof::{NodeMerkleProof, StorageRootProof},
         proof_merger::StateProofMerger,
-        recording_storage::RecordingStorage,
+        recording_storage::{RecordingStorage, StorageAccessRecord},
         snapshot_sync::{FullSyncVerifier, MptSlicer},
-        state_proof::StateProof,
+        state_proof::{StateProof, StateProofNodes},
         storage_db::{
             kvdb_rocksdb::KvdbRocksdb,
             kvdb_sqlite::{KvdbSqlite, KvdbSqliteStatements},
             snapshot_db_manager_sqlite::SnapshotDbManagerSqlite,
             sqlite::SqliteConnection,
         },
+        witness_storage::WitnessStorage,
     },
     replicated_state::ReplicatedState,
     state::{
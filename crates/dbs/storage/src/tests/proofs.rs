@@ -30,6 +30,22 @@ fn portion<T>(v: &Vec<T>, k: usize, n: usize) -> &[T] {
 fn generate_random_state(
     rng: &mut ChaChaRng,
 ) -> (FakeStateManager, State, DeltaMptKeyPadding, Vec<Vec<u8>>) {
+    let (state_manager, new_state, intermediate_padding, keys, _parent) =
+        generate_random_state_with_parent(rng);
+    (state_manager, new_state, intermediate_padding, keys)
+}
+
+// same as `generate_random_state`, but also returns the epoch id and the
+// state root of the parent of the new state
+fn generate_random_state_with_parent(
+    rng: &mut ChaChaRng,
+) -> (
+    FakeStateManager,
+    State,
+    DeltaMptKeyPadding,
+    Vec<Vec<u8>>,
+    (H256, StateRootWithAuxInfo),
+) {
     let snapshot_epoch_count = 1;
     let state_manager =
         new_state_manager_for_unit_test_with_snapshot_epoch_count(
@@ -220,7 +236,13 @@ fn generate_random_state(
         .unwrap()
         .unwrap();
 
-    (state_manager, new_state, intermediate_padding, keys)
+    (
+        state_manager,
+        new_state,
+        intermediate_padding,
+        keys,
+        (epoch_id_2, root_2),
+    )
 }
 
 fn select_keys(
@@ -694,6 +716,166 @@ fn test_recording_storage() {
     }
 }
 
+#[test]
+fn test_witness_storage() {
+    let mut rng = get_rng_for_test();
+
+    // note: do not drop state_manager
+    let (
+        state_manager,
+        mut state,
+        _padding,
+        keys,
+        (parent_epoch_id, parent_root),
+    ) = generate_random_state_with_parent(&mut rng);
+
+    // record the accesses over the parent state
+    let parent_state = state_manager
+        .get_state_no_commit_inner(
+            StateIndex::new_for_readonly(&parent_epoch_id, &parent_root),
+            /* try_open = */ false,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+    let mut recording = RecordingStorage::new(parent_state);
+    let record = recording.access_record();
+
+    let read_some = select_keys(&mut rng, &keys);
+    let read_none = generate_nonexistent_keys(&mut rng, &keys);
+    let not_read = keys
+        .iter()
+        .find(|key| !read_some.contains(key))
+        .expect("some keys should not be read");
+
+    for key in read_some.iter().chain(read_none.iter()) {
+        let _value = recording
+            .get(StorageKey::AccountKey(key).with_native_space())
+            .expect("kv lookup failed");
+    }
+
+    // update or delete the existing keys and insert the nonexistent ones
+    let apply_writes = |state: &mut dyn StateTrait| {
+        for (i, key) in read_some.iter().enumerate() {
+            let access_key = StorageKey::AccountKey(key).with_native_space();
+            if i % 2 == 0 {
+                state.set(access_key, vec![i as u8][..].into()).unwrap();
+            } else {
+                state.delete(access_key).unwrap();
+            }
+        }
+        for key in &read_none {
+            state
+                .set(
+                    StorageKey::AccountKey(key).with_native_space(),
+                    key[..].into(),
+                )
+                .unwrap();
+        }
+    };
+    apply_writes(&mut recording);
+    apply_writes(&mut state);
+
+    let mut epoch_id = H256::default();
+    epoch_id.as_bytes_mut()[0] = 4;
+    state.compute_state_root().unwrap();
+    let state_root = state.commit(epoch_id).unwrap();
+
+    // prove the modifications against the new state
+    let post_state = state_manager
+        .get_state_no_commit_inner(
+            StateIndex::new_for_readonly(&epoch_id, &state_root),
+            /* try_open = */ false,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+    let mut merger = StateProofMerger::default();
+    for key in record.lock().writes.keys() {
+        let access_key =
+            StorageKeyWithSpace::from_key_bytes::<CheckInput>(key).unwrap();
+        let (_, proof) = post_state.get_with_proof(access_key).unwrap();
+        merger.merge(proof);
+    }
+    let post_proof = merger.finish().unwrap();
+
+    let (keys_read, proof) = {
+        let mut record = record.lock();
+        let keys_read: Vec<_> = record.reads.keys().cloned().collect();
+        (
+            keys_read,
+            record.take_proof().expect("proof is inconsistent"),
+        )
+    };
+
+    let new_witness = || {
+        WitnessStorage::new(
+            &parent_root,
+            keys_read.clone(),
+            &proof,
+            state_root.clone(),
+            post_proof.clone(),
+        )
+        .expect("witness should be valid")
+    };
+
+    // replaying the same accesses should result in the same state root
+    let mut witness = new_witness();
+    for key in &read_some {
+        assert_eq!(
+            witness
+                .get(StorageKey::AccountKey(key).with_native_space())
+                .unwrap(),
+            Some(key[..].into())
+        );
+    }
+    for key in &read_none {
+        assert_eq!(
+            witness
+                .get(StorageKey::AccountKey(key).with_native_space())
+                .unwrap(),
+            None
+        );
+    }
+    assert!(witness
+        .get(StorageKey::AccountKey(not_read).with_native_space())
+        .is_err());
+
+    apply_writes(&mut witness);
+    assert_eq!(
+        witness.compute_state_root().unwrap().state_root,
+        state_root.state_root
+    );
+
+    // different modifications should not be proved
+    let mut witness = new_witness();
+    apply_writes(&mut witness);
+    witness
+        .set(
+            StorageKey::AccountKey(&read_none[0]).with_native_space(),
+            vec![1][..].into(),
+        )
+        .unwrap();
+    assert!(witness.compute_state_root().is_err());
+
+    // prefix iterations should fail since the witness can't prove that they
+    // are complete
+    let mut witness = new_witness();
+    let prefix = StorageKey::AccountKey(&read_some[0]).with_native_space();
+    assert!(witness.read_all(prefix).is_err());
+    assert!(witness.delete_all(prefix).is_err());
+
+    // the keys read should not be proved against other state roots
+    assert!(WitnessStorage::new(
+        &state_root,
+        keys_read,
+        &proof,
+        state_root.clone(),
+        post_proof,
+    )
+    .is_err());
+}
+
 use crate::{
     state::*,
     state_manager::*,
@@ -702,12 +884,13 @@ use crate::{
         new_state_manager_for_unit_test_with_snapshot_epoch_count,
         FakeStateManager, TEST_NUMBER_OF_KEYS,
     },
-    RecordingStorage,
+    RecordingStorage, StateProofMerger, WitnessStorage,
 };
+use cfx_internal_common::StateRootWithAuxInfo;
 use cfx_types::H256;
 use primitives::{
-    DeltaMptKeyPadding, MptValue, NodeMerkleTriplet, StateRoot, StorageKey,
-    StorageKeyWithSpace,
+    CheckInput, DeltaMptKeyPadding, MptValue, NodeMerkleTriplet, StateRoot,
+    StorageKey, StorageKeyWithSpace,
 };
 use rand::{seq::SliceRandom, Rng};
 use rand_chacha::ChaChaRng;
@@ -20,6 +20,7 @@ use cfx_parameters::{
 use cfx_types::{AllChainID, Space, SpaceMap, U256, U512};
 use cfx_vm_types::{CIP645Spec, ConsensusGasSpec, Spec};
 use primitives::{block::BlockHeight, BlockNumber};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 // FIXME: This type is mainly used for execution layer parameters, but some
@@ -65,7 +66,8 @@ pub struct CommonParams {
     pub transition_heights: TransitionsEpochHeight,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TransitionsBlockNumber {
     /// CIP-43: Introduce Finality Through Staking Vote
     pub cip43a: BlockNumber,
@@ -117,7 +119,8 @@ pub struct TransitionsBlockNumber {
     pub cip145: BlockNumber,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TransitionsEpochHeight {
     /// CIP-40: Reduce Block Base Reward to 2 CFX
     pub cip40: BlockHeight,
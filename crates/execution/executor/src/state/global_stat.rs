@@ -5,6 +5,10 @@ use cfx_statedb::{
     Result as DbResult, StateDbExt, StateDbGeneric as StateDb,
 };
 use cfx_types::U256;
+use std::sync::{
+    atomic::{AtomicU32, Ordering::Relaxed},
+    Arc,
+};
 
 /// Manages specially-treated global variables during execution.
 ///
@@ -23,8 +27,28 @@ use cfx_types::U256;
 // TODO: Incorporating these variables into existing cache/checkpoint logic
 // would make the code clean, but it would be difficult to achieve back forward
 // compatibility.
-#[derive(Copy, Clone, Debug)]
-pub(super) struct GlobalStat([U256; TOTAL_GLOBAL_PARAMS]);
+#[derive(Clone, Debug)]
+pub(super) struct GlobalStat {
+    values: [U256; TOTAL_GLOBAL_PARAMS],
+    /// The reads recorded during speculative execution, which is shared with
+    /// the copies in checkpoints since reverted reads still matter.
+    reads: Option<Arc<GlobalStatReads>>,
+}
+
+/// The reads of global statistic variables in a speculative execution, as
+/// bitmasks indexed by `GlobalParamKey::ID`.
+///
+/// The writes are not recorded: they are merged as the differences to the base
+/// values, because all the writes during transaction execution are additions
+/// or subtractions.
+#[derive(Debug, Default)]
+pub(super) struct GlobalStatReads {
+    /// Variables whose exact values were read.
+    exact: AtomicU32,
+    /// Variables subtracted with saturation which didn't saturate, so the
+    /// execution remains the same as long as the value doesn't decrease.
+    lower_bound: AtomicU32,
+}
 
 impl GlobalStat {
     /// Make new global statistical variables with their initialization value.
@@ -39,7 +63,10 @@ impl GlobalStat {
         for_all_global_param_keys! {
             init_value::<Key>(&mut ans);
         }
-        GlobalStat(ans)
+        GlobalStat {
+            values: ans,
+            reads: None,
+        }
     }
 
     /// Get loaded global statistic variables from the database.
@@ -56,7 +83,10 @@ impl GlobalStat {
         for_all_global_param_keys! {
             load_value::<Key>(&mut ans, db)?;
         }
-        Ok(GlobalStat(ans))
+        Ok(GlobalStat {
+            values: ans,
+            reads: None,
+        })
     }
 
     /// Assert the global statistic variables have never been inited in the
@@ -95,17 +125,93 @@ impl GlobalStat {
         }
         use global_params::*;
         for_all_global_param_keys! {
-            commit_param::<Key>(&self.0, db, debug_record.as_deref_mut())?;
+            commit_param::<Key>(&self.values, db, debug_record.as_deref_mut())?;
         }
         Ok(())
     }
 
     /// Get the owned value of a variable
-    pub fn get<T: GlobalParamKey>(&self) -> U256 { self.0[T::ID] }
+    pub fn get<T: GlobalParamKey>(&self) -> U256 {
+        self.record_read(T::ID);
+        self.values[T::ID]
+    }
 
     /// Get the immutable reference of a variable
-    pub fn refr<T: GlobalParamKey>(&self) -> &U256 { &self.0[T::ID] }
+    pub fn refr<T: GlobalParamKey>(&self) -> &U256 {
+        self.record_read(T::ID);
+        &self.values[T::ID]
+    }
+
+    /// Get the mutable reference of a variable. In speculative execution, the
+    /// change is merged as a difference, so it should only be used for
+    /// additions and subtractions during transaction execution.
+    pub fn val<T: GlobalParamKey>(&mut self) -> &mut U256 {
+        &mut self.values[T::ID]
+    }
+
+    /// Subtract from a variable with saturation at zero.
+    pub fn saturating_sub<T: GlobalParamKey>(&mut self, v: U256) {
+        let value = &mut self.values[T::ID];
+        if let Some(reads) = &self.reads {
+            if *value >= v {
+                reads.lower_bound.fetch_or(1 << T::ID, Relaxed);
+            } else {
+                reads.exact.fetch_or(1 << T::ID, Relaxed);
+            }
+        }
+        *value = value.saturating_sub(v);
+    }
+
+    fn record_read(&self, id: usize) {
+        if let Some(reads) = &self.reads {
+            reads.exact.fetch_or(1 << id, Relaxed);
+        }
+    }
+}
+
+/// Speculative execution.
+impl GlobalStat {
+    /// Make a copy which records the reads, for speculative execution.
+    pub fn speculative_copy(&self) -> Self {
+        GlobalStat {
+            values: self.values,
+            reads: Some(Default::default()),
+        }
+    }
+
+    /// Whether the speculative execution upon `base`, which results in
+    /// `speculative`, would make the same reads upon this one.
+    pub fn is_read_compatible(
+        &self, base: &GlobalStat, speculative: &GlobalStat,
+    ) -> bool {
+        let Some(reads) = &speculative.reads else {
+            return true;
+        };
+        let exact = reads.exact.load(Relaxed);
+        let lower_bound = reads.lower_bound.load(Relaxed);
+        (0..TOTAL_GLOBAL_PARAMS).all(|id| {
+            let bit = 1 << id;
+            if exact & bit != 0 {
+                self.values[id] == base.values[id]
+            } else if lower_bound & bit != 0 {
+                self.values[id] >= base.values[id]
+            } else {
+                true
+            }
+        })
+    }
+
+    /// Apply the changes from `base` to `speculative`.
+    pub fn apply_difference(
+        &mut self, base: &GlobalStat, speculative: &GlobalStat,
+    ) {
+        for id in 0..TOTAL_GLOBAL_PARAMS {
+            let (diff, _) =
+                speculative.values[id].overflowing_sub(base.values[id]);
+            self.values[id] = self.values[id].overflowing_add(diff).0;
+        }
+    }
 
-    /// Get the mutable reference of a variable
-    pub fn val<T: GlobalParamKey>(&mut self) -> &mut U256 { &mut self.0[T::ID] }
+    /// The values of all variables, in the order of `GlobalParamKey::ID`.
+    pub fn values(&self) -> &[U256] { &self.values }
 }
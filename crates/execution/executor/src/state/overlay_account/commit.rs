@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use cfx_internal_common::debug::ComputeEpochDebugRecord;
 use cfx_statedb::{Result as DbResult, StateDb, StateDbExt};
@@ -7,6 +7,7 @@ use primitives::{
     storage::WriteCacheItem, Account, CodeInfo, DepositList, StorageKey,
     StorageValue, VoteStakeList,
 };
+use rlp::{Encodable, RlpStream};
 
 use super::OverlayAccount;
 
@@ -129,3 +130,49 @@ impl OverlayAccount {
         }
     }
 }
+
+impl OverlayAccount {
+    /// Appends the fields to be committed, together with the storage read
+    /// cache and the transient storage which affect the following
+    /// transactions, to `stream` in a deterministic order. It is used to
+    /// compare the results of different executions, so the account should
+    /// have been committed to the committed cache.
+    pub fn append_digest(&self, stream: &mut RlpStream) {
+        fn append_optional<T: Encodable>(
+            stream: &mut RlpStream, value: Option<&T>,
+        ) {
+            match value {
+                Some(value) => stream.begin_list(1).append(value),
+                None => stream.begin_list(0),
+            };
+        }
+        fn append_sorted<V: Encodable>(
+            stream: &mut RlpStream, cache: &HashMap<Vec<u8>, V>,
+        ) {
+            let mut entries: Vec<_> = cache.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            stream.begin_list(entries.len());
+            for (key, value) in entries {
+                stream.begin_list(2).append(key).append(value);
+            }
+        }
+
+        stream.begin_list(10);
+        stream.append(&self.as_account());
+        append_optional(stream, self.deposit_list.as_ref());
+        append_optional(stream, self.vote_stake_list.as_ref());
+        append_optional(
+            stream,
+            self.storage_layout_change
+                .as_ref()
+                .map(|l| l.to_bytes())
+                .as_ref(),
+        );
+        append_optional(stream, self.create_transaction_hash.as_ref());
+        stream.append(&self.is_newly_created_contract);
+        stream.append(&self.pending_db_clear);
+        append_sorted(stream, &self.storage_committed_cache.read());
+        append_sorted(stream, &self.storage_read_cache.read());
+        append_sorted(stream, &self.transient_storage_cache.read());
+    }
+}
@@ -166,6 +166,16 @@ impl OverlayAccount {
         Ok(())
     }
 
+    /// Adds the storage entries read by another copy of this account, which
+    /// are loaded from the same database.
+    pub fn extend_storage_read_cache(&self, other: &OverlayAccount) {
+        let other_cache = other.storage_read_cache.read();
+        let mut read_cache = self.storage_read_cache.write();
+        for (key, value) in other_cache.iter() {
+            read_cache.entry(key.clone()).or_insert(*value);
+        }
+    }
+
     fn cached_entry_at(&self, key: &[u8]) -> Option<StorageValue> {
         if let Some(WriteCacheItem::Write(entry)) =
             self.storage_write_cache.read().get(key)
@@ -2,7 +2,9 @@
 //! provides interfaces for reading and writing account data. It also handles
 //! the logic for loading extension fields of an account.
 
-use super::{AccountEntry, OverlayAccount, RequireFields, State};
+use super::{
+    AccountEntry, OverlayAccount, RequireFields, SpeculationBase, State,
+};
 use crate::{state::overlay_account::AccountEntryWithWarm, unwrap_or_return};
 use cfx_statedb::{
     Error as DbErrorKind, Result as DbResult, StateDb, StateDbExt,
@@ -12,9 +14,12 @@ use parking_lot::{
     MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLockReadGuard,
     RwLockWriteGuard,
 };
-use std::collections::{
-    hash_map::Entry::{Occupied, Vacant},
-    HashMap,
+use std::{
+    collections::{
+        hash_map::Entry::{Occupied, Vacant},
+        HashMap,
+    },
+    sync::Arc,
 };
 
 pub type AccountReadGuard<'a> = MappedRwLockReadGuard<'a, OverlayAccount>;
@@ -46,10 +51,12 @@ impl State {
         let account_entry = Self::fetch_account_mut(
             &mut cache,
             &self.committed_cache,
+            self.speculation_base.as_ref(),
             &self.db,
             address,
             require,
         )?;
+        self.record_account_read(address);
 
         self.copy_cache_entry_to_checkpoint(*address, account_entry);
 
@@ -173,10 +180,12 @@ impl State {
         let account_entry = Self::fetch_account_mut(
             &mut cache,
             &self.committed_cache,
+            self.speculation_base.as_ref(),
             &self.db,
             address,
             require,
         )?;
+        self.record_account_write(address);
 
         // Save the value before modification into the checkpoint.
         self.copy_cache_entry_to_checkpoint(*address, account_entry);
@@ -199,21 +208,29 @@ impl State {
 
 impl State {
     /// Retrieves data using a read-through caching strategy and automatically
-    /// loads extension fields as required.
+    /// loads extension fields as required. For a speculative state, the
+    /// speculation base is read before the database.
     fn fetch_account_mut<'a>(
         cache: &'a mut HashMap<AddressWithSpace, AccountEntryWithWarm>,
         committed_cache: &'a HashMap<AddressWithSpace, AccountEntry>,
-        db: &StateDb, address: &AddressWithSpace, require: RequireFields,
+        speculation_base: Option<&Arc<SpeculationBase>>, db: &StateDb,
+        address: &AddressWithSpace, require: RequireFields,
     ) -> DbResult<&'a mut AccountEntryWithWarm> {
         let account_entry = match cache.entry(*address) {
             Occupied(e) => e.into_mut(),
             Vacant(e) => {
                 let entry = match committed_cache.get(address) {
                     Some(committed) => committed.clone_from_committed_cache(),
-                    None => {
-                        let address = *e.key();
-                        AccountEntry::new_loaded(db.get_account(&address)?)
-                    }
+                    None => match Self::speculation_base_entry(
+                        speculation_base,
+                        address,
+                    ) {
+                        Some(entry) => entry,
+                        None => {
+                            let address = *e.key();
+                            AccountEntry::new_loaded(db.get_account(&address)?)
+                        }
+                    },
                 };
                 // The item is set to "cold" by default when loading. After
                 // processing the checkpoint-related logic, it will be marked as
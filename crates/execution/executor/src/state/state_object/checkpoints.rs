@@ -53,7 +53,7 @@ impl State {
     /// the creation of the next checkpoint.
     pub(crate) fn checkpoint(&mut self) -> usize {
         self.checkpoints.get_mut().push_checkpoint(CheckpointLayer {
-            global_stat: self.global_stat,
+            global_stat: self.global_stat.clone(),
             entries: HashMap::new(),
         })
     }
@@ -95,6 +95,7 @@ impl State {
     /// to the checkpoint in needed.
     pub(super) fn insert_to_cache(&mut self, account: OverlayAccount) {
         let address = *account.address();
+        self.record_account_write(&address);
         let old_account_entry = self
             .cache
             .get_mut()
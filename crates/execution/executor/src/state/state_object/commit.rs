@@ -32,6 +32,14 @@ impl State {
 
     /// Commit to the statedb and compute state root. Only called in the genesis
     pub fn compute_state_root_for_genesis(
+        &mut self, debug_record: Option<&mut ComputeEpochDebugRecord>,
+    ) -> DbResult<StateRootWithAuxInfo> {
+        self.compute_state_root(debug_record)
+    }
+
+    /// Commit to the statedb and compute state root without committing the
+    /// storage, e.g. for a storage which is not backed by the state tries.
+    pub fn compute_state_root(
         &mut self, mut debug_record: Option<&mut ComputeEpochDebugRecord>,
     ) -> DbResult<StateRootWithAuxInfo> {
         self.apply_changes_to_statedb(debug_record.as_deref_mut())?;
@@ -18,8 +18,7 @@ impl State {
     /// Maintain `total_issued_tokens`. This is only used in the extremely
     /// unlikely case that there are a lot of partial invalid blocks.
     pub fn sub_total_issued(&mut self, v: U256) {
-        *self.global_stat.val::<TotalIssued>() =
-            self.global_stat.refr::<TotalIssued>().saturating_sub(v);
+        self.global_stat.saturating_sub::<TotalIssued>(v);
     }
 
     pub fn add_total_pos_staking(&mut self, v: U256) {
@@ -31,8 +30,7 @@ impl State {
     }
 
     pub fn sub_total_evm_tokens(&mut self, v: U256) {
-        *self.global_stat.val::<TotalEvmToken>() =
-            self.global_stat.refr::<TotalEvmToken>().saturating_sub(v);
+        self.global_stat.saturating_sub::<TotalEvmToken>(v);
     }
 
     pub fn total_staking_tokens(&self) -> U256 {
@@ -60,8 +58,7 @@ impl State {
     }
 
     pub fn sub_total_pos_staking(&mut self, v: U256) {
-        *self.global_stat.val::<TotalPosStaking>() =
-            self.global_stat.refr::<TotalPosStaking>().saturating_sub(v)
+        self.global_stat.saturating_sub::<TotalPosStaking>(v)
     }
 
     pub fn total_circulating_tokens(&self) -> DbResult<U256> {
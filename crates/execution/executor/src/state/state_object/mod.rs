@@ -45,6 +45,10 @@ mod storage_entry;
 
 mod reward;
 
+/// Implements the speculative execution of transactions upon a shared
+/// snapshot of `State`, and merging the results back.
+mod speculative;
+
 mod state_override;
 
 #[cfg(test)]
@@ -55,6 +59,7 @@ pub use self::{
     commit::StateCommitResult,
     pos::{distribute_pos_interest, update_pos_status},
     reward::initialize_cip137,
+    speculative::{AccountAccess, SpeculationBase, SpeculativeOutput},
     sponsor::COMMISSION_PRIVILEGE_SPECIAL_KEY,
     staking::initialize_or_update_dao_voted_params,
 };
@@ -72,8 +77,11 @@ use super::{
 use crate::substate::Substate;
 use cfx_statedb::{Result as DbResult, StateDbExt, StateDbGeneric as StateDb};
 use cfx_types::{AddressWithSpace, H256};
-use parking_lot::RwLock;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
 
 /// A caching and checkpoint layer built upon semantically meaningful database
 /// interfaces, providing interfaces and logics for managing accounts and global
@@ -99,6 +107,14 @@ pub struct State {
     // TODO: it seems `RwLock` is not necessary here. But we need to change the
     // signature of `write_account` from `&self` to `&mut self` first
     checkpoints: RwLock<LazyDiscardedVec<CheckpointLayer>>,
+
+    /// The accessed accounts, if they are being recorded for speculative
+    /// execution.
+    accessed_accounts: Option<Mutex<AccountAccess>>,
+
+    /// The shared accounts to be loaded before the database, if this is a
+    /// speculative state.
+    speculation_base: Option<Arc<SpeculationBase>>,
 }
 
 impl State {
@@ -119,6 +135,8 @@ impl State {
             checkpoints: Default::default(),
             tx_access_list: None,
             global_stat: world_stat,
+            accessed_accounts: None,
+            speculation_base: None,
         })
     }
 
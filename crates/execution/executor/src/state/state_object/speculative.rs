@@ -0,0 +1,235 @@
+//! Speculative Execution: Executes a transaction upon a shared snapshot of the
+//! state, records the accessed accounts and global statistic variables, and
+//! merges the result into the state if no earlier transaction interfered.
+
+use super::{AccountEntry, GlobalStat, State};
+use cfx_statedb::StateDbGeneric as StateDb;
+use cfx_types::{AddressWithSpace, H256};
+use keccak_hash::keccak;
+use parking_lot::Mutex;
+use rlp::RlpStream;
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    sync::Arc,
+};
+
+/// The accounts accessed by transactions, which are recorded for detecting
+/// conflicts between the transactions executed speculatively.
+#[derive(Debug, Default)]
+pub struct AccountAccess {
+    reads: HashSet<AddressWithSpace>,
+    writes: HashSet<AddressWithSpace>,
+    /// Accounts whose storage entries are deleted by ranges. The collateral
+    /// refunded by such a deletion depends on the storage entries cached by
+    /// the earlier transactions, even if they were only read.
+    range_deletions: HashSet<AddressWithSpace>,
+}
+
+impl AccountAccess {
+    /// Whether a transaction with this access, executed before `earlier`, may
+    /// behave differently after the transactions of `earlier`.
+    pub fn conflicts_with(&self, earlier: &AccountAccess) -> bool {
+        self.reads
+            .iter()
+            .chain(self.writes.iter())
+            .any(|address| earlier.writes.contains(address))
+            || self.range_deletions.iter().any(|address| {
+                earlier.reads.contains(address)
+                    || earlier.writes.contains(address)
+            })
+    }
+
+    pub fn extend(&mut self, other: AccountAccess) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+        self.range_deletions.extend(other.range_deletions);
+    }
+}
+
+/// The committed accounts and global statistic variables of a state, which
+/// are shared by the speculative executions upon it.
+pub struct SpeculationBase {
+    committed_cache: HashMap<AddressWithSpace, AccountEntry>,
+    global_stat: GlobalStat,
+}
+
+impl SpeculationBase {
+    fn get(&self, address: &AddressWithSpace) -> Option<&AccountEntry> {
+        self.committed_cache.get(address)
+    }
+}
+
+/// The result of a transaction executed speculatively.
+pub struct SpeculativeOutput {
+    /// The accounts touched by the transaction, in the form of the committed
+    /// cache.
+    accounts: HashMap<AddressWithSpace, AccountEntry>,
+    base_global_stat: GlobalStat,
+    global_stat: GlobalStat,
+    access: AccountAccess,
+}
+
+impl State {
+    /// Moves the committed accounts into a base for speculative execution.
+    /// The state should not be used until `end_speculation` is called.
+    pub fn start_speculation(&mut self) -> Arc<SpeculationBase> {
+        assert!(self.no_checkpoint());
+        assert!(self.cache.get_mut().is_empty());
+        Arc::new(SpeculationBase {
+            committed_cache: mem::take(&mut self.committed_cache),
+            global_stat: self.global_stat.clone(),
+        })
+    }
+
+    /// Takes back the committed accounts after all the speculative states
+    /// upon `base` have been finished.
+    pub fn end_speculation(&mut self, base: Arc<SpeculationBase>) {
+        let base = Arc::try_unwrap(base)
+            .ok()
+            .expect("speculative states should have been finished");
+        assert!(self.committed_cache.is_empty());
+        self.committed_cache = base.committed_cache;
+    }
+
+    /// Creates a state for executing a transaction upon `base`, where `db`
+    /// should be opened at the same snapshot as the state of `base`. The
+    /// accounts loaded from `base` are copied, so `base` is never modified.
+    pub fn new_speculative(db: StateDb, base: Arc<SpeculationBase>) -> Self {
+        State {
+            db,
+            cache: Default::default(),
+            committed_cache: Default::default(),
+            tx_access_list: None,
+            global_stat: base.global_stat.speculative_copy(),
+            checkpoints: Default::default(),
+            accessed_accounts: Some(Default::default()),
+            speculation_base: Some(base),
+        }
+    }
+
+    /// Finishes the speculative execution of a transaction, returning its
+    /// result and the database for reuse.
+    pub fn finish_speculation(mut self) -> (SpeculativeOutput, StateDb) {
+        assert!(self.no_checkpoint());
+        assert!(self.cache.get_mut().is_empty());
+        let base = self
+            .speculation_base
+            .take()
+            .expect("should be a speculative state");
+        let output = SpeculativeOutput {
+            accounts: mem::take(&mut self.committed_cache),
+            base_global_stat: base.global_stat.clone(),
+            global_stat: self.global_stat.clone(),
+            access: self.finish_recording_access(),
+        };
+        (output, self.db)
+    }
+
+    /// Applies the speculative result of a transaction, if it is the same as
+    /// executing the transaction on this state, where `accessed` is the
+    /// accounts accessed by the transactions since the speculation base.
+    /// Returns whether it is applied, and if so, `accessed` is extended.
+    pub fn apply_speculative(
+        &mut self, output: SpeculativeOutput, accessed: &mut AccountAccess,
+    ) -> bool {
+        assert!(self.no_checkpoint());
+        assert!(self.cache.get_mut().is_empty());
+        if output.access.conflicts_with(accessed)
+            || !self.global_stat.is_read_compatible(
+                &output.base_global_stat,
+                &output.global_stat,
+            )
+        {
+            return false;
+        }
+
+        for (address, mut entry) in output.accounts {
+            // The account may have been read by earlier transactions, whose
+            // reads should be kept in the read cache as in sequential
+            // execution.
+            if let (
+                AccountEntry::Cached(account, _),
+                Some(AccountEntry::Cached(committed, _)),
+            ) = (&mut entry, self.committed_cache.get(&address))
+            {
+                account.extend_storage_read_cache(committed);
+            }
+            self.committed_cache.insert(address, entry);
+        }
+        self.global_stat
+            .apply_difference(&output.base_global_stat, &output.global_stat);
+        accessed.extend(output.access);
+        true
+    }
+
+    /// Starts recording the accessed accounts.
+    pub fn start_recording_access(&mut self) {
+        self.accessed_accounts = Some(Default::default());
+    }
+
+    /// Stops recording and returns the accessed accounts since
+    /// `start_recording_access`.
+    pub fn finish_recording_access(&mut self) -> AccountAccess {
+        self.accessed_accounts
+            .take()
+            .map(Mutex::into_inner)
+            .unwrap_or_default()
+    }
+
+    /// Returns a digest of the committed accounts and the global statistic
+    /// variables, for comparing the results of different executions.
+    pub fn committed_digest(&self) -> H256 {
+        assert!(self.cache.read().is_empty());
+        let mut entries: Vec<_> = self.committed_cache.iter().collect();
+        entries.sort_by_key(|(address, _)| **address);
+
+        let mut stream = RlpStream::new_list(2);
+        stream.append_list(self.global_stat.values());
+        stream.begin_list(entries.len());
+        for (address, entry) in entries {
+            let space: &str = address.space.into();
+            stream.begin_list(3).append(&address.address).append(&space);
+            match entry {
+                AccountEntry::DbAbsent => {
+                    stream.begin_list(0);
+                }
+                AccountEntry::Cached(account, dirty) => {
+                    stream.begin_list(2).append(dirty);
+                    account.append_digest(&mut stream);
+                }
+            }
+        }
+        keccak(stream.out())
+    }
+}
+
+impl State {
+    /// Looks up an account in the speculation base, which is copied since the
+    /// base is shared.
+    pub(super) fn speculation_base_entry(
+        base: Option<&Arc<SpeculationBase>>, address: &AddressWithSpace,
+    ) -> Option<AccountEntry> {
+        base?.get(address).map(AccountEntry::clone_account)
+    }
+
+    pub(super) fn record_account_read(&self, address: &AddressWithSpace) {
+        if let Some(access) = &self.accessed_accounts {
+            access.lock().reads.insert(*address);
+        }
+    }
+
+    pub(super) fn record_account_write(&self, address: &AddressWithSpace) {
+        if let Some(access) = &self.accessed_accounts {
+            let mut access = access.lock();
+            access.reads.insert(*address);
+            access.writes.insert(*address);
+        }
+    }
+
+    pub(super) fn record_range_deletion(&self, address: &AddressWithSpace) {
+        if let Some(access) = &self.accessed_accounts {
+            access.lock().range_deletions.insert(*address);
+        }
+    }
+}
@@ -265,6 +265,7 @@ fn storage_range_deletion_for_account(
         StorageKey::new_storage_key(&address, key_prefix)
     }
     .with_native_space();
+    state.record_range_deletion(&address.with_native_space());
     let db_deletion_log = state
         .db
         .delete_all::<access_mode::Read>(storage_key_prefix, None)?
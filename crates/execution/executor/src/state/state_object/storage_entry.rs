@@ -3,12 +3,14 @@ use crate::{return_if, try_loaded};
 use cfx_parameters::internal_contract_addresses::{
     SPONSOR_WHITELIST_CONTROL_CONTRACT_ADDRESS, SYSTEM_STORAGE_ADDRESS,
 };
-use cfx_statedb::Result as DbResult;
+use cfx_statedb::{access_mode, Result as DbResult};
 use cfx_types::{
     Address, AddressSpaceUtil, AddressWithSpace, BigEndianHash, Space, H256,
     U256,
 };
-use primitives::StorageValue;
+use primitives::{
+    SkipInputCheck, StorageKey, StorageKeyWithSpace, StorageValue,
+};
 
 impl State {
     // System Storage shares the cache and checkpoint mechanisms with
@@ -116,6 +118,33 @@ impl State {
         Ok(())
     }
 
+    /// Reads all the storage entries of an account from the db. Changes held
+    /// in the caches of `State` are not included, so callers should apply
+    /// them to the db first.
+    pub fn read_all_storage_from_db(
+        &mut self, address: &AddressWithSpace,
+    ) -> DbResult<Vec<(Vec<u8>, U256)>> {
+        let storage_key_prefix =
+            StorageKey::new_storage_root_key(&address.address)
+                .with_space(address.space);
+        let mut entries = vec![];
+        for (key, raw_value) in self
+            .db
+            .delete_all::<access_mode::Read>(storage_key_prefix, None)?
+        {
+            if let StorageKeyWithSpace {
+                key: StorageKey::StorageKey { storage_key, .. },
+                ..
+            } = StorageKeyWithSpace::from_key_bytes::<SkipInputCheck>(&key)
+            {
+                let StorageValue { value, .. } =
+                    rlp::decode::<StorageValue>(&raw_value)?;
+                entries.push((storage_key.to_vec(), value));
+            }
+        }
+        Ok(entries)
+    }
+
     #[inline]
     pub fn transient_set_storage(
         &mut self, address: &AddressWithSpace, key: Vec<u8>, value: U256,
@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use super::{State, Substate};
+use super::{AccountAccess, State, Substate};
 use crate::internal_contract::{
     initialize_internal_contract_accounts, InternalContractMap,
 };
@@ -11,7 +11,8 @@ use cfx_parameters::{
 };
 use cfx_statedb::StateDb;
 use cfx_types::{
-    address_util::AddressUtil, Address, AddressSpaceUtil, BigEndianHash, U256,
+    address_util::AddressUtil, Address, AddressSpaceUtil, AddressWithSpace,
+    BigEndianHash, U256,
 };
 use keccak_hash::{keccak, KECCAK_EMPTY};
 use primitives::{EpochId, StorageKey, StorageLayout};
@@ -1191,6 +1192,58 @@ fn test_automatic_collateral_normal_account() {
     );
 }
 
+#[test]
+fn speculative_execution() {
+    let _ = get_state_for_genesis_write();
+    let genesis_epoch_id = EpochId::default();
+    let mut state = get_state_by_epoch_id(&genesis_epoch_id);
+    let mut sequential = get_state_by_epoch_id(&genesis_epoch_id);
+    let mut a = Address::zero();
+    a.set_user_account_type_bits();
+    let a = a.with_native_space();
+    let b = Address::random().with_native_space();
+    let total_issued = state.total_issued_tokens();
+
+    let base = state.start_speculation();
+    let execute = |tx: &dyn Fn(&mut State)| {
+        let db = StateDb::new_for_unit_test_with_epoch(&genesis_epoch_id);
+        let mut speculative = State::new_speculative(db, base.clone());
+        tx(&mut speculative);
+        speculative.update_state_post_tx_execution(false);
+        speculative.finish_speculation().0
+    };
+    let transfer_to = |address: AddressWithSpace| {
+        move |state: &mut State| {
+            state.add_balance(&address, &U256::from(10)).unwrap();
+            state.add_total_issued(U256::from(10));
+        }
+    };
+    let output_a = execute(&transfer_to(a));
+    let output_b = execute(&transfer_to(b));
+    let read_a = execute(&|state| {
+        state.balance(&a).unwrap();
+    });
+    let read_total_issued = execute(&|state| {
+        state.total_issued_tokens();
+    });
+    state.end_speculation(base);
+
+    let mut accessed = AccountAccess::default();
+    assert!(state.apply_speculative(output_a, &mut accessed));
+    assert!(state.apply_speculative(output_b, &mut accessed));
+    assert!(!state.apply_speculative(read_a, &mut accessed));
+    assert!(!state.apply_speculative(read_total_issued, &mut accessed));
+    assert_eq!(state.balance(&a).unwrap(), U256::from(10));
+    assert_eq!(state.total_issued_tokens(), total_issued + U256::from(20));
+    state.update_state_post_tx_execution(false);
+
+    for address in [a, b] {
+        transfer_to(address)(&mut sequential);
+        sequential.update_state_post_tx_execution(false);
+    }
+    assert_eq!(state.committed_digest(), sequential.committed_digest());
+}
+
 // #[test]
 // fn test_automatic_collateral_contract_account() {
 //     let storage_manager = new_state_manager_for_unit_test();
@@ -1710,6 +1710,8 @@ impl<Cost: CostType, const CANCUN: bool> InterpreterInfo
 
     fn return_stack(&self) -> &Vec<usize> { &self.return_stack }
 
+    fn return_data(&self) -> &[u8] { &self.return_data }
+
     fn stack(&self) -> &Vec<U256> { self.stack.content() }
 
     fn contract_address(&self) -> Address { self.params.address }
@@ -15,5 +15,7 @@ pub trait InterpreterInfo {
 
     fn return_stack(&self) -> &Vec<usize>;
 
+    fn return_data(&self) -> &[u8];
+
     fn contract_address(&self) -> Address;
 }
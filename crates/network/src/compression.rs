@@ -0,0 +1,139 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Optional compression of session packets.
+//!
+//! Peers advertise the support of snappy compression with an extension in the
+//! Hello packet. If both peers support it, every packet sent after the mode
+//! switch marker of the connection is framed with a trailing flag byte, which
+//! indicates whether the data is compressed. Small packets and packets that do
+//! not compress well are sent as is.
+
+use crate::Error;
+use bytes::BytesMut;
+use lazy_static::lazy_static;
+use log::debug;
+use metrics::{register_meter_with_group, Histogram, Meter, Sample};
+use std::sync::Arc;
+
+/// Hello packet extension to advertise the support of snappy compression.
+pub const EXTENSION_SNAPPY: u8 = 0x02;
+
+/// Packets smaller than this are not compressed.
+const MIN_COMPRESSION_SIZE: usize = 256;
+
+const FLAG_RAW: u8 = 0;
+const FLAG_SNAPPY: u8 = 1;
+
+lazy_static! {
+    static ref COMPRESS_RAW_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_compression", "raw");
+    static ref COMPRESS_COMPRESSED_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_compression", "compressed");
+    static ref DECOMPRESS_COMPRESSED_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_compression", "recv_compressed");
+    static ref DECOMPRESS_RAW_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_compression", "recv_raw");
+    /// Compressed size in percentage of the raw size.
+    static ref COMPRESSION_RATIO: Arc<dyn Histogram> = Sample::ExpDecay(0.015)
+        .register_with_group("network_compression", "ratio_percent", 1024);
+}
+
+/// Compress the packet data if it is worth, and append the frame flag.
+pub fn compress(mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if data.len() < MIN_COMPRESSION_SIZE {
+        data.push(FLAG_RAW);
+        return Ok(data);
+    }
+
+    let mut compressed = snap::raw::Encoder::new()
+        .compress_vec(&data)
+        .map_err(|e| Error::Msg(format!("failed to compress: {}", e)))?;
+
+    COMPRESS_RAW_METER.mark(data.len());
+    COMPRESS_COMPRESSED_METER.mark(compressed.len());
+    COMPRESSION_RATIO.update((compressed.len() * 100 / data.len()) as u64);
+
+    if compressed.len() >= data.len() {
+        data.push(FLAG_RAW);
+        return Ok(data);
+    }
+
+    compressed.push(FLAG_SNAPPY);
+    Ok(compressed)
+}
+
+/// Remove the frame flag and decompress the packet data if necessary. The
+/// decompressed data should not exceed `max_len`.
+pub fn decompress(
+    mut data: BytesMut, max_len: usize,
+) -> Result<BytesMut, Error> {
+    let flag = match data.len() {
+        0 => return Err(Error::BadProtocol),
+        len => data.split_off(len - 1)[0],
+    };
+
+    match flag {
+        FLAG_RAW => Ok(data),
+        FLAG_SNAPPY => {
+            let len = snap::raw::decompress_len(&data)
+                .map_err(|_| Error::BadProtocol)?;
+            if len > max_len {
+                debug!("decompressed packet is too large, len = {}", len);
+                return Err(Error::OversizedPacket);
+            }
+
+            let decompressed = snap::raw::Decoder::new()
+                .decompress_vec(&data)
+                .map_err(|_| Error::BadProtocol)?;
+
+            DECOMPRESS_COMPRESSED_METER.mark(data.len());
+            DECOMPRESS_RAW_METER.mark(decompressed.len());
+
+            Ok(BytesMut::from(&decompressed[..]))
+        }
+        _ => {
+            debug!("unknown compression flag {}", flag);
+            Err(Error::BadProtocol)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_packet_not_compressed() {
+        let data = compress(vec![1, 2, 3]).unwrap();
+        assert_eq!(data, vec![1, 2, 3, FLAG_RAW]);
+
+        let data = decompress(BytesMut::from(&data[..]), 1024).unwrap();
+        assert_eq!(&data[..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_compress_decompress() {
+        let raw: Vec<u8> = (0..4096).map(|i| (i % 7) as u8).collect();
+        let data = compress(raw.clone()).unwrap();
+        assert!(data.len() < raw.len());
+        assert_eq!(data.last(), Some(&FLAG_SNAPPY));
+
+        let data = decompress(BytesMut::from(&data[..]), raw.len()).unwrap();
+        assert_eq!(&data[..], &raw[..]);
+    }
+
+    #[test]
+    fn test_decompress_invalid() {
+        assert!(decompress(BytesMut::new(), 1024).is_err());
+        assert!(decompress(BytesMut::from(&[1u8, 2, 9][..]), 1024).is_err());
+        assert!(decompress(BytesMut::from(&[0xffu8, FLAG_SNAPPY][..]), 1024)
+            .is_err());
+
+        // decompressed size exceeds the limit
+        let raw = vec![0u8; 4096];
+        let data = compress(raw).unwrap();
+        assert!(decompress(BytesMut::from(&data[..]), 1024).is_err());
+    }
+}
@@ -3,7 +3,9 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    compression,
     iolib::{IoContext, StreamToken},
+    session_cipher::{SessionCipher, TAG_SIZE},
     throttling::THROTTLING_SERVICE,
     Error,
 };
@@ -19,6 +21,7 @@ use serde::Deserialize;
 use serde_derive::Serialize;
 use std::{
     io::{self, Read, Write},
+    mem,
     net::SocketAddr,
     sync::{
         atomic::{AtomicBool, Ordering as AtomicOrdering},
@@ -85,7 +88,7 @@ pub enum WriteStatus {
     Complete,
 }
 
-const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
+pub(crate) const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
 
 pub trait GenericSocket: Read + Write {}
 
@@ -188,6 +191,16 @@ pub struct GenericConnection<Socket: GenericSocket> {
     registered: AtomicBool,
     /// Assemble packet with extra information before sending out.
     assembler: Box<dyn PacketAssembler>,
+    /// Cipher to encrypt and decrypt packets once enabled for the session.
+    cipher: Option<SessionCipher>,
+    /// Whether packets sent are encrypted or compressed as negotiated, i.e.
+    /// the mode switch marker has been sent.
+    egress_switched: bool,
+    /// Whether packets received are encrypted or compressed as negotiated,
+    /// i.e. the mode switch marker has been received.
+    ingress_switched: bool,
+    /// Whether to compress and decompress packets.
+    compression: bool,
 }
 
 impl<Socket: GenericSocket> GenericConnection<Socket> {
@@ -227,7 +240,58 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
             }
         }
 
-        let packet = self.assembler.load(&mut self.recv_buf);
+        let mut packet = loop {
+            match self.assembler.load(&mut self.recv_buf) {
+                // The remote peer sends out the mode switch marker before
+                // the first encrypted or compressed packet.
+                Some(p)
+                    if p.is_empty()
+                        && self.is_mode_negotiated()
+                        && !self.ingress_switched =>
+                {
+                    debug!(
+                        "Ingress session modes switched on, token = {}",
+                        self.token
+                    );
+                    self.ingress_switched = true;
+                }
+                p => break p,
+            }
+        };
+
+        if let (Some(p), Some(cipher), true) =
+            (packet.as_mut(), self.cipher.as_mut(), self.ingress_switched)
+        {
+            if let Err(e) = cipher.open(p) {
+                debug!(
+                    "Failed to decrypt packet, token = {}, err = {:?}",
+                    self.token, e
+                );
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    e.to_string(),
+                ));
+            }
+        }
+
+        let packet = match packet {
+            Some(p) if self.compression && self.ingress_switched => {
+                match compression::decompress(p, MAX_PAYLOAD_SIZE) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        debug!(
+                            "Failed to decompress packet, token = {}, err = {:?}",
+                            self.token, e
+                        );
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            e.to_string(),
+                        ));
+                    }
+                }
+            }
+            p => p,
+        };
 
         if let Some(ref p) = packet {
             trace!(
@@ -241,9 +305,7 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     }
 
     /// Send the specified data out immediately
-    pub fn write_raw_data(
-        &mut self, mut data: Vec<u8>,
-    ) -> Result<usize, Error> {
+    pub fn write_raw_data(&mut self, data: Vec<u8>) -> Result<usize, Error> {
         trace!(
             "Sending raw buffer, token = {} data_len = {}, data = {:?}",
             self.token,
@@ -251,7 +313,7 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
             data
         );
 
-        self.assembler.assemble(&mut data)?;
+        let data = self.encode_packet(data)?;
         let size = self.socket.write(&data)?;
 
         trace!(
@@ -275,8 +337,9 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
                 None => return Ok(WriteStatus::Complete),
             };
 
-            // assemble packet to send, e.g. prefix length to packet
-            self.assembler.assemble(&mut packet.data)?;
+            // encrypt packet in the order of sending out, so that the peer
+            // could decrypt packets with the same sequence of nonces.
+            packet.data = self.encode_packet(mem::take(&mut packet.data))?;
 
             trace!(
                 "Packet ready for sent, token = {}, size = {}",
@@ -338,7 +401,10 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     ) -> Result<SendQueueStatus, Error> {
         if !data.is_empty() {
             let size = data.len();
-            if self.assembler.is_oversized(size) {
+            // The compression adds a flag byte to incompressible data.
+            let overhead = if self.cipher.is_some() { TAG_SIZE } else { 0 }
+                + self.compression as usize;
+            if self.assembler.is_oversized(size + overhead) {
                 return Err(Error::OversizedPacket.into());
             }
 
@@ -372,6 +438,62 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     }
 
     pub fn is_sending(&self) -> bool { self.interest.is_writable() }
+
+    fn is_mode_negotiated(&self) -> bool {
+        self.cipher.is_some() || self.compression
+    }
+
+    /// Compress and encrypt the packet as negotiated, and assemble it to send
+    /// out. The mode switch marker, i.e. an empty packet, is prepended to the
+    /// first packet after the negotiation.
+    fn encode_packet(&mut self, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if !self.is_mode_negotiated() {
+            self.assembler.assemble(&mut data)?;
+            return Ok(data);
+        }
+
+        if self.compression {
+            data = compression::compress(data)?;
+        }
+        if let Some(cipher) = self.cipher.as_mut() {
+            cipher.seal(&mut data)?;
+        }
+        self.assembler.assemble(&mut data)?;
+        if self.egress_switched {
+            return Ok(data);
+        }
+
+        debug!("Egress session modes switched on, token = {}", self.token);
+        self.egress_switched = true;
+        let mut marker = Vec::with_capacity(data.len() + 3);
+        self.assembler.assemble(&mut marker)?;
+        marker.extend_from_slice(&data);
+        Ok(marker)
+    }
+
+    /// Encrypt packets with `cipher` if any, and compress packets if
+    /// `compression` is true, from the next packet that sent out. Packets
+    /// received are decrypted and decompressed after the mode switch marker
+    /// from the remote peer.
+    ///
+    /// The modes are applied when a packet is popped from the send queue, so
+    /// packets that already in the queue are sent out in the new modes too,
+    /// and the peer could identify the switch point in the stream by the
+    /// marker.
+    pub fn enable_session_modes(
+        &mut self, cipher: Option<SessionCipher>, compression: bool,
+    ) {
+        assert!(
+            !self.is_mode_negotiated(),
+            "session modes should be negotiated once"
+        );
+        self.cipher = cipher;
+        self.compression = compression;
+    }
+
+    pub fn is_encrypted(&self) -> bool { self.cipher.is_some() }
+
+    pub fn is_compressed(&self) -> bool { self.compression }
 }
 
 pub type Connection = GenericConnection<TcpStream>;
@@ -387,6 +509,10 @@ impl Connection {
             interest: Ready::hup() | Ready::readable(),
             registered: AtomicBool::new(false),
             assembler: Box::new(PacketWithLenAssembler::default()),
+            cipher: None,
+            egress_switched: false,
+            ingress_switched: false,
+            compression: false,
         }
     }
 
@@ -479,6 +605,8 @@ impl Connection {
                 .len_by_priority(SendQueuePriority::High),
             interest: format!("{:?}", self.interest),
             registered: self.registered.load(AtomicOrdering::SeqCst),
+            encrypted: self.is_encrypted(),
+            compressed: self.is_compressed(),
         }
     }
 }
@@ -494,6 +622,8 @@ pub struct ConnectionDetails {
     pub priority_queue_high: usize,
     pub interest: String,
     pub registered: bool,
+    pub encrypted: bool,
+    pub compressed: bool,
 }
 
 /// Assembler that prefix packet with length information.
@@ -590,7 +720,7 @@ impl PacketAssembler for PacketWithLenAssembler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::iolib::*;
+    use crate::{iolib::*, session_cipher::tests::session_ciphers};
     use mio::Ready;
     use std::{
         cmp,
@@ -675,6 +805,10 @@ mod tests {
                 interest: Ready::hup() | Ready::readable(),
                 registered: AtomicBool::new(false),
                 assembler: Box::new(PacketWithLenAssembler::new(1, None)),
+                cipher: None,
+                egress_switched: false,
+                ingress_switched: false,
+                compression: false,
             }
         }
     }
@@ -739,6 +873,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn connection_encrypted_write_read() {
+        let io = test_io();
+        let mut sender = TestConnection::new();
+        let mut receiver = TestConnection::new();
+
+        // sent out before the encryption is enabled, e.g. the Hello packet
+        sender.send(&io, vec![9], SendQueuePriority::High).unwrap();
+        sender.writable(&io).unwrap();
+        // queued before the encryption is enabled
+        sender
+            .send(&io, vec![1, 3, 5, 7], SendQueuePriority::Normal)
+            .unwrap();
+
+        let (egress, ingress) = session_ciphers();
+        sender.enable_session_modes(Some(egress), false);
+        receiver.enable_session_modes(Some(ingress), false);
+        sender
+            .send(&io, vec![2, 4], SendQueuePriority::High)
+            .unwrap();
+        while !sender.send_queue.is_empty() {
+            sender.writable(&io).unwrap();
+        }
+
+        // the plain packet is followed by the mode switch marker
+        assert_eq!(&sender.socket.write_buf[..3], &[1, 9, 0]);
+
+        receiver.socket.read_buf = sender.socket.write_buf.clone();
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[9]);
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[2, 4]);
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[1, 3, 5, 7]);
+
+        // encrypted with another key
+        let (_, ingress) = session_ciphers();
+        let mut receiver = TestConnection::new();
+        receiver.enable_session_modes(Some(ingress), false);
+        receiver.socket.read_buf = sender.socket.write_buf.clone();
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[9]);
+        assert!(receiver.readable().is_err());
+    }
+
+    #[test]
+    fn connection_compressed_write_read() {
+        let io = test_io();
+        let mut sender = TestConnection::new();
+        sender.assembler = Box::new(PacketWithLenAssembler::default());
+        let mut receiver = TestConnection::new();
+        receiver.assembler = Box::new(PacketWithLenAssembler::default());
+
+        // queued before the compression is enabled
+        let big = vec![6; 4096];
+        sender
+            .send(&io, big.clone(), SendQueuePriority::Normal)
+            .unwrap();
+        sender.enable_session_modes(None, true);
+        receiver.enable_session_modes(None, true);
+        sender
+            .send(&io, vec![1, 3], SendQueuePriority::High)
+            .unwrap();
+        while !sender.send_queue.is_empty() {
+            sender.writable(&io).unwrap();
+        }
+        assert!(sender.socket.write_buf.len() < big.len());
+
+        receiver.socket.read_buf = sender.socket.write_buf.clone();
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[1, 3]);
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &big[..]);
+    }
+
+    #[test]
+    fn connection_encrypted_compressed_write_read() {
+        let io = test_io();
+        let mut sender = TestConnection::new();
+        sender.assembler = Box::new(PacketWithLenAssembler::default());
+        let mut receiver = TestConnection::new();
+        receiver.assembler = Box::new(PacketWithLenAssembler::default());
+
+        // sent out before the negotiation, e.g. the Hello packet
+        sender.send(&io, vec![9], SendQueuePriority::High).unwrap();
+        sender.writable(&io).unwrap();
+
+        let (egress, ingress) = session_ciphers();
+        sender.enable_session_modes(Some(egress), true);
+        receiver.enable_session_modes(Some(ingress), true);
+        let big = vec![6; 4096];
+        sender
+            .send(&io, big.clone(), SendQueuePriority::Normal)
+            .unwrap();
+        sender.writable(&io).unwrap();
+        assert!(sender.socket.write_buf.len() < big.len());
+
+        receiver.socket.read_buf = sender.socket.write_buf.clone();
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &[9]);
+        assert_eq!(&receiver.readable().unwrap().unwrap()[..], &big[..]);
+    }
+
     #[test]
     fn test_assembler_oversized() {
         let assembler = PacketWithLenAssembler::default();
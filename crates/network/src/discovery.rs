@@ -73,6 +73,8 @@ pub struct Discovery {
     id_hash: H256,
     secret: Secret,
     public_endpoint: NodeEndpoint,
+    /// The IPv6 endpoint advertised to IPv6 nodes by a dual-stack node.
+    public_endpoint_v6: Option<NodeEndpoint>,
     discovery_initiated: bool,
     discovery_round: Option<u16>,
     discovery_nodes: HashSet<NodeId>,
@@ -92,14 +94,15 @@ pub struct Discovery {
 
 impl Discovery {
     pub fn new(
-        key: &KeyPair, public: NodeEndpoint, ip_filter: IpFilter,
-        config: DiscoveryConfiguration,
+        key: &KeyPair, public: NodeEndpoint, public_v6: Option<NodeEndpoint>,
+        ip_filter: IpFilter, config: DiscoveryConfiguration,
     ) -> Discovery {
         Discovery {
             id: key.public().clone(),
             id_hash: keccak(key.public()),
             secret: key.secret().clone(),
             public_endpoint: public,
+            public_endpoint_v6: public_v6,
             discovery_initiated: false,
             discovery_round: None,
             discovery_nodes: HashSet::new(),
@@ -128,6 +131,26 @@ impl Discovery {
         entry.endpoint.is_allowed(&self.ip_filter) && entry.id != self.id
     }
 
+    /// Update the endpoint advertised in discovery packets, e.g. when the
+    /// NAT port mapping changes.
+    pub fn set_public_endpoint(&mut self, public: NodeEndpoint) {
+        self.public_endpoint = public;
+    }
+
+    /// Select the endpoint of the same address family as `to` to advertise,
+    /// so that dual-stack nodes are reachable by both IPv4 and IPv6 nodes.
+    fn advertised_endpoint(&self, to: &NodeEndpoint) -> &NodeEndpoint {
+        match &self.public_endpoint_v6 {
+            Some(v6)
+                if to.address.is_ipv6()
+                    && !self.public_endpoint.address.is_ipv6() =>
+            {
+                v6
+            }
+            _ => &self.public_endpoint,
+        }
+    }
+
     pub fn try_ping_nodes(
         &mut self, uio: &UdpIoContext, nodes: Vec<NodeEntry>,
     ) {
@@ -166,7 +189,8 @@ impl Discovery {
     ) -> Result<(), Error> {
         let mut rlp = RlpStream::new_list(4);
         rlp.append(&DISCOVER_PROTOCOL_VERSION);
-        self.public_endpoint.to_rlp_list(&mut rlp);
+        self.advertised_endpoint(&node.endpoint)
+            .to_rlp_list(&mut rlp);
         node.endpoint.to_rlp_list(&mut rlp);
         rlp.append(&self.config.expire_timestamp());
         let hash = self.send_packet(
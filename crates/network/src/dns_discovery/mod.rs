@@ -0,0 +1,337 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Discovery of nodes from signed node lists published in DNS, so that the
+//! bootnodes can be rotated without shipping new configs.
+
+mod resolver;
+mod tree;
+
+pub use self::{
+    resolver::{TxtResolver, UdpTxtResolver},
+    tree::{entry_hash, DnsTree, Entry, RootEntry, TreeUrl},
+};
+
+use crate::{node_table::NodeEntry, Error};
+use log::{debug, warn};
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    mem,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// The maximum number of records of a single tree, to bound the work of a
+/// malicious or broken tree.
+const MAX_TREE_ENTRIES: usize = 10_000;
+
+/// The maximum number of verified records kept in the cache, which is enough
+/// for a full tree.
+const MAX_CACHED_ENTRIES: usize = MAX_TREE_ENTRIES;
+
+/// The maximum number of trees synced in a round, including the linked ones.
+const MAX_LINKED_TREES: usize = 32;
+
+/// The nodes and links of a synced tree.
+#[derive(Default)]
+pub struct SyncedTree {
+    pub seq: u64,
+    pub nodes: Vec<NodeEntry>,
+    pub links: Vec<TreeUrl>,
+}
+
+/// Syncs trees from DNS and verifies them.
+pub struct DnsClient {
+    resolver: Box<dyn TxtResolver>,
+    /// Records already resolved and verified, by hash. They never change, and
+    /// the least recently used ones are evicted.
+    cache: LruCache<String, Entry>,
+    /// The sequence number of the last synced root of each tree.
+    last_seq: HashMap<TreeUrl, u64>,
+}
+
+impl DnsClient {
+    pub fn new(resolver: Box<dyn TxtResolver>) -> Self {
+        DnsClient {
+            resolver,
+            cache: LruCache::new(MAX_CACHED_ENTRIES),
+            last_seq: HashMap::new(),
+        }
+    }
+
+    fn resolve_root(&self, url: &TreeUrl) -> Result<RootEntry, Error> {
+        for text in self.resolver.resolve_txt(&url.domain)? {
+            if let Ok(Entry::Root(root)) = text.parse() {
+                if !root.verify(&url.public) {
+                    return Err(Error::DnsDiscovery(format!(
+                        "invalid root signature of {}",
+                        url
+                    )));
+                }
+                return Ok(root);
+            }
+        }
+        Err(Error::DnsDiscovery(format!("no root record for {}", url)))
+    }
+
+    fn resolve_entry(
+        &mut self, domain: &str, hash: &str,
+    ) -> Result<Entry, Error> {
+        if let Some(entry) = self.cache.get_mut(hash) {
+            return Ok(entry.clone());
+        }
+        let name = format!("{}.{}", hash, domain);
+        for text in self.resolver.resolve_txt(&name)? {
+            // Records not matching the hash are ignored, as if they were not
+            // published.
+            if entry_hash(&text) != hash {
+                continue;
+            }
+            let entry: Entry = text.parse()?;
+            if let Entry::Root(_) = entry {
+                return Err(Error::DnsDiscovery(format!(
+                    "unexpected root record at {}",
+                    name
+                )));
+            }
+            self.cache.insert(hash.to_string(), entry.clone());
+            return Ok(entry);
+        }
+        Err(Error::DnsDiscovery(format!("missing record {}", name)))
+    }
+
+    /// Resolve the leaves of the subtree with the given root hash.
+    fn sync_subtree(
+        &mut self, domain: &str, root: &str, count: &mut usize,
+        leaves: &mut Vec<Entry>,
+    ) -> Result<(), Error> {
+        let mut pending = VecDeque::new();
+        pending.push_back(root.to_string());
+        while let Some(hash) = pending.pop_front() {
+            *count += 1;
+            if *count > MAX_TREE_ENTRIES {
+                return Err(Error::DnsDiscovery(format!(
+                    "too many records in {}",
+                    domain
+                )));
+            }
+            match self.resolve_entry(domain, &hash)? {
+                Entry::Branch(children) => pending.extend(children),
+                leaf => leaves.push(leaf),
+            }
+        }
+        Ok(())
+    }
+
+    /// Sync a tree and return its nodes and links. Fails if the root is not
+    /// signed by the key in the URL, or is older than the last synced one.
+    pub fn sync_tree(&mut self, url: &TreeUrl) -> Result<SyncedTree, Error> {
+        let root = self.resolve_root(url)?;
+        if let Some(seq) = self.last_seq.get(url) {
+            if root.seq < *seq {
+                return Err(Error::DnsDiscovery(format!(
+                    "outdated root of {} with seq {} < {}",
+                    url, root.seq, seq
+                )));
+            }
+        }
+
+        let mut count = 0;
+        let mut leaves = Vec::new();
+        self.sync_subtree(
+            &url.domain,
+            &root.node_root,
+            &mut count,
+            &mut leaves,
+        )?;
+        self.sync_subtree(
+            &url.domain,
+            &root.link_root,
+            &mut count,
+            &mut leaves,
+        )?;
+
+        let mut tree = SyncedTree {
+            seq: root.seq,
+            ..Default::default()
+        };
+        for leaf in leaves {
+            match leaf {
+                Entry::Node(node) => tree.nodes.push(node),
+                Entry::Link(link) => tree.links.push(link),
+                _ => {}
+            }
+        }
+        self.last_seq.insert(url.clone(), root.seq);
+        Ok(tree)
+    }
+
+    /// Sync the trees and the trees linked from them, and return all the
+    /// nodes found. Trees that fail to sync are skipped.
+    pub fn sync_all(&mut self, urls: &[TreeUrl]) -> Vec<NodeEntry> {
+        let mut visited = BTreeSet::new();
+        let mut pending: VecDeque<TreeUrl> = urls.iter().cloned().collect();
+        let mut nodes = Vec::new();
+        while let Some(url) = pending.pop_front() {
+            if visited.len() >= MAX_LINKED_TREES {
+                break;
+            }
+            if !visited.insert(url.clone()) {
+                continue;
+            }
+            match self.sync_tree(&url) {
+                Ok(tree) => {
+                    debug!(
+                        "Synced DNS tree {} with seq {}, {} nodes, {} links",
+                        url,
+                        tree.seq,
+                        tree.nodes.len(),
+                        tree.links.len()
+                    );
+                    nodes.extend(tree.nodes);
+                    pending.extend(tree.links);
+                }
+                Err(e) => warn!("Failed to sync DNS tree {}: {}", url, e),
+            }
+        }
+        nodes
+    }
+}
+
+/// Periodically syncs the configured trees in a background thread.
+pub struct DnsDiscovery {
+    discovered: Arc<Mutex<Vec<NodeEntry>>>,
+    /// Dropped to stop the background thread.
+    _stop: mpsc::Sender<()>,
+}
+
+impl DnsDiscovery {
+    pub fn start(
+        urls: Vec<TreeUrl>, resolver: Box<dyn TxtResolver>,
+        refresh_interval: Duration,
+    ) -> Result<Self, Error> {
+        let discovered = Arc::new(Mutex::new(Vec::new()));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let mut client = DnsClient::new(resolver);
+        let pending = discovered.clone();
+        thread::Builder::new().name("dns_discovery".into()).spawn(
+            move || loop {
+                let nodes = client.sync_all(&urls);
+                pending.lock().extend(nodes);
+                match stopped.recv_timeout(refresh_interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            },
+        )?;
+        Ok(DnsDiscovery {
+            discovered,
+            _stop: stop,
+        })
+    }
+
+    /// Take the nodes discovered since the last call.
+    pub fn take_discovered(&self) -> Vec<NodeEntry> {
+        mem::take(&mut *self.discovered.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolver::tests::start_stub_server, *};
+    use crate::{
+        keylib::{Generator, KeyPair, Random},
+        node_table::NodeEndpoint,
+    };
+    use std::{collections::BTreeMap, net::SocketAddr, time::Instant};
+
+    fn random_nodes(count: u16) -> Vec<NodeEntry> {
+        (0..count)
+            .map(|i| NodeEntry {
+                id: *Random.generate().unwrap().public(),
+                endpoint: NodeEndpoint {
+                    address: SocketAddr::from(([10, 0, 0, 1], 30000 + i)),
+                    udp_port: 40000 + i,
+                },
+            })
+            .collect()
+    }
+
+    fn tree_url(key: &KeyPair, domain: &str) -> TreeUrl {
+        TreeUrl {
+            public: *key.public(),
+            domain: domain.into(),
+        }
+    }
+
+    #[test]
+    fn test_sync_linked_trees() {
+        let (key, linked_key) =
+            (Random.generate().unwrap(), Random.generate().unwrap());
+        let url = tree_url(&key, "nodes.example.org");
+        let linked_url = tree_url(&linked_key, "more.example.org");
+
+        let nodes = random_nodes(20);
+        let linked_nodes = random_nodes(3);
+        let mut zone = BTreeMap::new();
+        zone.extend(
+            DnsTree::build(
+                nodes.clone(),
+                vec![linked_url.clone()],
+                1,
+                key.secret(),
+            )
+            .unwrap()
+            .to_txt_records(&url.domain),
+        );
+        zone.extend(
+            DnsTree::build(
+                linked_nodes.clone(),
+                vec![url.clone()],
+                1,
+                linked_key.secret(),
+            )
+            .unwrap()
+            .to_txt_records(&linked_url.domain),
+        );
+        let server = start_stub_server(zone);
+
+        let mut client = DnsClient::new(Box::new(UdpTxtResolver::new(server)));
+        let tree = client.sync_tree(&url).unwrap();
+        assert_eq!(tree.seq, 1);
+        assert_eq!(tree.links, vec![linked_url.clone()]);
+        let ids: BTreeSet<_> = tree.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, nodes.iter().map(|n| n.id).collect());
+
+        // The link back to the first tree is not followed again.
+        let all = client.sync_all(&[url.clone()]);
+        assert_eq!(all.len(), nodes.len() + linked_nodes.len());
+
+        // A root signed by another key is rejected.
+        let wrong_url = tree_url(&linked_key, &url.domain);
+        assert!(client.sync_tree(&wrong_url).is_err());
+
+        let discovery = DnsDiscovery::start(
+            vec![url],
+            Box::new(UdpTxtResolver::new(server)),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let start = Instant::now();
+        let mut discovered = Vec::new();
+        while discovered.len() < all.len()
+            && start.elapsed() < Duration::from_secs(10)
+        {
+            thread::sleep(Duration::from_millis(50));
+            discovered.extend(discovery.take_discovered());
+        }
+        assert_eq!(discovered.len(), all.len());
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A minimal DNS client which only resolves TXT records over UDP.
+
+use crate::Error;
+use rand::Rng;
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+const TYPE_TXT: u16 = 16;
+const CLASS_IN: u16 = 1;
+const MAX_PACKET_SIZE: usize = 4096;
+const QUERY_ATTEMPTS: usize = 3;
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+
+pub trait TxtResolver: Send {
+    /// Resolve the TXT records of `name`. Returns an empty list if the name
+    /// does not exist.
+    fn resolve_txt(&self, name: &str) -> Result<Vec<String>, Error>;
+}
+
+/// Resolves TXT records with a recursive name server.
+pub struct UdpTxtResolver {
+    server: SocketAddr,
+    timeout: Duration,
+}
+
+impl UdpTxtResolver {
+    pub fn new(server: SocketAddr) -> Self {
+        UdpTxtResolver {
+            server,
+            timeout: DEFAULT_QUERY_TIMEOUT,
+        }
+    }
+
+    /// Use the first name server configured in the system.
+    pub fn from_system_config() -> Result<Self, Error> {
+        let conf = fs::read_to_string(RESOLV_CONF_PATH)?;
+        conf.lines()
+            .filter_map(|line| line.trim().strip_prefix("nameserver"))
+            .find_map(|ip| ip.trim().parse::<IpAddr>().ok())
+            .map(|ip| Self::new(SocketAddr::new(ip, 53)))
+            .ok_or_else(|| {
+                Error::DnsDiscovery(format!(
+                    "no name server found in {}",
+                    RESOLV_CONF_PATH
+                ))
+            })
+    }
+
+    /// Sends one query and waits for its response until the timeout, which
+    /// bounds the whole attempt, however many unrelated packets arrive.
+    fn query(
+        &self, socket: &UdpSocket, name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let id = rand::thread_rng().gen();
+        socket.send_to(&build_query(id, name)?, self.server)?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::from(io::ErrorKind::TimedOut).into());
+            }
+            socket.set_read_timeout(Some(remaining))?;
+            let (len, from) = socket.recv_from(&mut buf)?;
+            // Ignore stale responses of the previous attempts.
+            if from == self.server && buf[..len].starts_with(&id.to_be_bytes())
+            {
+                return parse_response(&buf[..len]);
+            }
+        }
+    }
+}
+
+impl TxtResolver for UdpTxtResolver {
+    fn resolve_txt(&self, name: &str) -> Result<Vec<String>, Error> {
+        let local: IpAddr = match self.server {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(local, 0))?;
+
+        let mut result = Err(Error::DnsDiscovery("no query sent".into()));
+        for _ in 0..QUERY_ATTEMPTS {
+            result = self.query(&socket, name);
+            match &result {
+                Err(Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue
+                }
+                _ => break,
+            }
+        }
+        result
+    }
+}
+
+fn build_query(id: u16, name: &str) -> Result<Vec<u8>, Error> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Standard query with recursion desired.
+    packet.extend_from_slice(&[0x01, 0x00]);
+    // One question, no answer, authority or additional records.
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(Error::DnsDiscovery(format!("invalid name {}", name)));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_TXT.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+struct PacketReader<'a> {
+    packet: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .packet
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| Error::DnsDiscovery("truncated response".into()))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> { Ok(self.bytes(1)?[0]) }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn skip_name(&mut self) -> Result<(), Error> {
+        loop {
+            let len = self.u8()?;
+            if len == 0 {
+                return Ok(());
+            }
+            // A compression pointer ends the name.
+            if len & 0xc0 == 0xc0 {
+                self.u8()?;
+                return Ok(());
+            }
+            self.bytes(len as usize)?;
+        }
+    }
+}
+
+fn parse_response(packet: &[u8]) -> Result<Vec<String>, Error> {
+    let mut reader = PacketReader { packet, pos: 0 };
+    let _id = reader.u16()?;
+    let flags = reader.u16()?;
+    if flags & 0x8000 == 0 {
+        return Err(Error::DnsDiscovery("not a response".into()));
+    }
+    if flags & 0x0200 != 0 {
+        return Err(Error::DnsDiscovery("truncated response".into()));
+    }
+    match flags & 0x000f {
+        0 => {}
+        // The name does not exist.
+        3 => return Ok(Vec::new()),
+        rcode => {
+            return Err(Error::DnsDiscovery(format!(
+                "query failed with rcode {}",
+                rcode
+            )))
+        }
+    }
+
+    let question_count = reader.u16()?;
+    let answer_count = reader.u16()?;
+    reader.bytes(4)?;
+    for _ in 0..question_count {
+        reader.skip_name()?;
+        reader.bytes(4)?;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answer_count {
+        reader.skip_name()?;
+        let record_type = reader.u16()?;
+        // Class and TTL.
+        reader.bytes(6)?;
+        let len = reader.u16()? as usize;
+        let data = reader.bytes(len)?;
+        // E.g. CNAME records before the TXT records.
+        if record_type != TYPE_TXT {
+            continue;
+        }
+
+        // A TXT record consists of strings of at most 255 bytes.
+        let mut data = PacketReader {
+            packet: data,
+            pos: 0,
+        };
+        let mut text = Vec::new();
+        while data.pos < len {
+            let len = data.u8()? as usize;
+            text.extend_from_slice(data.bytes(len)?);
+        }
+        records.push(String::from_utf8(text).map_err(|_| {
+            Error::DnsDiscovery("TXT record is not UTF-8".into())
+        })?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{collections::BTreeMap, thread};
+
+    /// Build the response of a TXT query with the records in `zone`.
+    fn build_response(
+        query: &[u8], zone: &BTreeMap<String, String>,
+    ) -> Option<Vec<u8>> {
+        let mut reader = PacketReader {
+            packet: query,
+            pos: 12,
+        };
+        let mut labels = Vec::new();
+        loop {
+            let len = reader.u8().ok()? as usize;
+            if len == 0 {
+                break;
+            }
+            labels.push(
+                String::from_utf8(reader.bytes(len).ok()?.to_vec()).ok()?,
+            );
+        }
+        let question_end = reader.pos + 4;
+        let name = labels.join(".");
+
+        let mut response = query.get(..question_end)?.to_vec();
+        // Response with recursion available.
+        response[2] = 0x81;
+        response[3] = 0x80;
+        match zone.get(&name) {
+            Some(text) => {
+                // One answer.
+                response[7] = 1;
+                // Pointer to the name in the question.
+                response.extend_from_slice(&[0xc0, 12]);
+                response.extend_from_slice(&TYPE_TXT.to_be_bytes());
+                response.extend_from_slice(&CLASS_IN.to_be_bytes());
+                response.extend_from_slice(&60u32.to_be_bytes());
+                let strings: Vec<&[u8]> = text.as_bytes().chunks(255).collect();
+                let len: usize = strings.iter().map(|s| s.len() + 1).sum();
+                response.extend_from_slice(&(len as u16).to_be_bytes());
+                for s in strings {
+                    response.push(s.len() as u8);
+                    response.extend_from_slice(s);
+                }
+            }
+            // NXDOMAIN
+            None => response[3] |= 3,
+        }
+        Some(response)
+    }
+
+    /// Start a local DNS server which answers TXT queries with the records in
+    /// `zone`, and stops after being idle for a while.
+    pub fn start_stub_server(zone: BTreeMap<String, String>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; MAX_PACKET_SIZE];
+            while let Ok((len, from)) = socket.recv_from(&mut buf) {
+                if let Some(response) = build_response(&buf[..len], &zone) {
+                    socket.send_to(&response, from).unwrap();
+                }
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_resolve_txt() {
+        let long_text = "x".repeat(300);
+        let mut zone = BTreeMap::new();
+        zone.insert("a.example.org".to_string(), "hello".to_string());
+        zone.insert("b.example.org".to_string(), long_text.clone());
+        let resolver = UdpTxtResolver::new(start_stub_server(zone));
+
+        assert_eq!(
+            resolver.resolve_txt("a.example.org").unwrap(),
+            vec!["hello".to_string()]
+        );
+        // Strings of a record are concatenated.
+        assert_eq!(
+            resolver.resolve_txt("b.example.org.").unwrap(),
+            vec![long_text]
+        );
+        assert!(resolver.resolve_txt("c.example.org").unwrap().is_empty());
+        assert!(resolver.resolve_txt("a..example.org").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Signed node lists published in DNS TXT records, in the style of EIP-1459.
+//!
+//! A tree is published under a domain. The root record at the domain itself
+//! points to two subtrees by hash, one with the nodes and one with links to
+//! other trees. Every other record is published at `<hash>.<domain>`, where
+//! the hash is the hex encoded first 16 bytes of the keccak hash of the
+//! record, so that the records can be verified and cached on their own. Only
+//! the root record is signed, with the key in the tree URL
+//! `cfxtree://<public key>@<domain>`.
+
+use crate::{
+    keylib::{sign, verify_public, Public, Secret, Signature},
+    node_table::{Node, NodeEntry},
+    Error,
+};
+use keccak_hash::keccak;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+const ROOT_PREFIX: &str = "cfxtree-root:v1";
+const BRANCH_PREFIX: &str = "cfxtree-branch:";
+const LINK_PREFIX: &str = "cfxtree://";
+const NODE_PREFIX: &str = "cfxnode://";
+
+/// Maximum number of children of a branch, so that a branch record fits into
+/// a single TXT string of 255 bytes.
+const MAX_BRANCH_CHILDREN: usize = 7;
+
+/// The hash of a record, which is also the subdomain it is published at.
+pub fn entry_hash(text: &str) -> String {
+    keccak(text.as_bytes())[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// URL of a tree, i.e. `cfxtree://<public key>@<domain>`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TreeUrl {
+    pub public: Public,
+    pub domain: String,
+}
+
+impl FromStr for TreeUrl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::DnsDiscovery(format!("invalid tree url {}", s));
+        let (public, domain) = s
+            .strip_prefix(LINK_PREFIX)
+            .and_then(|s| s.split_once('@'))
+            .ok_or_else(invalid)?;
+        if domain.is_empty() {
+            return Err(invalid());
+        }
+        Ok(TreeUrl {
+            public: public.parse().map_err(|_| invalid())?,
+            domain: domain.trim_end_matches('.').to_lowercase(),
+        })
+    }
+}
+
+impl fmt::Display for TreeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{:x}@{}", LINK_PREFIX, self.public, self.domain)
+    }
+}
+
+/// The signed root record of a tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RootEntry {
+    /// Hash of the root of the node subtree.
+    pub node_root: String,
+    /// Hash of the root of the link subtree.
+    pub link_root: String,
+    /// Sequence number which is increased whenever the tree is updated.
+    pub seq: u64,
+    pub signature: Signature,
+}
+
+impl RootEntry {
+    fn sign(
+        node_root: String, link_root: String, seq: u64, secret: &Secret,
+    ) -> Result<Self, Error> {
+        let mut root = RootEntry {
+            node_root,
+            link_root,
+            seq,
+            signature: Signature::default(),
+        };
+        root.signature = sign(secret, &keccak(root.signed_text()))?;
+        Ok(root)
+    }
+
+    fn signed_text(&self) -> String {
+        format!(
+            "{} e={} l={} seq={}",
+            ROOT_PREFIX, self.node_root, self.link_root, self.seq
+        )
+    }
+
+    pub fn verify(&self, public: &Public) -> bool {
+        verify_public(public, &self.signature, &keccak(self.signed_text()))
+            .unwrap_or(false)
+    }
+}
+
+/// A record of a tree.
+#[derive(Clone, Debug)]
+pub enum Entry {
+    Root(RootEntry),
+    /// Hashes of the children.
+    Branch(Vec<String>),
+    Node(NodeEntry),
+    Link(TreeUrl),
+}
+
+impl FromStr for Entry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::DnsDiscovery(format!("invalid record {}", s));
+
+        if let Some(fields) = s.strip_prefix(ROOT_PREFIX) {
+            let mut values = BTreeMap::new();
+            for field in fields.split_whitespace() {
+                let (key, value) = field.split_once('=').ok_or_else(invalid)?;
+                values.insert(key, value);
+            }
+            let mut value = |key: &str| values.remove(key).ok_or_else(invalid);
+            Ok(Entry::Root(RootEntry {
+                node_root: value("e")?.to_string(),
+                link_root: value("l")?.to_string(),
+                seq: value("seq")?.parse().map_err(|_| invalid())?,
+                signature: value("sig")?.parse().map_err(|_| invalid())?,
+            }))
+        } else if let Some(children) = s.strip_prefix(BRANCH_PREFIX) {
+            Ok(Entry::Branch(
+                children
+                    .split(',')
+                    .filter(|child| !child.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ))
+        } else if s.starts_with(NODE_PREFIX) {
+            let node = Node::from_str(s).map_err(|_| invalid())?;
+            Ok(Entry::Node(NodeEntry {
+                id: node.id,
+                endpoint: node.endpoint,
+            }))
+        } else if s.starts_with(LINK_PREFIX) {
+            Ok(Entry::Link(s.parse()?))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Root(root) => {
+                write!(f, "{} sig={}", root.signed_text(), root.signature)
+            }
+            Entry::Branch(children) => {
+                write!(f, "{}{}", BRANCH_PREFIX, children.join(","))
+            }
+            Entry::Node(node) => {
+                write!(f, "{}", Node::new(node.id, node.endpoint.clone()))
+            }
+            Entry::Link(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A signed tree built from a list of nodes and links to other trees.
+pub struct DnsTree {
+    root: RootEntry,
+    /// All records except the root, by hash.
+    entries: BTreeMap<String, String>,
+}
+
+impl DnsTree {
+    pub fn build(
+        nodes: Vec<NodeEntry>, links: Vec<TreeUrl>, seq: u64, secret: &Secret,
+    ) -> Result<Self, Error> {
+        let mut entries = BTreeMap::new();
+        let node_root = Self::build_subtree(
+            nodes.into_iter().map(Entry::Node).collect(),
+            &mut entries,
+        );
+        let link_root = Self::build_subtree(
+            links.into_iter().map(Entry::Link).collect(),
+            &mut entries,
+        );
+        Ok(DnsTree {
+            root: RootEntry::sign(node_root, link_root, seq, secret)?,
+            entries,
+        })
+    }
+
+    /// Add the records of a subtree and return the hash of its root.
+    fn build_subtree(
+        leaves: Vec<Entry>, entries: &mut BTreeMap<String, String>,
+    ) -> String {
+        let mut insert = |entry: Entry| {
+            let text = entry.to_string();
+            let hash = entry_hash(&text);
+            entries.insert(hash.clone(), text);
+            hash
+        };
+
+        let mut hashes: Vec<String> =
+            leaves.into_iter().map(&mut insert).collect();
+        if hashes.len() == 1 {
+            return hashes.pop().expect("not empty");
+        }
+        while hashes.len() > MAX_BRANCH_CHILDREN {
+            hashes = hashes
+                .chunks(MAX_BRANCH_CHILDREN)
+                .map(|children| insert(Entry::Branch(children.to_vec())))
+                .collect();
+        }
+        insert(Entry::Branch(hashes))
+    }
+
+    pub fn root(&self) -> &RootEntry { &self.root }
+
+    /// The TXT records to publish for the tree at `domain`, by the full
+    /// names.
+    pub fn to_txt_records(&self, domain: &str) -> BTreeMap<String, String> {
+        let mut records: BTreeMap<String, String> = self
+            .entries
+            .iter()
+            .map(|(hash, text)| (format!("{}.{}", hash, domain), text.clone()))
+            .collect();
+        records.insert(
+            domain.to_string(),
+            Entry::Root(self.root.clone()).to_string(),
+        );
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keylib::{Generator, Random};
+
+    #[test]
+    fn test_root_signature() {
+        let key = Random.generate().unwrap();
+        let tree = DnsTree::build(vec![], vec![], 3, key.secret()).unwrap();
+        let records = tree.to_txt_records("nodes.example.org");
+
+        let root = match records["nodes.example.org"].parse().unwrap() {
+            Entry::Root(root) => root,
+            entry => panic!("unexpected entry {:?}", entry),
+        };
+        assert_eq!(&root, tree.root());
+        assert!(root.verify(key.public()));
+
+        let other = Random.generate().unwrap();
+        assert!(!root.verify(other.public()));
+        let tampered = RootEntry { seq: 4, ..root };
+        assert!(!tampered.verify(key.public()));
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let key = Random.generate().unwrap();
+        let url = TreeUrl {
+            public: *key.public(),
+            domain: "nodes.example.org".into(),
+        };
+        assert_eq!(url.to_string().parse::<TreeUrl>().unwrap(), url);
+
+        for text in [
+            "cfxtree-branch:0123,4567".to_string(),
+            url.to_string(),
+            format!("cfxnode://{:x}@127.0.0.1:32323+32324", key.public()),
+        ] {
+            let entry: Entry = text.parse().unwrap();
+            assert_eq!(entry.to_string(), text);
+        }
+        assert!("cfxtree-unknown:0123".parse::<Entry>().is_err());
+    }
+}
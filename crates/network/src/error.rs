@@ -166,6 +166,9 @@ pub enum Error {
         min_supported_version: Option<ProtocolVersion>,
     },
 
+    #[error("DNS discovery failure: {0}")]
+    DnsDiscovery(String),
+
     #[error("throttling failure: {0}")]
     Throttling(ThrottlingReason),
 
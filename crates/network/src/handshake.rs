@@ -67,6 +67,8 @@ pub struct Handshake {
     pub state: HandshakeState,
     /// nonce for verification
     nonce: H256,
+    /// nonce of the remote node
+    remote_nonce: Option<H256>,
 }
 
 impl Handshake {
@@ -79,6 +81,7 @@ impl Handshake {
             connection: Connection::new(token, socket),
             state: HandshakeState::New,
             nonce: H256::random(),
+            remote_nonce: None,
         }
     }
 
@@ -101,6 +104,14 @@ impl Handshake {
     /// Check if handshake is complete
     pub fn done(&self) -> bool { self.state == HandshakeState::StartSession }
 
+    /// Returns the local and remote nonces exchanged in the handshake, which
+    /// are only known to the both sides. Returns `None` if the cryptography is
+    /// bypassed.
+    pub fn nonces(&self) -> Option<(H256, H256)> {
+        self.remote_nonce
+            .map(|remote_nonce| (self.nonce, remote_nonce))
+    }
+
     /// Readable IO handler. Drives the state change.
     pub fn readable<Message>(
         &mut self, io: &IoContext<Message>, host: &HostMetadata,
@@ -192,6 +203,7 @@ impl Handshake {
 
         let (remote_public, remote_nonce) = auth.split_at(NodeId::len_bytes());
         self.id.assign_from_slice(remote_public);
+        self.remote_nonce = Some(H256::from_slice(remote_nonce));
 
         self.write_ack_of_auth(io, remote_nonce)
     }
@@ -267,6 +279,7 @@ impl Handshake {
             debug!("failed to read ack of auth, nonce mismatch");
             return Err(Error::BadProtocol.into());
         }
+        self.remote_nonce = Some(H256::from_slice(remote_nonce));
 
         self.write_ack_of_ack(io, remote_nonce)
     }
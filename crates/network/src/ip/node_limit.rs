@@ -1,5 +1,9 @@
 use crate::{
-    ip::{bucket::NodeBucket, sample::SampleHashMap, util::SubnetType},
+    ip::{
+        bucket::NodeBucket,
+        sample::SampleHashMap,
+        util::{Subnet, SubnetType},
+    },
     node_database::NodeDatabase,
     node_table::NodeId,
 };
@@ -49,9 +53,9 @@ pub struct NodeIpLimit {
     evict_timeout: Duration, // used to evict out-of-date node
 
     // all trusted nodes grouped by subnet
-    trusted_buckets: SampleHashMap<u32, NodeBucket>,
+    trusted_buckets: SampleHashMap<Subnet, NodeBucket>,
     // all untrusted nodes grouped by subnet
-    untrusted_buckets: SampleHashMap<u32, NodeBucket>,
+    untrusted_buckets: SampleHashMap<Subnet, NodeBucket>,
 
     // helpful indices
     ip_index: HashMap<IpAddr, NodeId>,
@@ -75,7 +79,7 @@ impl NodeIpLimit {
     pub fn is_enabled(&self) -> bool { self.subnet_quota > 0 }
 
     /// Get the subnet of specified node `id`.
-    pub fn subnet(&self, id: &NodeId) -> Option<u32> {
+    pub fn subnet(&self, id: &NodeId) -> Option<Subnet> {
         let ip = self.node_index.get(id)?;
         Some(self.subnet_type.subnet(ip))
     }
@@ -104,7 +108,8 @@ impl NodeIpLimit {
 
     /// Remove node from specified buckets.
     fn remove_with_buckets(
-        buckets: &mut SampleHashMap<u32, NodeBucket>, subnet: u32, id: &NodeId,
+        buckets: &mut SampleHashMap<Subnet, NodeBucket>, subnet: Subnet,
+        id: &NodeId,
     ) -> bool {
         let bucket = match buckets.get_mut(&subnet) {
             Some(bucket) => bucket,
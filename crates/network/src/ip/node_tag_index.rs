@@ -5,7 +5,7 @@
 use crate::{
     ip::{
         sample::{SampleHashMap, SampleHashSet},
-        util::SubnetType,
+        util::{Subnet, SubnetType},
     },
     node_table::{NodeId, NodeTable},
     Node,
@@ -20,7 +20,7 @@ pub struct NodeTagIndex {
     // map<tag_key, map<tag_value, map<subnet, set<node_id>>>>
     items: HashMap<
         String,
-        HashMap<String, SampleHashMap<u32, SampleHashSet<NodeId>>>,
+        HashMap<String, SampleHashMap<Subnet, SampleHashSet<NodeId>>>,
     >,
 }
 
@@ -42,7 +42,7 @@ impl NodeTagIndex {
     }
 
     pub fn insert(
-        &mut self, id: NodeId, subnet: u32, key: String, value: String,
+        &mut self, id: NodeId, subnet: Subnet, key: String, value: String,
     ) -> bool {
         self.items
             .entry(key)
@@ -54,7 +54,7 @@ impl NodeTagIndex {
     }
 
     pub fn remove(
-        &mut self, id: &NodeId, subnet: u32, key: &String, value: &String,
+        &mut self, id: &NodeId, subnet: Subnet, key: &String, value: &String,
     ) -> Option<()> {
         let tag_key_values = self.items.get_mut(key)?;
         let buckets = tag_key_values.get_mut(value)?;
@@ -127,7 +127,10 @@ impl NodeTagIndex {
 
 #[cfg(test)]
 mod tests {
-    use crate::{ip::NodeTagIndex, node_table::NodeId};
+    use crate::{
+        ip::{util::Subnet, NodeTagIndex},
+        node_table::NodeId,
+    };
 
     #[test]
     fn test_insert() {
@@ -135,27 +138,32 @@ mod tests {
 
         let n1 = NodeId::random();
         assert_eq!(
-            index.insert(n1.clone(), 38, "k1".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k1".into(), "v1".into()),
             true
         );
         assert_eq!(
-            index.insert(n1.clone(), 38, "k1".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k1".into(), "v1".into()),
             false
         );
         assert_eq!(
-            index.insert(n1.clone(), 38, "k1".into(), "v2".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k1".into(), "v2".into()),
             true
         );
         assert_eq!(
-            index.insert(n1.clone(), 38, "k2".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k2".into(), "v1".into()),
             true
         );
         assert_eq!(
-            index.insert(n1.clone(), 39, "k1".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(39), "k1".into(), "v1".into()),
             true
         );
         assert_eq!(
-            index.insert(NodeId::random(), 38, "k1".into(), "v1".into()),
+            index.insert(
+                NodeId::random(),
+                Subnet::V4(38),
+                "k1".into(),
+                "v1".into()
+            ),
             true
         );
     }
@@ -166,16 +174,31 @@ mod tests {
 
         let n1 = NodeId::random();
         assert_eq!(
-            index.insert(n1.clone(), 38, "k1".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k1".into(), "v1".into()),
             true
         );
 
         let n2 = NodeId::random();
-        assert_eq!(index.remove(&n2, 38, &"k1".into(), &"v1".into()), None);
-        assert_eq!(index.remove(&n1, 39, &"k1".into(), &"v1".into()), None);
-        assert_eq!(index.remove(&n1, 38, &"k2".into(), &"v1".into()), None);
-        assert_eq!(index.remove(&n1, 38, &"k1".into(), &"v2".into()), None);
-        assert_eq!(index.remove(&n1, 38, &"k1".into(), &"v1".into()), Some(()));
+        assert_eq!(
+            index.remove(&n2, Subnet::V4(38), &"k1".into(), &"v1".into()),
+            None
+        );
+        assert_eq!(
+            index.remove(&n1, Subnet::V4(39), &"k1".into(), &"v1".into()),
+            None
+        );
+        assert_eq!(
+            index.remove(&n1, Subnet::V4(38), &"k2".into(), &"v1".into()),
+            None
+        );
+        assert_eq!(
+            index.remove(&n1, Subnet::V4(38), &"k1".into(), &"v2".into()),
+            None
+        );
+        assert_eq!(
+            index.remove(&n1, Subnet::V4(38), &"k1".into(), &"v1".into()),
+            Some(())
+        );
     }
 
     #[test]
@@ -188,7 +211,7 @@ mod tests {
         // add index and sampled 1 node.
         let n1 = NodeId::random();
         assert_eq!(
-            index.insert(n1.clone(), 38, "k1".into(), "v1".into()),
+            index.insert(n1.clone(), Subnet::V4(38), "k1".into(), "v1".into()),
             true
         );
         let sampled = index.sample(1, &"k1".into(), &"v1".into());
@@ -1,11 +1,11 @@
-use crate::ip::util::SubnetType;
+use crate::ip::util::{host_subnet, Subnet, SubnetType};
 use std::{
     collections::HashMap, convert::TryFrom, hash::Hash, net::IpAddr,
     str::FromStr,
 };
 
 /// SessionIpLimit is used to limits the number of sessions for a single IP
-/// address or subnet.
+/// address or subnet. Note, a single IPv6 address means its /64 prefix here.
 pub trait SessionIpLimit: Send + Sync {
     fn contains(&self, _ip: &IpAddr) -> bool { true }
     fn is_allowed(&self, _ip: &IpAddr) -> bool { true }
@@ -19,6 +19,9 @@ pub struct SessionIpLimitConfig {
     subnet_a_quota: usize,
     subnet_b_quota: usize,
     subnet_c_quota: usize,
+    ipv6_subnet_a_quota: usize,
+    ipv6_subnet_b_quota: usize,
+    ipv6_subnet_c_quota: usize,
 }
 
 impl TryFrom<String> for SessionIpLimitConfig {
@@ -34,11 +37,16 @@ impl TryFrom<String> for SessionIpLimitConfig {
             nums.push(num);
         }
 
-        if nums.len() != 4 {
-            return Err(format!(
-                "invalid number of fields, expected = 4, actual = {}",
-                nums.len()
-            ));
+        // The IPv6 subnet quotas are the same as IPv4 ones if not specified.
+        match nums.len() {
+            4 => nums.extend_from_slice(&[nums[1], nums[2], nums[3]]),
+            7 => {}
+            n => {
+                return Err(format!(
+                    "invalid number of fields, expected = 4 or 7, actual = {}",
+                    n
+                ))
+            }
         }
 
         Ok(SessionIpLimitConfig {
@@ -46,13 +54,17 @@ impl TryFrom<String> for SessionIpLimitConfig {
             subnet_a_quota: nums[1],
             subnet_b_quota: nums[2],
             subnet_c_quota: nums[3],
+            ipv6_subnet_a_quota: nums[4],
+            ipv6_subnet_b_quota: nums[5],
+            ipv6_subnet_c_quota: nums[6],
         })
     }
 }
 
 /// Creates a SessionIpLimit instance with specified IP quotas. The
 /// `subnet_quotas` represents subnet-a (ip/8), subnet-b (ip/16) and subnet-c
-/// (ip/24) respectively.
+/// (ip/24) respectively, and the `ipv6_subnet_quotas` represents subnet-a
+/// (ip/24), subnet-b (ip/32) and subnet-c (ip/48) of IPv6 addresses.
 pub fn new_session_ip_limit(
     config: &SessionIpLimitConfig,
 ) -> Box<dyn SessionIpLimit> {
@@ -62,25 +74,31 @@ pub fn new_session_ip_limit(
         limits.push(Box::new(SingleIpLimit::new(config.single_ip_quota)));
     }
 
-    if config.subnet_a_quota > 0 {
-        limits.push(Box::new(SubnetLimit::new(
+    let subnet_quotas = [
+        (
             config.subnet_a_quota,
+            config.ipv6_subnet_a_quota,
             SubnetType::A,
-        )));
-    }
-
-    if config.subnet_b_quota > 0 {
-        limits.push(Box::new(SubnetLimit::new(
+        ),
+        (
             config.subnet_b_quota,
+            config.ipv6_subnet_b_quota,
             SubnetType::B,
-        )));
-    }
-
-    if config.subnet_c_quota > 0 {
-        limits.push(Box::new(SubnetLimit::new(
+        ),
+        (
             config.subnet_c_quota,
+            config.ipv6_subnet_c_quota,
             SubnetType::C,
-        )));
+        ),
+    ];
+    for (ipv4_quota, ipv6_quota, subnet_type) in subnet_quotas {
+        if ipv4_quota > 0 || ipv6_quota > 0 {
+            limits.push(Box::new(SubnetLimit::new(
+                ipv4_quota,
+                ipv6_quota,
+                subnet_type,
+            )));
+        }
     }
 
     if limits.is_empty() {
@@ -151,7 +169,7 @@ impl<T: Hash + Eq> GenericLimit<T> {
 }
 
 struct SingleIpLimit {
-    inner: GenericLimit<IpAddr>,
+    inner: GenericLimit<Subnet>,
 }
 
 impl SingleIpLimit {
@@ -163,48 +181,84 @@ impl SingleIpLimit {
 }
 
 impl SessionIpLimit for SingleIpLimit {
-    fn contains(&self, ip: &IpAddr) -> bool { self.inner.contains(ip) }
+    fn contains(&self, ip: &IpAddr) -> bool {
+        self.inner.contains(&host_subnet(ip))
+    }
 
-    fn is_allowed(&self, ip: &IpAddr) -> bool { self.inner.is_allowed(ip) }
+    fn is_allowed(&self, ip: &IpAddr) -> bool {
+        self.inner.is_allowed(&host_subnet(ip))
+    }
 
-    fn add(&mut self, ip: IpAddr) -> bool { self.inner.add(ip) }
+    fn add(&mut self, ip: IpAddr) -> bool { self.inner.add(host_subnet(&ip)) }
 
-    fn remove(&mut self, ip: &IpAddr) -> bool { self.inner.remove(ip) }
+    fn remove(&mut self, ip: &IpAddr) -> bool {
+        self.inner.remove(&host_subnet(ip))
+    }
 }
 
+/// Limits the sessions of subnets with separate quotas for IPv4 and IPv6.
+/// Addresses of the family without quota are not limited.
 struct SubnetLimit {
-    inner: GenericLimit<u32>,
+    ipv4: Option<GenericLimit<Subnet>>,
+    ipv6: Option<GenericLimit<Subnet>>,
     subnet_type: SubnetType,
 }
 
 impl SubnetLimit {
-    fn new(quota: usize, subnet_type: SubnetType) -> Self {
+    fn new(
+        ipv4_quota: usize, ipv6_quota: usize, subnet_type: SubnetType,
+    ) -> Self {
         SubnetLimit {
-            inner: GenericLimit::new(quota),
+            ipv4: Some(ipv4_quota)
+                .filter(|quota| *quota > 0)
+                .map(GenericLimit::new),
+            ipv6: Some(ipv6_quota)
+                .filter(|quota| *quota > 0)
+                .map(GenericLimit::new),
             subnet_type,
         }
     }
+
+    fn limit_mut(
+        &mut self, subnet: &Subnet,
+    ) -> Option<&mut GenericLimit<Subnet>> {
+        match subnet {
+            Subnet::V4(_) => self.ipv4.as_mut(),
+            Subnet::V6(_) => self.ipv6.as_mut(),
+        }
+    }
+
+    fn limit(&self, subnet: &Subnet) -> Option<&GenericLimit<Subnet>> {
+        match subnet {
+            Subnet::V4(_) => self.ipv4.as_ref(),
+            Subnet::V6(_) => self.ipv6.as_ref(),
+        }
+    }
 }
 
 impl SessionIpLimit for SubnetLimit {
     fn contains(&self, ip: &IpAddr) -> bool {
         let subnet = self.subnet_type.subnet(ip);
-        self.inner.contains(&subnet)
+        self.limit(&subnet)
+            .map_or(true, |limit| limit.contains(&subnet))
     }
 
     fn is_allowed(&self, ip: &IpAddr) -> bool {
         let subnet = self.subnet_type.subnet(ip);
-        self.inner.is_allowed(&subnet)
+        self.limit(&subnet)
+            .map_or(true, |limit| limit.is_allowed(&subnet))
     }
 
     fn add(&mut self, ip: IpAddr) -> bool {
         let subnet = self.subnet_type.subnet(&ip);
-        self.inner.add(subnet)
+        self.limit_mut(&subnet)
+            .map_or(true, |limit| limit.add(subnet))
     }
 
     fn remove(&mut self, ip: &IpAddr) -> bool {
         let subnet = self.subnet_type.subnet(ip);
-        self.inner.remove(&subnet)
+        self.limit_mut(&subnet)
+            .map_or(true, |limit| limit.remove(&subnet))
     }
 }
 
@@ -318,4 +372,35 @@ mod tests {
         assert_eq!(limit.add(new_ip("127.2.0.1")), true);
         assert_eq!(limit.add(new_ip("127.3.0.1")), true);
     }
+
+    #[test]
+    fn test_ipv6() {
+        let mut limit = new_limit("1,0,0,2");
+
+        // A single IPv6 host is limited by its /64 prefix.
+        assert_eq!(limit.add(new_ip("2001:db8::1")), true);
+        assert_eq!(limit.add(new_ip("2001:db8::2")), false);
+
+        // subnet c of IPv6 is /48
+        assert_eq!(limit.add(new_ip("2001:db8:0:1::1")), true);
+        assert_eq!(limit.add(new_ip("2001:db8:0:2::1")), false);
+        assert_eq!(limit.add(new_ip("2001:db8:1::1")), true);
+
+        // IPv4-mapped addresses are limited as IPv4 addresses.
+        assert_eq!(limit.add(new_ip("127.0.0.1")), true);
+        assert_eq!(limit.add(new_ip("::ffff:127.0.0.1")), false);
+    }
+
+    #[test]
+    fn test_separate_ipv6_quotas() {
+        let mut limit = new_limit("0,0,0,1,0,0,0");
+
+        assert_eq!(limit.add(new_ip("127.0.0.1")), true);
+        assert_eq!(limit.add(new_ip("127.0.0.2")), false);
+
+        // IPv6 addresses are not limited.
+        assert_eq!(limit.add(new_ip("2001:db8::1")), true);
+        assert_eq!(limit.add(new_ip("2001:db8::2")), true);
+        assert_eq!(limit.remove(&new_ip("2001:db8::2")), true);
+    }
 }
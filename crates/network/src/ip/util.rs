@@ -1,31 +1,57 @@
+use crate::ip_utils::canonical_ip;
 use std::{convert::TryFrom, net::IpAddr};
 
+/// Subnet of an IP address, so that IPv4 and IPv6 subnets never collide.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Subnet {
+    V4(u32),
+    V6(u64),
+}
+
+/// IPv6 addresses are allocated in much larger blocks than IPv4 ones, e.g. a
+/// site usually gets a /48 and an ISP a /32, so they are grouped with longer
+/// prefixes.
 #[derive(Debug)]
 pub enum SubnetType {
-    A, // a.xxx.xxx.xxx/8
-    B, // a.b.xxx.xxx/16
-    C, // a.b.c.xxx/24
+    A, // a.xxx.xxx.xxx/8, or a:bb::/24 for IPv6
+    B, // a.b.xxx.xxx/16, or a:b::/32 for IPv6
+    C, // a.b.c.xxx/24, or a:b:c::/48 for IPv6
 }
 
+/// Prefix bits of IPv6 addresses that usually belong to a single host.
+const IPV6_HOST_PREFIX_BITS: u32 = 64;
+
 impl SubnetType {
-    pub fn subnet(&self, ip: &IpAddr) -> u32 {
-        match *self {
-            SubnetType::A => SubnetType::calc_subnet(ip, 8),
-            SubnetType::B => SubnetType::calc_subnet(ip, 16),
-            SubnetType::C => SubnetType::calc_subnet(ip, 24),
-        }
+    pub fn subnet(&self, ip: &IpAddr) -> Subnet {
+        let (ipv4_prefix_bits, ipv6_prefix_bits) = match *self {
+            SubnetType::A => (8, 24),
+            SubnetType::B => (16, 32),
+            SubnetType::C => (24, 48),
+        };
+        calc_subnet(ip, ipv4_prefix_bits, ipv6_prefix_bits)
     }
+}
 
-    fn calc_subnet(ip: &IpAddr, prefix_bits: usize) -> u32 {
-        match ip {
-            IpAddr::V4(ipv4) => {
-                let num: u32 = ipv4.clone().into();
-                num >> (32 - prefix_bits)
-            }
-            IpAddr::V6(ipv6) => {
-                let num: u128 = ipv6.clone().into();
-                (num >> (128 - prefix_bits)) as u32
-            }
+/// The host of an IP address, i.e. the address itself for IPv4 and the /64
+/// prefix for IPv6, since a single IPv6 host can easily use many addresses of
+/// its /64.
+pub fn host_subnet(ip: &IpAddr) -> Subnet {
+    calc_subnet(ip, 32, IPV6_HOST_PREFIX_BITS)
+}
+
+fn calc_subnet(
+    ip: &IpAddr, ipv4_prefix_bits: u32, ipv6_prefix_bits: u32,
+) -> Subnet {
+    match canonical_ip(ip) {
+        IpAddr::V4(ipv4) => {
+            let num: u32 = ipv4.into();
+            Subnet::V4(num.checked_shr(32 - ipv4_prefix_bits).unwrap_or(0))
+        }
+        IpAddr::V6(ipv6) => {
+            let num: u128 = ipv6.into();
+            Subnet::V6(
+                num.checked_shr(128 - ipv6_prefix_bits).unwrap_or(0) as u64
+            )
         }
     }
 }
@@ -45,7 +71,7 @@ impl TryFrom<usize> for SubnetType {
 
 #[cfg(test)]
 mod tests {
-    use super::SubnetType;
+    use super::{host_subnet, SubnetType};
     use std::{net::IpAddr, str::FromStr};
 
     fn new_ip(ip: &'static str) -> IpAddr { IpAddr::from_str(ip).unwrap() }
@@ -91,4 +117,53 @@ mod tests {
             SubnetType::A.subnet(&new_ip("192.0.0.1"))
         );
     }
+
+    #[test]
+    fn test_ipv6_subnet() {
+        assert_eq!(
+            SubnetType::C.subnet(&new_ip("2001:db8:1::1")),
+            SubnetType::C.subnet(&new_ip("2001:db8:1:ffff::1"))
+        );
+        assert_ne!(
+            SubnetType::C.subnet(&new_ip("2001:db8:1::1")),
+            SubnetType::C.subnet(&new_ip("2001:db8:2::1"))
+        );
+
+        assert_eq!(
+            SubnetType::B.subnet(&new_ip("2001:db8:1::1")),
+            SubnetType::B.subnet(&new_ip("2001:db8:2::1"))
+        );
+        assert_ne!(
+            SubnetType::B.subnet(&new_ip("2001:db8::1")),
+            SubnetType::B.subnet(&new_ip("2001:db9::1"))
+        );
+
+        // IPv4 and IPv6 subnets never collide.
+        assert_ne!(
+            SubnetType::A.subnet(&new_ip("0.0.0.1")),
+            SubnetType::A.subnet(&new_ip("::1"))
+        );
+
+        // IPv4-mapped addresses are treated as IPv4 addresses.
+        assert_eq!(
+            SubnetType::C.subnet(&new_ip("::ffff:127.0.0.1")),
+            SubnetType::C.subnet(&new_ip("127.0.0.2"))
+        );
+    }
+
+    #[test]
+    fn test_host_subnet() {
+        assert_ne!(
+            host_subnet(&new_ip("127.0.0.1")),
+            host_subnet(&new_ip("127.0.0.2"))
+        );
+        assert_eq!(
+            host_subnet(&new_ip("2001:db8::1")),
+            host_subnet(&new_ip("2001:db8::ffff:2"))
+        );
+        assert_ne!(
+            host_subnet(&new_ip("2001:db8::1")),
+            host_subnet(&new_ip("2001:db8:0:1::1"))
+        );
+    }
 }
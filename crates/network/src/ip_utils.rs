@@ -21,9 +21,12 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{node_table::NodeEndpoint, NatType};
-use igd::{search_gateway, PortMappingProtocol, SearchOptions};
+use igd::{
+    search_gateway, AddAnyPortError, AddPortError, Gateway,
+    PortMappingProtocol, SearchOptions,
+};
 use ipnetwork::IpNetwork;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use natpmp::{Natpmp, Protocol, Response};
 use std::{
     io,
@@ -31,11 +34,22 @@ use std::{
         IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
         TcpStream,
     },
+    sync::mpsc::{self, Receiver, TryRecvError},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-const NAT_PMP_PORT_MAPPING_LIFETIME: u32 = 3600 * 24;
+/// Lifetime in seconds of the UPnP and NAT-PMP port mappings. Mappings are
+/// renewed every `NAT_MAPPING_RENEWAL_INTERVAL`, so that they are released
+/// by the gateway soon after the node is stopped.
+const UPNP_PORT_MAPPING_LEASE: u32 = 3600 * 2;
+const NAT_PMP_PORT_MAPPING_LIFETIME: u32 = 3600 * 2;
+const NAT_MAPPING_LEASE: Duration = Duration::from_secs(3600 * 2);
+const NAT_MAPPING_RENEWAL_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Interval to retry after a failed renewal.
+const NAT_MAPPING_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+/// Interval to check whether the renewal is due or has completed.
+pub const NAT_RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 // Waiting duration in milliseconds for response from router after sending port
 // mapping request. 50 milliseconds might be enough for low RTT.
 const NAT_PMP_PORT_MAPPING_WAITING_DURATION: u64 = 50;
@@ -324,7 +338,117 @@ pub fn select_public_address(port: u16) -> SocketAddr {
     SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port))
 }
 
-fn search_upnp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
+/// Select a public IPv6 address to advertise besides the IPv4 one on a
+/// dual-stack node.
+pub fn select_public_ipv6_address(port: u16) -> Option<SocketAddr> {
+    match get_if_addrs() {
+        Ok(list) => list.into_iter().find_map(|addr| match addr {
+            IpAddr::V6(a) if a.is_usable_public() => {
+                Some(SocketAddr::V6(SocketAddrV6::new(a, port, 0, 0)))
+            }
+            _ => None,
+        }),
+        Err(e) => {
+            debug!("Error listing public interfaces: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Convert IPv4-mapped IPv6 addresses, e.g. the addresses of IPv4 peers
+/// accepted by a dual-stack socket, to IPv4 addresses.
+pub fn canonical_ip(ip: &IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => *ip,
+        },
+        IpAddr::V4(_) => *ip,
+    }
+}
+
+pub fn canonical_socket_addr(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(canonical_ip(&addr.ip()), addr.port())
+}
+
+/// Convert IPv4 addresses to IPv4-mapped IPv6 addresses if they are used with
+/// an IPv6 socket, which does not accept IPv4 addresses.
+pub fn to_socket_family(addr: SocketAddr, ipv6_socket: bool) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(a) if ipv6_socket => SocketAddr::V6(SocketAddrV6::new(
+            a.ip().to_ipv6_mapped(),
+            a.port(),
+            0,
+            0,
+        )),
+        _ => addr,
+    }
+}
+
+/// Port mapping created on the NAT gateway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NatMapping {
+    /// Method used to create the mapping, either UPnP or NAT-PMP.
+    pub nat_type: NatType,
+    pub local: NodeEndpoint,
+    pub external: NodeEndpoint,
+}
+
+/// Map the local address to the requested external port, or to any port if
+/// the requested one is not available. Returns the mapped external port.
+fn upnp_add_port(
+    gateway: &Gateway, protocol: PortMappingProtocol, local_addr: SocketAddrV4,
+    external_port: Option<u16>, description: &str,
+) -> Option<u16> {
+    if let Some(external_port) = external_port {
+        let mut result = gateway.add_port(
+            protocol,
+            external_port,
+            local_addr,
+            UPNP_PORT_MAPPING_LEASE,
+            description,
+        );
+        if let Err(AddPortError::OnlyPermanentLeasesSupported) = result {
+            result = gateway.add_port(
+                protocol,
+                external_port,
+                local_addr,
+                0,
+                description,
+            );
+        }
+        match result {
+            Ok(()) => return Some(external_port),
+            Err(ref err) => debug!(
+                "Port mapping error for external port {}: {}",
+                external_port, err
+            ),
+        }
+    }
+
+    let mut result = gateway.add_any_port(
+        protocol,
+        local_addr,
+        UPNP_PORT_MAPPING_LEASE,
+        description,
+    );
+    if let Err(AddAnyPortError::OnlyPermanentLeasesSupported) = result {
+        result = gateway.add_any_port(protocol, local_addr, 0, description);
+    }
+    match result {
+        Ok(port) => Some(port),
+        Err(ref err) => {
+            debug!("Port mapping error: {}", err);
+            None
+        }
+    }
+}
+
+/// Map the local TCP and UDP ports with UPnP. If `external` is given, try to
+/// keep the same external ports, e.g. when renewing an existing mapping.
+fn search_upnp(
+    local: &NodeEndpoint, external: Option<&NodeEndpoint>,
+) -> Option<NodeEndpoint> {
     let local_addr = match local.address {
         SocketAddr::V4(ref local_addr) => local_addr,
         _ => return None,
@@ -371,32 +495,22 @@ fn search_upnp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
             }
         };
 
-        let tcp_port = match gateway.add_any_port(
+        let tcp_port = upnp_add_port(
+            &gateway,
             PortMappingProtocol::TCP,
             SocketAddrV4::new(client_ip, local_port),
-            0,
+            external.map(|e| e.address.port()),
             "Conflux Node/TCP",
-        ) {
-            Err(ref err) => {
-                debug!("Port mapping error: {}", err);
-                return None;
-            }
-            Ok(tcp_port) => tcp_port,
-        };
+        )?;
         debug!("UPnP gets tcp port: {}", tcp_port);
 
-        let udp_port = match gateway.add_any_port(
+        let udp_port = upnp_add_port(
+            &gateway,
             PortMappingProtocol::UDP,
             SocketAddrV4::new(client_ip, local_udp_port),
-            0,
+            external.map(|e| e.udp_port),
             "Conflux Node/UDP",
-        ) {
-            Err(ref err) => {
-                debug!("Port mapping error: {}", err);
-                return None;
-            }
-            Ok(udp_port) => udp_port,
-        };
+        )?;
         debug!("UPnP gets udp port: {}", udp_port);
 
         let address =
@@ -405,10 +519,18 @@ fn search_upnp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
     })
 }
 
-fn search_natpmp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
+/// Map the local TCP and UDP ports with NAT-PMP. If `external` is given,
+/// request the same external ports, otherwise request the local ports.
+fn search_natpmp(
+    local: &NodeEndpoint, external: Option<&NodeEndpoint>,
+) -> Option<NodeEndpoint> {
     if let SocketAddr::V4(ref local_addr) = local.address {
         let local_port = local_addr.port();
         let local_udp_port = local.udp_port;
+        let (public_port, public_udp_port) = match external {
+            Some(e) => (e.address.port(), e.udp_port),
+            None => (local_port, local_udp_port),
+        };
 
         let search_gateway_child = ::std::thread::spawn(move || {
             let mut n = Natpmp::new()?;
@@ -450,7 +572,7 @@ fn search_natpmp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
             n.send_port_mapping_request(
                 Protocol::TCP,
                 local_port,
-                local_port,
+                public_port,
                 NAT_PMP_PORT_MAPPING_LIFETIME,
             )?;
             ::std::thread::sleep(Duration::from_millis(
@@ -470,7 +592,7 @@ fn search_natpmp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
             n.send_port_mapping_request(
                 Protocol::UDP,
                 local_udp_port,
-                local_udp_port,
+                public_udp_port,
                 NAT_PMP_PORT_MAPPING_LIFETIME,
             )?;
             ::std::thread::sleep(Duration::from_millis(
@@ -506,24 +628,140 @@ fn search_natpmp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
 }
 
 /// Port mapping using ether UPnP or Nat-PMP.
-/// NAT PMP has higher priority than UPnP.
+/// UPnP has higher priority than NAT PMP.
 pub fn map_external_address(
     local: &NodeEndpoint, nat_type: &NatType,
-) -> Option<NodeEndpoint> {
-    match *nat_type {
-        NatType::Any => match search_upnp(local) {
-            Some(end_point) => Some(end_point),
-            None => search_natpmp(local),
+) -> Option<NatMapping> {
+    let (nat_type, external) = match *nat_type {
+        NatType::Any => match search_upnp(local, None) {
+            Some(end_point) => (NatType::UPnP, end_point),
+            None => (NatType::NatPMP, search_natpmp(local, None)?),
         },
-        NatType::NatPMP => search_natpmp(local),
-        NatType::UPnP => search_upnp(local),
+        NatType::NatPMP => (NatType::NatPMP, search_natpmp(local, None)?),
+        NatType::UPnP => (NatType::UPnP, search_upnp(local, None)?),
         _ => {
             trace!("Can't map external address using NAT");
-            None
+            return None;
         }
+    };
+    Some(NatMapping {
+        nat_type,
+        local: local.clone(),
+        external,
+    })
+}
+
+/// Renew the port mapping before its lease expires. The gateway may assign
+/// other external ports or the external IP may have changed, so the renewed
+/// external endpoint is returned.
+pub fn renew_port_mapping(mapping: &NatMapping) -> Option<NodeEndpoint> {
+    match mapping.nat_type {
+        NatType::UPnP => search_upnp(&mapping.local, Some(&mapping.external)),
+        NatType::NatPMP => {
+            search_natpmp(&mapping.local, Some(&mapping.external))
+        }
+        _ => None,
     }
 }
 
+/// Schedules the renewal of a NAT port mapping. The renewal searches the
+/// gateway, which blocks for seconds, so it runs on a background thread and
+/// the result is collected by the next `tick`.
+pub struct NatRenewal {
+    mapping: NatMapping,
+    /// When the lease of the latest successful mapping expires.
+    expires_at: Instant,
+    next_renewal: Instant,
+    /// Result of the ongoing renewal.
+    pending: Option<Receiver<Option<NodeEndpoint>>>,
+}
+
+impl NatRenewal {
+    pub fn new(mapping: NatMapping, now: Instant) -> Self {
+        NatRenewal {
+            mapping,
+            expires_at: now + NAT_MAPPING_LEASE,
+            next_renewal: now + NAT_MAPPING_RENEWAL_INTERVAL,
+            pending: None,
+        }
+    }
+
+    pub fn mapping(&self) -> &NatMapping { &self.mapping }
+
+    /// Collects the result of the ongoing renewal, and starts to renew with
+    /// `renew` on a background thread if it is due. Returns the new external
+    /// endpoint if it changed.
+    pub fn tick<F>(&mut self, now: Instant, renew: F) -> Option<NodeEndpoint>
+    where F: FnOnce(NatMapping) -> Option<NodeEndpoint> + Send + 'static {
+        let mut changed = None;
+        if let Some(pending) = &self.pending {
+            let result = match pending.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => None,
+            };
+            self.pending = None;
+            changed = self.on_renewed(now, result);
+        }
+
+        if now >= self.next_renewal {
+            let (sender, receiver) = mpsc::channel();
+            let mapping = self.mapping.clone();
+            let spawned = thread::Builder::new()
+                .name("nat_renewal".into())
+                .spawn(move || {
+                    let _ = sender.send(renew(mapping));
+                });
+            match spawned {
+                Ok(_) => self.pending = Some(receiver),
+                Err(e) => {
+                    warn!("Failed to spawn NAT renewal thread: {:?}", e);
+                    self.next_renewal = now + NAT_MAPPING_RETRY_INTERVAL;
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn on_renewed(
+        &mut self, now: Instant, result: Option<NodeEndpoint>,
+    ) -> Option<NodeEndpoint> {
+        let external = match result {
+            Some(external) => external,
+            None => {
+                // The gateway may be temporarily unavailable, so keep the
+                // current mapping and retry soon.
+                self.next_renewal = now + NAT_MAPPING_RETRY_INTERVAL;
+                if now >= self.expires_at {
+                    warn!(
+                        "NAT port mapping with {:?} expired, external = {:?}",
+                        self.mapping.nat_type, self.mapping.external
+                    );
+                } else {
+                    debug!(
+                        "Failed to renew NAT port mapping with {:?}",
+                        self.mapping.nat_type
+                    );
+                }
+                return None;
+            }
+        };
+        debug!("NAT port mapping renewed, external = {:?}", external);
+
+        self.expires_at = now + NAT_MAPPING_LEASE;
+        self.next_renewal = now + NAT_MAPPING_RENEWAL_INTERVAL;
+        if external == self.mapping.external {
+            return None;
+        }
+        self.mapping.external = external.clone();
+        Some(external)
+    }
+
+    /// Whether the lease of the latest successful mapping has expired.
+    pub fn is_expired(&self, now: Instant) -> bool { now >= self.expires_at }
+}
+
 #[test]
 fn can_select_public_address() {
     let pub_address = select_public_address(40477);
@@ -543,6 +781,80 @@ fn can_map_external_address_or_fail() {
     );
 }
 
+#[ignore]
+#[test]
+fn can_renew_port_mapping_or_fail() {
+    let pub_address = select_public_address(40479);
+    if let Some(mapping) = map_external_address(
+        &NodeEndpoint {
+            address: pub_address,
+            udp_port: 40479,
+        },
+        &NatType::Any,
+    ) {
+        assert!(renew_port_mapping(&mapping).is_some());
+    }
+}
+
+#[cfg(test)]
+fn wait_nat_renewal(
+    renewal: &mut NatRenewal, now: Instant,
+) -> Option<NodeEndpoint> {
+    while renewal.pending.is_some() {
+        if let Some(changed) = renewal.tick(now, |_| unreachable!()) {
+            return Some(changed);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    None
+}
+
+#[test]
+fn nat_renewal_schedule() {
+    let endpoint = |port: u16| NodeEndpoint {
+        address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), port),
+        udp_port: port,
+    };
+    let mapping = NatMapping {
+        nat_type: NatType::UPnP,
+        local: endpoint(1),
+        external: endpoint(2),
+    };
+    let start = Instant::now();
+    let mut renewal = NatRenewal::new(mapping.clone(), start);
+
+    // not due yet
+    assert_eq!(renewal.tick(start, |_| unreachable!()), None);
+    assert!(renewal.pending.is_none());
+
+    // renewed with the same external endpoint
+    let now = start + NAT_MAPPING_RENEWAL_INTERVAL;
+    assert_eq!(renewal.tick(now, |m| Some(m.external)), None);
+    assert!(renewal.pending.is_some());
+    assert_eq!(wait_nat_renewal(&mut renewal, now), None);
+    assert_eq!(renewal.next_renewal, now + NAT_MAPPING_RENEWAL_INTERVAL);
+    assert_eq!(renewal.expires_at, now + NAT_MAPPING_LEASE);
+
+    // failed renewals are retried until the lease expires
+    let mut now = now + NAT_MAPPING_RENEWAL_INTERVAL;
+    let expires_at = renewal.expires_at;
+    while now < expires_at {
+        renewal.tick(now, |_| None);
+        assert_eq!(wait_nat_renewal(&mut renewal, now), None);
+        assert_eq!(renewal.next_renewal, now + NAT_MAPPING_RETRY_INTERVAL);
+        assert!(!renewal.is_expired(now));
+        now = renewal.next_renewal;
+    }
+    assert!(renewal.is_expired(now));
+    assert_eq!(renewal.mapping(), &mapping);
+
+    // the gateway assigns another external port
+    renewal.tick(now, |_| Some(endpoint(3)));
+    assert_eq!(wait_nat_renewal(&mut renewal, now), Some(endpoint(3)));
+    assert_eq!(renewal.mapping().external, endpoint(3));
+    assert!(!renewal.is_expired(now));
+}
+
 #[test]
 fn ipv4_properties() {
     fn check(
@@ -12,22 +12,28 @@ pub type ProtocolId = [u8; PROTOCOL_ID_SIZE];
 pub type HandlerWorkType = u8;
 pub type PeerId = usize;
 
+mod compression;
 mod connection;
 mod discovery;
+pub mod dns_discovery;
 mod error;
 mod handshake;
 mod ip;
 mod ip_utils;
 mod node_database;
+mod node_reputation;
 pub mod node_table;
 pub mod service;
 mod session;
+mod session_cipher;
 mod session_manager;
+mod static_peers;
 pub mod throttling;
 
 pub use crate::{
     error::{DisconnectReason, Error, ThrottlingReason},
     ip::SessionIpLimitConfig,
+    node_reputation::{PeerReputation, ReputationConfig, ReputationEvent},
     node_table::Node,
     service::NetworkService,
     session::SessionDetails,
@@ -39,6 +45,7 @@ use crate::{
     service::{
         ProtocolVersion, DEFAULT_CONNECTION_LIFETIME_FOR_PROMOTION,
         DEFAULT_DISCOVERY_REFRESH_TIMEOUT, DEFAULT_DISCOVERY_ROUND_TIMEOUT,
+        DEFAULT_DNS_DISCOVERY_REFRESH_INTERVAL,
         DEFAULT_FAST_DISCOVERY_REFRESH_TIMEOUT, DEFAULT_HOUSEKEEPING_TIMEOUT,
         DEFAULT_NODE_TABLE_TIMEOUT,
     },
@@ -65,6 +72,10 @@ pub const NODE_TAG_FULL: &str = "full";
 #[derive(Debug, Clone, PartialEq)]
 pub struct NetworkConfiguration {
     pub is_consortium: bool,
+    /// Encrypt session packets if the remote peer also supports it
+    pub session_encryption: bool,
+    /// Compress session packets if the remote peer also supports it
+    pub session_compression: bool,
     /// Network identifier
     pub id: u64,
     network_type: Network,
@@ -74,12 +85,24 @@ pub struct NetworkConfiguration {
     pub listen_address: Option<SocketAddr>,
     /// IP address to advertise. Detected automatically if none.
     pub public_address: Option<SocketAddr>,
+    /// IPv6 address to advertise to IPv6 nodes if listening on an IPv6
+    /// address. Detected automatically if none.
+    pub public_address_v6: Option<SocketAddr>,
     pub udp_port: Option<u16>,
     /// Enable NAT configuration
     pub nat_enabled: bool,
+    /// Method to map the external address if NAT is enabled
+    pub nat_type: NatType,
     /// Enable discovery
     pub discovery_enabled: bool,
     pub boot_nodes: Vec<String>,
+    /// URLs of the signed node lists published in DNS, i.e.
+    /// `cfxtree://<public key>@<domain>`.
+    pub dns_discovery_urls: Vec<String>,
+    /// The name server to resolve the node lists with. The system one is
+    /// used if not set.
+    pub dns_discovery_nameserver: Option<SocketAddr>,
+    pub dns_discovery_refresh_interval: Duration,
     /// Use provided node key instead of default
     pub use_secret: Option<Secret>,
     /// Maximum number of outgoing peers
@@ -92,8 +115,12 @@ pub struct NetworkConfiguration {
     pub max_incoming_peers: usize,
     /// Maximum number of ongoing handshakes
     pub max_handshakes: usize,
-    /// List of reserved node addresses.
-    pub reserved_nodes: Vec<String>,
+    /// Node URLs of the static peers, which are always connected regardless
+    /// of the peer limits.
+    pub static_peers: Vec<String>,
+    /// Only connect to and accept the static peers, e.g. for a validator
+    /// behind sentry nodes. Discovery is disabled in this mode.
+    pub static_peers_only: bool,
     /// IP filter
     pub ip_filter: IpFilter,
     /// Timeout duration for initiating peer connection management
@@ -111,9 +138,10 @@ pub struct NetworkConfiguration {
     /// Connection lifetime threshold for promotion
     pub connection_lifetime_for_promotion: Duration,
     pub test_mode: bool,
-    /// Maximum number of P2P nodes for subnet B (ip/16).
+    /// Maximum number of P2P nodes for subnet C (ip/24, or ip/48 for IPv6).
     pub subnet_quota: usize,
     pub session_ip_limit_config: SessionIpLimitConfig,
+    pub reputation_config: ReputationConfig,
 
     pub discovery_config: DiscoveryConfiguration,
 }
@@ -124,21 +152,30 @@ impl NetworkConfiguration {
 
         NetworkConfiguration {
             is_consortium: false,
+            session_encryption: false,
+            session_compression: false,
             id,
             network_type,
             config_path: Some("./net_config".to_string()),
             listen_address: None,
             public_address: None,
+            public_address_v6: None,
             udp_port: None,
             nat_enabled: true,
+            nat_type: NatType::Any,
             discovery_enabled: false,
             boot_nodes: Vec::new(),
+            dns_discovery_urls: Vec::new(),
+            dns_discovery_nameserver: None,
+            dns_discovery_refresh_interval:
+                DEFAULT_DNS_DISCOVERY_REFRESH_INTERVAL,
             use_secret: None,
             max_outgoing_peers: 0,
             max_outgoing_peers_archive: 0,
             max_incoming_peers: 0,
             max_handshakes: 0,
-            reserved_nodes: Vec::new(),
+            static_peers: Vec::new(),
+            static_peers_only: false,
             ip_filter: IpFilter::default(),
             housekeeping_timeout: DEFAULT_HOUSEKEEPING_TIMEOUT,
             discovery_refresh_timeout: DEFAULT_DISCOVERY_REFRESH_TIMEOUT,
@@ -151,6 +188,7 @@ impl NetworkConfiguration {
             test_mode: false,
             subnet_quota: 32,
             session_ip_limit_config: SessionIpLimitConfig::default(),
+            reputation_config: ReputationConfig::default(),
             discovery_config,
         }
     }
@@ -287,6 +325,10 @@ pub trait NetworkContext {
         &self, node_id: &NodeId, op: Option<UpdateNodeOperation>, reason: &str,
     );
 
+    /// Lower the reputation of a peer for misbehavior. The peer is
+    /// disconnected and banned temporarily if its reputation is too low.
+    fn report_peer(&self, node_id: &NodeId, event: ReputationEvent);
+
     /// Register a new IO timer. 'IoHandler::timeout' will be called with the
     /// token.
     fn register_timer(
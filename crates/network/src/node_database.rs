@@ -8,11 +8,19 @@ use crate::{
     IpFilter,
 };
 use io::StreamToken;
-use std::{collections::HashSet, net::IpAddr, time::Duration};
+use log::warn;
+use std::{
+    collections::HashSet,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 const TRUSTED_NODES_FILE: &str = "trusted_nodes.json";
 const UNTRUSTED_NODES_FILE: &str = "untrusted_nodes.json";
 const BLACKLISTED_NODES_FILE: &str = "blacklisted_nodes.json";
+const BANNED_NODES_FILE: &str = "banned_nodes.json";
 
 /// Node database maintains all P2P nodes in trusted and untrusted node tables,
 /// and supports to limit the number of nodes for the same IP address.
@@ -92,6 +100,12 @@ pub struct NodeDatabase {
     // Maximum duration to blacklist a node since last contact.
     blacklisted_lifetime: Duration,
 
+    // Nodes banned by the node operator, which never expire and are only
+    // removed by unbanning them explicitly. Unlike blacklisted nodes, they
+    // need not be known in the node tables.
+    banned_nodes: HashSet<NodeId>,
+    banned_nodes_path: Option<PathBuf>,
+
     // IP address/subnet index for trusted and untrusted nodes.
     ip_limit: NodeIpLimit,
 
@@ -113,11 +127,21 @@ impl NodeDatabase {
         let trusted_node_tag_index =
             NodeTagIndex::new_with_node_table(&trusted_nodes);
 
+        let banned_nodes_path = path
+            .as_ref()
+            .map(|dir| Path::new(dir).join(BANNED_NODES_FILE));
+        let banned_nodes = banned_nodes_path
+            .as_ref()
+            .map(|path| Self::load_banned_nodes(path))
+            .unwrap_or_default();
+
         let mut db = NodeDatabase {
             trusted_nodes,
             untrusted_nodes,
             blacklisted_nodes: NodeTable::new(path, BLACKLISTED_NODES_FILE),
             blacklisted_lifetime: Duration::from_secs(7 * 24 * 3600),
+            banned_nodes,
+            banned_nodes_path,
             ip_limit,
             trusted_node_tag_index,
         };
@@ -163,6 +187,24 @@ impl NodeDatabase {
         }
     }
 
+    /// Add a new untrusted node if not exists, e.g. a node learned from a
+    /// third party which is not contacted yet.
+    pub fn insert_untrusted(&mut self, entry: NodeEntry) {
+        if self.evaluate_blacklisted(&entry.id)
+            || self.trusted_nodes.contains(&entry.id)
+            || self.untrusted_nodes.contains(&entry.id)
+        {
+            return;
+        }
+
+        let node = Node::new(entry.id, entry.endpoint);
+        let ip = node.endpoint.address.ip();
+        if self.insert_ip_limit(node.id.clone(), ip, false /* trusted */) {
+            self.untrusted_nodes
+                .add_node(node, false /* preserve_last_contact */);
+        }
+    }
+
     fn insert_ip_limit(
         &mut self, id: NodeId, ip: IpAddr, trusted: bool,
     ) -> bool {
@@ -400,6 +442,7 @@ impl NodeDatabase {
         self.untrusted_nodes.clear_useless();
 
         self.blacklisted_nodes.save();
+        self.save_banned_nodes();
     }
 
     /// Promote untrusted nodes to trusted with the given duration.
@@ -581,10 +624,86 @@ impl NodeDatabase {
         }
     }
 
-    /// Check if the specified node is blacklisted.
+    /// Ban the specified node permanently, and remove it from the node
+    /// tables. The ban is saved immediately. Returns false if the node is
+    /// already banned.
+    pub fn ban(&mut self, id: &NodeId) -> bool {
+        if !self.banned_nodes.insert(*id) {
+            return false;
+        }
+        self.remove(id);
+        self.save_banned_nodes();
+        true
+    }
+
+    /// Lift the ban of the specified node. The change is saved immediately.
+    /// Returns false if the node is not banned.
+    pub fn unban(&mut self, id: &NodeId) -> bool {
+        if !self.banned_nodes.remove(id) {
+            return false;
+        }
+        self.save_banned_nodes();
+        true
+    }
+
+    /// Get all the banned nodes.
+    pub fn banned_nodes(&self) -> Vec<NodeId> {
+        self.banned_nodes.iter().copied().collect()
+    }
+
+    pub fn is_banned(&self, id: &NodeId) -> bool {
+        self.banned_nodes.contains(id)
+    }
+
+    fn load_banned_nodes(path: &Path) -> HashSet<NodeId> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(_) => return HashSet::new(),
+        };
+        match serde_json::from_reader::<_, Vec<NodeId>>(file) {
+            Ok(nodes) => nodes.into_iter().collect(),
+            Err(e) => {
+                warn!("Error reading banned nodes file: {:?}", e);
+                HashSet::new()
+            }
+        }
+    }
+
+    fn save_banned_nodes(&self) {
+        let path = match self.banned_nodes_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("Error creating banned nodes directory: {:?}", e);
+                return;
+            }
+        }
+
+        let mut nodes = self.banned_nodes();
+        nodes.sort();
+        match fs::File::create(path) {
+            Ok(file) => {
+                if let Err(e) = serde_json::to_writer_pretty(file, &nodes) {
+                    warn!("Error writing banned nodes file: {:?}", e);
+                }
+            }
+            Err(e) => {
+                warn!("Error creating banned nodes file: {:?}", e);
+            }
+        }
+    }
+
+    /// Check if the specified node is banned or blacklisted.
     /// If blacklisted for a long time, it will be removed from blacklisted node
     /// table.
     pub fn evaluate_blacklisted(&mut self, id: &NodeId) -> bool {
+        if self.is_banned(id) {
+            return true;
+        }
+
         let node = match self.blacklisted_nodes.get_mut(id) {
             Some(node) => node,
             None => return false,
@@ -643,6 +762,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_untrusted() {
+        let mut db = NodeDatabase::new(None, 2);
+
+        // add a new node, which is untrusted
+        let entry = new_entry("127.0.0.1:999");
+        db.insert_untrusted(entry.clone());
+        assert_eq!(db.get(&entry.id, true /* trusted_only */), None);
+        assert!(db.get(&entry.id, false /* trusted_only */).is_some());
+
+        // a trusted node is not demoted
+        let trusted = new_entry("127.0.0.2:999");
+        db.insert_trusted(trusted.clone());
+        db.insert_untrusted(trusted.clone());
+        assert!(db.get(&trusted.id, true /* trusted_only */).is_some());
+    }
+
     #[test]
     fn test_insert_with_token_updated_trusted() {
         let mut db = NodeDatabase::new(None, 2);
@@ -783,6 +919,46 @@ mod tests {
         assert_eq!(db.get(&n.id, false), None);
     }
 
+    #[test]
+    fn test_banned() {
+        let dir = std::env::temp_dir()
+            .join(format!("node_database_banned_{:x}", NodeId::random()));
+        let path = Some(dir.to_str().unwrap().to_string());
+        let mut db = NodeDatabase::new(path.clone(), 2);
+
+        // unknown nodes could be banned
+        let unknown = NodeId::random();
+        assert!(db.ban(&unknown));
+        assert!(!db.ban(&unknown));
+        assert_eq!(db.evaluate_blacklisted(&unknown), true);
+
+        let n = new_entry("127.0.0.1:999");
+        db.insert_trusted(n.clone());
+        assert!(db.ban(&n.id));
+        assert_eq!(db.get(&n.id, false), None);
+        db.insert_trusted(n.clone());
+        assert_eq!(db.get(&n.id, false), None);
+
+        // bans never expire and survive restarts
+        db.blacklisted_lifetime = Duration::from_millis(1);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(db.evaluate_blacklisted(&n.id), true);
+        drop(db);
+        let mut db = NodeDatabase::new(path, 2);
+        let mut banned = db.banned_nodes();
+        banned.sort();
+        let mut expected = vec![unknown, n.id];
+        expected.sort();
+        assert_eq!(banned, expected);
+
+        assert!(db.unban(&n.id));
+        assert!(!db.unban(&n.id));
+        assert_eq!(db.evaluate_blacklisted(&n.id), false);
+        assert_eq!(db.banned_nodes(), vec![unknown]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn test_blacklisted_lifetime() {
         let mut db = NodeDatabase::new(None, 2);
@@ -0,0 +1,280 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Numeric reputation of peers, which is lowered by the misbehaviors reported
+//! by the protocol handlers and recovers over time.
+//!
+//! The score of a peer starts at zero and decays exponentially towards zero
+//! with the configured half life. A peer with a negative score is reconnected
+//! with a backoff that doubles for every `BACKOFF_SCORE_STEP` below zero, and
+//! is banned temporarily once its score drops to the ban threshold.
+
+use crate::node_table::NodeId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The score below zero which doubles the reconnect backoff.
+const BACKOFF_SCORE_STEP: f64 = 10.0;
+
+/// Peers whose score decays within this distance of zero are forgotten.
+const NEGLIGIBLE_SCORE: f64 = 0.5;
+
+/// Misbehaviors of peers reported by the protocol handlers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationEvent {
+    /// The peer relayed an invalid block or block header.
+    InvalidBlock,
+    /// The peer sent a malformed message or violated the protocol.
+    InvalidMessage,
+    /// The peer did not respond to a request in time.
+    Timeout,
+    /// The peer sent an unexpected, empty or unverifiable response.
+    UselessResponse,
+    /// The peer sent too many messages and has been throttled.
+    Spam,
+}
+
+impl ReputationEvent {
+    fn score_delta(&self) -> f64 {
+        match self {
+            ReputationEvent::InvalidBlock => -50.0,
+            ReputationEvent::InvalidMessage => -40.0,
+            ReputationEvent::Spam => -20.0,
+            ReputationEvent::UselessResponse => -10.0,
+            ReputationEvent::Timeout => -5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReputationConfig {
+    /// The time for a score to decay to half of its value.
+    pub half_life: Duration,
+    /// A peer is banned once its score drops to this value.
+    pub ban_threshold: f64,
+    pub ban_duration: Duration,
+    /// The reconnect backoff of a peer whose score is just below zero.
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        ReputationConfig {
+            half_life: Duration::from_secs(1800),
+            ban_threshold: -100.0,
+            ban_duration: Duration::from_secs(3600),
+            min_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// The reputation of a peer at some point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerReputation {
+    pub score: f64,
+    /// The remaining time of the ban, if the peer is banned.
+    pub banned_for: Option<Duration>,
+    /// The remaining time before the peer could be connected again.
+    pub backoff_for: Option<Duration>,
+}
+
+struct Reputation {
+    /// The score right after the last penalty.
+    score: f64,
+    last_penalized: Instant,
+    banned_until: Option<Instant>,
+}
+
+impl Reputation {
+    fn score_at(&self, half_life: Duration, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_penalized);
+        self.score
+            * 0.5f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64())
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.map_or(false, |until| until > now)
+    }
+}
+
+pub struct ReputationTable {
+    config: ReputationConfig,
+    peers: HashMap<NodeId, Reputation>,
+}
+
+impl ReputationTable {
+    pub fn new(config: ReputationConfig) -> Self {
+        ReputationTable {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    fn decayed_score(&self, reputation: &Reputation, now: Instant) -> f64 {
+        reputation.score_at(self.config.half_life, now)
+    }
+
+    /// Record a misbehavior of the peer. Returns true if the peer gets
+    /// banned because of it.
+    pub fn report(
+        &mut self, id: &NodeId, event: ReputationEvent, now: Instant,
+    ) -> bool {
+        let score = self
+            .peers
+            .get(id)
+            .map_or(0.0, |reputation| self.decayed_score(reputation, now))
+            + event.score_delta();
+        let ban_until = now + self.config.ban_duration;
+        let reputation = self.peers.entry(*id).or_insert(Reputation {
+            score,
+            last_penalized: now,
+            banned_until: None,
+        });
+        reputation.score = score;
+        reputation.last_penalized = now;
+
+        if !reputation.is_banned(now) && score <= self.config.ban_threshold {
+            reputation.banned_until = Some(ban_until);
+            return true;
+        }
+        false
+    }
+
+    /// The current score of the peer, which is zero for unknown peers.
+    pub fn score(&self, id: &NodeId, now: Instant) -> f64 {
+        self.peers
+            .get(id)
+            .map_or(0.0, |reputation| self.decayed_score(reputation, now))
+    }
+
+    pub fn is_banned(&self, id: &NodeId, now: Instant) -> bool {
+        self.peers
+            .get(id)
+            .map_or(false, |reputation| reputation.is_banned(now))
+    }
+
+    fn backoff(&self, score: f64) -> Duration {
+        if score >= 0.0 {
+            return Duration::ZERO;
+        }
+        let factor = 2f64.powf(-score / BACKOFF_SCORE_STEP);
+        self.config
+            .min_backoff
+            .mul_f64(factor)
+            .min(self.config.max_backoff)
+    }
+
+    fn backoff_until(&self, reputation: &Reputation) -> Instant {
+        reputation.last_penalized + self.backoff(reputation.score)
+    }
+
+    /// Whether an outgoing connection to the peer could be started, i.e. it
+    /// is not banned and its reconnect backoff has elapsed.
+    pub fn is_connectable(&self, id: &NodeId, now: Instant) -> bool {
+        match self.peers.get(id) {
+            None => true,
+            Some(reputation) => {
+                !reputation.is_banned(now)
+                    && self.backoff_until(reputation) <= now
+            }
+        }
+    }
+
+    pub fn get(&self, id: &NodeId, now: Instant) -> Option<PeerReputation> {
+        let reputation = self.peers.get(id)?;
+        let remaining = |until: Instant| {
+            Some(until.saturating_duration_since(now)).filter(|d| !d.is_zero())
+        };
+        Some(PeerReputation {
+            score: self.decayed_score(reputation, now),
+            banned_for: reputation.banned_until.and_then(remaining),
+            backoff_for: remaining(self.backoff_until(reputation)),
+        })
+    }
+
+    /// The reputation of all peers not pruned yet.
+    pub fn all(&self, now: Instant) -> Vec<(NodeId, PeerReputation)> {
+        self.peers
+            .keys()
+            .filter_map(|id| Some((*id, self.get(id, now)?)))
+            .collect()
+    }
+
+    /// Forget the peers which have recovered, to bound the memory usage.
+    pub fn prune(&mut self, now: Instant) {
+        let half_life = self.config.half_life;
+        self.peers.retain(|_, reputation| {
+            reputation.score_at(half_life, now).abs() >= NEGLIGIBLE_SCORE
+                || reputation.is_banned(now)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_decay() {
+        let mut table = ReputationTable::new(ReputationConfig::default());
+        let id = NodeId::random();
+        let now = Instant::now();
+
+        assert!(!table.report(&id, ReputationEvent::UselessResponse, now));
+        assert_eq!(table.score(&id, now), -10.0);
+        let later = now + Duration::from_secs(1800);
+        assert!((table.score(&id, later) + 5.0).abs() < 1e-9);
+
+        // The peer is forgotten once recovered.
+        table.prune(later);
+        assert!(table.get(&id, later).is_some());
+        table.prune(now + Duration::from_secs(1800 * 5));
+        assert!(table.get(&id, now).is_none());
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let mut table = ReputationTable::new(ReputationConfig::default());
+        let id = NodeId::random();
+        let now = Instant::now();
+        assert!(table.is_connectable(&id, now));
+
+        table.report(&id, ReputationEvent::Timeout, now);
+        assert!(!table.is_connectable(&id, now));
+        // 30s * 2^0.5
+        assert!(table.is_connectable(&id, now + Duration::from_secs(43)));
+
+        table.report(&id, ReputationEvent::InvalidMessage, now);
+        assert!(!table.is_connectable(&id, now + Duration::from_secs(43)));
+        // 30s * 2^4.5
+        assert!(table.is_connectable(&id, now + Duration::from_secs(680)));
+    }
+
+    #[test]
+    fn test_ban() {
+        let mut table = ReputationTable::new(ReputationConfig::default());
+        let id = NodeId::random();
+        let now = Instant::now();
+
+        assert!(!table.report(&id, ReputationEvent::InvalidBlock, now));
+        assert!(!table.is_banned(&id, now));
+        assert!(table.report(&id, ReputationEvent::InvalidBlock, now));
+        assert!(table.is_banned(&id, now));
+        // Already banned.
+        assert!(!table.report(&id, ReputationEvent::Spam, now));
+
+        let reputation = table.get(&id, now).unwrap();
+        assert_eq!(reputation.score, -120.0);
+        assert_eq!(reputation.banned_for, Some(Duration::from_secs(3600)));
+
+        let later = now + Duration::from_secs(3600);
+        assert!(!table.is_banned(&id, later));
+        assert!(table.get(&id, later).unwrap().banned_for.is_none());
+    }
+}
@@ -23,7 +23,6 @@ use std::{
         ToSocketAddrs,
     },
     path::{Path, PathBuf},
-    slice,
     str::FromStr,
     time::{self, Duration, SystemTime},
 };
@@ -118,11 +117,16 @@ impl NodeEndpoint {
             SocketAddr::V4(a) => {
                 rlp.append(&(&a.ip().octets()[..]));
             }
-            SocketAddr::V6(a) => unsafe {
-                let segments = a.ip().segments();
-                let o: *const u8 = segments.as_ptr() as *const u8;
-                rlp.append(&slice::from_raw_parts(o, 16));
-            },
+            SocketAddr::V6(a) => {
+                // The segments are in little-endian as decoded in `from_rlp`,
+                // regardless of the platform.
+                let mut bytes = [0u8; 16];
+                for (i, segment) in a.ip().segments().iter().enumerate() {
+                    bytes[2 * i..2 * i + 2]
+                        .copy_from_slice(&segment.to_le_bytes());
+                }
+                rlp.append(&(&bytes[..]));
+            }
         };
         rlp.append(&self.udp_port);
         rlp.append(&self.address.port());
@@ -312,23 +316,33 @@ impl FromStr for Node {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (id, endpoint) =
-            if let Some(id_and_address_str) = s.strip_prefix("cfxnode://") {
-                // A node url with format "cfxnode://ID@IP:PORT"
-                let delimiter_index =
-                    id_and_address_str.find("@").ok_or(Error::AddressParse)?;
-                (
-                    id_and_address_str[..delimiter_index]
-                        .parse()
-                        .map_err(|_| Error::InvalidNodeId)?,
-                    NodeEndpoint::from_str(
-                        &id_and_address_str[delimiter_index + 1..],
-                    )?,
-                )
-            } else {
-                // A simple address without node id.
-                (NodeId::default(), NodeEndpoint::from_str(s)?)
+        let (id, endpoint) = if let Some(id_and_address_str) =
+            s.strip_prefix("cfxnode://")
+        {
+            // A node url with format "cfxnode://ID@IP:PORT", optionally
+            // followed by "+UDP_PORT" if the UDP port is different.
+            let delimiter_index =
+                id_and_address_str.find("@").ok_or(Error::AddressParse)?;
+            let address_str = &id_and_address_str[delimiter_index + 1..];
+            let endpoint = match address_str.split_once('+') {
+                Some((address_str, udp_port)) => {
+                    let mut endpoint = NodeEndpoint::from_str(address_str)?;
+                    endpoint.udp_port =
+                        udp_port.parse().map_err(|_| Error::AddressParse)?;
+                    endpoint
+                }
+                None => NodeEndpoint::from_str(address_str)?,
             };
+            (
+                id_and_address_str[..delimiter_index]
+                    .parse()
+                    .map_err(|_| Error::InvalidNodeId)?,
+                endpoint,
+            )
+        } else {
+            // A simple address without node id.
+            (NodeId::default(), NodeEndpoint::from_str(s)?)
+        };
 
         Ok(Node {
             id,
@@ -11,7 +11,10 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{atomic::Ordering as AtomicOrdering, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -40,15 +43,22 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     discovery::Discovery,
+    dns_discovery::{DnsDiscovery, TreeUrl, TxtResolver, UdpTxtResolver},
     handshake::BYPASS_CRYPTOGRAPHY,
     iolib::*,
-    ip_utils::{map_external_address, select_public_address},
+    ip_utils::{
+        canonical_socket_addr, map_external_address, renew_port_mapping,
+        select_public_address, select_public_ipv6_address, to_socket_family,
+        NatRenewal, NAT_RENEWAL_CHECK_INTERVAL,
+    },
     node_database::NodeDatabase,
+    node_reputation::{PeerReputation, ReputationEvent, ReputationTable},
     node_table::*,
     parse_msg_id_leb128_2_bytes_at_most,
     session::{self, Session, SessionData, SessionDetails},
     session_manager::SessionManager,
-    Error, HandlerWorkType, IpFilter, NatType, NetworkConfiguration,
+    static_peers::StaticPeers,
+    Error, HandlerWorkType, IpFilter, NetworkConfiguration,
     NetworkContext as NetworkContextTrait, NetworkIoMessage,
     NetworkProtocolHandler, PeerInfo, ProtocolId, ProtocolInfo,
     UpdateNodeOperation, NODE_TAG_ARCHIVE, NODE_TAG_NODE_TYPE,
@@ -72,6 +82,7 @@ const DISCOVERY_ROUND: TimerToken = SYS_TIMER + 6;
 const NODE_TABLE: TimerToken = SYS_TIMER + 7;
 const SEND_DELAYED_MESSAGES: TimerToken = SYS_TIMER + 8;
 const CHECK_SESSIONS: TimerToken = SYS_TIMER + 9;
+const NAT_RENEWAL: TimerToken = SYS_TIMER + 10;
 const HANDLER_TIMER: TimerToken = LAST_SESSION + 256;
 const STOP_NET_POLL: TimerToken = HANDLER_TIMER + 1;
 
@@ -93,6 +104,9 @@ pub const DEFAULT_NODE_TABLE_TIMEOUT: Duration = Duration::from_secs(300);
 pub const DEFAULT_CONNECTION_LIFETIME_FOR_PROMOTION: Duration =
     Duration::from_secs(3 * 24 * 3600);
 const DEFAULT_CHECK_SESSIONS_TIMEOUT: Duration = Duration::from_secs(10);
+// How often the node lists published in DNS are synced.
+pub const DEFAULT_DNS_DISCOVERY_REFRESH_INTERVAL: Duration =
+    Duration::from_secs(1800);
 
 #[derive(
     Clone,
@@ -273,6 +287,124 @@ impl NetworkService {
         }
     }
 
+    /// Add a P2P peer as a static peer, which is always connected regardless
+    /// of the peer limits. Returns false if it is already a static peer.
+    pub fn add_static_peer(&self, node: NodeEntry) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.static_peers.write().insert(node, Instant::now()))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Remove a P2P peer from the static peers without disconnecting it, so
+    /// that it is subject to the peer limits again. Returns false if it is
+    /// not a static peer.
+    pub fn remove_static_peer(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.static_peers.write().remove(id))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Update the limits of P2P peers, where `None` keeps the current limit
+    /// of outgoing archive peers. Existing sessions are kept if the limits
+    /// decrease.
+    pub fn set_peer_limits(
+        &self, max_incoming_peers: usize, max_outgoing_peers: usize,
+        max_outgoing_peers_archive: Option<usize>,
+    ) -> Result<(), Error> {
+        if let Some(ref x) = self.inner {
+            x.sessions.set_max_ingress_sessions(max_incoming_peers);
+            x.max_outgoing_peers
+                .store(max_outgoing_peers, AtomicOrdering::Relaxed);
+            if let Some(max) = max_outgoing_peers_archive {
+                x.max_outgoing_peers_archive
+                    .store(max, AtomicOrdering::Relaxed);
+            }
+            Ok(())
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Return the static P2P peers
+    pub fn get_static_peers(&self) -> Option<Vec<NodeEntry>> {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.static_peers.read().nodes())
+    }
+
+    /// Remove a P2P peer from the static peers and node database, and
+    /// disconnect it if connected. Returns false if the peer is unknown.
+    pub fn remove_peer(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            let is_static = x.static_peers.write().remove(id);
+            let known = x.node_db.read().get(id, false).is_some();
+            let connected = x.sessions.contains_node(id);
+            x.drop_node(*id)?;
+            self.disconnect_node(id, None);
+            Ok(is_static || known || connected)
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Ban a P2P peer permanently and disconnect it if connected. The ban is
+    /// saved in the node database immediately, so that it survives restarts,
+    /// and is only lifted by `unban_peer`. Returns false if the peer is
+    /// already banned.
+    pub fn ban_peer(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            let banned = x.node_db.write().ban(id);
+            x.static_peers.write().remove(id);
+            self.disconnect_node(id, None);
+            Ok(banned)
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Lift the ban of a P2P peer. Returns false if the peer is not banned.
+    pub fn unban_peer(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.node_db.write().unban(id))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Return the reputation of the P2P peers which have misbehaved recently
+    pub fn get_peer_reputations(
+        &self,
+    ) -> Option<Vec<(NodeId, PeerReputation)>> {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.reputation.read().all(Instant::now()))
+    }
+
+    /// Return the P2P peers banned by `ban_peer`
+    pub fn get_banned_peers(&self) -> Option<Vec<NodeId>> {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.node_db.read().banned_nodes())
+    }
+
+    /// Get the public endpoint advertised to other nodes
+    pub fn public_endpoint(&self) -> Option<NodeEndpoint> {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.metadata.public_endpoint.read().clone())
+    }
+
+    /// Get the registered protocols
+    pub fn protocols(&self) -> Option<Vec<ProtocolInfo>> {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.metadata.protocols.read().clone())
+    }
+
     /// Get the local address of the client
     pub fn local_addr(&self) -> Option<SocketAddr> {
         self.inner.as_ref().map(|inner_ref| inner_ref.local_addr())
@@ -426,8 +558,9 @@ pub struct HostMetadata {
     pub local_address: SocketAddr,
     /// Local address + discovery port
     pub local_endpoint: NodeEndpoint,
-    /// Public address + discovery port
-    pub public_endpoint: NodeEndpoint,
+    /// Public address + discovery port, which may change when the NAT port
+    /// mapping is renewed.
+    pub public_endpoint: RwLock<NodeEndpoint>,
 }
 
 impl HostMetadata {
@@ -457,8 +590,20 @@ pub struct NetworkServiceInner {
     timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
     timer_counter: RwLock<usize>,
     pub node_db: RwLock<NodeDatabase>,
-    reserved_nodes: RwLock<HashSet<NodeId>>,
+    /// Peers always connected regardless of the peer limits.
+    static_peers: RwLock<StaticPeers>,
     dropped_nodes: RwLock<HashSet<NodeId>>,
+    /// Scores of misbehaving peers, which control the reconnect backoff, peer
+    /// selection and temporary bans.
+    reputation: RwLock<ReputationTable>,
+    /// Port mapping on the NAT gateway, which is renewed periodically off
+    /// the IO threads.
+    nat_renewal: Mutex<Option<NatRenewal>>,
+    /// Syncs the node lists published in DNS into the node database.
+    dns_discovery: Option<DnsDiscovery>,
+    /// Limits of outgoing peers, which could be updated at runtime.
+    max_outgoing_peers: AtomicUsize,
+    max_outgoing_peers_archive: AtomicUsize,
 
     is_consortium: bool,
 
@@ -574,6 +719,7 @@ impl NetworkServiceInner {
             UdpSocket::bind(&udp_addr).expect("Error binding UDP socket");
 
         let public_address = config.public_address;
+        let mut nat_renewal = None;
         let public_endpoint = match public_address {
             None => {
                 let public_address =
@@ -583,12 +729,20 @@ impl NetworkServiceInner {
                     udp_port: local_endpoint.udp_port,
                 };
                 if config.nat_enabled {
-                    match map_external_address(&local_endpoint, &NatType::Any) {
-                        Some(endpoint) => {
+                    match map_external_address(
+                        &local_endpoint,
+                        &config.nat_type,
+                    ) {
+                        Some(mapping) => {
                             info!(
-                                "NAT mapped to external address {}",
-                                endpoint.address
+                                "NAT mapped to external address {} with {:?}, udp port {}",
+                                mapping.external.address,
+                                mapping.nat_type,
+                                mapping.external.udp_port
                             );
+                            let endpoint = mapping.external.clone();
+                            nat_renewal =
+                                Some(NatRenewal::new(mapping, Instant::now()));
                             endpoint
                         }
                         None => public_endpoint,
@@ -603,12 +757,34 @@ impl NetworkServiceInner {
             },
         };
 
+        // A node listening on an IPv6 address accepts both IPv4 and IPv6
+        // peers, so it also advertises an IPv6 endpoint to IPv6 nodes.
+        let public_endpoint_v6 = if listen_address.is_ipv6() {
+            config
+                .public_address_v6
+                .or_else(|| {
+                    select_public_ipv6_address(local_endpoint.address.port())
+                })
+                .map(|address| NodeEndpoint {
+                    address,
+                    udp_port: local_endpoint.udp_port,
+                })
+        } else {
+            None
+        };
+        if let Some(endpoint) = &public_endpoint_v6 {
+            info!("Public IPv6 endpoint: {:?}", endpoint);
+        }
+
         let allow_ips = config.ip_filter.clone();
         let discovery = {
-            if config.discovery_enabled {
+            // Do not reveal the node to others if only static peers are
+            // allowed.
+            if config.discovery_enabled && !config.static_peers_only {
                 Some(Discovery::new(
                     &keys,
                     public_endpoint.clone(),
+                    public_endpoint_v6,
                     allow_ips,
                     config.discovery_config.clone(),
                 ))
@@ -627,7 +803,7 @@ impl NetworkServiceInner {
                 minimum_peer_protocol_version: Default::default(),
                 local_address: listen_address,
                 local_endpoint,
-                public_endpoint,
+                public_endpoint: RwLock::new(public_endpoint),
             },
             config: config.clone(),
             udp_channel: RwLock::new(UdpChannel::new()),
@@ -648,8 +824,17 @@ impl NetworkServiceInner {
                 nodes_path,
                 config.subnet_quota,
             )),
-            reserved_nodes: RwLock::new(HashSet::new()),
+            static_peers: RwLock::new(StaticPeers::default()),
             dropped_nodes: RwLock::new(HashSet::new()),
+            reputation: RwLock::new(ReputationTable::new(
+                config.reputation_config.clone(),
+            )),
+            nat_renewal: Mutex::new(nat_renewal),
+            dns_discovery: Self::start_dns_discovery(config),
+            max_outgoing_peers: AtomicUsize::new(config.max_outgoing_peers),
+            max_outgoing_peers_archive: AtomicUsize::new(
+                config.max_outgoing_peers_archive,
+            ),
             is_consortium: config.is_consortium,
             delayed_queue: None,
         };
@@ -658,10 +843,9 @@ impl NetworkServiceInner {
             inner.add_boot_node(n);
         }
 
-        let reserved_nodes = config.reserved_nodes.clone();
-        for n in reserved_nodes {
-            if let Err(e) = inner.add_reserved_node(&n) {
-                debug!("Error parsing node id: {}: {:?}", n, e);
+        for n in &config.static_peers {
+            if let Err(e) = inner.add_static_node(n) {
+                warn!("Error parsing static peer {}: {:?}", n, e);
             }
         }
 
@@ -700,6 +884,80 @@ impl NetworkServiceInner {
 
     pub fn get_ip_filter(&self) -> &IpFilter { &self.config.ip_filter }
 
+    fn start_dns_discovery(
+        config: &NetworkConfiguration,
+    ) -> Option<DnsDiscovery> {
+        let urls: Vec<TreeUrl> = config
+            .dns_discovery_urls
+            .iter()
+            .filter_map(|url| match url.parse() {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    warn!("Invalid DNS discovery url {}: {}", url, e);
+                    None
+                }
+            })
+            .collect();
+        if urls.is_empty() || config.static_peers_only {
+            return None;
+        }
+
+        let resolver: Box<dyn TxtResolver> =
+            match config.dns_discovery_nameserver {
+                Some(server) => Box::new(UdpTxtResolver::new(server)),
+                None => match UdpTxtResolver::from_system_config() {
+                    Ok(resolver) => Box::new(resolver),
+                    Err(e) => {
+                        warn!("DNS discovery disabled: {}", e);
+                        return None;
+                    }
+                },
+            };
+        match DnsDiscovery::start(
+            urls,
+            resolver,
+            config.dns_discovery_refresh_interval,
+        ) {
+            Ok(dns_discovery) => Some(dns_discovery),
+            Err(e) => {
+                warn!("Failed to start DNS discovery: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Add the nodes discovered from DNS since the last call to the node
+    /// database as untrusted ones, and ping them so that they are promoted
+    /// to trusted once they respond, like the nodes found by discovery.
+    fn update_dns_discovered_nodes(&self) {
+        if let Some(dns_discovery) = &self.dns_discovery {
+            let nodes: Vec<NodeEntry> = dns_discovery
+                .take_discovered()
+                .into_iter()
+                .filter(|node| {
+                    node.endpoint.is_allowed(&self.config.ip_filter)
+                        && node.id != *self.metadata.id()
+                })
+                .collect();
+            if nodes.is_empty() {
+                return;
+            }
+            debug!("Add {} nodes discovered from DNS", nodes.len());
+            {
+                let mut node_db = self.node_db.write();
+                for node in &nodes {
+                    node_db.insert_untrusted(node.clone());
+                }
+            }
+            if let Some(discovery) = self.discovery.lock().as_mut() {
+                discovery.try_ping_nodes(
+                    &UdpIoContext::new(&self.udp_channel, &self.node_db),
+                    nodes,
+                );
+            }
+        }
+    }
+
     fn add_boot_node(&self, id: &str) {
         match Node::from_str(id) {
             Err(e) => {
@@ -714,13 +972,15 @@ impl NetworkServiceInner {
         }
     }
 
-    fn add_reserved_node(&mut self, id: &str) -> Result<(), Error> {
+    fn add_static_node(&mut self, id: &str) -> Result<(), Error> {
         let n = Node::from_str(id)?;
-        self.node_db.write().insert_trusted(NodeEntry {
-            id: n.id.clone(),
-            endpoint: n.endpoint.clone(),
-        });
-        self.reserved_nodes.write().insert(n.id);
+        self.static_peers.write().insert(
+            NodeEntry {
+                id: n.id,
+                endpoint: n.endpoint,
+            },
+            Instant::now(),
+        );
         Ok(())
     }
 
@@ -753,6 +1013,9 @@ impl NetworkServiceInner {
         }
         io.register_timer(NODE_TABLE, self.config.node_table_timeout)?;
         io.register_timer(CHECK_SESSIONS, DEFAULT_CHECK_SESSIONS_TIMEOUT)?;
+        if self.nat_renewal.lock().is_some() {
+            io.register_timer(NAT_RENEWAL, NAT_RENEWAL_CHECK_INTERVAL)?;
+        }
 
         Ok(())
     }
@@ -807,6 +1070,8 @@ impl NetworkServiceInner {
     }
 
     fn on_housekeeping(&self, io: &IoContext<NetworkIoMessage>) {
+        self.update_dns_discovered_nodes();
+        self.reputation.write().prune(Instant::now());
         if self.is_consortium {
             unimplemented!();
         } else {
@@ -815,7 +1080,7 @@ impl NetworkServiceInner {
         self.drop_peers(io);
     }
 
-    // Connect to all reserved and trusted peers if not yet
+    // Connect to all static and trusted peers if not yet
     fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
         if self.metadata.minimum_peer_protocol_version.read().len() == 0 {
             // The protocol handler has not been registered, we just wait for
@@ -825,18 +1090,23 @@ impl NetworkServiceInner {
 
         let self_id = self.metadata.id().clone();
 
+        self.connect_static_peers(io);
+        if self.config.static_peers_only {
+            return;
+        }
+
         let sampled_archive_nodes = self.sample_archive_nodes();
 
         let (handshake_count, egress_count, ingress_count) =
             self.sessions.stat();
         let samples;
         {
-            let egress_attempt_count = if self.config.max_outgoing_peers
+            let max_outgoing_peers =
+                self.max_outgoing_peers.load(AtomicOrdering::Relaxed);
+            let egress_attempt_count = if max_outgoing_peers
                 > egress_count + sampled_archive_nodes.len()
             {
-                self.config.max_outgoing_peers
-                    - egress_count
-                    - sampled_archive_nodes.len()
+                max_outgoing_peers - egress_count - sampled_archive_nodes.len()
             } else {
                 0
             };
@@ -846,17 +1116,28 @@ impl NetworkServiceInner {
             );
         }
 
-        let reserved_nodes = self.reserved_nodes.read();
-        // Try to connect all reserved peers and trusted peers
-        let nodes = reserved_nodes
-            .iter()
-            .cloned()
-            .chain(sampled_archive_nodes)
-            .chain(samples);
+        // Skip the sampled peers which are banned or in reconnect backoff,
+        // and prefer the ones with better reputation.
+        let samples = {
+            let now = Instant::now();
+            let reputation = self.reputation.read();
+            let mut samples: Vec<NodeId> = sampled_archive_nodes
+                .into_iter()
+                .chain(samples)
+                .filter(|id| reputation.is_connectable(id, now))
+                .collect();
+            samples.sort_by(|a, b| {
+                reputation
+                    .score(b, now)
+                    .total_cmp(&reputation.score(a, now))
+            });
+            samples
+        };
 
         let max_handshakes_per_round = self.config.max_handshakes / 2;
         let mut started: usize = 0;
-        for id in nodes
+        for id in samples
+            .into_iter()
             .filter(|id| !self.sessions.contains_node(id) && *id != self_id)
             .take(min(
                 max_handshakes_per_round,
@@ -880,9 +1161,41 @@ impl NetworkServiceInner {
         }
     }
 
+    /// Connect the static peers which are not connected, regardless of the
+    /// peer limits. Peers that failed to connect are retried with backoff.
+    fn connect_static_peers(&self, io: &IoContext<NetworkIoMessage>) {
+        let now = Instant::now();
+        // Sessions are locked before the static peers in the handshake, so
+        // they are checked without holding the lock of static peers.
+        let mut connected = HashSet::new();
+        let mut ready = Vec::new();
+        for node in self.static_peers.read().nodes() {
+            if let Some(session) = self.sessions.get_by_id(&node.id) {
+                if session.read().is_ready() {
+                    ready.push(node.id);
+                }
+                connected.insert(node.id);
+            }
+        }
+        let due = {
+            let mut static_peers = self.static_peers.write();
+            for id in &ready {
+                static_peers.note_connected(id, now);
+            }
+            static_peers.take_due(now, |id| connected.contains(id))
+        };
+        for id in due {
+            debug!("Connecting static peer {:?}", id);
+            self.connect_peer(&id, io);
+        }
+    }
+
     /// Sample archive nodes for outgoing connections if not enough.
     fn sample_archive_nodes(&self) -> HashSet<NodeId> {
-        if self.config.max_outgoing_peers_archive == 0 {
+        let max_outgoing_peers_archive = self
+            .max_outgoing_peers_archive
+            .load(AtomicOrdering::Relaxed);
+        if max_outgoing_peers_archive == 0 {
             return HashSet::new();
         }
 
@@ -890,12 +1203,12 @@ impl NetworkServiceInner {
         let value: String = NODE_TAG_ARCHIVE.into();
         let archive_sessions = self.sessions.count_with_tag(&key, &value);
 
-        if archive_sessions >= self.config.max_outgoing_peers_archive {
+        if archive_sessions >= max_outgoing_peers_archive {
             return HashSet::new();
         }
 
         self.node_db.read().sample_trusted_node_ids_with_tag(
-            (self.config.max_outgoing_peers_archive - archive_sessions) as u32,
+            (max_outgoing_peers_archive - archive_sessions) as u32,
             &key,
             &value,
         )
@@ -934,10 +1247,19 @@ impl NetworkServiceInner {
             return;
         }
 
+        let static_address = self
+            .static_peers
+            .read()
+            .get(id)
+            .map(|node| node.endpoint.address);
+        let is_static = static_address.is_some();
         let (socket, address) = {
             let address = {
-                // outgoing connection must pick node from trusted node table
-                if let Some(node) = self.node_db.read().get(id, true) {
+                // outgoing connection must pick node from static peers or
+                // trusted node table
+                if let Some(address) = static_address {
+                    address
+                } else if let Some(node) = self.node_db.read().get(id, true) {
                     node.endpoint.address
                 } else {
                     debug!("Abort connect. Node expired");
@@ -945,7 +1267,7 @@ impl NetworkServiceInner {
                 }
             };
 
-            if !self.sessions.is_ip_allowed(&address.ip()) {
+            if !is_static && !self.sessions.is_ip_allowed(&address.ip()) {
                 debug!("cannot create outgoing connection to node, id = {:?}, address = {:?}", id, address);
                 return;
             }
@@ -969,7 +1291,9 @@ impl NetworkServiceInner {
             }
         };
 
-        if let Err(e) = self.create_connection(socket, address, Some(id), io) {
+        if let Err(e) =
+            self.create_connection(socket, address, Some(id), is_static, io)
+        {
             self.node_db.write().note_failure(
                 id, true, /* by_connection */
                 true, /* trusted_only */
@@ -1021,11 +1345,15 @@ impl NetworkServiceInner {
     // This function can be invoked in either of 2 cases:
     // 1. proactively connect to a peer;
     // 2. passively connected by a peer;
+    // Connections of static peers are not limited by the peer limits.
     fn create_connection(
         &self, socket: TcpStream, address: SocketAddr, id: Option<&NodeId>,
-        io: &IoContext<NetworkIoMessage>,
+        is_static: bool, io: &IoContext<NetworkIoMessage>,
     ) -> Result<(), Error> {
-        match self.sessions.create(socket, address, id, io, self) {
+        match self
+            .sessions
+            .create(socket, address, id, is_static, io, self)
+        {
             Ok(token) => {
                 debug!("new session created, token = {}, address = {:?}, id = {:?}", token, address, id);
                 if let Some(id) = id {
@@ -1232,7 +1560,7 @@ impl NetworkServiceInner {
         trace!("Accepting incoming connection");
         loop {
             let (socket, address) = match self.tcp_listener.lock().accept() {
-                Ok((sock, addr)) => (sock, addr),
+                Ok((sock, addr)) => (sock, canonical_socket_addr(addr)),
                 Err(e) => {
                     if e.kind() != io::ErrorKind::WouldBlock {
                         debug!("Error accepting connection: {:?}", e);
@@ -1241,12 +1569,52 @@ impl NetworkServiceInner {
                 }
             };
 
-            if let Err(e) = self.create_connection(socket, address, None, io) {
+            // The node id is unknown before the handshake, so incoming
+            // connections of static peers are recognized by IP address, and
+            // verified by node id once the handshake completes.
+            let is_static = self.static_peers.read().contains_ip(&address.ip());
+            if let Err(e) =
+                self.create_connection(socket, address, None, is_static, io)
+            {
                 debug!("Can't accept connection: {:?}", e);
             }
         }
     }
 
+    /// Lower the reputation of a peer, and disconnect it if it gets banned.
+    /// Static peers are never banned.
+    fn report_peer(
+        &self, node_id: &NodeId, event: ReputationEvent,
+        io: &IoContext<NetworkIoMessage>,
+    ) {
+        let banned =
+            self.reputation
+                .write()
+                .report(node_id, event, Instant::now());
+        debug!("Peer {:?} reported for {:?}", node_id, event);
+        if banned && !self.static_peers.read().contains(node_id) {
+            info!("Peer {:?} banned for low reputation", node_id);
+            self.kill_connection(node_id, io, true, None, "low reputation");
+        }
+    }
+
+    /// Whether the peer is banned for low reputation.
+    pub fn is_peer_banned(&self, node_id: &NodeId) -> bool {
+        self.reputation.read().is_banned(node_id, Instant::now())
+            && !self.static_peers.read().contains(node_id)
+    }
+
+    pub fn is_static_peer(&self, node_id: &NodeId) -> bool {
+        self.static_peers.read().contains(node_id)
+    }
+
+    /// Whether an incoming connection of the peer is allowed, i.e. it is a
+    /// static peer if only static peers are allowed.
+    pub fn is_peer_allowed(&self, node_id: &NodeId) -> bool {
+        !self.config.static_peers_only
+            || self.static_peers.read().contains(node_id)
+    }
+
     fn kill_connection_by_token(
         &self, token: StreamToken, io: &IoContext<NetworkIoMessage>,
         remote: bool, op: Option<UpdateNodeOperation>, reason: &str,
@@ -1415,7 +1783,7 @@ impl NetworkServiceInner {
         let mut buf = [0u8; MAX_DATAGRAM_SIZE];
         match udp_socket.recv_from(&mut buf) {
             Ok(Some((len, address))) => self
-                .on_udp_packet(&buf[0..len], address)
+                .on_udp_packet(&buf[0..len], canonical_socket_addr(address))
                 .unwrap_or_else(|e| {
                     debug!("Error processing UDP packet: {:?}", e);
                 }),
@@ -1444,8 +1812,10 @@ impl NetworkServiceInner {
     fn udp_writable(&self, io: &IoContext<NetworkIoMessage>) {
         let udp_socket = self.udp_socket.lock();
         let mut udp_channel = self.udp_channel.write();
+        let ipv6_socket = self.metadata.local_endpoint.address.is_ipv6();
         while let Some(data) = udp_channel.dequeue_send() {
-            match udp_socket.send_to(&data.payload, &data.address) {
+            let address = to_socket_family(data.address, ipv6_socket);
+            match udp_socket.send_to(&data.payload, &address) {
                 Ok(Some(size)) if size == data.payload.len() => {}
                 Ok(Some(_)) => {
                     warn!("UDP sent incomplete datagram");
@@ -1519,6 +1889,35 @@ impl NetworkServiceInner {
             );
         }
     }
+
+    /// Renew the NAT port mapping before its lease expires, and advertise
+    /// the new external endpoint if it changed. The renewal itself runs on a
+    /// background thread, so this never blocks the IO thread.
+    fn on_nat_renewal(&self) {
+        let (previous, external) = {
+            let mut nat_renewal = self.nat_renewal.lock();
+            let renewal = match nat_renewal.as_mut() {
+                Some(renewal) => renewal,
+                None => return,
+            };
+            let previous = renewal.mapping().external.address;
+            match renewal
+                .tick(Instant::now(), |mapping| renew_port_mapping(&mapping))
+            {
+                Some(external) => (previous, external),
+                None => return,
+            }
+        };
+
+        info!(
+            "NAT mapped external address changed from {} to {}, udp port {}",
+            previous, external.address, external.udp_port
+        );
+        *self.metadata.public_endpoint.write() = external.clone();
+        if let Some(discovery) = self.discovery.lock().as_mut() {
+            discovery.set_public_endpoint(external);
+        }
+    }
 }
 
 impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
@@ -1578,11 +1977,12 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the _slow_ discovery if enough peers are connected
                 let disc_general = self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
-                    self.config.max_outgoing_peers_archive,
+                    self.max_outgoing_peers_archive
+                        .load(AtomicOrdering::Relaxed),
                 );
                 if disc_general || disc_archive {
                     self.discovery.lock().as_mut().map(|d| {
@@ -1599,11 +1999,12 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the fast discovery if not enough peers are connected
                 let disc_general = !self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = !self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
-                    self.config.max_outgoing_peers_archive,
+                    self.max_outgoing_peers_archive
+                        .load(AtomicOrdering::Relaxed),
                 );
                 if disc_general || disc_archive {
                     self.discovery.lock().as_mut().map(|d| {
@@ -1633,6 +2034,7 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 self.node_db.write().save();
             }
             CHECK_SESSIONS => self.on_check_sessions(io),
+            NAT_RENEWAL => self.on_nat_renewal(),
             SEND_DELAYED_MESSAGES => {
                 if let Some(ref queue) = self.delayed_queue {
                     queue.send_delayed_messages(self);
@@ -2042,6 +2444,10 @@ impl<'a> NetworkContextTrait for NetworkContext<'a> {
             .kill_connection(node_id, self.io, true, op, reason);
     }
 
+    fn report_peer(&self, node_id: &NodeId, event: ReputationEvent) {
+        self.network_service.report_peer(node_id, event, self.io);
+    }
+
     fn register_timer(
         &self, token: TimerToken, delay: Duration,
     ) -> Result<(), Error> {
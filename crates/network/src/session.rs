@@ -3,15 +3,22 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    compression::EXTENSION_SNAPPY,
     connection::{Connection, ConnectionDetails, SendQueueStatus, WriteStatus},
     handshake::Handshake,
+    keylib::{Generator, KeyPair, Random},
     node_table::{NodeEndpoint, NodeEntry, NodeId},
     parse_msg_id_leb128_2_bytes_at_most,
     service::{NetworkServiceInner, ProtocolVersion},
+    session_cipher::{
+        encryption_extension, parse_encryption_extension, SessionCipher,
+        SessionKeyMaterial,
+    },
     DisconnectReason, Error, ProtocolId, ProtocolInfo, SessionMetadata,
     UpdateNodeOperation, PROTOCOL_ID_SIZE,
 };
 use bytes::Bytes;
+use cfx_types::H256;
 use cfx_util_macros::bail;
 use diem_crypto::{bls::BLS_PUBLIC_KEY_LENGTH, ValidCryptoMaterial};
 use diem_types::validator_config::{ConsensusPublicKey, ConsensusVRFPublicKey};
@@ -37,9 +44,14 @@ use std::{
 /// packet to exchange the supported protocols. Then, session is ready to send
 /// and receive protocol packets.
 ///
-/// Conflux do not use AES based encrypted connection to send protocol packets.
-/// This is because that Conflux has high TPS, and the encryption/decryption
-/// workloads are very heavy (about 20% CPU time in 3000 TPS).
+/// By default, Conflux do not use AES based encrypted connection to send
+/// protocol packets. This is because that Conflux has high TPS, and the
+/// encryption/decryption workloads are very heavy (about 20% CPU time in 3000
+/// TPS). If `NetworkConfiguration::session_encryption` is enabled, the support
+/// of ChaCha20-Poly1305 is advertised in the Hello packet, and packets after
+/// Hello are encrypted if the remote peer also supports it. Similarly, packets
+/// are compressed with snappy if `NetworkConfiguration::session_compression`
+/// is enabled on both sides.
 pub struct Session {
     /// Session information
     pub metadata: SessionMetadata,
@@ -58,6 +70,14 @@ pub struct Session {
     last_read: Instant,
     last_write: (Instant, WriteStatus),
     pos_public_key: Option<(ConsensusPublicKey, ConsensusVRFPublicKey)>,
+    /// Local and remote nonces exchanged in handshake, which are used to
+    /// derive the keys of session encryption.
+    handshake_nonces: Option<(H256, H256)>,
+    /// The ephemeral key advertised in the Hello packet for the session
+    /// encryption.
+    ephemeral_key: Option<KeyPair>,
+    /// Whether the session compression is advertised in the Hello packet.
+    compression_advertised: bool,
 }
 
 /// Session state.
@@ -131,6 +151,9 @@ impl Session {
             last_read: Instant::now(),
             last_write: (Instant::now(), WriteStatus::Complete),
             pos_public_key,
+            handshake_nonces: None,
+            ephemeral_key: None,
+            compression_advertised: false,
         })
     }
 
@@ -201,7 +224,8 @@ impl Session {
     }
 
     /// Complete the handshake process:
-    /// 1. For incoming session, check if the remote peer is blacklisted.
+    /// 1. For incoming session, check if the remote peer is blacklisted or
+    /// banned.
     /// 2. Change the session state to `State::Session`.
     /// 3. Send Hello packet to remote peer.
     fn complete_handshake<Message>(
@@ -217,14 +241,41 @@ impl Session {
         if self.metadata.id.is_none() {
             let id = wrapper.get().id.clone();
 
-            // refuse incoming session if the node is blacklisted
-            if host.node_db.write().evaluate_blacklisted(&id) {
+            // refuse incoming session if the node is blacklisted or banned
+            // for low reputation
+            if host.node_db.write().evaluate_blacklisted(&id)
+                || host.is_peer_banned(&id)
+            {
                 return Err(self.send_disconnect(DisconnectReason::Blacklisted));
             }
 
+            // refuse incoming session if only static peers are allowed
+            if !host.is_peer_allowed(&id) {
+                return Err(self.send_disconnect(DisconnectReason::UselessPeer));
+            }
+
+            // incoming sessions from the IP of a static peer are exempted
+            // from the limits, which apply again if the node id is not one
+            if !host.is_static_peer(&id) {
+                if let Err(reason) = host
+                    .sessions
+                    .revoke_exemption(self.token(), &self.address.ip())
+                {
+                    debug!(
+                        "refuse ingress session not from static peer, reason = {:?}, id = {:?}",
+                        reason, id
+                    );
+                    return Err(
+                        self.send_disconnect(DisconnectReason::IpLimited)
+                    );
+                }
+            }
+
             self.metadata.id = Some(id);
         }
 
+        self.handshake_nonces = wrapper.get().nonces();
+
         // write HELLO packet to remote peer
         self.state = State::Session(wrapper.take().connection);
         self.write_hello(io, host)?;
@@ -313,6 +364,7 @@ impl Session {
                 // Handle Hello packet to exchange protocols
                 let rlp = Rlp::new(&packet.data);
                 let pos_public_key = self.read_hello(&rlp, host)?;
+                self.negotiate_extensions(&packet.extensions, host)?;
                 Ok(SessionDataWithDisconnectInfo {
                     session_data: SessionData::Ready { pos_public_key },
                     token_to_disconnect,
@@ -487,11 +539,80 @@ impl Session {
         }
     }
 
+    /// Enable the session encryption and compression if both peers advertised
+    /// the support of them in Hello packets. They are switched on by the
+    /// connection with a marker in the stream of each direction.
+    fn negotiate_extensions(
+        &mut self, hello_extensions: &[Vec<u8>], host: &NetworkServiceInner,
+    ) -> Result<(), Error> {
+        if self.connection().is_encrypted() || self.connection().is_compressed()
+        {
+            return Ok(());
+        }
+
+        let compression = self.compression_advertised
+            && hello_extensions
+                .iter()
+                .any(|ext| ext[..] == [EXTENSION_SNAPPY]);
+
+        let remote_ephemeral_public = hello_extensions
+            .iter()
+            .find_map(|ext| parse_encryption_extension(ext));
+        let cipher = match (&self.ephemeral_key, remote_ephemeral_public) {
+            (Some(ephemeral_key), Some(remote_ephemeral_public)) => {
+                let (local_nonce, remote_nonce) = self
+                    .handshake_nonces
+                    .expect("encryption advertised with handshake nonces");
+                let remote_public = self
+                    .metadata
+                    .id
+                    .expect("should have node id after handshake");
+                Some(SessionCipher::new(&SessionKeyMaterial {
+                    originated: self.metadata.originated,
+                    local_secret: host.metadata.secret(),
+                    local_ephemeral_secret: ephemeral_key.secret(),
+                    local_nonce: &local_nonce,
+                    remote_public: &remote_public,
+                    remote_ephemeral_public: &remote_ephemeral_public,
+                    remote_nonce: &remote_nonce,
+                })?)
+            }
+            (Some(_), None) => {
+                debug!(
+                    "Remote peer does not support encryption, session = {:?}",
+                    self
+                );
+                None
+            }
+            (None, _) => None,
+        };
+
+        if cipher.is_none() && !compression {
+            return Ok(());
+        }
+        self.connection_mut()
+            .enable_session_modes(cipher, compression);
+        debug!(
+            "Session modes enabled, encrypted = {}, compressed = {}, session = {:?}",
+            self.connection().is_encrypted(),
+            compression,
+            self
+        );
+        Ok(())
+    }
+
     /// Assemble a packet with specified protocol id, packet id and data.
     /// Return concrete error if session is expired or the protocol id is
     /// invalid.
     fn prepare_packet(
         &self, protocol: Option<ProtocolId>, packet_id: u8, data: Vec<u8>,
+    ) -> Result<Vec<u8>, Error> {
+        self.prepare_packet_with_extensions(protocol, packet_id, data, &[])
+    }
+
+    fn prepare_packet_with_extensions(
+        &self, protocol: Option<ProtocolId>, packet_id: u8, data: Vec<u8>,
+        extensions: &[Vec<u8>],
     ) -> Result<Vec<u8>, Error> {
         if protocol.is_some() && self.had_hello.is_none() {
             debug!(
@@ -509,11 +630,12 @@ impl Session {
             return Err(Error::Expired.into());
         }
 
-        Ok(SessionPacket::assemble(
+        Ok(SessionPacket::assemble_with_extensions(
             packet_id,
             self.metadata.peer_header_version,
             protocol,
             data,
+            extensions,
         ))
     }
 
@@ -595,22 +717,35 @@ impl Session {
         let mut rlp = RlpStream::new_list(4);
         rlp.append(&host.metadata.network_id);
         rlp.append_list(&*host.metadata.protocols.read());
-        host.metadata.public_endpoint.to_rlp_list(&mut rlp);
+        host.metadata.public_endpoint.read().to_rlp_list(&mut rlp);
         let mut key_bytes =
             self.pos_public_key.as_ref().unwrap().0.to_bytes().to_vec();
         key_bytes.append(
             &mut self.pos_public_key.as_ref().unwrap().1.to_bytes().to_vec(),
         );
         rlp.append(&key_bytes);
-        self.send_packet(
-            io,
+
+        // Peers that do not support the extensions just ignore them.
+        let mut extensions = Vec::new();
+        if host.config.session_encryption && self.handshake_nonces.is_some() {
+            let ephemeral_key = Random.generate()?;
+            extensions.push(encryption_extension(ephemeral_key.public()));
+            self.ephemeral_key = Some(ephemeral_key);
+        }
+        self.compression_advertised = host.config.session_compression;
+        if self.compression_advertised {
+            extensions.push(vec![EXTENSION_SNAPPY]);
+        }
+
+        let packet = self.prepare_packet_with_extensions(
             None,
-            ProtocolVersion::default(),
             PACKET_HELLO,
             rlp.drain(),
-            SendQueuePriority::High,
-        )
-        .map(|_| ())
+            &extensions,
+        )?;
+        self.connection_mut()
+            .send(io, packet, SendQueuePriority::High)
+            .map(|_| ())
     }
 
     /// Writable IO handler. Sends pending packets.
@@ -752,17 +887,36 @@ struct SessionPacket {
 
 impl SessionPacket {
     // data + Option<protocol> + protocol_flag + packet_id
+    #[cfg(test)]
     fn assemble(
+        id: u8, header_version: u8, protocol: Option<ProtocolId>, data: Vec<u8>,
+    ) -> Vec<u8> {
+        Self::assemble_with_extensions(id, header_version, protocol, data, &[])
+    }
+
+    // data + extensions + Option<protocol> + header_byte + packet_id
+    fn assemble_with_extensions(
         id: u8, header_version: u8, protocol: Option<ProtocolId>,
-        mut data: Vec<u8>,
+        mut data: Vec<u8>, extensions: &[Vec<u8>],
     ) -> Vec<u8> {
+        // The extensions are parsed from the end, so the first extension is
+        // appended at last.
+        for (i, extension) in extensions.iter().enumerate().rev() {
+            assert!(extension.len() < 1 << 7);
+            let has_next_extension = (i + 1 < extensions.len()) as u8;
+            data.extend_from_slice(extension);
+            data.push(((extension.len() as u8) << 1) + has_next_extension);
+        }
+
         let mut protocol_flag = 0;
         if let Some(protocol) = protocol {
             data.extend_from_slice(&protocol);
             protocol_flag = 1;
         }
 
-        let header_byte = (header_version << 1) + protocol_flag;
+        let has_extension = !extensions.is_empty() as u8;
+        let header_byte =
+            (has_extension << 4) + (header_version << 1) + protocol_flag;
         data.push(header_byte);
         data.push(id);
 
@@ -875,6 +1029,7 @@ impl fmt::Debug for SessionPacket {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session_cipher::EXTENSION_CHACHA20_POLY1305;
 
     #[test]
     fn test_packet_assemble() {
@@ -891,6 +1046,30 @@ mod tests {
         assert_eq!(packet, vec![2, 4, 8, 8, 8, 1, 6]);
     }
 
+    #[test]
+    fn test_packet_extensions() {
+        let packet = SessionPacket::assemble_with_extensions(
+            PACKET_HELLO,
+            PACKET_HEADER_VERSION,
+            None,
+            vec![1, 3],
+            &[vec![EXTENSION_CHACHA20_POLY1305], vec![5, 6]],
+        );
+        assert_eq!(packet, vec![1, 3, 5, 6, 4, 1, 3, 0x10, PACKET_HELLO]);
+
+        let packet = SessionPacket::parse(packet.into()).unwrap();
+        assert_eq!(
+            packet,
+            SessionPacket {
+                id: PACKET_HELLO,
+                header_version: 0,
+                protocol: None,
+                data: vec![1, 3].into(),
+                extensions: vec![vec![EXTENSION_CHACHA20_POLY1305], vec![5, 6]],
+            }
+        );
+    }
+
     #[test]
     fn test_packet_parse() {
         // packet id missed
@@ -0,0 +1,318 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Optional authenticated encryption of session packets.
+//!
+//! Peers advertise the support of encryption with an extension in the Hello
+//! packet, which also carries an ephemeral public key of the session. If both
+//! peers support it, packets are encrypted with ChaCha20-Poly1305. The key of
+//! each direction is derived from two ECDH shared secrets: one of the node
+//! keys, which are authenticated by the handshake, and one of the ephemeral
+//! keys, so that recorded sessions can not be decrypted even if the node keys
+//! are leaked later. The nonce of each packet is a counter, so packets must be
+//! sealed in the same order as they are written to the socket.
+
+use crate::{
+    hash::keccak,
+    keylib::{crypto::ecdh, Public, Secret},
+    Error,
+};
+use bytes::BytesMut;
+use cfx_types::H256;
+use chacha20poly1305::{
+    aead::{AeadInPlace, KeyInit},
+    ChaCha20Poly1305, Key, Nonce, Tag,
+};
+
+/// Hello packet extension to advertise the support of ChaCha20-Poly1305,
+/// followed by the ephemeral public key of the session.
+pub const EXTENSION_CHACHA20_POLY1305: u8 = 0x01;
+
+/// Size of the authentication tag appended to every encrypted packet.
+pub const TAG_SIZE: usize = 16;
+
+const INITIATOR_TO_RESPONDER: &[u8] = b"conflux-session-initiator";
+const RESPONDER_TO_INITIATOR: &[u8] = b"conflux-session-responder";
+
+/// Returns the Hello packet extension with the ephemeral public key.
+pub fn encryption_extension(ephemeral_public: &Public) -> Vec<u8> {
+    let mut extension = Vec::with_capacity(1 + Public::len_bytes());
+    extension.push(EXTENSION_CHACHA20_POLY1305);
+    extension.extend_from_slice(ephemeral_public.as_bytes());
+    extension
+}
+
+/// Returns the ephemeral public key if `extension` is the encryption
+/// extension.
+pub fn parse_encryption_extension(extension: &[u8]) -> Option<Public> {
+    match extension.split_first() {
+        Some((&EXTENSION_CHACHA20_POLY1305, public))
+            if public.len() == Public::len_bytes() =>
+        {
+            Some(Public::from_slice(public))
+        }
+        _ => None,
+    }
+}
+
+/// The keys and nonces of both peers to derive the session keys.
+pub struct SessionKeyMaterial<'a> {
+    /// Whether the local node initiated the handshake.
+    pub originated: bool,
+    pub local_secret: &'a Secret,
+    pub local_ephemeral_secret: &'a Secret,
+    pub local_nonce: &'a H256,
+    pub remote_public: &'a Public,
+    pub remote_ephemeral_public: &'a Public,
+    pub remote_nonce: &'a H256,
+}
+
+struct DirectionalCipher {
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl DirectionalCipher {
+    fn new(
+        label: &[u8], shared_secrets: &[&Secret], initiator_nonce: &H256,
+        responder_nonce: &H256,
+    ) -> Self {
+        let mut seed = Vec::with_capacity(
+            label.len()
+                + shared_secrets.len() * H256::len_bytes()
+                + 2 * H256::len_bytes(),
+        );
+        seed.extend_from_slice(label);
+        for secret in shared_secrets {
+            seed.extend_from_slice(secret.as_bytes());
+        }
+        seed.extend_from_slice(initiator_nonce.as_bytes());
+        seed.extend_from_slice(responder_nonce.as_bytes());
+        let key = keccak(&seed);
+
+        DirectionalCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key.as_bytes())),
+            counter: 0,
+        }
+    }
+
+    fn next_nonce(&mut self) -> Result<Nonce, Error> {
+        // Never reuse a nonce with the same key.
+        let counter = self.counter;
+        self.counter = counter.checked_add(1).ok_or(Error::BadProtocol)?;
+
+        let mut nonce = Nonce::default();
+        nonce[4..].copy_from_slice(&counter.to_le_bytes());
+        Ok(nonce)
+    }
+}
+
+/// Per-session cipher, which holds independent keys and counters for the
+/// egress and ingress directions.
+pub struct SessionCipher {
+    egress: DirectionalCipher,
+    ingress: DirectionalCipher,
+}
+
+impl SessionCipher {
+    /// Create the cipher from the node keys, the ephemeral keys and the
+    /// handshake nonces of the local and remote node.
+    pub fn new(keys: &SessionKeyMaterial) -> Result<Self, Error> {
+        let static_shared = ecdh::agree(keys.local_secret, keys.remote_public)?;
+        let ephemeral_shared = ecdh::agree(
+            keys.local_ephemeral_secret,
+            keys.remote_ephemeral_public,
+        )?;
+        let shared_secrets = [&static_shared, &ephemeral_shared];
+
+        let (initiator_nonce, responder_nonce) = if keys.originated {
+            (keys.local_nonce, keys.remote_nonce)
+        } else {
+            (keys.remote_nonce, keys.local_nonce)
+        };
+
+        let to_responder = DirectionalCipher::new(
+            INITIATOR_TO_RESPONDER,
+            &shared_secrets,
+            initiator_nonce,
+            responder_nonce,
+        );
+        let to_initiator = DirectionalCipher::new(
+            RESPONDER_TO_INITIATOR,
+            &shared_secrets,
+            initiator_nonce,
+            responder_nonce,
+        );
+
+        Ok(if keys.originated {
+            SessionCipher {
+                egress: to_responder,
+                ingress: to_initiator,
+            }
+        } else {
+            SessionCipher {
+                egress: to_initiator,
+                ingress: to_responder,
+            }
+        })
+    }
+
+    /// Encrypt the packet in place and append the authentication tag.
+    pub fn seal(&mut self, data: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce = self.egress.next_nonce()?;
+        let tag = self
+            .egress
+            .cipher
+            .encrypt_in_place_detached(&nonce, &[], data)
+            .map_err(|_| Error::OversizedPacket)?;
+        data.extend_from_slice(&tag);
+        Ok(())
+    }
+
+    /// Verify and decrypt the packet in place, and remove the authentication
+    /// tag.
+    pub fn open(&mut self, data: &mut BytesMut) -> Result<(), Error> {
+        if data.len() < TAG_SIZE {
+            return Err(Error::BadProtocol);
+        }
+
+        let nonce = self.ingress.next_nonce()?;
+        let tag = data.split_off(data.len() - TAG_SIZE);
+        self.ingress
+            .cipher
+            .decrypt_in_place_detached(&nonce, &[], data, Tag::from_slice(&tag))
+            .map_err(|_| Error::Auth)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::keylib::{Generator, Random};
+
+    pub(crate) fn session_ciphers() -> (SessionCipher, SessionCipher) {
+        let initiator = Random.generate().unwrap();
+        let initiator_ephemeral = Random.generate().unwrap();
+        let initiator_nonce = H256::random();
+        let responder = Random.generate().unwrap();
+        let responder_ephemeral = Random.generate().unwrap();
+        let responder_nonce = H256::random();
+        (
+            SessionCipher::new(&SessionKeyMaterial {
+                originated: true,
+                local_secret: initiator.secret(),
+                local_ephemeral_secret: initiator_ephemeral.secret(),
+                local_nonce: &initiator_nonce,
+                remote_public: responder.public(),
+                remote_ephemeral_public: responder_ephemeral.public(),
+                remote_nonce: &responder_nonce,
+            })
+            .unwrap(),
+            SessionCipher::new(&SessionKeyMaterial {
+                originated: false,
+                local_secret: responder.secret(),
+                local_ephemeral_secret: responder_ephemeral.secret(),
+                local_nonce: &responder_nonce,
+                remote_public: initiator.public(),
+                remote_ephemeral_public: initiator_ephemeral.public(),
+                remote_nonce: &initiator_nonce,
+            })
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let (mut initiator, mut responder) = session_ciphers();
+
+        for i in 0..3u8 {
+            let plain = vec![i; 100];
+            let mut data = plain.clone();
+            initiator.seal(&mut data).unwrap();
+            assert_eq!(data.len(), plain.len() + TAG_SIZE);
+            assert_ne!(&data[..plain.len()], &plain[..]);
+
+            let mut buf = BytesMut::from(&data[..]);
+            responder.open(&mut buf).unwrap();
+            assert_eq!(&buf[..], &plain[..]);
+        }
+
+        // the other direction uses another key
+        let mut data = vec![7; 10];
+        responder.seal(&mut data).unwrap();
+        let mut buf = BytesMut::from(&data[..]);
+        initiator.open(&mut buf).unwrap();
+        assert_eq!(&buf[..], &[7; 10]);
+    }
+
+    #[test]
+    fn test_open_tampered() {
+        let (mut initiator, mut responder) = session_ciphers();
+
+        let mut data = vec![1, 2, 3];
+        initiator.seal(&mut data).unwrap();
+        data[0] ^= 1;
+        assert!(responder.open(&mut BytesMut::from(&data[..])).is_err());
+
+        // too short to contain the tag
+        assert!(responder.open(&mut BytesMut::from(&[0u8; 4][..])).is_err());
+    }
+
+    #[test]
+    fn test_open_out_of_order() {
+        let (mut initiator, mut responder) = session_ciphers();
+
+        let mut first = vec![1];
+        initiator.seal(&mut first).unwrap();
+        let mut second = vec![2];
+        initiator.seal(&mut second).unwrap();
+
+        assert!(responder.open(&mut BytesMut::from(&second[..])).is_err());
+    }
+
+    #[test]
+    fn test_key_depends_on_secrets() {
+        // A third party that observed the ephemeral public keys and the
+        // nonces, but does not know the node secrets, derives other keys.
+        let initiator = Random.generate().unwrap();
+        let ephemeral = Random.generate().unwrap();
+        let responder = Random.generate().unwrap();
+        let remote_ephemeral = Random.generate().unwrap();
+        let eavesdropper = Random.generate().unwrap();
+        let nonces = (H256::random(), H256::random());
+        let keys = |local_secret| SessionKeyMaterial {
+            originated: true,
+            local_secret,
+            local_ephemeral_secret: ephemeral.secret(),
+            local_nonce: &nonces.0,
+            remote_public: responder.public(),
+            remote_ephemeral_public: remote_ephemeral.public(),
+            remote_nonce: &nonces.1,
+        };
+        let mut sender = SessionCipher::new(&keys(initiator.secret())).unwrap();
+        let mut guess =
+            SessionCipher::new(&keys(eavesdropper.secret())).unwrap();
+
+        let mut data = vec![1, 2, 3];
+        sender.seal(&mut data).unwrap();
+        let mut guessed = vec![1, 2, 3];
+        guess.seal(&mut guessed).unwrap();
+        assert_ne!(data, guessed);
+    }
+
+    #[test]
+    fn test_encryption_extension() {
+        let ephemeral = Random.generate().unwrap();
+        let extension = encryption_extension(ephemeral.public());
+        assert_eq!(
+            parse_encryption_extension(&extension),
+            Some(*ephemeral.public())
+        );
+        assert_eq!(
+            parse_encryption_extension(&[EXTENSION_CHACHA20_POLY1305]),
+            None
+        );
+        assert_eq!(parse_encryption_extension(&extension[1..]), None);
+    }
+}
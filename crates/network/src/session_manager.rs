@@ -33,6 +33,8 @@ use std::{
 ///
 /// The session manager also limits the maximum number of incoming TCP
 /// connections, so as to establish some trusted outgoing connections.
+///
+/// Sessions of static peers are exempted from both limits.
 pub struct SessionManager {
     sessions: RwLock<Slab<Arc<RwLock<Session>>>>,
     capacity: usize,
@@ -43,12 +45,14 @@ pub struct SessionManager {
     offset: usize,
 
     /// used to limit the ingress sessions.
-    max_ingress_sessions: usize,
+    max_ingress_sessions: AtomicUsize,
     cur_ingress_sessions: AtomicUsize,
 
     /// session indices
     node_id_index: RwLock<HashMap<NodeId, usize>>,
     ip_limit: RwLock<Box<dyn SessionIpLimit>>,
+    /// sessions not counted in the ingress and IP limits
+    exempted_sessions: RwLock<HashSet<usize>>,
     tag_index: RwLock<SessionTagIndex>,
     /// pos public key
     pub self_pos_public_key:
@@ -69,10 +73,11 @@ impl SessionManager {
             sessions: RwLock::new(Slab::with_capacity(capacity)),
             offset,
             capacity,
-            max_ingress_sessions,
+            max_ingress_sessions: AtomicUsize::new(max_ingress_sessions),
             cur_ingress_sessions: AtomicUsize::new(0),
             node_id_index: RwLock::new(HashMap::new()),
             ip_limit: RwLock::new(new_session_ip_limit(ip_limit_config)),
+            exempted_sessions: Default::default(),
             tag_index: Default::default(),
             self_pos_public_key,
         }
@@ -81,6 +86,13 @@ impl SessionManager {
     /// Get the number of sessions in `SessionManager`.
     pub fn count(&self) -> usize { self.sessions.read().len() }
 
+    /// Update the maximum number of ingress sessions. Existing sessions are
+    /// kept if the limit decreases.
+    pub fn set_max_ingress_sessions(&self, max_ingress_sessions: usize) {
+        self.max_ingress_sessions
+            .store(max_ingress_sessions, Ordering::Relaxed);
+    }
+
     /// Get the session of specified index.
     pub fn get(&self, idx: usize) -> Option<Arc<RwLock<Session>>> {
         self.sessions.read().get(idx).cloned()
@@ -151,14 +163,16 @@ impl SessionManager {
     }
 
     /// Creates a new session with specified TCP socket. It is egress connection
-    /// if the `id` is not `None`, otherwise it is ingress connection.
+    /// if the `id` is not `None`, otherwise it is ingress connection. The
+    /// session is not limited by the ingress and IP limits if `exempted`.
     pub fn create(
         &self, socket: TcpStream, address: SocketAddr, id: Option<&NodeId>,
-        io: &IoContext<NetworkIoMessage>, host: &NetworkServiceInner,
+        exempted: bool, io: &IoContext<NetworkIoMessage>,
+        host: &NetworkServiceInner,
     ) -> Result<usize, String> {
         debug!(
-            "SessionManager.create: enter, address = {:?}, id = {:?}",
-            address, id
+            "SessionManager.create: enter, address = {:?}, id = {:?}, exempted = {}",
+            address, id, exempted
         );
 
         let mut sessions = self.sessions.write();
@@ -167,11 +181,12 @@ impl SessionManager {
 
         // limits ingress sessions whose node id is `None`.
         let ingress = self.cur_ingress_sessions.load(Ordering::Relaxed);
-        if id.is_none() && ingress >= self.max_ingress_sessions {
+        let max_ingress = self.max_ingress_sessions.load(Ordering::Relaxed);
+        if id.is_none() && !exempted && ingress >= max_ingress {
             debug!("SessionManager.create: leave on maximum ingress sessions reached");
             return Err(format!(
                 "maximum ingress sessions reached, current = {}, max = {}",
-                ingress, self.max_ingress_sessions
+                ingress, max_ingress
             ));
         }
 
@@ -190,7 +205,7 @@ impl SessionManager {
 
         // validate against node IP policy.
         let ip = address.ip();
-        if !ip_limit.is_allowed(&ip) {
+        if !exempted && !ip_limit.is_allowed(&ip) {
             debug!("SessionManager.create: leave on IP policy limited");
             return Err(format!(
                 "IP policy limited, nodeId = {:?}, addr = {:?}",
@@ -228,10 +243,14 @@ impl SessionManager {
             node_id_index.insert(node_id.clone(), index);
         }
 
-        assert!(ip_limit.add(ip));
+        if exempted {
+            self.exempted_sessions.write().insert(index);
+        } else {
+            assert!(ip_limit.add(ip));
 
-        if id.is_none() {
-            self.cur_ingress_sessions.fetch_add(1, Ordering::Relaxed);
+            if id.is_none() {
+                self.cur_ingress_sessions.fetch_add(1, Ordering::Relaxed);
+            }
         }
 
         debug!("SessionManager.create: leave");
@@ -257,10 +276,12 @@ impl SessionManager {
                 }
             }
 
-            assert!(self.ip_limit.write().remove(&session.address().ip()));
+            if !self.exempted_sessions.write().remove(&session.token()) {
+                assert!(self.ip_limit.write().remove(&session.address().ip()));
 
-            if !session.metadata.originated {
-                self.cur_ingress_sessions.fetch_sub(1, Ordering::Relaxed);
+                if !session.metadata.originated {
+                    self.cur_ingress_sessions.fetch_sub(1, Ordering::Relaxed);
+                }
             }
 
             self.tag_index.write().remove(session.token());
@@ -271,6 +292,37 @@ impl SessionManager {
         debug!("SessionManager.remove: leave");
     }
 
+    /// Apply the ingress and IP limits to an exempted ingress session whose
+    /// node id turns out not to be a static peer after the handshake.
+    /// Return error if the limits are reached, and the session should be
+    /// disconnected.
+    pub fn revoke_exemption(
+        &self, idx: usize, ip: &IpAddr,
+    ) -> Result<(), String> {
+        let mut ip_limit = self.ip_limit.write();
+        let mut exempted_sessions = self.exempted_sessions.write();
+        if !exempted_sessions.contains(&idx) {
+            return Ok(());
+        }
+
+        let ingress = self.cur_ingress_sessions.load(Ordering::Relaxed);
+        let max_ingress = self.max_ingress_sessions.load(Ordering::Relaxed);
+        if ingress >= max_ingress {
+            return Err(format!(
+                "maximum ingress sessions reached, current = {}, max = {}",
+                ingress, max_ingress
+            ));
+        }
+        if !ip_limit.is_allowed(ip) {
+            return Err(format!("IP policy limited, addr = {:?}", ip));
+        }
+
+        exempted_sessions.remove(&idx);
+        assert!(ip_limit.add(*ip));
+        self.cur_ingress_sessions.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
     /// Update the node id index for ingress session.
     /// Return error if the session index does not exist, or the node id already
     /// in use by other session.
@@ -0,0 +1,155 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Static peers are always kept connected regardless of the peer limits, e.g.
+//! the sentry nodes of a validator or the members of a consortium.
+//!
+//! They are kept apart from the node database, so that they are never evicted
+//! by the subnet quota, and are reconnected with an exponential backoff after
+//! failures.
+
+use crate::node_table::{NodeEntry, NodeId};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// The delay before reconnecting a static peer after the first failure.
+const MIN_RECONNECT_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(300);
+
+struct StaticPeer {
+    node: NodeEntry,
+    /// The number of connection attempts since the last connected session.
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+#[derive(Default)]
+pub struct StaticPeers {
+    peers: HashMap<NodeId, StaticPeer>,
+}
+
+impl StaticPeers {
+    /// Add a static peer, or update its address. Returns false if the peer
+    /// is already added with the same address.
+    pub fn insert(&mut self, node: NodeEntry, now: Instant) -> bool {
+        if let Some(peer) = self.peers.get_mut(&node.id) {
+            if peer.node.endpoint == node.endpoint {
+                return false;
+            }
+            peer.node = node;
+            peer.attempts = 0;
+            peer.next_attempt = now;
+            return true;
+        }
+        self.peers.insert(
+            node.id,
+            StaticPeer {
+                node,
+                attempts: 0,
+                next_attempt: now,
+            },
+        );
+        true
+    }
+
+    pub fn remove(&mut self, id: &NodeId) -> bool {
+        self.peers.remove(id).is_some()
+    }
+
+    pub fn contains(&self, id: &NodeId) -> bool { self.peers.contains_key(id) }
+
+    /// Whether the IP address belongs to a static peer, so that incoming
+    /// connections from it are not limited before the handshake.
+    pub fn contains_ip(&self, ip: &IpAddr) -> bool {
+        self.peers
+            .values()
+            .any(|peer| peer.node.endpoint.address.ip() == *ip)
+    }
+
+    pub fn get(&self, id: &NodeId) -> Option<&NodeEntry> {
+        self.peers.get(id).map(|peer| &peer.node)
+    }
+
+    pub fn nodes(&self) -> Vec<NodeEntry> {
+        self.peers.values().map(|peer| peer.node.clone()).collect()
+    }
+
+    /// Take the static peers whose reconnect backoff has elapsed, and
+    /// schedule their next attempts with a doubled backoff.
+    pub fn take_due<F>(
+        &mut self, now: Instant, is_connected: F,
+    ) -> Vec<NodeId>
+    where F: Fn(&NodeId) -> bool {
+        let mut due = Vec::new();
+        for (id, peer) in self.peers.iter_mut() {
+            if is_connected(id) || peer.next_attempt > now {
+                continue;
+            }
+            let backoff = MIN_RECONNECT_BACKOFF
+                .saturating_mul(1 << peer.attempts.min(16))
+                .min(MAX_RECONNECT_BACKOFF);
+            peer.attempts += 1;
+            peer.next_attempt = now + backoff;
+            due.push(*id);
+        }
+        due
+    }
+
+    /// Reset the reconnect backoff of a static peer once connected.
+    pub fn note_connected(&mut self, id: &NodeId, now: Instant) {
+        if let Some(peer) = self.peers.get_mut(id) {
+            peer.attempts = 0;
+            peer.next_attempt = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_table::NodeEndpoint;
+    use std::net::SocketAddr;
+
+    fn node(ip: [u8; 4]) -> NodeEntry {
+        NodeEntry {
+            id: NodeId::random(),
+            endpoint: NodeEndpoint {
+                address: SocketAddr::from((ip, 32323)),
+                udp_port: 32323,
+            },
+        }
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let mut peers = StaticPeers::default();
+        let now = Instant::now();
+        let (a, b) = (node([10, 0, 0, 1]), node([10, 0, 0, 2]));
+        assert!(peers.insert(a.clone(), now));
+        assert!(peers.insert(b.clone(), now));
+        assert!(!peers.insert(a.clone(), now));
+        assert!(peers.contains_ip(&a.endpoint.address.ip()));
+
+        // Connected peers are skipped.
+        assert_eq!(peers.take_due(now, |id| *id == b.id), vec![a.id]);
+        assert_eq!(peers.take_due(now, |_| false), vec![b.id]);
+        assert!(peers.take_due(now, |_| false).is_empty());
+
+        // 5s after the first attempt, 10s after the second one.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(peers.take_due(later, |id| *id == b.id), vec![a.id]);
+        assert!(peers
+            .take_due(later + Duration::from_secs(9), |id| *id == b.id)
+            .is_empty());
+        peers.note_connected(&a.id, later);
+        assert_eq!(peers.take_due(later, |id| *id == b.id), vec![a.id]);
+
+        assert!(peers.remove(&a.id));
+        assert!(!peers.contains(&a.id));
+        assert!(!peers.contains_ip(&a.endpoint.address.ip()));
+    }
+}
@@ -138,6 +138,10 @@ pub mod rpc {
     pub const TRANSACTION_COUNT_PER_BLOCK_WATER_LINE_LOW: usize = 100;
     pub const TRANSACTION_COUNT_PER_BLOCK_WATER_LINE_MEDIUM: usize = 600;
     pub const GAS_PRICE_DEFAULT_VALUE: usize = 1_000_000_000;
+    /// The default and max page sizes of `cfx_getTransactionsByAddress` and
+    /// `eth_getTransactionsByAddress`.
+    pub const TRANSACTIONS_BY_ADDRESS_DEFAULT_LIMIT: usize = 100;
+    pub const TRANSACTIONS_BY_ADDRESS_MAX_LIMIT: usize = 1000;
 }
 
 pub mod sync {
@@ -41,7 +41,8 @@ use cfx_rpc::{helpers::ChainInfo, *};
 use cfx_rpc_cfx_types::RpcImplConfiguration;
 use cfx_rpc_eth_api::*;
 use cfxcore::{
-    SharedConsensusGraph, SharedSynchronizationService, SharedTransactionPool,
+    pow::PowWorkProvider, SharedConsensusGraph, SharedSynchronizationService,
+    SharedTransactionPool,
 };
 pub use jsonrpsee::server::ServerBuilder;
 use jsonrpsee::{
@@ -56,8 +57,9 @@ use jsonrpsee::{
     Methods, RpcModule,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Arc,
     /* time::{Duration, SystemTime, UNIX_EPOCH}, */
 };
 pub use tower::layer::util::{Identity, Stack};
@@ -74,6 +76,7 @@ pub struct RpcModuleBuilder {
     sync: SharedSynchronizationService,
     tx_pool: SharedTransactionPool,
     executor: TaskExecutor,
+    work_provider: Option<Arc<dyn PowWorkProvider>>,
 }
 
 impl RpcModuleBuilder {
@@ -81,6 +84,7 @@ impl RpcModuleBuilder {
         config: RpcImplConfiguration, consensus: SharedConsensusGraph,
         sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
         executor: TaskExecutor,
+        work_provider: Option<Arc<dyn PowWorkProvider>>,
     ) -> Self {
         Self {
             config,
@@ -88,6 +92,7 @@ impl RpcModuleBuilder {
             sync,
             tx_pool,
             executor,
+            work_provider,
         }
     }
 
@@ -108,10 +113,16 @@ impl RpcModuleBuilder {
                 sync,
                 tx_pool,
                 executor,
+                work_provider,
             } = self;
 
             let mut registry = RpcRegistryInner::new(
-                config, consensus, sync, tx_pool, executor,
+                config,
+                consensus,
+                sync,
+                tx_pool,
+                executor,
+                work_provider,
             );
 
             modules.config = module_config;
@@ -132,6 +143,7 @@ pub struct RpcRegistryInner {
     tx_pool: SharedTransactionPool,
     modules: HashMap<EthRpcModule, Methods>,
     executor: TaskExecutor,
+    work_provider: Option<Arc<dyn PowWorkProvider>>,
 }
 
 impl RpcRegistryInner {
@@ -139,6 +151,7 @@ impl RpcRegistryInner {
         config: RpcImplConfiguration, consensus: SharedConsensusGraph,
         sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
         executor: TaskExecutor,
+        work_provider: Option<Arc<dyn PowWorkProvider>>,
     ) -> Self {
         Self {
             consensus,
@@ -147,6 +160,7 @@ impl RpcRegistryInner {
             tx_pool,
             modules: Default::default(),
             executor,
+            work_provider,
         }
     }
 
@@ -236,6 +250,7 @@ impl RpcRegistryInner {
                         self.sync.clone(),
                         self.tx_pool.clone(),
                         self.executor.clone(),
+                        self.work_provider.clone(),
                     )
                     .into_rpc()
                     .into(),
@@ -261,6 +276,7 @@ impl RpcRegistryInner {
                             self.sync.clone(),
                             self.tx_pool.clone(),
                             self.executor.clone(),
+                            self.work_provider.clone(),
                         );
                         ParityApi::new(eth_api).into_rpc().into()
                     }
@@ -453,18 +469,32 @@ impl RpcServerConfig {
         self, modules: &TransportRpcModules,
         throttling_conf_file: Option<String>, enable_metrics: bool,
     ) -> Result<RpcServerHandle, RpcError> {
-        // TODO: handle enable metrics
         debug!("enable metrics: {}", enable_metrics);
 
-        let rpc_middleware = RpcServiceBuilder::new()
-            .layer_fn(move |s| {
-                Throttle::new(
-                    throttling_conf_file.as_ref().map(|s| s.as_str()),
-                    "rpc",
-                    s,
-                )
-            })
-            .layer_fn(|s| Metrics::new(s));
+        // The transport is recorded in the metrics, which is "http_ws" if
+        // both are served by the same server. The calls are recorded by the
+        // names of the methods served by `module`.
+        let rpc_middleware =
+            |transport: &'static str, module: Option<&RpcModule<()>>| {
+                let throttling_conf_file = throttling_conf_file.clone();
+                let methods: Arc<HashSet<&'static str>> =
+                    Arc::new(module.map_or_else(Default::default, |module| {
+                        module.method_names().collect()
+                    }));
+                RpcServiceBuilder::new()
+                    .layer_fn(move |s| {
+                        Throttle::new(throttling_conf_file.as_deref(), "rpc", s)
+                    })
+                    .layer_fn(move |s| {
+                        Metrics::new(
+                            s,
+                            "evm",
+                            transport,
+                            methods.clone(),
+                            enable_metrics,
+                        )
+                    })
+            };
 
         let http_socket_addr =
             self.http_addr.unwrap_or(SocketAddr::V4(SocketAddrV4::new(
@@ -503,8 +533,9 @@ impl RpcServerConfig {
             modules.config.ensure_ws_http_identical()?;
 
             if let Some(builder) = self.http_server_config {
+                let module = modules.http.as_ref().or(modules.ws.as_ref());
                 let server = builder
-                    .set_rpc_middleware(rpc_middleware)
+                    .set_rpc_middleware(rpc_middleware("http_ws", module))
                     .build(http_socket_addr)
                     .await
                     .map_err(|err| {
@@ -519,9 +550,7 @@ impl RpcServerConfig {
                         ServerKind::WsHttp(http_socket_addr),
                     )
                 })?;
-                if let Some(module) =
-                    modules.http.as_ref().or(modules.ws.as_ref())
-                {
+                if let Some(module) = module {
                     let handle = server.start(module.clone());
                     let http_handle = Some(handle.clone());
                     let ws_handle = Some(handle);
@@ -549,7 +578,7 @@ impl RpcServerConfig {
         if let Some(builder) = self.ws_server_config {
             let server = builder
                 .ws_only()
-                .set_rpc_middleware(rpc_middleware.clone())
+                .set_rpc_middleware(rpc_middleware("ws", modules.ws.as_ref()))
                 .build(ws_socket_addr)
                 .await
                 .map_err(|err| {
@@ -573,7 +602,10 @@ impl RpcServerConfig {
         if let Some(builder) = self.http_server_config {
             let server = builder
                 .http_only()
-                .set_rpc_middleware(rpc_middleware)
+                .set_rpc_middleware(rpc_middleware(
+                    "http",
+                    modules.http.as_ref(),
+                ))
                 .build(http_socket_addr)
                 .await
                 .map_err(|err| {
@@ -10,6 +10,7 @@ use std::{
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub enum Api {
+    Admin,
     Cfx,
     Debug,
     Pubsub,
@@ -25,6 +26,7 @@ impl FromStr for Api {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use self::Api::*;
         match s {
+            "admin" => Ok(Admin),
             "cfx" => Ok(Cfx),
             "debug" => Ok(Debug),
             "pubsub" => Ok(Pubsub),
@@ -40,6 +42,7 @@ impl FromStr for Api {
 impl Display for Api {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            Api::Admin => write!(f, "admin"),
             Api::Cfx => write!(f, "cfx"),
             Api::Debug => write!(f, "debug"),
             Api::Pubsub => write!(f, "pubsub"),
@@ -63,6 +66,7 @@ impl ApiSet {
         match *self {
             ApiSet::List(ref apis) => apis.clone(),
             ApiSet::All => [
+                Api::Admin,
                 Api::Cfx,
                 Api::Debug,
                 Api::Pubsub,
@@ -3,7 +3,7 @@ use cfx_rpc_eth_types::{
     BlockNumber as BlockId, BlockOverrides, Bundle, EthCallResponse,
     EthRpcLogFilter as Filter, FeeHistory, Header, Log, Receipt,
     RpcStateOverride, SimulatePayload, SimulatedBlock, StateContext,
-    SyncStatus, Transaction, TransactionRequest,
+    SyncStatus, Transaction, TransactionRequest, TransactionsByAddress, Work,
 };
 use cfx_rpc_primitives::{Bytes, Index};
 use cfx_types::{Address, H256, H64, U256, U64};
@@ -289,8 +289,8 @@ pub trait EthApi {
 
     /// Returns the hash of the current block, the seedHash, and the boundary
     /// condition to be met (“target”)
-    // #[method(name = "getWork")]
-    // async fn get_work(&self) -> RpcResult<Work>;
+    #[method(name = "getWork")]
+    async fn get_work(&self) -> RpcResult<Work>;
 
     /// Used for submitting mining hashrate.
     ///
@@ -363,4 +363,11 @@ pub trait EthApi {
         &self, address: Address, maybe_start_nonce: Option<U256>,
         maybe_limit: Option<U64>,
     ) -> RpcResult<AccountPendingTransactions>;
+
+    /// Returns the executed transactions sent from or to an address, from the
+    /// newest to the oldest. Requires `persist_address_tx_index`.
+    #[method(name = "getTransactionsByAddress")]
+    async fn transactions_by_address(
+        &self, address: Address, cursor: Option<U64>, limit: Option<U64>,
+    ) -> RpcResult<TransactionsByAddress>;
 }
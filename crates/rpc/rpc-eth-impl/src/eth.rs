@@ -4,22 +4,26 @@ use cfx_execute_helper::estimation::EstimateRequest;
 use cfx_executor::executive::{
     Executed, ExecutionError, ExecutionOutcome, TxDropError,
 };
-use cfx_parameters::rpc::GAS_PRICE_DEFAULT_VALUE;
+use cfx_parameters::rpc::{
+    GAS_PRICE_DEFAULT_VALUE, TRANSACTIONS_BY_ADDRESS_DEFAULT_LIMIT,
+    TRANSACTIONS_BY_ADDRESS_MAX_LIMIT,
+};
 use cfx_rpc_cfx_types::{
     traits::BlockProvider, PhantomBlock, RpcImplConfiguration,
 };
 use cfx_rpc_eth_api::EthApiServer;
 use cfx_rpc_eth_types::{
-    AccessListResult, AccountOverride, AccountPendingTransactions, Block,
-    BlockNumber as BlockId, BlockOverrides, Bundle, Error, EthCallResponse,
-    EthRpcLogFilter, EthRpcLogFilter as Filter, EvmOverrides, FeeHistory,
-    Header, Log, Receipt, RpcStateOverride, SimulatePayload, SimulatedBlock,
-    StateContext, SyncInfo, SyncStatus, Transaction, TransactionRequest,
+    AccessListResult, AccountOverride, AccountPendingTransactions,
+    AddressTransaction, Block, BlockNumber as BlockId, BlockOverrides, Bundle,
+    Error, EthCallResponse, EthRpcLogFilter, EthRpcLogFilter as Filter,
+    EvmOverrides, FeeHistory, Header, Log, Receipt, RpcStateOverride,
+    SimulatePayload, SimulatedBlock, StateContext, SyncInfo, SyncStatus,
+    Transaction, TransactionRequest, TransactionsByAddress, Work,
 };
 use cfx_rpc_primitives::{Bytes, Index, U64 as HexU64};
 use cfx_rpc_utils::{
     error::{
-        errors::*, jsonrpc_error_helpers::*,
+        error_codes, errors::*, jsonrpc_error_helpers::*,
         jsonrpsee_error_helpers::internal_error as jsonrpsee_internal_error,
     },
     helpers::SpawnBlocking,
@@ -33,6 +37,7 @@ use cfx_util_macros::bail;
 use cfx_vm_types::Error as VmError;
 use cfxcore::{
     errors::{Error as CoreError, Result as CoreResult},
+    pow::PowWorkProvider,
     ConsensusGraph, SharedConsensusGraph, SharedSynchronizationService,
     SharedTransactionPool,
 };
@@ -44,7 +49,7 @@ use primitives::{
 };
 use rustc_hex::ToHex;
 use solidity_abi::string_revert_reason_decode;
-use std::{collections::HashMap, future::Future};
+use std::{collections::HashMap, future::Future, sync::Arc};
 
 type BlockNumber = BlockId;
 type BlockNumberOrTag = BlockId;
@@ -60,6 +65,7 @@ pub struct EthApi {
     tx_pool: SharedTransactionPool,
     fee_history_cache: FeeHistoryCache,
     executor: TaskExecutor,
+    work_provider: Option<Arc<dyn PowWorkProvider>>,
 }
 
 impl EthApi {
@@ -67,6 +73,7 @@ impl EthApi {
         config: RpcImplConfiguration, consensus: SharedConsensusGraph,
         sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
         executor: TaskExecutor,
+        work_provider: Option<Arc<dyn PowWorkProvider>>,
     ) -> Self {
         EthApi {
             config,
@@ -75,6 +82,7 @@ impl EthApi {
             tx_pool,
             fee_history_cache: FeeHistoryCache::new(),
             executor,
+            work_provider,
         }
     }
 
@@ -505,6 +513,57 @@ impl EthApi {
         }
     }
 
+    pub fn is_mining(&self) -> bool {
+        self.work_provider
+            .as_ref()
+            .map_or(false, |provider| provider.is_mining())
+    }
+
+    pub fn hashrate(&self) -> U256 {
+        self.work_provider
+            .as_ref()
+            .map_or(U256::zero(), |provider| provider.hashrate())
+    }
+
+    pub fn get_work(&self) -> CoreResult<Work> {
+        let provider = self.work_provider()?;
+        let (problem, seed_hash) = match provider.work() {
+            Some(work) => work,
+            None => bail!(build_rpc_server_error(
+                error_codes::NO_WORK,
+                "Still syncing or no work available yet.".into()
+            )),
+        };
+        Ok(Work {
+            pow_hash: problem.block_hash,
+            seed_hash,
+            target: BigEndianHash::from_uint(&problem.boundary),
+            number: Some(problem.block_height),
+        })
+    }
+
+    pub fn submit_work(&self, nonce: H64, pow_hash: H256) -> CoreResult<bool> {
+        let provider = self.work_provider()?;
+        Ok(provider.submit_work(pow_hash, U256::from(nonce.to_low_u64_be())))
+    }
+
+    pub fn submit_hashrate(&self, hashrate: U256, id: H256) -> bool {
+        self.work_provider
+            .as_ref()
+            .map_or(false, |provider| provider.submit_hashrate(hashrate, id))
+    }
+
+    fn work_provider(&self) -> CoreResult<&Arc<dyn PowWorkProvider>> {
+        match self.work_provider.as_ref() {
+            Some(provider) if provider.is_mining() => Ok(provider),
+            _ => bail!(build_rpc_server_error(
+                error_codes::INCAPABLE,
+                "Remote mining is not enabled, set mining_type to \"getwork\"."
+                    .into()
+            )),
+        }
+    }
+
     pub fn chain_id(&self) -> u32 {
         self.consensus.best_chain_id().in_evm_space()
     }
@@ -522,7 +581,7 @@ impl EthApi {
             .unwrap_or(GAS_PRICE_DEFAULT_VALUE.into());
         std::cmp::max(
             consensus_gas_price,
-            self.tx_pool.config.min_eth_tx_price.into(),
+            self.tx_pool.config.min_tx_price(Space::Ethereum).into(),
         )
     }
 
@@ -897,6 +956,53 @@ impl EthApi {
         Ok(self.get_tx_from_txpool(hash))
     }
 
+    pub fn transactions_by_address(
+        &self, address: Address, cursor: Option<U64>, limit: Option<U64>,
+    ) -> CoreResult<TransactionsByAddress> {
+        let limit = match limit {
+            None => TRANSACTIONS_BY_ADDRESS_DEFAULT_LIMIT,
+            Some(limit)
+                if limit.as_usize() <= TRANSACTIONS_BY_ADDRESS_MAX_LIMIT =>
+            {
+                limit.as_usize()
+            }
+            Some(_) => bail!(RpcError::invalid_params(format!(
+                "limit should not exceed {}",
+                TRANSACTIONS_BY_ADDRESS_MAX_LIMIT
+            ))),
+        };
+
+        let (transactions, next_cursor) = match self
+            .consensus_graph()
+            .get_transactions_by_address(
+                &address.with_evm_space(),
+                cursor.map(|cursor| cursor.as_u64()),
+                limit,
+            ) {
+            Some(page) => page,
+            None => bail!(build_rpc_server_error(
+                error_codes::INCAPABLE,
+                "Address transaction index is not enabled, set persist_address_tx_index to true."
+                    .into()
+            )),
+        };
+
+        Ok(TransactionsByAddress {
+            transactions: transactions
+                .into_iter()
+                .map(|tx| AddressTransaction {
+                    transaction_hash: tx.hash,
+                    // In the Ethereum space, the pivot block of the epoch
+                    // acts as the block of the transaction.
+                    block_hash: tx.epoch_hash,
+                    block_number: tx.epoch.into(),
+                    transaction_index: tx.index.into(),
+                })
+                .collect(),
+            next_cursor: next_cursor.map(Into::into),
+        })
+    }
+
     pub fn transaction_receipt(
         &self, tx_hash: H256,
     ) -> CoreResult<Option<Receipt>> {
@@ -1409,14 +1515,16 @@ impl EthApiServer for EthApi {
     }
 
     /// Returns whether the client is actively mining new blocks.
-    async fn is_mining(&self) -> RpcResult<bool> { Ok(false) }
+    async fn is_mining(&self) -> RpcResult<bool> { Ok(self.is_mining()) }
 
     /// Returns the number of hashes per second that the node is mining with.
-    async fn hashrate(&self) -> RpcResult<U256> { Ok(U256::zero()) }
+    async fn hashrate(&self) -> RpcResult<U256> { Ok(self.hashrate()) }
 
     /// Returns the hash of the current block, the seedHash, and the boundary
     /// condition to be met (“target”)
-    // async fn get_work(&self) -> RpcResult<Work>;
+    async fn get_work(&self) -> RpcResult<Work> {
+        self.get_work().map_err(|err| err.into())
+    }
 
     /// Used for submitting mining hashrate.
     ///
@@ -1427,16 +1535,16 @@ impl EthApiServer for EthApi {
     async fn submit_hashrate(
         &self, hashrate: U256, id: H256,
     ) -> RpcResult<bool> {
-        let _ = (hashrate, id);
-        Ok(false)
+        Ok(self.submit_hashrate(hashrate, id))
     }
 
     /// Used for submitting a proof-of-work solution.
     async fn submit_work(
         &self, nonce: H64, pow_hash: H256, mix_digest: H256,
     ) -> RpcResult<bool> {
-        let _ = (nonce, pow_hash, mix_digest);
-        Ok(false)
+        // The mix digest is not part of a Conflux PoW solution.
+        let _ = mix_digest;
+        self.submit_work(nonce, pow_hash).map_err(|err| err.into())
     }
 
     /// Sends transaction; will block waiting for signer to return the
@@ -1508,4 +1616,11 @@ impl EthApiServer for EthApi {
         )
         .map_err(|err| err.into())
     }
+
+    async fn transactions_by_address(
+        &self, address: Address, cursor: Option<U64>, limit: Option<U64>,
+    ) -> RpcResult<TransactionsByAddress> {
+        self.transactions_by_address(address, cursor, limit)
+            .map_err(|err| err.into())
+    }
 }
@@ -0,0 +1,28 @@
+// Copyright 2019-2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H256, U64};
+use serde::Serialize;
+
+/// A transaction sent from or to an address.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransaction {
+    pub transaction_hash: H256,
+    pub block_hash: H256,
+    pub block_number: U64,
+    pub transaction_index: U64,
+}
+
+/// A page of the transactions of an address, from the newest to the oldest.
+/// The number of entries scanned for a page is bounded, so a page may have
+/// fewer transactions than the limit even if there are more transactions.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsByAddress {
+    pub transactions: Vec<AddressTransaction>,
+    /// The cursor to get the next page with, or `None` if there are no more
+    /// transactions.
+    pub next_cursor: Option<U64>,
+}
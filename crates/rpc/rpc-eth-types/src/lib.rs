@@ -1,4 +1,5 @@
 mod access_list;
+mod address_transactions;
 mod authorization;
 mod block;
 mod block_number;
@@ -16,8 +17,10 @@ pub mod trace_filter;
 mod transaction;
 mod transaction_request;
 mod tx_pool;
+mod work;
 
 pub use access_list::*;
+pub use address_transactions::{AddressTransaction, TransactionsByAddress};
 pub use authorization::{Authorization, SignedAuthorization};
 pub use block::{Block, BlockOverrides, Header};
 pub use block_number::BlockNumber;
@@ -42,3 +45,4 @@ pub use transaction_request::{
     TransactionRequest, DEFAULT_ETH_GAS_CALL_REQUEST,
 };
 pub use tx_pool::*;
+pub use work::Work;
@@ -39,7 +39,7 @@
 //! may be handy in some scenarios such CORS but if you want to access
 //! to the actual JSON-RPC details this is the middleware to use.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use cfx_rpc_middlewares::{Metrics, Throttle};
 use jsonrpsee::{
@@ -100,16 +100,6 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
 
     debug!("throttling config path: {:?}", config_path);
 
-    let rpc_middleware = RpcServiceBuilder::new()
-        .layer_fn(move |s| {
-            Throttle::new(Some(config_path.to_str().unwrap()), "test", s)
-        })
-        .layer_fn(|s| Metrics::new(s));
-
-    let server = Server::builder()
-        .set_rpc_middleware(rpc_middleware)
-        .build("127.0.0.1:0")
-        .await?;
     let mut module = RpcModule::new(());
     module.register_method("say_hello", |_, _, _| "lo")?;
     module.register_method("thready", |params, _, _| {
@@ -121,6 +111,20 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
         }
         ""
     })?;
+    let methods = Arc::new(module.method_names().collect());
+
+    let rpc_middleware = RpcServiceBuilder::new()
+        .layer_fn(move |s| {
+            Throttle::new(Some(config_path.to_str().unwrap()), "test", s)
+        })
+        .layer_fn(move |s| {
+            Metrics::new(s, "evm", "http", methods.clone(), true)
+        });
+
+    let server = Server::builder()
+        .set_rpc_middleware(rpc_middleware)
+        .build("127.0.0.1:0")
+        .await?;
     let addr = server.local_addr()?;
     let handle = server.start(module);
 
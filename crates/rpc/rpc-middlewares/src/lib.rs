@@ -1,5 +1,5 @@
 mod metrics;
 mod throttle;
 
-pub use metrics::Metrics;
+pub use metrics::{Metrics, RpcCallMetrics};
 pub use throttle::Throttle;
@@ -1,40 +1,145 @@
-use futures::{future::lazy, FutureExt};
+use futures::FutureExt;
 use futures_util::future::BoxFuture;
-// use jsonrpc_core::Result as RpcResult;
-use jsonrpsee::{
-    core::RpcResult,
-    server::{middleware::rpc::RpcServiceT, MethodResponse},
-};
+use jsonrpsee::server::{middleware::rpc::RpcServiceT, MethodResponse};
 use jsonrpsee_types::Request;
 use lazy_static::lazy_static;
-use log::debug;
-use metrics::{register_timer_with_group, ScopeTimer, Timer};
-use parking_lot::Mutex;
-use std::{collections::HashMap, sync::Arc};
+use metrics::{
+    Counter, CounterVec, Gauge, GaugeVec, HistogramVec, DEFAULT_BUCKETS,
+};
+use std::{collections::HashSet, sync::Arc, time::Instant};
+use tracing::{info_span, Instrument};
+
+/// The labels of the RPC metrics, e.g. `["native", "http", "cfx_epochNumber"]`.
+const RPC_LABELS: [&str; 3] = ["space", "transport", "method"];
+
+/// The method label of the calls to unregistered methods, whose names are
+/// chosen by clients and thus not recorded to bound the number of series.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// The upper bounds of response sizes in bytes.
+const RESPONSE_SIZE_BUCKETS: [f64; 8] = [
+    128.0, 1024.0, 8192.0, 65536.0, 524288.0, 4194304.0, 16777216.0, 67108864.0,
+];
 
 lazy_static! {
-    static ref METRICS_INTERCEPTOR_TIMERS: Mutex<HashMap<String, Arc<dyn Timer>>> =
-        Default::default();
+    static ref RPC_REQUESTS: Arc<CounterVec> =
+        CounterVec::register_with_group("rpc", "requests_total", &RPC_LABELS);
+    static ref RPC_ERRORS: Arc<CounterVec> = CounterVec::register_with_group(
+        "rpc",
+        "errors_total",
+        &["space", "transport", "method", "code"]
+    );
+    static ref RPC_DURATION: Arc<HistogramVec> =
+        HistogramVec::register_with_group(
+            "rpc",
+            "request_duration_seconds",
+            &RPC_LABELS,
+            &DEFAULT_BUCKETS
+        );
+    static ref RPC_RESPONSE_SIZE: Arc<HistogramVec> =
+        HistogramVec::register_with_group(
+            "rpc",
+            "response_size_bytes",
+            &RPC_LABELS,
+            &RESPONSE_SIZE_BUCKETS
+        );
+    static ref RPC_IN_FLIGHT: Arc<GaugeVec> =
+        GaugeVec::register_with_group("rpc", "in_flight_requests", &RPC_LABELS);
+}
+
+/// The metrics of an RPC call, which is shared by the servers of both spaces
+/// so that they are reported uniformly.
+///
+/// The call is counted as in flight until this is finished or dropped, e.g.
+/// the request is cancelled.
+pub struct RpcCallMetrics {
+    /// The values of [`RPC_LABELS`].
+    labels: [String; 3],
+    start: Instant,
+}
+
+impl RpcCallMetrics {
+    /// Starts recording a call of `method` in `space` ("native" or "evm")
+    /// through `transport`, e.g. "http" or "ws".
+    pub fn start(space: &str, transport: &str, method: &str) -> Self {
+        let labels = [space.to_string(), transport.to_string(), method.into()];
+        let call = RpcCallMetrics {
+            labels,
+            start: Instant::now(),
+        };
+
+        RPC_REQUESTS.with_label_values(&call.label_values()).inc(1);
+        RPC_IN_FLIGHT.with_label_values(&call.label_values()).inc(1);
+        call
+    }
+
+    /// Finishes the call with the size of the serialized result or error in
+    /// bytes, and the error code if the call failed.
+    pub fn finish(self, response_size: usize, error_code: Option<i64>) {
+        let label_values = self.label_values();
+        RPC_DURATION
+            .with_label_values(&label_values)
+            .observe(self.start.elapsed().as_secs_f64());
+        RPC_RESPONSE_SIZE
+            .with_label_values(&label_values)
+            .observe(response_size as f64);
+
+        if let Some(code) = error_code {
+            let code = code.to_string();
+            let [space, transport, method] = label_values;
+            RPC_ERRORS
+                .with_label_values(&[space, transport, method, code.as_str()])
+                .inc(1);
+        }
+    }
+
+    fn label_values(&self) -> [&str; 3] {
+        [
+            self.labels[0].as_str(),
+            self.labels[1].as_str(),
+            self.labels[2].as_str(),
+        ]
+    }
 }
+
+impl Drop for RpcCallMetrics {
+    fn drop(&mut self) {
+        RPC_IN_FLIGHT.with_label_values(&self.label_values()).dec(1);
+    }
+}
+
 #[derive(Clone)]
 pub struct Metrics<S> {
     service: S,
+    space: &'static str,
+    transport: &'static str,
+    /// The registered methods, which are recorded by their names.
+    methods: Arc<HashSet<&'static str>>,
+    enabled: bool,
 }
 
 impl<S> Metrics<S> {
-    pub fn new(service: S) -> Self { Self { service } }
-}
+    /// Records the calls of `space` served through `transport` if `enabled`.
+    /// The calls to methods not in `methods` are recorded as "unknown".
+    pub fn new(
+        service: S, space: &'static str, transport: &'static str,
+        methods: Arc<HashSet<&'static str>>, enabled: bool,
+    ) -> Self {
+        Self {
+            service,
+            space,
+            transport,
+            methods,
+            enabled,
+        }
+    }
 
-impl<S> Metrics<S> {
-    fn before(&self, name: &String) -> RpcResult<()> {
-        // Use a global variable here because `http` and `web3` setup different
-        // interceptors for the same RPC API.
-        let mut timers = METRICS_INTERCEPTOR_TIMERS.lock();
-        if !timers.contains_key(name) {
-            let timer = register_timer_with_group("async_rpc", name.as_str());
-            timers.insert(name.clone(), timer);
+    fn method_label<'b>(&self, method: &'b str) -> &'b str {
+        if self.methods.contains(method) {
+            method
+        } else {
+            UNKNOWN_METHOD
         }
-        Ok(())
     }
 }
 
@@ -44,36 +149,24 @@ where S: RpcServiceT<'a> + Send + Sync + Clone + 'static
     type Future = BoxFuture<'a, MethodResponse>;
 
     fn call(&self, req: Request<'a>) -> Self::Future {
-        let before_result = self.before(&req.method_name().to_string());
-
-        debug!("run metrics interceptor before_result: {:?}", before_result);
-        match before_result {
-            Ok(_) => {
-                let maybe_timer = METRICS_INTERCEPTOR_TIMERS
-                    .lock()
-                    .get(req.method_name())
-                    .map(|timer| timer.clone());
-                let setup = lazy(move |_| {
-                    Ok(maybe_timer
-                        .as_ref()
-                        .map(|timer| ScopeTimer::time_scope(timer.clone())))
-                });
-
-                let service = self.service.clone();
-                Box::pin(async move {
-                    let timer: Result<_, ()> = setup.await;
-                    let resp = service.call(req).await;
-                    drop(timer);
-                    resp
-                })
-                .boxed()
-            }
-            Err(e) => {
-                return Box::pin(
-                    async move { MethodResponse::error(req.id, e) },
-                )
-                .boxed()
+        let method = self.method_label(req.method_name());
+        let call = self
+            .enabled
+            .then(|| RpcCallMetrics::start(self.space, self.transport, method));
+
+        let service = self.service.clone();
+        let span = info_span!("rpc", method);
+        Box::pin(async move {
+            let resp = service.call(req).await;
+            if let Some(call) = call {
+                call.finish(
+                    resp.as_result().len(),
+                    resp.as_error_code().map(i64::from),
+                );
             }
-        }
+            resp
+        })
+        .instrument(span)
+        .boxed()
     }
 }
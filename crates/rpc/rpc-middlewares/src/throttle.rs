@@ -21,11 +21,8 @@ pub struct Throttle<S> {
 
 impl<S> Throttle<S> {
     pub fn new(file: Option<&str>, section: &str, s: S) -> Self {
-        let manager = match file {
-            Some(file) => TokenBucketManager::load(file, Some(section))
-                .expect("invalid throttling configuration file"),
-            None => TokenBucketManager::default(),
-        };
+        let manager = TokenBucketManager::load_reloadable(file, section)
+            .expect("invalid throttling configuration file");
 
         Throttle {
             service: s,
@@ -35,7 +35,7 @@
 /// by 1.
 ///
 /// Do not recycle deprecated error codes.
-const NEXT_SERVER_ERROR_CODE: i64 = -32078;
+const NEXT_SERVER_ERROR_CODE: i64 = -32081;
 /// When the above number is equal to -32100, take the number below on the
 /// right for new error code, then increase it by 1.
 const CFX_EXTRA_SERVER_ERROR_CODE: i64 = -31999;
@@ -67,6 +67,9 @@ pub const REQUEST_REJECTED_TOO_MANY_REQUESTS: i64 = -32072;
 /// This is mostly an application error but it's generic enough to define it
 /// here.
 pub const REQUEST_REJECTED_LIMIT_DATA: i64 = -32041;
+/// The request is authenticated by a token which does not grant the
+/// namespace of the rpc.
+pub const REQUEST_REJECTED_UNAUTHORIZED: i64 = -32079;
 
 /* Conflux node status related error codes
  *
@@ -87,6 +90,9 @@ pub const SUSPICIOUS_MINING_RATE: i64 = -32076;
 /// When the node is still in catch up mode, it is not capable to handle
 /// certain requests. We will return this code in this situation.
 pub const REQUEST_REJECTED_IN_CATCH_UP: i64 = -32077;
+/// The node is mining remotely but has no work to serve yet, e.g. it is still
+/// syncing.
+pub const NO_WORK: i64 = -32080;
 
 /* Other server error codes */
 /// Any exception happened while processing the transaction. Mostly likely
@@ -137,6 +137,14 @@ pub fn request_rejected_too_many_request_error(
     }
 }
 
+pub fn request_rejected_unauthorized_error(details: Option<String>) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_UNAUTHORIZED),
+        message: "Request rejected due to insufficient authorization.".into(),
+        data: details.map(Value::String),
+    }
+}
+
 pub fn request_rejected_in_catch_up_mode(details: Option<String>) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::REQUEST_REJECTED_IN_CATCH_UP),
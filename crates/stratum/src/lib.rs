@@ -123,7 +123,16 @@ impl PushWorkHandler for Stratum {
         debug!("Pushing job {} to miners", payload);
 
         self.implementation
-            .push_work_all(payload, &self.tcp_dispatcher)
+            .push_work_each(&|_, _| payload.clone(), &self.tcp_dispatcher)
+    }
+
+    fn push_work_each(
+        &self, payload_for: &dyn Fn(&SocketAddr, &str) -> String,
+    ) -> Result<(), Error> {
+        debug!("Pushing per-worker jobs to miners");
+
+        self.implementation
+            .push_work_each(payload_for, &self.tcp_dispatcher)
     }
 }
 
@@ -160,17 +169,19 @@ impl StratumImpl {
                 }
             }
             debug!(target: "stratum", "New worker #{} registered", worker_id);
+            self.dispatcher.worker_subscribed(meta.addr(), &worker_id);
             self.workers.write().insert(meta.addr().clone(), worker_id);
             to_value(true)
         }).map(|v| v.expect("Only true/false is returned and it's always serializable; qed"))
     }
 
     /// rpc method `mining.submit`
-    async fn submit(&self, params: Params, _meta: SocketMetadata) -> RpcResult {
+    async fn submit(&self, params: Params, meta: SocketMetadata) -> RpcResult {
         Ok(Value::Array(match params {
             Params::Array(vals) => {
                 // first two elements are service messages (worker_id & job_id)
-                match self.dispatcher.submit(
+                match self.dispatcher.submit_from(
+                    meta.addr(),
                     vals.iter()
                         .filter_map(|val| match *val {
                             Value::String(ref s) => Some(s.to_owned()),
@@ -201,8 +212,9 @@ impl StratumImpl {
         }))
     }
 
-    fn push_work_all(
-        &self, payload: String, tcp_dispatcher: &Dispatcher,
+    fn push_work_each(
+        &self, payload_for: &dyn Fn(&SocketAddr, &str) -> String,
+        tcp_dispatcher: &Dispatcher,
     ) -> Result<(), Error> {
         let hup_peers = {
             let workers = self.workers.read();
@@ -217,11 +229,11 @@ impl StratumImpl {
             };
 
             let mut hup_peers = HashSet::with_capacity(0); // most of the cases won't be needed, hence avoid allocation
-            let workers_msg = format!("{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}", next_request_id, payload);
-            trace!(target: "stratum", "Pushing work for {} workers (payload: '{}')", workers.len(), &workers_msg);
+            trace!(target: "stratum", "Pushing work for {} workers", workers.len());
             for (ref addr, worker_id) in workers.iter() {
-                trace!(target: "stratum", "Pushing work to {} at addr {}", &worker_id, &addr);
-                match tcp_dispatcher.push_message(addr, workers_msg.clone()) {
+                let workers_msg = format!("{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}", next_request_id, payload_for(*addr, worker_id.as_str()));
+                trace!(target: "stratum", "Pushing work to {} at addr {} (payload: '{}')", &worker_id, &addr, &workers_msg);
+                match tcp_dispatcher.push_message(addr, workers_msg) {
                     Err(PushMessageError::NoSuchPeer) => {
                         debug!(target: "stratum", "Worker no longer connected: {} addr {}", &worker_id, &addr);
                         hup_peers.insert(**addr);
@@ -239,6 +251,7 @@ impl StratumImpl {
             let mut workers = self.workers.write();
             for hup_peer in hup_peers {
                 workers.remove(&hup_peer);
+                self.dispatcher.worker_disconnected(&hup_peer);
             }
         }
 
@@ -482,6 +495,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_push_work_each() {
+        let addr = "127.0.0.1:19996".parse().unwrap();
+
+        struct TestDispatcher {
+            subscribed: Arc<RwLock<Vec<String>>>,
+        }
+
+        impl JobDispatcher for TestDispatcher {
+            fn submit(&self, _payload: Vec<String>) -> Result<(), Error> {
+                Ok(())
+            }
+
+            fn worker_subscribed(&self, _addr: &SocketAddr, worker_id: &str) {
+                self.subscribed.write().push(worker_id.to_owned());
+            }
+        }
+
+        let test_dispatcher = TestDispatcher {
+            subscribed: Arc::new(RwLock::new(Vec::new())),
+        };
+        let subscribed = test_dispatcher.subscribed.clone();
+
+        let stratum = Stratum::start(&addr, Arc::new(test_dispatcher), None)
+            .expect("Should start stratum");
+
+        let mut auth_request =
+            r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner1", ""], "id": 1}"#
+            .as_bytes()
+            .to_vec();
+        auth_request.extend(b"\n");
+
+        let auth_response = "{\"jsonrpc\":\"2.0\",\"result\":true,\"id\":1}\n";
+
+        let runtime = Runtime::new()
+            .expect("Tokio Runtime should be created with no errors");
+
+        let response = runtime.block_on(async {
+            let mut stream = TcpStream::connect(&addr)
+                .await
+                .expect("Should connect to server");
+
+            stream
+                .write_all(&auth_request)
+                .await
+                .expect("Should write auth request");
+
+            let mut read_buf0 = vec![0u8; auth_response.len()];
+            stream
+                .read_exact(&mut read_buf0)
+                .await
+                .expect("Should read auth response");
+            assert_eq!(String::from_utf8(read_buf0).unwrap(), auth_response);
+
+            sleep(std::time::Duration::from_millis(100)).await;
+
+            stratum
+                .push_work_each(&|_, worker_id| {
+                    format!(r#"[ "{}" ]"#, worker_id)
+                })
+                .expect("Pushing work should produce no errors");
+
+            sleep(std::time::Duration::from_millis(200)).await;
+
+            stream.shutdown().await.expect("Should shutdown write half");
+
+            let mut read_buf1 = Vec::with_capacity(2048);
+            stream
+                .read_to_end(&mut read_buf1)
+                .await
+                .expect("Should read work response");
+            read_buf1
+        });
+
+        assert_eq!(vec!["miner1".to_owned()], *subscribed.read());
+        assert_eq!(
+            "{ \"id\": 17, \"method\": \"mining.notify\", \"params\": [ \"miner1\" ] }\n",
+            String::from_utf8(response).expect("Response should be utf-8")
+        );
+    }
+
     #[test]
     fn test_can_subscribe_with_secret() {
         let addr = "127.0.0.1:19971".parse().unwrap();
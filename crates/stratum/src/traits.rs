@@ -20,7 +20,7 @@
 
 use cfx_types::H256;
 use jsonrpc_tcp_server::PushMessageError;
-use std;
+use std::{self, net::SocketAddr};
 
 #[derive(Debug, Clone)]
 pub enum Error {
@@ -46,6 +46,21 @@ impl From<PushMessageError> for Error {
 pub trait JobDispatcher: Send + Sync {
     // miner job result
     fn submit(&self, payload: Vec<String>) -> Result<(), Error>;
+
+    /// miner job result together with the address of the submitting peer.
+    /// Dispatchers that keep per-connection state (e.g. share difficulty)
+    /// should override this.
+    fn submit_from(
+        &self, _addr: &SocketAddr, payload: Vec<String>,
+    ) -> Result<(), Error> {
+        self.submit(payload)
+    }
+
+    /// A worker has been authorized on the connection `addr`.
+    fn worker_subscribed(&self, _addr: &SocketAddr, _worker_id: &str) {}
+
+    /// The worker on the connection `addr` is no longer reachable.
+    fn worker_disconnected(&self, _addr: &SocketAddr) {}
 }
 
 /// Interface that can handle requests to push job for workers
@@ -53,6 +68,12 @@ pub trait PushWorkHandler: Send + Sync {
     /// push the same work package for all workers (`payload`: json of
     /// pow-specific set of work specification)
     fn push_work_all(&self, payload: String) -> Result<(), Error>;
+
+    /// push a work package built separately for every worker by
+    /// `payload_for(addr, worker_id)`
+    fn push_work_each(
+        &self, payload_for: &dyn Fn(&SocketAddr, &str) -> String,
+    ) -> Result<(), Error>;
 }
 
 pub struct ServiceConfiguration {
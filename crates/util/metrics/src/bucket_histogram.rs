@@ -0,0 +1,119 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    metrics::{is_enabled, Metric},
+    registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
+    timer::Timer,
+};
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+
+/// The default upper bounds of buckets, in seconds, which are the same as
+/// the Prometheus client libraries.
+pub const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A histogram that counts the observed values in buckets of fixed upper
+/// bounds, which is exported as a Prometheus histogram. Unlike the sampling
+/// [`crate::Histogram`], quantiles could be aggregated across buckets and
+/// nodes by the server.
+pub struct BucketHistogram {
+    bounds: Vec<f64>,
+    inner: Mutex<BucketHistogramInner>,
+}
+
+struct BucketHistogramInner {
+    /// Non-cumulative counts of the buckets, and the last one is for
+    /// values greater than all bounds.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl BucketHistogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        assert!(
+            bounds.windows(2).all(|w| w[0] < w[1]),
+            "bucket bounds should be in increasing order"
+        );
+
+        BucketHistogram {
+            bounds: bounds.to_vec(),
+            inner: Mutex::new(BucketHistogramInner {
+                counts: vec![0; bounds.len() + 1],
+                sum: 0.0,
+            }),
+        }
+    }
+
+    /// Registers a histogram with `bounds`. The histogram is not registered
+    /// if metrics are disabled.
+    pub fn register(name: &str, bounds: &[f64]) -> Arc<BucketHistogram> {
+        let histogram = Arc::new(BucketHistogram::new(bounds));
+        if is_enabled() {
+            DEFAULT_REGISTRY
+                .write()
+                .register(name.into(), histogram.clone());
+        }
+        histogram
+    }
+
+    pub fn register_with_group(
+        group: &str, name: &str, bounds: &[f64],
+    ) -> Arc<BucketHistogram> {
+        let histogram = Arc::new(BucketHistogram::new(bounds));
+        if is_enabled() {
+            DEFAULT_GROUPING_REGISTRY.write().register(
+                group.into(),
+                name.into(),
+                histogram.clone(),
+            );
+        }
+        histogram
+    }
+
+    pub fn observe(&self, value: f64) {
+        let index = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+
+        let mut inner = self.inner.lock();
+        inner.counts[index] += 1;
+        inner.sum += value;
+    }
+
+    pub fn count(&self) -> u64 { self.inner.lock().counts.iter().sum() }
+
+    pub fn sum(&self) -> f64 { self.inner.lock().sum }
+
+    /// Returns the upper bounds of buckets with the cumulative counts, the
+    /// total count and the sum of observed values.
+    pub fn snapshot(&self) -> (Vec<(f64, u64)>, u64, f64) {
+        let inner = self.inner.lock();
+        let mut cumulative = 0;
+        let buckets = self
+            .bounds
+            .iter()
+            .zip(inner.counts.iter())
+            .map(|(bound, count)| {
+                cumulative += count;
+                (*bound, cumulative)
+            })
+            .collect();
+        let count = cumulative + inner.counts[self.bounds.len()];
+        (buckets, count, inner.sum)
+    }
+}
+
+/// Observes the durations in seconds.
+impl Timer for BucketHistogram {
+    fn update(&self, d: Duration) { self.observe(d.as_secs_f64()); }
+}
+
+impl Metric for BucketHistogram {
+    fn get_type(&self) -> &str { "BucketHistogram" }
+}
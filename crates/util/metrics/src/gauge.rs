@@ -11,6 +11,8 @@ use std::sync::{atomic::AtomicUsize, Arc};
 pub trait Gauge<T: Default>: Send + Sync {
     fn value(&self) -> T { T::default() }
     fn update(&self, _value: T) {}
+    fn inc(&self, _delta: T) {}
+    fn dec(&self, _delta: T) {}
 }
 
 struct NoopGauge;
@@ -60,6 +62,10 @@ macro_rules! construct_gauge {
             fn value(&self) -> usize { self.value.load(ORDER) }
 
             fn update(&self, value: usize) { self.value.store(value, ORDER); }
+
+            fn inc(&self, delta: usize) { self.value.fetch_add(delta, ORDER); }
+
+            fn dec(&self, delta: usize) { self.value.fetch_sub(delta, ORDER); }
         }
 
         impl Metric for $name {
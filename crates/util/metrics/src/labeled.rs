@@ -0,0 +1,173 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    bucket_histogram::BucketHistogram,
+    counter::CounterUsize,
+    gauge::GaugeUsize,
+    metrics::{is_enabled, Metric},
+    registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
+    report::Reportable,
+    report_influxdb::InfluxdbReportable,
+    report_prometheus::PrometheusSamples,
+};
+use parking_lot::RwLock;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The metric types which could be labeled.
+pub trait LabeledMetric:
+    Send + Sync + Reportable + InfluxdbReportable + PrometheusSamples
+{
+}
+
+impl LabeledMetric for CounterUsize {}
+impl LabeledMetric for GaugeUsize {}
+impl LabeledMetric for BucketHistogram {}
+
+/// A family of metrics with the same name, which are distinguished by the
+/// values of labels, e.g. the method of RPCs.
+///
+/// A metric is created on the first access of its label values and is only
+/// removed explicitly, so the label values should be of bounded cardinality.
+pub struct MetricVec<M> {
+    label_names: Vec<String>,
+    metrics: RwLock<BTreeMap<Vec<String>, Arc<M>>>,
+    new_metric: Box<dyn Fn() -> M + Send + Sync>,
+}
+
+pub type CounterVec = MetricVec<CounterUsize>;
+pub type GaugeVec = MetricVec<GaugeUsize>;
+pub type HistogramVec = MetricVec<BucketHistogram>;
+
+impl<M: LabeledMetric + 'static> MetricVec<M> {
+    fn new(
+        label_names: &[&str], new_metric: Box<dyn Fn() -> M + Send + Sync>,
+    ) -> Self {
+        MetricVec {
+            label_names: label_names.iter().map(|l| l.to_string()).collect(),
+            metrics: Default::default(),
+            new_metric,
+        }
+    }
+
+    /// Registers the family if metrics are enabled.
+    fn register_vec(self, group: Option<&str>, name: &str) -> Arc<Self> {
+        let metrics = Arc::new(self);
+        if is_enabled() {
+            match group {
+                Some(group) => DEFAULT_GROUPING_REGISTRY.write().register(
+                    group.into(),
+                    name.into(),
+                    metrics.clone(),
+                ),
+                None => DEFAULT_REGISTRY
+                    .write()
+                    .register(name.into(), metrics.clone()),
+            }
+        }
+        metrics
+    }
+
+    /// Returns the metric of `label_values`, which are in the order of the
+    /// label names.
+    pub fn with_label_values(&self, label_values: &[&str]) -> Arc<M> {
+        assert_eq!(
+            label_values.len(),
+            self.label_names.len(),
+            "label values do not match label names {:?}",
+            self.label_names
+        );
+
+        let key: Vec<String> =
+            label_values.iter().map(|v| v.to_string()).collect();
+        if let Some(metric) = self.metrics.read().get(&key) {
+            return metric.clone();
+        }
+        self.metrics
+            .write()
+            .entry(key)
+            .or_insert_with(|| Arc::new((self.new_metric)()))
+            .clone()
+    }
+
+    /// Removes the metric of `label_values`, so that label values which are
+    /// no longer in use, e.g. of disconnected peers, are not reported.
+    pub fn remove_label_values(&self, label_values: &[&str]) -> Option<Arc<M>> {
+        let key: Vec<String> =
+            label_values.iter().map(|v| v.to_string()).collect();
+        self.metrics.write().remove(&key)
+    }
+
+    pub fn label_names(&self) -> &[String] { &self.label_names }
+
+    /// Returns the label values with the metrics, ordered by label values.
+    pub fn get_all(&self) -> Vec<(Vec<String>, Arc<M>)> {
+        self.metrics
+            .read()
+            .iter()
+            .map(|(values, metric)| (values.clone(), metric.clone()))
+            .collect()
+    }
+
+    /// Returns the pairs of label names and values of `label_values`.
+    pub(crate) fn labels<'a>(
+        &'a self, label_values: &'a [String],
+    ) -> Vec<(&'a str, &'a str)> {
+        self.label_names
+            .iter()
+            .map(String::as_str)
+            .zip(label_values.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+impl CounterVec {
+    pub fn register(name: &str, label_names: &[&str]) -> Arc<CounterVec> {
+        Self::new(label_names, Box::new(CounterUsize::default))
+            .register_vec(None, name)
+    }
+
+    pub fn register_with_group(
+        group: &str, name: &str, label_names: &[&str],
+    ) -> Arc<CounterVec> {
+        Self::new(label_names, Box::new(CounterUsize::default))
+            .register_vec(Some(group), name)
+    }
+}
+
+impl GaugeVec {
+    pub fn register(name: &str, label_names: &[&str]) -> Arc<GaugeVec> {
+        Self::new(label_names, Box::new(GaugeUsize::default))
+            .register_vec(None, name)
+    }
+
+    pub fn register_with_group(
+        group: &str, name: &str, label_names: &[&str],
+    ) -> Arc<GaugeVec> {
+        Self::new(label_names, Box::new(GaugeUsize::default))
+            .register_vec(Some(group), name)
+    }
+}
+
+impl HistogramVec {
+    pub fn register(
+        name: &str, label_names: &[&str], bounds: &[f64],
+    ) -> Arc<HistogramVec> {
+        let bounds = bounds.to_vec();
+        Self::new(label_names, Box::new(move || BucketHistogram::new(&bounds)))
+            .register_vec(None, name)
+    }
+
+    pub fn register_with_group(
+        group: &str, name: &str, label_names: &[&str], bounds: &[f64],
+    ) -> Arc<HistogramVec> {
+        let bounds = bounds.to_vec();
+        Self::new(label_names, Box::new(move || BucketHistogram::new(&bounds)))
+            .register_vec(Some(group), name)
+    }
+}
+
+impl<M: LabeledMetric + 'static> Metric for MetricVec<M> {
+    fn get_type(&self) -> &str { "Labeled" }
+}
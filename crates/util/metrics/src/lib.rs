@@ -2,13 +2,16 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
+mod bucket_histogram;
 mod counter;
 mod ewma;
 mod gauge;
 mod histogram;
+mod labeled;
 mod lock;
 mod meter;
 mod metrics;
+mod process;
 mod queue;
 mod registry;
 mod report;
@@ -17,9 +20,11 @@ mod report_prometheus;
 mod timer;
 
 pub use self::{
+    bucket_histogram::{BucketHistogram, DEFAULT_BUCKETS},
     counter::{Counter, CounterUsize},
     gauge::{Gauge, GaugeUsize},
     histogram::{Histogram, Sample},
+    labeled::{CounterVec, GaugeVec, HistogramVec, LabeledMetric, MetricVec},
     lock::{Lock, MutexExtensions, RwLockExtensions},
     meter::{register_meter, register_meter_with_group, Meter, MeterTimer},
     metrics::{initialize, is_enabled, Metric, MetricsConfiguration},
@@ -0,0 +1,119 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::fmt::{self, Write};
+
+/// Writes the standard Prometheus process metrics, e.g.
+/// `process_cpu_seconds_total` and `process_resident_memory_bytes`. The
+/// metrics which could not be read are skipped.
+#[cfg(target_os = "linux")]
+pub fn write_process_metrics(buffer: &mut dyn Write) -> fmt::Result {
+    use std::fs;
+
+    // SAFETY: `sysconf` has no side effects.
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as f64;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as f64;
+
+    if let Ok(stat) = fs::read_to_string("/proc/self/stat") {
+        // The fields after the command name, which is in parentheses and
+        // may contain spaces. `fields[0]` is the 3rd field `state`.
+        let fields: Vec<&str> = stat
+            .rsplit_once(')')
+            .map_or(vec![], |(_, rest)| rest.split_whitespace().collect());
+        let field =
+            |n: usize| fields.get(n - 3).and_then(|f| f.parse::<f64>().ok());
+
+        if let (Some(utime), Some(stime)) = (field(14), field(15)) {
+            write_metric(
+                buffer,
+                "process_cpu_seconds_total",
+                "counter",
+                "Total user and system CPU time spent in seconds.",
+                (utime + stime) / ticks_per_second,
+            )?;
+        }
+        if let Some(threads) = field(20) {
+            write_metric(
+                buffer,
+                "process_threads",
+                "gauge",
+                "Number of OS threads in the process.",
+                threads,
+            )?;
+        }
+        let boot_time = fs::read_to_string("/proc/stat").ok().and_then(|s| {
+            s.lines()
+                .find_map(|line| line.strip_prefix("btime "))
+                .and_then(|t| t.trim().parse::<f64>().ok())
+        });
+        if let (Some(boot_time), Some(start_time)) = (boot_time, field(22)) {
+            write_metric(
+                buffer,
+                "process_start_time_seconds",
+                "gauge",
+                "Start time of the process since unix epoch in seconds.",
+                boot_time + start_time / ticks_per_second,
+            )?;
+        }
+        if let Some(vsize) = field(23) {
+            write_metric(
+                buffer,
+                "process_virtual_memory_bytes",
+                "gauge",
+                "Virtual memory size in bytes.",
+                vsize,
+            )?;
+        }
+        if let Some(rss) = field(24) {
+            write_metric(
+                buffer,
+                "process_resident_memory_bytes",
+                "gauge",
+                "Resident memory size in bytes.",
+                rss * page_size,
+            )?;
+        }
+    }
+
+    if let Ok(fds) = fs::read_dir("/proc/self/fd") {
+        write_metric(
+            buffer,
+            "process_open_fds",
+            "gauge",
+            "Number of open file descriptors.",
+            fds.count() as f64,
+        )?;
+    }
+
+    let max_fds = fs::read_to_string("/proc/self/limits").ok().and_then(|s| {
+        s.lines()
+            .find_map(|line| line.strip_prefix("Max open files"))
+            .and_then(|limits| limits.split_whitespace().next())
+            .and_then(|soft| soft.parse::<f64>().ok())
+    });
+    if let Some(max_fds) = max_fds {
+        write_metric(
+            buffer,
+            "process_max_fds",
+            "gauge",
+            "Maximum number of open file descriptors.",
+            max_fds,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn write_metric(
+    buffer: &mut dyn Write, name: &str, metric_type: &str, help: &str,
+    value: f64,
+) -> fmt::Result {
+    writeln!(buffer, "# HELP {} {}", name, help)?;
+    writeln!(buffer, "# TYPE {} {}", name, metric_type)?;
+    writeln!(buffer, "{} {}", name, value)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn write_process_metrics(_buffer: &mut dyn Write) -> fmt::Result { Ok(()) }
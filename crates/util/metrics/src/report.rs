@@ -3,9 +3,11 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    bucket_histogram::BucketHistogram,
     counter::{Counter, CounterUsize},
     gauge::{Gauge, GaugeUsize},
     histogram::Histogram,
+    labeled::{LabeledMetric, MetricVec},
     meter::{Meter, StandardMeter},
     metrics::is_enabled,
     registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
@@ -199,3 +201,57 @@ impl<T: Histogram> Reportable for T {
         )
     }
 }
+
+impl Reportable for BucketHistogram {
+    fn get_value(&self) -> String {
+        let (buckets, count, sum) = self.snapshot();
+        let buckets: Vec<String> = buckets
+            .iter()
+            .map(|(bound, count)| format!("le_{}: {}", bound, count))
+            .collect();
+        format!("{{count: {}, sum: {}, {}}}", count, sum, buckets.join(", "))
+    }
+
+    fn get_value_with_group(&self, name: &String) -> String {
+        let (buckets, count, sum) = self.snapshot();
+        let buckets: Vec<String> = buckets
+            .iter()
+            .map(|(bound, count)| format!("{}.le_{}: {}", name, bound, count))
+            .collect();
+        format!(
+            "{0}.count: {1}, {0}.sum: {2}, {3}",
+            name,
+            count,
+            sum,
+            buckets.join(", ")
+        )
+    }
+}
+
+impl<M: LabeledMetric + 'static> Reportable for MetricVec<M> {
+    fn get_value(&self) -> String {
+        let values: Vec<String> = self
+            .get_all()
+            .iter()
+            .map(|(label_values, metric)| {
+                metric.get_value_with_group(&label_values.join("."))
+            })
+            .collect();
+        format!("{{{}}}", values.join(", "))
+    }
+
+    fn get_value_with_group(&self, name: &String) -> String {
+        let values: Vec<String> = self
+            .get_all()
+            .iter()
+            .map(|(label_values, metric)| {
+                metric.get_value_with_group(&format!(
+                    "{}.{}",
+                    name,
+                    label_values.join(".")
+                ))
+            })
+            .collect();
+        values.join(", ")
+    }
+}
@@ -3,9 +3,11 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    bucket_histogram::BucketHistogram,
     counter::{Counter, CounterUsize},
     gauge::{Gauge, GaugeUsize},
     histogram::Histogram,
+    labeled::{LabeledMetric, MetricVec},
     meter::{Meter, StandardMeter},
     registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
     report::Reporter,
@@ -215,3 +217,29 @@ impl<T: Histogram> InfluxdbReportable for T {
             )
     }
 }
+
+impl InfluxdbReportable for BucketHistogram {
+    fn add_field(&self, point: Point, prefix: Option<&String>) -> Point {
+        let (buckets, count, sum) = self.snapshot();
+        let mut point = point
+            .add_field(field("count", prefix), Value::Integer(count as i64))
+            .add_field(field("sum", prefix), Value::Float(sum));
+        for (bound, count) in buckets {
+            point = point.add_field(
+                field(&format!("le_{}", bound), prefix),
+                Value::Integer(count as i64),
+            );
+        }
+        point
+    }
+}
+
+impl<M: LabeledMetric + 'static> InfluxdbReportable for MetricVec<M> {
+    fn add_field(&self, mut point: Point, prefix: Option<&String>) -> Point {
+        for (label_values, metric) in self.get_all() {
+            let label_prefix = field(&label_values.join("."), prefix);
+            point = metric.add_field(point, Some(&label_prefix));
+        }
+        point
+    }
+}
@@ -3,11 +3,14 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    bucket_histogram::BucketHistogram,
     counter::{Counter, CounterUsize},
     gauge::{Gauge, GaugeUsize},
     histogram::Histogram,
+    labeled::{LabeledMetric, MetricVec},
     meter::{Meter, StandardMeter},
     metrics::is_enabled,
+    process::write_process_metrics,
     registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
 };
 use hyper_util::rt::TokioIo;
@@ -155,6 +158,8 @@ impl PrometheusReporter {
             }
         }
 
+        write_process_metrics(&mut buffer)?;
+
         PREVIOUS_METRICS_SIZE.store(buffer.len(), Ordering::Relaxed);
         Ok(buffer)
     }
@@ -164,28 +169,153 @@ pub trait PrometheusReportable {
         &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
     ) -> std::fmt::Result;
 }
+
+/// Metrics which could be written as samples with labels, used to export
+/// both the plain and the labeled metrics.
+pub trait PrometheusSamples {
+    const TYPE: &'static str;
+
+    fn write_samples(
+        &self, name: &str, labels: &[(&str, &str)], buffer: &mut dyn Write,
+    ) -> std::fmt::Result;
+}
+
+/// Returns the name prefixed by the group, with the characters not allowed
+/// by Prometheus replaced by `_`.
+fn metric_name(name: &str, group: Option<&str>) -> String {
+    let full_name =
+        group.map_or_else(|| name.to_string(), |g| format!("{}_{}", g, name));
+    full_name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Formats the labels as `{name="value",...}`, or an empty string if there
+/// are no labels.
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+fn write_header(
+    full_name: &str, metric_type: &str, buffer: &mut dyn Write,
+) -> std::fmt::Result {
+    writeln!(buffer, "# HELP {} {}", full_name, full_name)?;
+    writeln!(buffer, "# TYPE {} {}", full_name, metric_type)
+}
+
+impl PrometheusSamples for CounterUsize {
+    const TYPE: &'static str = "counter";
+
+    fn write_samples(
+        &self, name: &str, labels: &[(&str, &str)], buffer: &mut dyn Write,
+    ) -> std::fmt::Result {
+        writeln!(buffer, "{}{} {}", name, format_labels(labels), self.count())
+    }
+}
+
+impl PrometheusSamples for GaugeUsize {
+    const TYPE: &'static str = "gauge";
+
+    fn write_samples(
+        &self, name: &str, labels: &[(&str, &str)], buffer: &mut dyn Write,
+    ) -> std::fmt::Result {
+        writeln!(buffer, "{}{} {}", name, format_labels(labels), self.value())
+    }
+}
+
+impl PrometheusSamples for BucketHistogram {
+    const TYPE: &'static str = "histogram";
+
+    fn write_samples(
+        &self, name: &str, labels: &[(&str, &str)], buffer: &mut dyn Write,
+    ) -> std::fmt::Result {
+        let (buckets, count, sum) = self.snapshot();
+        for (bound, bucket_count) in buckets {
+            let bound = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &bound));
+            writeln!(
+                buffer,
+                "{}_bucket{} {}",
+                name,
+                format_labels(&bucket_labels),
+                bucket_count
+            )?;
+        }
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", "+Inf"));
+        writeln!(
+            buffer,
+            "{}_bucket{} {}",
+            name,
+            format_labels(&bucket_labels),
+            count
+        )?;
+        let labels = format_labels(labels);
+        writeln!(buffer, "{}_sum{} {}", name, labels, sum)?;
+        writeln!(buffer, "{}_count{} {}", name, labels, count)
+    }
+}
+
 impl PrometheusReportable for CounterUsize {
     fn write_prometheus(
         &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
     ) -> std::fmt::Result {
-        let full_name = group
-            .map_or_else(|| name.to_string(), |g| format!("{}_{}", g, name));
-        writeln!(buffer, "# HELP {} {}", full_name, full_name)?;
-        writeln!(buffer, "# TYPE {} counter", full_name)?;
-        writeln!(buffer, "{} {}", full_name, self.count())?;
-        Ok(())
+        let full_name = metric_name(name, group);
+        write_header(&full_name, Self::TYPE, buffer)?;
+        self.write_samples(&full_name, &[], buffer)
     }
 }
 impl PrometheusReportable for GaugeUsize {
     fn write_prometheus(
         &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
     ) -> std::fmt::Result {
-        let full_name = group
-            .map_or_else(|| name.to_string(), |g| format!("{}_{}", g, name));
-
-        writeln!(buffer, "# HELP {} {}", full_name, full_name)?;
-        writeln!(buffer, "# TYPE {} gauge", full_name)?;
-        writeln!(buffer, "{} {}", full_name, self.value())?;
+        let full_name = metric_name(name, group);
+        write_header(&full_name, Self::TYPE, buffer)?;
+        self.write_samples(&full_name, &[], buffer)
+    }
+}
+impl PrometheusReportable for BucketHistogram {
+    fn write_prometheus(
+        &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
+    ) -> std::fmt::Result {
+        let full_name = metric_name(name, group);
+        write_header(&full_name, Self::TYPE, buffer)?;
+        self.write_samples(&full_name, &[], buffer)
+    }
+}
+impl<M: LabeledMetric + 'static> PrometheusReportable for MetricVec<M> {
+    fn write_prometheus(
+        &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
+    ) -> std::fmt::Result {
+        let full_name = metric_name(name, group);
+        write_header(&full_name, M::TYPE, buffer)?;
+        for (label_values, metric) in self.get_all() {
+            metric.write_samples(
+                &full_name,
+                &self.labels(&label_values),
+                buffer,
+            )?;
+        }
         Ok(())
     }
 }
@@ -193,8 +323,7 @@ impl PrometheusReportable for StandardMeter {
     fn write_prometheus(
         &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
     ) -> std::fmt::Result {
-        let base_name = group
-            .map_or_else(|| name.to_string(), |g| format!("{}_{}", g, name));
+        let base_name = metric_name(name, group);
         let snapshot = self.snapshot();
         // count (counter)
         let count_name = format!("{}_total", base_name);
@@ -238,8 +367,7 @@ impl<T: Histogram> PrometheusReportable for T {
     fn write_prometheus(
         &self, name: &str, group: Option<&str>, buffer: &mut dyn Write,
     ) -> std::fmt::Result {
-        let base_name = group
-            .map_or_else(|| name.to_string(), |g| format!("{}_{}", g, name));
+        let base_name = metric_name(name, group);
         let snapshot = self.snapshot();
 
         writeln!(buffer, "# HELP {} {}", base_name, base_name)?;
@@ -269,7 +397,10 @@ mod tests {
     use std::time::Duration;
 
     use super::*;
-    use crate::{metrics, CounterUsize, GaugeUsize, Histogram, Meter};
+    use crate::{
+        metrics, CounterUsize, CounterVec, GaugeUsize, Histogram, HistogramVec,
+        Meter,
+    };
     use cfx_tasks::TaskManager;
     use tokio::{net::TcpStream, time::timeout};
 
@@ -539,4 +670,77 @@ mod tests {
             buffer.contains("node_test_request_histogram{quantile=\"0.999\"}")
         );
     }
+
+    #[test]
+    fn test_bucket_histogram_prometheus() {
+        let histogram = BucketHistogram::new(&[1.0, 5.0, 10.0]);
+        for value in [0.5, 1.0, 3.0, 7.0, 20.0] {
+            histogram.observe(value);
+        }
+
+        let mut buffer = String::new();
+        histogram
+            .write_prometheus("test_histogram", Some("node"), &mut buffer)
+            .unwrap();
+
+        assert!(buffer.contains("# TYPE node_test_histogram histogram"));
+        assert!(buffer.contains("node_test_histogram_bucket{le=\"1\"} 2"));
+        assert!(buffer.contains("node_test_histogram_bucket{le=\"5\"} 3"));
+        assert!(buffer.contains("node_test_histogram_bucket{le=\"10\"} 4"));
+        assert!(buffer.contains("node_test_histogram_bucket{le=\"+Inf\"} 5"));
+        assert!(buffer.contains("node_test_histogram_sum 31.5"));
+        assert!(buffer.contains("node_test_histogram_count 5"));
+    }
+
+    #[test]
+    fn test_labeled_prometheus() {
+        let counter =
+            CounterVec::register_with_group("rpc", "requests", &["method"]);
+        counter.with_label_values(&["cfx_call"]).inc(3);
+        counter.with_label_values(&["eth_\"call\""]).inc(1);
+
+        let histogram =
+            HistogramVec::register("test_latency", &["space"], &[0.1, 1.0]);
+        histogram.with_label_values(&["native"]).observe(0.5);
+
+        let mut buffer = String::new();
+        counter
+            .write_prometheus("requests", Some("rpc"), &mut buffer)
+            .unwrap();
+        histogram
+            .write_prometheus("test_latency", None, &mut buffer)
+            .unwrap();
+
+        assert_eq!(buffer.matches("# TYPE rpc_requests counter").count(), 1);
+        assert!(buffer.contains("rpc_requests{method=\"cfx_call\"} 3"));
+        assert!(buffer.contains("rpc_requests{method=\"eth_\\\"call\\\"\"} 1"));
+        assert!(buffer.contains("# TYPE test_latency histogram"));
+        assert!(buffer
+            .contains("test_latency_bucket{space=\"native\",le=\"0.1\"} 0"));
+        assert!(
+            buffer.contains("test_latency_bucket{space=\"native\",le=\"1\"} 1")
+        );
+        assert!(buffer.contains("test_latency_count{space=\"native\"} 1"));
+    }
+
+    #[test]
+    fn test_metric_name() {
+        assert_eq!(
+            metric_name("tx_pool::insert_new_tx", Some("timer")),
+            "timer_tx_pool__insert_new_tx"
+        );
+        assert_eq!(metric_name("1st-metric", None), "_st_metric");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_metrics() {
+        let mut buffer = String::new();
+        write_process_metrics(&mut buffer).unwrap();
+
+        assert!(buffer.contains("# TYPE process_cpu_seconds_total counter"));
+        assert!(buffer.contains("process_resident_memory_bytes "));
+        assert!(buffer.contains("process_open_fds "));
+        assert!(buffer.contains("process_start_time_seconds "));
+    }
 }
@@ -3,6 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    bucket_histogram::{BucketHistogram, DEFAULT_BUCKETS},
     histogram::{Histogram, Sample},
     meter::{register_meter_with_group, Meter},
     metrics::is_enabled,
@@ -21,7 +22,7 @@ pub trait Timer: Send + Sync {
 }
 
 fn register_timer_exp_decay(
-    group: &str, counter_name: &str, time_name: &str,
+    group: &str, counter_name: &str, time_name: &str, seconds_name: &str,
 ) -> Arc<dyn Timer> {
     if !is_enabled() {
         Arc::new(NoopTimer)
@@ -30,18 +31,29 @@ fn register_timer_exp_decay(
             meter: register_meter_with_group(group, counter_name),
             histogram: Sample::ExpDecay(0.015)
                 .register_with_group(group, time_name, 1024),
+            buckets: BucketHistogram::register_with_group(
+                group,
+                seconds_name,
+                &DEFAULT_BUCKETS,
+            ),
         })
     }
 }
 
 pub fn register_timer(name: &str) -> Arc<dyn Timer> {
-    register_timer_exp_decay(name, "counter", "time_expdec")
+    register_timer_exp_decay(name, "counter", "time_expdec", "seconds")
 }
 
 pub fn register_timer_with_group(group: &str, name: &str) -> Arc<dyn Timer> {
     let counter_name = format!("{}_counter", name);
     let time_name = format!("{}_time_expdec", name);
-    register_timer_exp_decay(group, counter_name.as_str(), time_name.as_str())
+    let seconds_name = format!("{}_seconds", name);
+    register_timer_exp_decay(
+        group,
+        counter_name.as_str(),
+        time_name.as_str(),
+        seconds_name.as_str(),
+    )
 }
 
 struct NoopTimer;
@@ -50,12 +62,15 @@ impl Timer for NoopTimer {}
 struct StandardTimer {
     meter: Arc<dyn Meter>,
     histogram: Arc<dyn Histogram>,
+    /// The time in seconds, exported as a Prometheus histogram.
+    buckets: Arc<BucketHistogram>,
 }
 
 impl Timer for StandardTimer {
     fn update(&self, d: Duration) {
         self.meter.mark(1);
         self.histogram.update(d.as_nanos() as u64);
+        self.buckets.update(d);
     }
 }
 
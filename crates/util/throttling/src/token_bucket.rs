@@ -2,19 +2,30 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
+use lazy_static::lazy_static;
 use malloc_size_of::MallocSizeOf;
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use std::{
     cmp::{max, min},
     collections::HashMap,
     fs::read_to_string,
     hash::Hash,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Weak},
     time::{Duration, Instant},
 };
 
+type Buckets = HashMap<String, Arc<Mutex<TokenBucket>>>;
+
+lazy_static! {
+    /// Buckets of the managers created by
+    /// `TokenBucketManager::load_reloadable`, with the section of the
+    /// throttling file they are loaded from.
+    static ref RELOADABLE_MANAGERS: Mutex<Vec<(String, Weak<RwLock<Buckets>>)>> =
+        Default::default();
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ThrottleResult {
     Success,
@@ -254,21 +265,23 @@ impl FromStr for TokenBucket {
 
 #[derive(Default, DeriveMallocSizeOf, Clone)]
 pub struct TokenBucketManager {
-    // manage buckets by name
-    buckets: HashMap<String, Arc<Mutex<TokenBucket>>>,
+    // manage buckets by name, shared by the clones so that a reload applies
+    // to all of them
+    buckets: Arc<RwLock<Buckets>>,
 }
 
 impl TokenBucketManager {
     pub fn register(&mut self, name: String, bucket: TokenBucket) {
-        if self.buckets.contains_key(&name) {
+        let mut buckets = self.buckets.write();
+        if buckets.contains_key(&name) {
             panic!("token bucket {:?} already registered", name);
         }
 
-        self.buckets.insert(name, Arc::new(Mutex::new(bucket)));
+        buckets.insert(name, Arc::new(Mutex::new(bucket)));
     }
 
     pub fn get(&self, name: &str) -> Option<Arc<Mutex<TokenBucket>>> {
-        self.buckets.get(name).cloned()
+        self.buckets.read().get(name).cloned()
     }
 
     pub fn load(
@@ -287,7 +300,9 @@ impl TokenBucketManager {
             },
             None => &toml_val,
         };
-        let table = val.as_table().expect("not table value");
+        let table = val.as_table().ok_or_else(|| {
+            format!("invalid value type {:?}, table required", val.type_str())
+        })?;
 
         let mut manager = TokenBucketManager::default();
 
@@ -307,6 +322,51 @@ impl TokenBucketManager {
 
         Ok(manager)
     }
+
+    /// Loads the manager like `load` if `toml_file` is specified, and
+    /// registers it to be updated by `reload_managers`.
+    pub fn load_reloadable(
+        toml_file: Option<&str>, section: &str,
+    ) -> Result<Self, String> {
+        let manager = match toml_file {
+            Some(file) => TokenBucketManager::load(file, Some(section))?,
+            None => TokenBucketManager::default(),
+        };
+
+        RELOADABLE_MANAGERS
+            .lock()
+            .push((section.into(), Arc::downgrade(&manager.buckets)));
+
+        Ok(manager)
+    }
+}
+
+/// Reloads the managers created by `TokenBucketManager::load_reloadable` from
+/// `toml_file`, or removes all their buckets if it is not specified. No
+/// manager is changed if any section is invalid. Returns the number of
+/// reloaded managers.
+///
+/// Note, the reloaded buckets start with a clean state.
+pub fn reload_managers(toml_file: Option<&str>) -> Result<usize, String> {
+    let mut managers = RELOADABLE_MANAGERS.lock();
+    managers.retain(|(_, buckets)| buckets.strong_count() > 0);
+
+    let mut reloaded = Vec::with_capacity(managers.len());
+    for (section, _) in managers.iter() {
+        let manager = match toml_file {
+            Some(file) => TokenBucketManager::load(file, Some(section))?,
+            None => TokenBucketManager::default(),
+        };
+        reloaded.push(manager);
+    }
+
+    for ((_, buckets), manager) in managers.iter().zip(reloaded) {
+        if let Some(buckets) = buckets.upgrade() {
+            *buckets.write() = std::mem::take(&mut *manager.buckets.write());
+        }
+    }
+
+    Ok(managers.len())
 }
 
 #[derive(Default, DeriveMallocSizeOf)]
@@ -340,8 +400,10 @@ impl<K: Eq + Hash + MallocSizeOf> ThrottledManager<K> {
 
 #[cfg(test)]
 mod tests {
-    use crate::token_bucket::{ThrottleResult, TokenBucket};
-    use std::{thread::sleep, time::Duration};
+    use crate::token_bucket::{
+        reload_managers, ThrottleResult, TokenBucket, TokenBucketManager,
+    };
+    use std::{fs, thread::sleep, time::Duration};
 
     #[test]
     fn test_init_tokens() {
@@ -440,4 +502,36 @@ mod tests {
         // already throttled
         assert_eq!(bucket.throttle(1, 1), ThrottleResult::AlreadyThrottled);
     }
+
+    #[test]
+    fn test_reload_managers() {
+        let file = std::env::temp_dir()
+            .join(format!("throttling_reload_{}.toml", std::process::id()));
+        let file = file.to_str().unwrap();
+        fs::write(file, "[reload_test]\ncfx_epochNumber=\"3,1,1,1,0\"\n")
+            .unwrap();
+
+        let manager =
+            TokenBucketManager::load_reloadable(Some(file), "reload_test")
+                .unwrap();
+        let cloned = manager.clone();
+        assert!(cloned.get("cfx_epochNumber").is_some());
+        assert!(cloned.get("cfx_getStatus").is_none());
+
+        // an invalid file keeps the current buckets
+        fs::write(file, "[reload_test]\ncfx_getStatus=\"3,1\"\n").unwrap();
+        assert!(reload_managers(Some(file)).is_err());
+        assert!(cloned.get("cfx_epochNumber").is_some());
+
+        fs::write(file, "[reload_test]\ncfx_getStatus=\"3,1,1,1,0\"\n")
+            .unwrap();
+        reload_managers(Some(file)).unwrap();
+        assert!(manager.get("cfx_epochNumber").is_none());
+        assert!(cloned.get("cfx_getStatus").is_some());
+
+        reload_managers(None).unwrap();
+        assert!(cloned.get("cfx_getStatus").is_none());
+
+        fs::remove_file(file).unwrap();
+    }
 }
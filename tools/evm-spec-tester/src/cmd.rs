@@ -1,4 +1,7 @@
-use crate::{blocktest::BlockchainTestCmd, statetest::command::StateTestCmd};
+use crate::{
+    blocktest::BlockchainTestCmd, run::command::RunCmd,
+    statetest::command::StateTestCmd, t8n::command::T8nCmd,
+};
 use clap::{Parser, Subcommand};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 
@@ -24,6 +27,11 @@ pub enum Commands {
     Statetest(StateTestCmd),
     /// Execute blockchain tests of ethereum execution spec tests
     Blocktest(BlockchainTestCmd),
+    /// Apply transactions on a pre-state, like the geth `evm t8n` tool
+    T8n(T8nCmd),
+    /// Run bytecode or a contract call against a pre-state, like the geth
+    /// `evm run` tool
+    Run(RunCmd),
 }
 
 impl MainCmd {
@@ -31,6 +39,8 @@ impl MainCmd {
         match self.command {
             Commands::Statetest(cmd) => cmd.run(),
             Commands::Blocktest(cmd) => cmd.run(),
+            Commands::T8n(cmd) => cmd.run(),
+            Commands::Run(cmd) => cmd.run(),
         }
     }
 }
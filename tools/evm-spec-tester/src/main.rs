@@ -3,7 +3,9 @@ extern crate log;
 
 mod blocktest;
 mod cmd;
+mod run;
 mod statetest;
+mod t8n;
 mod util;
 
 use clap::Parser;
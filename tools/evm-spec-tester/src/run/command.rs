@@ -0,0 +1,87 @@
+use crate::util::make_configuration;
+use cfx_config::Configuration;
+use cfx_rpc_eth_types::Bytes;
+use cfx_types::{Address, Space, U256};
+use clap::Args;
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+
+/// Runs bytecode or a contract call against a pre-state, like geth `evm run`.
+#[derive(Args, Debug)]
+pub struct RunCmd {
+    /// Bytecode to run, in hex
+    pub(super) code: Option<String>,
+
+    /// File containing the bytecode to run, in hex
+    #[arg(long, conflicts_with = "code")]
+    pub(super) codefile: Option<PathBuf>,
+
+    /// Conflux client configuration
+    #[arg(short, long, value_parser = make_configuration, default_value = "", help = "Path to the configuration file")]
+    pub(super) config: Arc<Configuration>,
+
+    /// Pre-state allocation of accounts, in the format of `t8n` alloc
+    #[arg(long)]
+    pub(super) prestate: Option<PathBuf>,
+
+    /// Space whose rules are used: `native` (core space) or `ethereum`
+    /// (eSpace)
+    #[arg(long, value_parser = parse_space, default_value = "ethereum")]
+    pub(super) space: Space,
+
+    /// Sender of the call, funded if absent from the pre-state
+    #[arg(long, value_parser = parse_address, default_value = "0x1000000000000000000000000000000000000001")]
+    pub(super) sender: Address,
+
+    /// Receiver of the call, where the bytecode is placed
+    #[arg(long, value_parser = parse_address, default_value = "0x8000000000000000000000000000000000000001")]
+    pub(super) receiver: Address,
+
+    /// Call data, in hex
+    #[arg(long, value_parser = parse_bytes, default_value = "")]
+    pub(super) input: Bytes,
+
+    /// Value transferred to the receiver
+    #[arg(long, value_parser = parse_u256, default_value = "0")]
+    pub(super) value: U256,
+
+    /// Gas limit of the call
+    #[arg(long, default_value_t = 10_000_000)]
+    pub(super) gas: u64,
+
+    /// Gas price of the call
+    #[arg(long, value_parser = parse_u256, default_value = "0")]
+    pub(super) price: U256,
+
+    /// Run the bytecode as the init code of a contract creation
+    #[arg(long)]
+    pub(super) create: bool,
+
+    /// Print the EIP-3155 trace of the executed opcodes to stderr
+    #[arg(long)]
+    pub(super) trace: bool,
+}
+
+fn parse_space(s: &str) -> Result<Space, String> {
+    match s {
+        "native" | "core" => Ok(Space::Native),
+        "ethereum" | "evm" | "espace" => Ok(Space::Ethereum),
+        _ => Err(format!("unknown space {}", s)),
+    }
+}
+
+fn parse_address(s: &str) -> Result<Address, String> {
+    Address::from_str(s.trim_start_matches("0x")).map_err(|e| e.to_string())
+}
+
+pub(super) fn parse_bytes(s: &str) -> Result<Bytes, String> {
+    hex::decode(s.trim().trim_start_matches("0x"))
+        .map(Bytes)
+        .map_err(|e| e.to_string())
+}
+
+fn parse_u256(s: &str) -> Result<U256, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => U256::from_str(hex).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(s).map_err(|e| format!("{:?}", e)),
+    }
+}
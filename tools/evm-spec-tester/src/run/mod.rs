@@ -0,0 +1,348 @@
+pub(crate) mod command;
+mod tracer;
+
+use crate::{
+    statetest::unit_tester::pre_transact, t8n::types::T8nAccount,
+    util::read_json,
+};
+use cfx_executor::{
+    executive::{
+        ChargeCollateral, Executed, ExecutionOutcome, ExecutiveContext,
+        TransactOptions, TransactSettings,
+    },
+    machine::{Machine, VmFactory},
+};
+use cfx_parameters::consensus::ONE_CFX_IN_DRIP;
+use cfx_rpc_eth_types::Bytes;
+use cfx_types::{Address, AddressSpaceUtil, Space, H256, U256};
+use command::{parse_bytes, RunCmd};
+use eest_types::{AccountInfo, Env as StateTestEnv};
+use primitives::{
+    transaction::{Action, Eip155Transaction, NativeTransaction},
+    SignedTransaction,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracer::{Eip3155TraceKey, Eip3155Tracer};
+
+/// The result of the run, also used as the summary line of the trace.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunResult {
+    output: Bytes,
+    gas_used: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RunCmd {
+    /// Runs `run` command.
+    pub fn run(&self) -> bool {
+        match self.execute() {
+            Ok(success) => success,
+            Err(e) => {
+                eprintln!("run failed: {}", e);
+                false
+            }
+        }
+    }
+
+    fn load_code(&self) -> Result<Vec<u8>, String> {
+        match (&self.code, &self.codefile) {
+            (Some(code), _) => parse_bytes(code).map(|b| b.0),
+            (None, Some(path)) => {
+                let code = std::fs::read_to_string(path).map_err(|e| {
+                    format!("failed to read {}: {}", path.display(), e)
+                })?;
+                parse_bytes(&code).map(|b| b.0)
+            }
+            (None, None) => Ok(vec![]),
+        }
+    }
+
+    fn execute(&self) -> Result<bool, String> {
+        let (error, executed) = self.transact()?;
+        let success = error.is_none();
+        self.report(error, executed)?;
+        Ok(success)
+    }
+
+    /// Executes the bytecode or call, returning the execution error and the
+    /// execution result.
+    fn transact(&self) -> Result<(Option<String>, Executed), String> {
+        let code = self.load_code()?;
+
+        let mut alloc: BTreeMap<Address, T8nAccount> = match &self.prestate {
+            Some(path) => read_json(path)?,
+            None => BTreeMap::new(),
+        };
+        if !self.create && !code.is_empty() {
+            alloc.entry(self.receiver).or_default().code = Bytes(code.clone());
+        }
+        let fund_sender = !alloc.contains_key(&self.sender);
+        let pre: HashMap<Address, AccountInfo> = alloc
+            .into_iter()
+            .map(|(address, account)| (address, account.into()))
+            .collect();
+
+        let machine = {
+            let vm_factory = VmFactory::new(1024 * 32);
+            Arc::new(Machine::new_with_builtin(
+                self.config.common_params(),
+                vm_factory,
+            ))
+        };
+
+        let mut state = pre_transact::make_state_in_space(&pre, self.space);
+        let sender = self.sender.with_space(self.space);
+        if fund_sender {
+            // Give the sender enough balance for the value, gas and storage
+            // collateral, like a virtual call without a sender.
+            let balance = self.value.saturating_add(
+                U256::from(1_000_000_000) * U256::from(ONE_CFX_IN_DRIP),
+            );
+            state.add_balance(&sender, &balance).expect("db error");
+            state.add_total_issued(balance);
+            if self.space == Space::Ethereum {
+                state.add_total_evm_tokens(balance);
+            }
+        }
+
+        let evm_chain_id = machine
+            .params()
+            .chain_id
+            .read()
+            .get_chain_id(0)
+            .in_evm_space();
+        let env = pre_transact::make_block_env(
+            &machine,
+            &StateTestEnv {
+                current_coinbase: Address::zero(),
+                current_difficulty: U256::zero(),
+                current_gas_limit: U256::from(self.gas),
+                current_number: U256::one(),
+                current_timestamp: U256::one(),
+                current_base_fee: None,
+                previous_hash: None,
+                current_random: None,
+                current_beacon_root: None,
+                current_withdrawals_root: None,
+                parent_blob_gas_used: None,
+                parent_excess_blob_gas: None,
+                parent_target_blobs_per_block: None,
+                current_excess_blob_gas: None,
+            },
+            evm_chain_id as u64,
+            H256::zero(),
+        );
+
+        let nonce = state.nonce(&sender).expect("db error");
+        let (action, data) = if self.create {
+            (Action::Create, code)
+        } else {
+            (Action::Call(self.receiver), self.input.0.clone())
+        };
+        let tx: SignedTransaction = match self.space {
+            Space::Native => NativeTransaction {
+                nonce,
+                gas_price: self.price,
+                gas: U256::from(self.gas),
+                action,
+                value: self.value,
+                storage_limit: u64::MAX,
+                epoch_height: env.epoch_height,
+                chain_id: env.chain_id[&Space::Native],
+                data,
+            }
+            .fake_sign(sender),
+            Space::Ethereum => Eip155Transaction {
+                nonce,
+                gas_price: self.price,
+                gas: U256::from(self.gas),
+                action,
+                value: self.value,
+                chain_id: Some(env.chain_id[&Space::Ethereum]),
+                data,
+            }
+            .fake_sign_rpc(sender),
+        };
+
+        let settings = TransactSettings {
+            charge_collateral: ChargeCollateral::Normal,
+            charge_gas: true,
+            check_base_price: false,
+            check_epoch_bound: false,
+            forbid_eoa_with_code: false,
+        };
+        let spec = machine.spec(env.number, env.epoch_height);
+        let executive =
+            ExecutiveContext::new(&mut state, &env, &machine, &spec);
+        let outcome = if self.trace {
+            let options = TransactOptions {
+                observer: Eip3155Tracer::new(&spec),
+                settings,
+            };
+            executive.transact(&tx, options)
+        } else {
+            let options = TransactOptions {
+                observer: (),
+                settings,
+            };
+            executive.transact(&tx, options)
+        }
+        .expect("db error");
+
+        match outcome {
+            ExecutionOutcome::NotExecutedDrop(e) => {
+                Err(format!("transaction dropped: {:?}", e))
+            }
+            ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                Err(format!("transaction not executed: {:?}", e))
+            }
+            ExecutionOutcome::ExecutionErrorBumpNonce(e, executed) => {
+                Ok((Some(format!("{:?}", e)), executed))
+            }
+            ExecutionOutcome::Finished(executed) => Ok((None, executed)),
+        }
+    }
+
+    fn report(
+        &self, error: Option<String>, executed: Executed,
+    ) -> Result<(), String> {
+        let result = summary_line(error, &executed)?;
+
+        if self.trace {
+            for line in trace_lines(&executed)? {
+                eprintln!("{}", line);
+            }
+            eprintln!("{}", result);
+        }
+
+        println!("{}", result);
+        Ok(())
+    }
+}
+
+/// Serializes the result of the run as the summary line of the trace.
+fn summary_line(
+    error: Option<String>, executed: &Executed,
+) -> Result<String, String> {
+    let result = RunResult {
+        output: Bytes(executed.output.clone()),
+        gas_used: executed.gas_used,
+        error,
+    };
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// Serializes the recorded EIP-3155 steps, one line per step.
+fn trace_lines(executed: &Executed) -> Result<Vec<String>, String> {
+    executed
+        .ext_result
+        .get::<Eip3155TraceKey>()
+        .into_iter()
+        .flatten()
+        .map(|step| serde_json::to_string(step).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use serde_json::Value;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        run: RunCmd,
+    }
+
+    /// Runs `code` with tracing, returning the parsed trace steps and the
+    /// parsed summary line.
+    fn trace(code: &str) -> (Vec<Value>, Value) {
+        let cmd = TestCli::try_parse_from(["run", "--trace", code])
+            .unwrap()
+            .run;
+        let (error, executed) = cmd.transact().unwrap();
+        let steps = trace_lines(&executed)
+            .unwrap()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let summary = summary_line(error, &executed).unwrap();
+        (steps, serde_json::from_str(&summary).unwrap())
+    }
+
+    fn hex(value: u64) -> Value { Value::from(format!("{:#x}", value)) }
+
+    fn gas_of(step: &Value) -> U256 {
+        U256::from_str_radix(&step["gas"].as_str().unwrap()[2..], 16).unwrap()
+    }
+
+    #[test]
+    fn test_trace_steps() {
+        // PUSH1 1, PUSH1 2, ADD, PUSH1 0, MSTORE, PUSH1 0x20, PUSH1 0, RETURN
+        let (steps, summary) = trace("600160020160005260206000f3");
+
+        let pcs: Vec<_> = steps.iter().map(|s| s["pc"].clone()).collect();
+        assert_eq!(pcs, [0, 2, 4, 5, 7, 8, 10, 12].map(Value::from).to_vec());
+        let ops: Vec<_> = steps.iter().map(|s| s["opName"].clone()).collect();
+        assert_eq!(
+            ops,
+            [
+                "PUSH1", "PUSH1", "ADD", "PUSH1", "MSTORE", "PUSH1", "PUSH1",
+                "RETURN"
+            ]
+            .map(Value::from)
+            .to_vec()
+        );
+        assert_eq!(steps[2]["op"], Value::from(0x01));
+
+        for step in &steps {
+            assert_eq!(step["depth"], Value::from(1));
+            assert!(step.get("error").is_none());
+        }
+        assert_eq!(steps[0]["stack"], Value::Array(vec![]));
+        assert_eq!(steps[2]["stack"], Value::Array(vec![hex(1), hex(2)]));
+        assert_eq!(steps[3]["stack"], Value::Array(vec![hex(3)]));
+        assert_eq!(steps[7]["memSize"], Value::from(32));
+
+        // The gas before each step is the gas before the previous step minus
+        // its cost.
+        assert_eq!(steps[0]["gasCost"], hex(3));
+        assert_eq!(steps[2]["gasCost"], hex(3));
+        for pair in steps.windows(2) {
+            let cost = U256::from_str_radix(
+                &pair[0]["gasCost"].as_str().unwrap()[2..],
+                16,
+            )
+            .unwrap();
+            assert_eq!(gas_of(&pair[1]), gas_of(&pair[0]) - cost);
+        }
+
+        assert_eq!(summary["output"], Value::from(format!("0x{:064x}", 3)));
+        assert!(summary["gasUsed"].is_string());
+        assert!(summary.get("error").is_none());
+    }
+
+    #[test]
+    fn test_trace_error() {
+        // PUSH1 1, then an undefined opcode.
+        let (steps, summary) = trace("600121");
+
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[1]["pc"], Value::from(2));
+        assert_eq!(steps[1]["op"], Value::from(0x21));
+        assert_eq!(steps[1]["opName"], Value::from("opcode 0x21 not defined"));
+        assert_eq!(steps[1]["stack"], Value::Array(vec![hex(1)]));
+        assert!(steps[0].get("error").is_none());
+        assert!(steps[1]["error"].is_string());
+
+        assert_eq!(summary["output"], Value::from("0x"));
+        assert!(summary["error"].is_string());
+    }
+}
@@ -0,0 +1,162 @@
+use cfx_executor::{
+    executive_observer::{
+        AsTracer, CallTracer, CheckpointTracer, DrainTrace,
+        InternalTransferTracer, OpcodeTracer, StorageTracer, TracerTrait,
+    },
+    stack::FrameResult,
+};
+use cfx_rpc_eth_types::Bytes;
+use cfx_types::U256;
+use cfx_vm_interpreter::instructions::{
+    InstructionInfo, INSTRUCTIONS, INSTRUCTIONS_CANCUN, INSTRUCTIONS_CIP645,
+};
+use cfx_vm_types::{ActionParams, InterpreterInfo, Spec};
+use serde::Serialize;
+use typemap::ShareDebugMap;
+
+/// A step of the EIP-3155 trace. The `refund` counter is not tracked by the
+/// interpreter and is omitted.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceStep {
+    pub pc: u64,
+    pub op: u8,
+    pub gas: U256,
+    pub gas_cost: U256,
+    pub mem_size: usize,
+    pub stack: Vec<U256>,
+    pub return_data: Bytes,
+    pub depth: usize,
+    pub op_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub struct Eip3155TraceKey;
+
+impl typemap::Key for Eip3155TraceKey {
+    type Value = Vec<TraceStep>;
+}
+
+/// Records the executed opcodes in the EIP-3155 format.
+pub struct Eip3155Tracer {
+    depth: usize,
+    steps: Vec<TraceStep>,
+    instructions: &'static [Option<InstructionInfo>; 0x100],
+}
+
+impl Eip3155Tracer {
+    /// Creates a tracer naming the opcodes with the instruction table the
+    /// interpreter uses under `spec`.
+    pub fn new(spec: &Spec) -> Self {
+        let instructions = if !spec.cancun_opcodes {
+            &*INSTRUCTIONS
+        } else if !spec.cip645.opcode_update {
+            &*INSTRUCTIONS_CANCUN
+        } else {
+            &*INSTRUCTIONS_CIP645
+        };
+        Eip3155Tracer {
+            depth: 0,
+            steps: vec![],
+            instructions,
+        }
+    }
+
+    fn record_frame_result(&mut self, result: &FrameResult) {
+        if let Err(e) = result {
+            if let Some(step) =
+                self.steps.iter_mut().rev().find(|s| s.depth == self.depth)
+            {
+                step.error = Some(e.to_string());
+            }
+        }
+        self.depth -= 1;
+    }
+}
+
+impl DrainTrace for Eip3155Tracer {
+    fn drain_trace(self, map: &mut ShareDebugMap) {
+        map.insert::<Eip3155TraceKey>(self.steps);
+    }
+}
+
+impl AsTracer for Eip3155Tracer {
+    fn as_tracer<'a>(&'a mut self) -> Box<dyn 'a + TracerTrait> {
+        Box::new(self)
+    }
+}
+
+impl CallTracer for Eip3155Tracer {
+    fn record_call(&mut self, _params: &ActionParams) { self.depth += 1; }
+
+    fn record_call_result(&mut self, result: &FrameResult) {
+        self.record_frame_result(result);
+    }
+
+    fn record_create(&mut self, _params: &ActionParams) { self.depth += 1; }
+
+    fn record_create_result(&mut self, result: &FrameResult) {
+        self.record_frame_result(result);
+    }
+}
+
+impl OpcodeTracer for Eip3155Tracer {
+    fn do_trace_opcode(&self, enabled: &mut bool) { *enabled = true; }
+
+    fn step(&mut self, interp: &dyn InterpreterInfo) {
+        let op = interp.current_opcode();
+        let op_name = self.instructions[op as usize].map_or_else(
+            || format!("opcode {:#04x} not defined", op),
+            |info| info.name.to_string(),
+        );
+        self.steps.push(TraceStep {
+            pc: interp.program_counter(),
+            op,
+            gas: interp.gas_remainning(),
+            gas_cost: U256::zero(),
+            mem_size: interp.mem().len(),
+            stack: interp.stack().clone(),
+            return_data: Bytes(interp.return_data().to_vec()),
+            depth: self.depth,
+            op_name,
+            error: None,
+        });
+    }
+
+    fn step_end(&mut self, interp: &dyn InterpreterInfo) {
+        // `step_end` follows `step` of the same instruction immediately, even
+        // for the instructions entering a sub-call.
+        if let Some(step) = self.steps.last_mut() {
+            step.gas_cost = step.gas.saturating_sub(interp.gas_remainning());
+        }
+    }
+}
+
+impl CheckpointTracer for Eip3155Tracer {}
+impl InternalTransferTracer for Eip3155Tracer {}
+impl StorageTracer for Eip3155Tracer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op_name(spec: &Spec, op: u8) -> Option<&'static str> {
+        Eip3155Tracer::new(spec).instructions[op as usize].map(|i| i.name)
+    }
+
+    #[test]
+    fn test_instructions_of_spec() {
+        let mut spec = Spec::new_spec_for_test();
+        assert_eq!(op_name(&spec, 0x5e), Some("JUMPSUB"));
+        assert_eq!(op_name(&spec, 0x49), None);
+
+        spec.cancun_opcodes = true;
+        assert_eq!(op_name(&spec, 0x5e), Some("MCOPY"));
+        assert_eq!(op_name(&spec, 0x49), None);
+
+        spec.cip645.opcode_update = true;
+        assert_eq!(op_name(&spec, 0x5e), Some("MCOPY"));
+        assert_eq!(op_name(&spec, 0x49), Some("BLOBHASH"));
+    }
+}
@@ -1,6 +1,6 @@
 pub(crate) mod command;
 mod error;
-mod unit_tester;
+pub(crate) mod unit_tester;
 mod utils;
 
 pub use error::TestError;
@@ -1,5 +1,5 @@
-mod post_transact;
-mod pre_transact;
+pub(crate) mod post_transact;
+pub(crate) mod pre_transact;
 
 use self::post_transact::is_unsupport_reason;
 
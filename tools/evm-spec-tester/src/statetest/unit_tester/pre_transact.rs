@@ -160,6 +160,12 @@ pub fn make_transact_options(check_base_price: bool) -> TransactOptions<()> {
 }
 
 pub fn make_state(pre_state: &HashMap<Address, AccountInfo>) -> State {
+    make_state_in_space(pre_state, Space::Ethereum)
+}
+
+pub fn make_state_in_space(
+    pre_state: &HashMap<Address, AccountInfo>, space: Space,
+) -> State {
     // step1: setup the state according the pre state
     let mut state_override = StateOverride::new();
     for (address, info) in pre_state {
@@ -184,9 +190,8 @@ pub fn make_state(pre_state: &HashMap<Address, AccountInfo>) -> State {
 
     let statedb = StateDb::new_for_unit_test();
 
-    let mut state =
-        State::new_with_override(statedb, &state_override, Space::Ethereum)
-            .expect("db error");
+    let mut state = State::new_with_override(statedb, &state_override, space)
+        .expect("db error");
     state.commit_cache(false);
     state
 }
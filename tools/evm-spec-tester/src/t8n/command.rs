@@ -0,0 +1,61 @@
+use crate::util::make_configuration;
+use cfx_config::Configuration;
+use clap::Args;
+use std::{path::PathBuf, sync::Arc};
+
+/// State transition tool, compatible with the interface of geth `evm t8n`.
+///
+/// Input and output file names follow the geth conventions:
+/// `stdin` reads all inputs from a single json object on stdin, and `stdout`
+/// (or `stderr`) writes the outputs as a single json object.
+#[derive(Args, Debug)]
+pub struct T8nCmd {
+    /// Conflux client configuration
+    #[arg(short, long, value_parser = make_configuration, default_value = "", help = "Path to the configuration file")]
+    pub(super) config: Arc<Configuration>,
+
+    /// Pre-state allocation of accounts
+    #[arg(long = "input.alloc", default_value = "alloc.json")]
+    pub(super) input_alloc: String,
+
+    /// Block environment
+    #[arg(long = "input.env", default_value = "env.json")]
+    pub(super) input_env: String,
+
+    /// Transactions to apply, as json or as rlp (with the `.rlp` extension)
+    #[arg(long = "input.txs", default_value = "txs.json")]
+    pub(super) input_txs: String,
+
+    /// Directory to place the output files in
+    #[arg(long = "output.basedir", default_value = "")]
+    pub(super) output_basedir: PathBuf,
+
+    /// Execution result
+    #[arg(long = "output.result", default_value = "result.json")]
+    pub(super) output_result: String,
+
+    /// Post-state allocation of accounts
+    #[arg(long = "output.alloc", default_value = "alloc.json")]
+    pub(super) output_alloc: String,
+
+    /// Rlp of the included transactions
+    #[arg(long = "output.body")]
+    pub(super) output_body: Option<String>,
+
+    /// Name of the fork rules. Only `Prague`, which Conflux eSpace follows,
+    /// is supported.
+    #[arg(long = "state.fork", default_value = "Prague")]
+    pub(super) fork: String,
+
+    /// Chain id of eSpace
+    #[arg(long = "state.chainid", default_value_t = 1)]
+    pub(super) chain_id: u64,
+
+    /// Block reward of the coinbase, -1 disables the reward
+    #[arg(
+        long = "state.reward",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    pub(super) reward: i64,
+}
@@ -0,0 +1,646 @@
+pub(crate) mod command;
+mod trie;
+pub(crate) mod types;
+
+use crate::{
+    statetest::unit_tester::{post_transact, pre_transact},
+    util::read_json,
+};
+use cfx_executor::{
+    executive::{ExecutionOutcome, ExecutiveContext},
+    machine::{Machine, VmFactory},
+    state::State,
+};
+use cfx_rpc_eth_types::Bytes;
+use cfx_types::{Address, AddressSpaceUtil, Bloom, Space, H256, U256, U64};
+use cfxcore::verification::VerificationConfig;
+use command::T8nCmd;
+use eest_types::{AccountInfo, SpecId, SpecName};
+use keccak_hash::keccak;
+use primitives::{
+    log_entry::build_bloom, Action, LogEntry, SignedTransaction,
+    TransactionWithSignature,
+};
+use rlp::{Rlp, RlpStream};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Read,
+    sync::Arc,
+};
+use types::{
+    recover_sender, RejectedTx, T8nAccount, T8nEnv, T8nLog, T8nReceipt,
+    T8nResult, T8nStdinInput, T8nTransactions,
+};
+
+const STDIN: &str = "stdin";
+const STDOUT: &str = "stdout";
+const STDERR: &str = "stderr";
+
+struct T8nInput {
+    alloc: BTreeMap<Address, T8nAccount>,
+    env: T8nEnv,
+    txs: Vec<Result<SignedTransaction, String>>,
+}
+
+struct T8nOutput {
+    result: T8nResult,
+    alloc: BTreeMap<Address, T8nAccount>,
+    body: Bytes,
+}
+
+impl T8nCmd {
+    /// Runs `t8n` command.
+    pub fn run(&self) -> bool {
+        // Logs are written to stdout, so errors are reported to stderr to keep
+        // the `stdout` output parseable.
+        match self.transition() {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("t8n failed: {}", e);
+                false
+            }
+        }
+    }
+
+    fn transition(&self) -> Result<(), String> {
+        check_fork(&self.fork)?;
+
+        let input = self.load_input()?;
+
+        let machine = {
+            let vm_factory = VmFactory::new(1024 * 32);
+            Arc::new(Machine::new_with_builtin(
+                self.config.common_params(),
+                vm_factory,
+            ))
+        };
+        let verification = self.config.verification_config(machine.clone());
+
+        let output = self.apply(&machine, &verification, input)?;
+        self.write_output(output)
+    }
+
+    fn load_input(&self) -> Result<T8nInput, String> {
+        let mut stdin_input =
+            if [&self.input_alloc, &self.input_env, &self.input_txs]
+                .iter()
+                .any(|input| *input == STDIN)
+            {
+                let mut s = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut s)
+                    .map_err(|e| e.to_string())?;
+                serde_json::from_str(&s).map_err(|e| e.to_string())?
+            } else {
+                T8nStdinInput::default()
+            };
+
+        let alloc = match &*self.input_alloc {
+            STDIN => {
+                stdin_input.alloc.take().ok_or("missing alloc in stdin")?
+            }
+            path => read_json(path)?,
+        };
+        let env = match &*self.input_env {
+            STDIN => stdin_input.env.take().ok_or("missing env in stdin")?,
+            path => read_json(path)?,
+        };
+        let txs = match &*self.input_txs {
+            STDIN => stdin_input.txs.take().ok_or("missing txs in stdin")?,
+            path if path.ends_with(".rlp") => {
+                T8nTransactions::Rlp(read_json(path)?)
+            }
+            path => T8nTransactions::Json(read_json(path)?),
+        };
+
+        let txs = match txs {
+            T8nTransactions::Json(txs) => txs
+                .into_iter()
+                .map(|tx| tx.into_signed(self.chain_id))
+                .collect(),
+            T8nTransactions::Rlp(bytes) => {
+                let rlp = Rlp::new(&bytes.0);
+                if !rlp.is_list() {
+                    return Err("txs rlp is not a list".into());
+                }
+                rlp.iter()
+                    .map(|item| {
+                        let tx = item
+                            .as_val::<TransactionWithSignature>()
+                            .map_err(|e| e.to_string())?;
+                        if tx.unsigned.space() != Space::Ethereum {
+                            return Err("not an eSpace transaction".into());
+                        }
+                        recover_sender(tx)
+                    })
+                    .collect()
+            }
+        };
+
+        Ok(T8nInput { alloc, env, txs })
+    }
+
+    /// Applies the transactions on the pre-state one by one, like the
+    /// transactions in a block.
+    fn apply(
+        &self, machine: &Machine, verification: &VerificationConfig,
+        input: T8nInput,
+    ) -> Result<T8nOutput, String> {
+        let T8nInput {
+            alloc,
+            env: t8n_env,
+            txs,
+        } = input;
+
+        let pre: HashMap<Address, AccountInfo> = alloc
+            .into_iter()
+            .map(|(address, account)| {
+                let account = AccountInfo::try_from(account).map_err(|e| {
+                    format!("invalid alloc of {:?}: {}", address, e)
+                })?;
+                Ok((address, account))
+            })
+            .collect::<Result<_, String>>()?;
+        let mut state = pre_transact::make_state(&pre);
+        let block_env = t8n_env.to_state_test_env();
+
+        let mut included = vec![];
+        let mut receipts = vec![];
+        let mut receipts_rlp = vec![];
+        let mut rejected = vec![];
+        let mut all_logs = vec![];
+        let mut cumulative_gas_used = U256::zero();
+
+        for (index, tx) in txs.into_iter().enumerate() {
+            let tx = match tx {
+                Ok(tx) => tx,
+                Err(error) => {
+                    rejected.push(RejectedTx { index, error });
+                    continue;
+                }
+            };
+
+            let mut env = pre_transact::make_block_env(
+                machine,
+                &block_env,
+                self.chain_id,
+                tx.hash(),
+            );
+            env.accumulated_gas_used = cumulative_gas_used;
+
+            if cumulative_gas_used.saturating_add(*tx.gas()) > env.gas_limit {
+                rejected.push(RejectedTx {
+                    index,
+                    error: format!(
+                        "gas limit reached: have {}, want {}",
+                        env.gas_limit.saturating_sub(cumulative_gas_used),
+                        tx.gas()
+                    ),
+                });
+                continue;
+            }
+
+            if let Err(e) =
+                pre_transact::check_tx_common(machine, &env, &tx, verification)
+            {
+                rejected.push(RejectedTx {
+                    index,
+                    error: format!("{:?}", e),
+                });
+                continue;
+            }
+
+            let spec = machine.spec(env.number, env.epoch_height);
+            let outcome =
+                ExecutiveContext::new(&mut state, &env, machine, &spec)
+                    .transact(&tx, pre_transact::make_transact_options(true))
+                    .expect("db error");
+            state.update_state_post_tx_execution(false);
+
+            let (status, executed) = match outcome {
+                ExecutionOutcome::Finished(executed) => (1u8, executed),
+                ExecutionOutcome::ExecutionErrorBumpNonce(_, executed) => {
+                    (0u8, executed)
+                }
+                ExecutionOutcome::NotExecutedDrop(e) => {
+                    rejected.push(RejectedTx {
+                        index,
+                        error: format!("{:?}", e),
+                    });
+                    continue;
+                }
+                ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                    rejected.push(RejectedTx {
+                        index,
+                        error: format!("{:?}", e),
+                    });
+                    continue;
+                }
+            };
+
+            post_transact::distribute_tx_fee_to_miner(
+                &mut state,
+                &executed,
+                &env.author,
+            );
+            cumulative_gas_used += executed.gas_used;
+
+            let raw_tx = rlp::encode(&tx.transaction.transaction).to_vec();
+            // Typed transactions start with the type byte, while legacy
+            // transactions start with the rlp list prefix.
+            let tx_type = if raw_tx[0] < 0x7f { raw_tx[0] } else { 0 };
+            let transaction_index = U64::from(included.len() as u64);
+            let logs_bloom = build_bloom(&executed.logs);
+            let contract_address = match tx.unsigned.action() {
+                Action::Create if status == 1 => {
+                    executed.contracts_created.last().map(|a| a.address)
+                }
+                _ => None,
+            };
+            let effective_gas_price = match block_env.current_base_fee {
+                Some(base_fee) => tx.unsigned.effective_gas_price(&base_fee),
+                None => *tx.gas_price(),
+            };
+
+            receipts_rlp.push(encode_receipt(
+                tx_type,
+                status,
+                &cumulative_gas_used,
+                &logs_bloom,
+                &executed.logs,
+            ));
+            receipts.push(T8nReceipt {
+                tx_type: U64::from(tx_type as u64),
+                root: Bytes::default(),
+                status: U64::from(status as u64),
+                cumulative_gas_used: U64::from(cumulative_gas_used.low_u64()),
+                logs_bloom,
+                logs: executed
+                    .logs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, log)| T8nLog {
+                        address: log.address,
+                        topics: log.topics.clone(),
+                        data: Bytes(log.data.clone()),
+                        block_number: U64::from(
+                            block_env.current_number.low_u64(),
+                        ),
+                        transaction_hash: tx.hash(),
+                        transaction_index,
+                        block_hash: H256::zero(),
+                        log_index: U64::from((all_logs.len() + i) as u64),
+                        removed: false,
+                    })
+                    .collect(),
+                transaction_hash: tx.hash(),
+                contract_address,
+                gas_used: U64::from(executed.gas_used.low_u64()),
+                effective_gas_price,
+                block_hash: H256::zero(),
+                transaction_index,
+            });
+            all_logs.extend(executed.logs);
+            included.push((tx.transaction.clone(), raw_tx));
+        }
+
+        if self.reward > 0 {
+            state
+                .add_balance(
+                    &block_env.current_coinbase.with_evm_space(),
+                    &U256::from(self.reward),
+                )
+                .expect("db error");
+        }
+
+        let post = dump_alloc(&mut state, pre.keys());
+
+        let state_root = trie::sec_trie_root(
+            post.iter()
+                .map(|(address, account)| (*address, encode_account(account)))
+                .collect(),
+        );
+
+        let mut logs_stream = RlpStream::new_list(all_logs.len());
+        for log in &all_logs {
+            append_log(&mut logs_stream, log);
+        }
+
+        let mut body = RlpStream::new_list(included.len());
+        for (tx, _) in &included {
+            body.append(tx);
+        }
+
+        let result = T8nResult {
+            state_root,
+            tx_root: trie::ordered_trie_root(
+                included.into_iter().map(|(_, raw_tx)| raw_tx),
+            ),
+            receipts_root: trie::ordered_trie_root(receipts_rlp),
+            logs_hash: keccak(logs_stream.out()),
+            logs_bloom: build_bloom(&all_logs),
+            receipts,
+            rejected,
+            current_difficulty: t8n_env.current_difficulty,
+            gas_used: U64::from(cumulative_gas_used.low_u64()),
+            current_base_fee: t8n_env.current_base_fee,
+            current_excess_blob_gas: t8n_env.current_excess_blob_gas,
+        };
+
+        Ok(T8nOutput {
+            result,
+            alloc: post,
+            body: Bytes(body.out().to_vec()),
+        })
+    }
+
+    fn write_output(&self, output: T8nOutput) -> Result<(), String> {
+        let mut stdout = Map::new();
+        let mut stderr = Map::new();
+
+        let outputs = [
+            (
+                "result",
+                Some(&self.output_result),
+                to_value(&output.result)?,
+            ),
+            ("alloc", Some(&self.output_alloc), to_value(&output.alloc)?),
+            ("body", self.output_body.as_ref(), to_value(&output.body)?),
+        ];
+        for (name, dest, value) in outputs {
+            match dest.map(String::as_str) {
+                None => {}
+                Some(STDOUT) => {
+                    stdout.insert(name.into(), value);
+                }
+                Some(STDERR) => {
+                    stderr.insert(name.into(), value);
+                }
+                Some(file) => {
+                    let path = self.output_basedir.join(file);
+                    let s = serde_json::to_string_pretty(&value)
+                        .map_err(|e| e.to_string())?;
+                    std::fs::write(&path, s).map_err(|e| {
+                        format!("failed to write {}: {}", path.display(), e)
+                    })?;
+                }
+            }
+        }
+
+        if !stdout.is_empty() {
+            println!("{}", Value::Object(stdout));
+        }
+        if !stderr.is_empty() {
+            eprintln!("{}", Value::Object(stderr));
+        }
+        Ok(())
+    }
+}
+
+fn check_fork(fork: &str) -> Result<(), String> {
+    let spec = serde_json::from_value::<SpecName>(Value::String(fork.into()))
+        .map_err(|e| e.to_string())?;
+    match spec {
+        SpecName::Unknown
+        | SpecName::Constantinople
+        | SpecName::ByzantiumToConstantinopleAt5 => {
+            Err(format!("unknown fork {}", fork))
+        }
+        spec if spec.to_spec_id() != SpecId::PRAGUE => Err(format!(
+            "unsupported fork {}, only Prague is supported",
+            fork
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+/// Collects the eSpace accounts in the post-state. Besides the pre-state
+/// accounts, the accounts touched by the transactions are found in the
+/// committed cache of `state`.
+fn dump_alloc<'a>(
+    state: &mut State, pre_state: impl Iterator<Item = &'a Address>,
+) -> BTreeMap<Address, T8nAccount> {
+    state.commit_cache(false);
+    let addresses: BTreeSet<Address> = state
+        .committed_cache
+        .keys()
+        .filter(|address| address.space == Space::Ethereum)
+        .map(|address| address.address)
+        .chain(pre_state.cloned())
+        .collect();
+    state.apply_changes_to_statedb(None).expect("db error");
+
+    let mut post = BTreeMap::new();
+    for address in addresses {
+        let user_addr = address.with_evm_space();
+        if !state.exists(&user_addr).expect("db error") {
+            continue;
+        }
+        let storage = state
+            .read_all_storage_from_db(&user_addr)
+            .expect("db error")
+            .into_iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(key, value)| (U256::from_big_endian(&key), value))
+            .collect();
+        let code = state
+            .code(&user_addr)
+            .expect("db error")
+            .map_or_else(Vec::new, |code| code.to_vec());
+        post.insert(
+            address,
+            T8nAccount {
+                balance: state.balance(&user_addr).expect("db error"),
+                code: Bytes(code),
+                nonce: state.nonce(&user_addr).expect("db error"),
+                storage,
+            },
+        );
+    }
+    post
+}
+
+/// Encodes an account in the Ethereum state trie.
+fn encode_account(account: &T8nAccount) -> Vec<u8> {
+    let storage_root = trie::sec_trie_root(
+        account
+            .storage
+            .iter()
+            .map(|(key, value)| {
+                let mut key_bytes = [0u8; 32];
+                key.to_big_endian(&mut key_bytes);
+                (key_bytes, rlp::encode(value).to_vec())
+            })
+            .collect(),
+    );
+    let mut s = RlpStream::new_list(4);
+    s.append(&account.nonce);
+    s.append(&account.balance);
+    s.append(&storage_root);
+    s.append(&keccak(&account.code.0));
+    s.out().to_vec()
+}
+
+/// Encodes a log in the Ethereum format, which does not include the space.
+fn append_log(s: &mut RlpStream, log: &LogEntry) {
+    s.begin_list(3);
+    s.append(&log.address);
+    s.append_list(&log.topics);
+    s.append(&log.data);
+}
+
+/// Encodes a receipt in the Ethereum receipt trie.
+fn encode_receipt(
+    tx_type: u8, status: u8, cumulative_gas_used: &U256, logs_bloom: &Bloom,
+    logs: &[LogEntry],
+) -> Vec<u8> {
+    let mut s = RlpStream::new_list(4);
+    s.append(&status);
+    s.append(cumulative_gas_used);
+    s.append(logs_bloom);
+    s.begin_list(logs.len());
+    for log in logs {
+        append_log(&mut s, log);
+    }
+    let mut encoded = if tx_type == 0 { vec![] } else { vec![tx_type] };
+    encoded.extend_from_slice(&s.out());
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::{fs, path::Path, str::FromStr};
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        t8n: T8nCmd,
+    }
+
+    const SENDER: &str = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+    const SENDER_KEY: &str =
+        "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+    const RECEIVER: &str = "0x1000000000000000000000000000000000000001";
+
+    fn address(s: &str) -> Address {
+        Address::from_str(s.trim_start_matches("0x")).unwrap()
+    }
+
+    fn write(dir: &Path, name: &str, value: Value) -> String {
+        let path = dir.join(name);
+        fs::write(&path, value.to_string()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn transfer(nonce: u64, gas: &str) -> Value {
+        serde_json::json!({
+            "type": "0x0",
+            "nonce": format!("{:#x}", nonce),
+            "gasPrice": "0xa",
+            "gas": gas,
+            "to": RECEIVER,
+            "value": "0x3e8",
+            "input": "0x",
+            "secretKey": SENDER_KEY,
+        })
+    }
+
+    #[test]
+    fn test_transition() {
+        let dir = std::env::temp_dir()
+            .join(format!("t8n-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let alloc = write(
+            &dir,
+            "alloc.json",
+            serde_json::json!({ SENDER: { "balance": "0x3b9aca00" } }),
+        );
+        let env = write(
+            &dir,
+            "env.json",
+            serde_json::json!({
+                "currentCoinbase": "0x2000000000000000000000000000000000000002",
+                "currentGasLimit": "0x1000000",
+                "currentNumber": "0x1",
+                "currentTimestamp": "0x3e8",
+                "currentBaseFee": "0x7",
+            }),
+        );
+        // The second transaction reuses the nonce, and the gas of the third
+        // one overflows the gas counter.
+        let txs = write(
+            &dir,
+            "txs.json",
+            Value::Array(vec![
+                transfer(0, "0x5208"),
+                transfer(0, "0x5208"),
+                transfer(1, &format!("{:#x}", U256::max_value())),
+            ]),
+        );
+        let cmd = TestCli::try_parse_from([
+            "t8n",
+            "--input.alloc",
+            &alloc,
+            "--input.env",
+            &env,
+            "--input.txs",
+            &txs,
+            "--output.basedir",
+            dir.to_str().unwrap(),
+            "--output.result",
+            "result-out.json",
+            "--output.alloc",
+            "alloc-out.json",
+        ])
+        .unwrap()
+        .t8n;
+        cmd.transition().unwrap();
+
+        let result: Value =
+            crate::util::read_json(dir.join("result-out.json")).unwrap();
+        let post: BTreeMap<Address, T8nAccount> =
+            crate::util::read_json(dir.join("alloc-out.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let sender = post[&address(SENDER)].clone();
+        assert_eq!(sender.nonce, U256::one());
+        assert_eq!(
+            sender.balance,
+            U256::from(1_000_000_000 - 1_000 - 21_000 * 10)
+        );
+        assert_eq!(post[&address(RECEIVER)].balance, U256::from(1_000));
+        let state_root = trie::sec_trie_root(
+            post.iter()
+                .map(|(address, account)| (*address, encode_account(account)))
+                .collect(),
+        );
+        assert_eq!(result["stateRoot"], to_value(&state_root).unwrap());
+
+        let receipts_root = trie::ordered_trie_root(vec![encode_receipt(
+            0,
+            1,
+            &U256::from(21_000),
+            &Bloom::default(),
+            &[],
+        )]);
+        assert_eq!(result["receiptsRoot"], to_value(&receipts_root).unwrap());
+        assert_eq!(result["gasUsed"], Value::from("0x5208"));
+
+        let rejected: Vec<_> = result["rejected"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tx| tx["index"].clone())
+            .collect();
+        assert_eq!(rejected, vec![Value::from(1), Value::from(2)]);
+    }
+}
@@ -0,0 +1,179 @@
+//! A minimal Ethereum Merkle Patricia Trie root calculator, used to compute
+//! the state, transaction and receipt roots reported by `t8n`.
+
+use cfx_types::H256;
+use keccak_hash::{keccak, KECCAK_NULL_RLP};
+use rlp::RlpStream;
+
+/// Computes the root of a trie whose keys are the rlp encoded indices of
+/// `values`.
+pub fn ordered_trie_root<I>(values: I) -> H256
+where I: IntoIterator<Item = Vec<u8>> {
+    trie_root(
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (rlp::encode(&index).to_vec(), value))
+            .collect(),
+    )
+}
+
+/// Computes the root of a secure trie, in which the keys are hashed before
+/// insertion.
+pub fn sec_trie_root<K: AsRef<[u8]>>(entries: Vec<(K, Vec<u8>)>) -> H256 {
+    trie_root(
+        entries
+            .into_iter()
+            .map(|(key, value)| (keccak(key).as_bytes().to_vec(), value))
+            .collect(),
+    )
+}
+
+/// Computes the root of a trie with the given key-value pairs. Keys must be
+/// distinct.
+pub fn trie_root(entries: Vec<(Vec<u8>, Vec<u8>)>) -> H256 {
+    if entries.is_empty() {
+        return KECCAK_NULL_RLP;
+    }
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+        .into_iter()
+        .map(|(key, value)| (to_nibbles(&key), value))
+        .collect();
+    entries.sort();
+    keccak(encode_node(&entries, 0))
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Encodes a nibble path with the hex-prefix encoding.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push((flag << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+/// Appends the reference to a child node: nodes shorter than 32 bytes are
+/// inlined, others are referred to by hash.
+fn append_child(stream: &mut RlpStream, encoded: Vec<u8>) {
+    if encoded.len() < 32 {
+        stream.append_raw(&encoded, 1);
+    } else {
+        stream.append(&keccak(&encoded));
+    }
+}
+
+/// Encodes the node covering `entries`, which are sorted and share the first
+/// `depth` nibbles.
+fn encode_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+
+    if let [(key, value)] = entries {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&key[depth..], true));
+        stream.append(value);
+        return stream.out().to_vec();
+    }
+
+    let first = &entries[0].0;
+    let last = &entries[entries.len() - 1].0;
+    let shared = first[depth..]
+        .iter()
+        .zip(last[depth..].iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if shared > 0 {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&first[depth..depth + shared], false));
+        append_child(&mut stream, encode_node(entries, depth + shared));
+        return stream.out().to_vec();
+    }
+
+    let (value, mut rest) = if first.len() == depth {
+        (&entries[0].1[..], &entries[1..])
+    } else {
+        (&[][..], entries)
+    };
+
+    stream.begin_list(17);
+    for nibble in 0..16u8 {
+        let len = rest
+            .iter()
+            .take_while(|(key, _)| key[depth] == nibble)
+            .count();
+        if len == 0 {
+            stream.append_empty_data();
+        } else {
+            append_child(&mut stream, encode_node(&rest[..len], depth + 1));
+        }
+        rest = &rest[len..];
+    }
+    stream.append(&value.to_vec());
+    stream.out().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn root_of(entries: &[(&str, &str)]) -> H256 {
+        trie_root(
+            entries
+                .iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(trie_root(vec![]), KECCAK_NULL_RLP);
+        assert_eq!(ordered_trie_root(vec![]), KECCAK_NULL_RLP);
+    }
+
+    #[test]
+    fn test_trie_root() {
+        let long_value = "a".repeat(50);
+        assert_eq!(
+            root_of(&[("A", long_value.as_str())]),
+            H256::from_str(
+                "d23786fb4a010da3ce639d66d5e904a11dbc02746d1ce25029e53290cabf28ab"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            root_of(&[
+                ("doe", "reindeer"),
+                ("dog", "puppy"),
+                ("dogglesworth", "cat")
+            ]),
+            H256::from_str(
+                "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            root_of(&[
+                ("do", "verb"),
+                ("dog", "puppy"),
+                ("doge", "coin"),
+                ("horse", "stallion")
+            ]),
+            H256::from_str(
+                "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use cfx_rpc_eth_types::Bytes;
+use cfx_types::{u256_to_h256_be, Address, Bloom, H256, U256, U64};
+use cfxkey::{Secret, Signature};
+use eest_types::{
+    AccountInfo, Env as StateTestEnv, SignedAuthorization, TestAuthorization,
+};
+use primitives::{
+    transaction::{
+        eth_transaction::eip155_signature, Action, AuthorizationListItem,
+        Eip1559Transaction, Eip155Transaction, Eip2930Transaction,
+        Eip7702Transaction, EthereumTransaction,
+    },
+    AccessList, SignedTransaction, Transaction, TransactionWithSignature,
+};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Pre-state and post-state account of the transition tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default, skip_serializing_if = "is_empty_bytes")]
+    pub code: Bytes,
+    #[serde(default, skip_serializing_if = "U256::is_zero")]
+    pub nonce: U256,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+fn is_empty_bytes(bytes: &Bytes) -> bool { bytes.0.is_empty() }
+
+impl TryFrom<T8nAccount> for AccountInfo {
+    type Error = String;
+
+    fn try_from(account: T8nAccount) -> Result<Self, String> {
+        if account.nonce > U256::from(u64::MAX) {
+            return Err(format!("nonce {} exceeds 2^64-1", account.nonce));
+        }
+        Ok(AccountInfo {
+            balance: account.balance,
+            code: account.code,
+            nonce: account.nonce.low_u64(),
+            storage: account.storage.into_iter().collect(),
+        })
+    }
+}
+
+/// Block environment of the transition tool. Unlike the state test `Env`,
+/// unknown fields (e.g. `parentTimestamp`, `withdrawals`) are tolerated.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nEnv {
+    pub current_coinbase: Address,
+    pub current_difficulty: Option<U256>,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    pub current_base_fee: Option<U256>,
+    pub current_random: Option<H256>,
+    pub current_excess_blob_gas: Option<U256>,
+    /// Recent block hashes, keyed by the hex or decimal block number.
+    #[serde(default)]
+    pub block_hashes: BTreeMap<String, H256>,
+}
+
+impl T8nEnv {
+    pub fn to_state_test_env(&self) -> StateTestEnv {
+        let previous_hash = self
+            .current_number
+            .checked_sub(U256::one())
+            .and_then(|parent| {
+                self.block_hashes.iter().find_map(|(number, hash)| {
+                    let number = match number.strip_prefix("0x") {
+                        Some(hex) => U256::from_str(hex).ok(),
+                        None => U256::from_dec_str(number).ok(),
+                    };
+                    (number == Some(parent)).then_some(*hash)
+                })
+            });
+        // Before the merge, the DIFFICULTY opcode reads the difficulty.
+        let current_random = self
+            .current_random
+            .or_else(|| self.current_difficulty.map(u256_to_h256_be));
+        StateTestEnv {
+            current_coinbase: self.current_coinbase,
+            current_difficulty: self.current_difficulty.unwrap_or_default(),
+            current_gas_limit: self.current_gas_limit,
+            current_number: self.current_number,
+            current_timestamp: self.current_timestamp,
+            current_base_fee: self.current_base_fee,
+            previous_hash,
+            current_random,
+            current_beacon_root: None,
+            current_withdrawals_root: None,
+            parent_blob_gas_used: None,
+            parent_excess_blob_gas: None,
+            parent_target_blobs_per_block: None,
+            current_excess_blob_gas: self.current_excess_blob_gas,
+        }
+    }
+}
+
+/// A transaction of the transition tool input. It is either signed with
+/// `secretKey`, or carries the signature in `v`, `r` and `s`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nTransaction {
+    #[serde(default, rename = "type")]
+    pub tx_type: U64,
+    pub chain_id: Option<U256>,
+    pub nonce: U256,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(alias = "gasLimit")]
+    pub gas: U256,
+    #[serde(default, deserialize_with = "deserialize_maybe_null_address")]
+    pub to: Option<Address>,
+    pub value: U256,
+    #[serde(alias = "data")]
+    pub input: Bytes,
+    #[serde(default)]
+    pub v: U256,
+    #[serde(default)]
+    pub r: U256,
+    #[serde(default)]
+    pub s: U256,
+    pub secret_key: Option<H256>,
+    pub access_list: Option<AccessList>,
+    pub authorization_list: Option<Vec<TestAuthorization>>,
+    /// Whether a legacy transaction signed with `secretKey` has EIP-155
+    /// replay protection.
+    #[serde(default = "default_protected")]
+    pub protected: bool,
+}
+
+fn default_protected() -> bool { true }
+
+fn deserialize_maybe_null_address<'de, D>(
+    deserializer: D,
+) -> Result<Option<Address>, D::Error>
+where D: Deserializer<'de> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(string) if !string.is_empty() => {
+            Address::from_str(string.trim_start_matches("0x"))
+                .map(Some)
+                .map_err(de::Error::custom)
+        }
+        _ => Ok(None),
+    }
+}
+
+impl T8nTransaction {
+    pub fn into_signed(
+        self, chain_id: u64,
+    ) -> Result<SignedTransaction, String> {
+        let action = match self.to {
+            Some(to) => Action::Call(to),
+            None => Action::Create,
+        };
+        let typed_chain_id = match self.chain_id {
+            Some(id) if id > U256::from(u32::MAX) => {
+                return Err(format!("invalid chain id {}", id))
+            }
+            Some(id) => id.low_u32(),
+            None => chain_id as u32,
+        };
+        let gas_price = self.gas_price.unwrap_or_default();
+        let max_fee_per_gas = self.max_fee_per_gas.unwrap_or_default();
+        let max_priority_fee_per_gas =
+            self.max_priority_fee_per_gas.unwrap_or_default();
+        let access_list = self.access_list.unwrap_or_default();
+        let is_legacy = self.tx_type.is_zero();
+
+        let tx = match self.tx_type.as_u64() {
+            0 => {
+                let legacy_chain_id = if self.secret_key.is_some() {
+                    self.protected.then_some(typed_chain_id)
+                } else {
+                    eip155_signature::extract_chain_id_from_legacy_v(
+                        self.v.low_u64(),
+                    )
+                    .map(|id| id as u32)
+                };
+                EthereumTransaction::Eip155(Eip155Transaction {
+                    nonce: self.nonce,
+                    gas_price,
+                    gas: self.gas,
+                    action,
+                    value: self.value,
+                    data: self.input.0,
+                    chain_id: legacy_chain_id,
+                })
+            }
+            1 => EthereumTransaction::Eip2930(Eip2930Transaction {
+                nonce: self.nonce,
+                gas_price,
+                gas: self.gas,
+                action,
+                value: self.value,
+                data: self.input.0,
+                chain_id: typed_chain_id,
+                access_list,
+            }),
+            2 => EthereumTransaction::Eip1559(Eip1559Transaction {
+                nonce: self.nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas: self.gas,
+                action,
+                value: self.value,
+                data: self.input.0,
+                chain_id: typed_chain_id,
+                access_list,
+            }),
+            4 => {
+                let authorization_list = self
+                    .authorization_list
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|v| {
+                        let auth = SignedAuthorization::from(v);
+                        AuthorizationListItem {
+                            address: auth.inner().address,
+                            nonce: auth.inner().nonce,
+                            chain_id: auth.inner().chain_id,
+                            y_parity: auth.y_parity(),
+                            r: auth.r(),
+                            s: auth.s(),
+                        }
+                    })
+                    .collect();
+                EthereumTransaction::Eip7702(Eip7702Transaction {
+                    nonce: self.nonce,
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                    gas: self.gas,
+                    destination: self.to.ok_or(
+                        "EIP-7702 transaction must have a destination",
+                    )?,
+                    value: self.value,
+                    data: self.input.0,
+                    chain_id: typed_chain_id,
+                    access_list,
+                    authorization_list,
+                })
+            }
+            tx_type => {
+                return Err(format!("unsupported transaction type {}", tx_type))
+            }
+        };
+        let tx = Transaction::Ethereum(tx);
+
+        if let Some(secret) = self.secret_key {
+            return Ok(tx.sign(&Secret::from(secret)));
+        }
+
+        let invalid_v = || format!("invalid signature v {}", self.v);
+        if self.v > U256::from(u64::MAX) {
+            return Err(invalid_v());
+        }
+        let y_parity = if is_legacy {
+            eip155_signature::extract_standard_v(self.v.as_u64())
+        } else {
+            self.v.as_u64().try_into().map_err(|_| invalid_v())?
+        };
+        if y_parity > 1 {
+            return Err(invalid_v());
+        }
+        let signature = Signature::from_rsv(
+            &u256_to_h256_be(self.r),
+            &u256_to_h256_be(self.s),
+            y_parity,
+        );
+        recover_sender(tx.with_signature(signature))
+    }
+}
+
+pub fn recover_sender(
+    tx: TransactionWithSignature,
+) -> Result<SignedTransaction, String> {
+    let public = tx.recover_public().map_err(|e| e.to_string())?;
+    Ok(SignedTransaction::new(public, tx))
+}
+
+/// Transactions of the transition tool input, either as a json list or as
+/// the hex encoded rlp list of signed transactions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum T8nTransactions {
+    Rlp(Bytes),
+    Json(Vec<T8nTransaction>),
+}
+
+/// Input of the transition tool, when it is read from stdin as a whole.
+#[derive(Debug, Default, Deserialize)]
+pub struct T8nStdinInput {
+    pub alloc: Option<BTreeMap<Address, T8nAccount>>,
+    pub env: Option<T8nEnv>,
+    pub txs: Option<T8nTransactions>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+    pub block_number: U64,
+    pub transaction_hash: H256,
+    pub transaction_index: U64,
+    pub block_hash: H256,
+    pub log_index: U64,
+    pub removed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nReceipt {
+    #[serde(rename = "type")]
+    pub tx_type: U64,
+    pub root: Bytes,
+    pub status: U64,
+    pub cumulative_gas_used: U64,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<T8nLog>,
+    pub transaction_hash: H256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<Address>,
+    pub gas_used: U64,
+    pub effective_gas_price: U256,
+    pub block_hash: H256,
+    pub transaction_index: U64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RejectedTx {
+    pub index: usize,
+    pub error: String,
+}
+
+/// The `result` output of the transition tool.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nResult {
+    pub state_root: H256,
+    pub tx_root: H256,
+    pub receipts_root: H256,
+    pub logs_hash: H256,
+    pub logs_bloom: Bloom,
+    pub receipts: Vec<T8nReceipt>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<RejectedTx>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_difficulty: Option<U256>,
+    pub gas_used: U64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_base_fee: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_excess_blob_gas: Option<U256>,
+}
@@ -1,5 +1,6 @@
 use cfx_config::{Configuration, RawConfiguration};
 use primitives::block_header::CIP112_TRANSITION_HEIGHT;
+use serde::de::DeserializeOwned;
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
@@ -20,6 +21,16 @@ pub(crate) fn find_all_json_tests(path: &Path) -> Vec<PathBuf> {
     }
 }
 
+pub(crate) fn read_json<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+) -> Result<T, String> {
+    let path = path.as_ref();
+    let s = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&s)
+        .map_err(|e| format!("failed to parse {}: {}", path.display(), e))
+}
+
 pub(crate) fn make_configuration(
     config_file: &str,
 ) -> Result<Arc<Configuration>, String> {